@@ -0,0 +1,65 @@
+//! Featurization cost of Breakthrough states visited by a tree search, with and without
+//! the feature cache.
+//!
+//! Usage: `cargo bench --bench feature_cache`
+
+#![feature(test)]
+
+extern crate test;
+
+use ggpf::deep::feature_cache::FeatureCache;
+use ggpf::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+use ggpf::game::{Base, Features, GameBuilder, Playable};
+
+use futures::executor::block_on;
+use rand::seq::SliceRandom;
+use test::Bencher;
+
+/// Number of random descents from the root.
+const N_DESCENTS: usize = 200;
+/// Depth of each descent.
+const DEPTH: usize = 6;
+
+/// States visited by random descents from the initial position, in visit order.
+/// Shallow states are visited many times, as in a tree search.
+fn visited_states() -> Vec<Breakthrough> {
+    block_on(async {
+        let root = BreakthroughBuilder { size: 8 }.create(Color::Black).await;
+        let mut visited = vec![];
+        for _ in 0..N_DESCENTS {
+            let mut state = root.clone();
+            visited.push(state.clone());
+            for _ in 0..DEPTH {
+                let action = *state
+                    .possible_moves()
+                    .choose(&mut rand::thread_rng())
+                    .unwrap();
+                state.play(&action).await;
+                visited.push(state.clone());
+            }
+        }
+        visited
+    })
+}
+
+#[bench]
+fn featurize_uncached(b: &mut Bencher) {
+    let visited = visited_states();
+    b.iter(|| {
+        for state in visited.iter() {
+            test::black_box(state.state_to_feature(Color::Black));
+        }
+    });
+}
+
+#[bench]
+fn featurize_cached(b: &mut Bencher) {
+    let visited = visited_states();
+    b.iter(|| {
+        // a fresh cache per search.
+        let cache = FeatureCache::new(visited.len());
+        for state in visited.iter() {
+            test::black_box(cache.get(state, Color::Black));
+        }
+    });
+}
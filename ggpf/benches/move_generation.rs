@@ -0,0 +1,57 @@
+//! Breakthrough legal move generation on 8x8 boards, with the pawn lists and with a
+//! scan of the whole board.
+//!
+//! Usage: `cargo bench --bench move_generation`
+
+#![feature(test)]
+
+extern crate test;
+
+use ggpf::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+use ggpf::game::{Base, GameBuilder, Playable};
+
+use futures::executor::block_on;
+use rand::seq::SliceRandom;
+use test::Bencher;
+
+/// Number of random games.
+const N_GAMES: usize = 10;
+
+/// States of random games on an 8x8 board.
+fn random_states() -> Vec<Breakthrough> {
+    block_on(async {
+        let mut states = vec![];
+        for _ in 0..N_GAMES {
+            let mut state = BreakthroughBuilder { size: 8 }.create(Color::Black).await;
+            while !state.is_finished() {
+                states.push(state.clone());
+                let action = *state
+                    .possible_moves()
+                    .choose(&mut rand::thread_rng())
+                    .unwrap();
+                state.play(&action).await;
+            }
+        }
+        states
+    })
+}
+
+#[bench]
+fn moves_incremental(b: &mut Bencher) {
+    let states = random_states();
+    b.iter(|| {
+        for state in states.iter() {
+            test::black_box(state.possible_moves());
+        }
+    });
+}
+
+#[bench]
+fn moves_rescan(b: &mut Bencher) {
+    let states = random_states();
+    b.iter(|| {
+        for state in states.iter() {
+            test::black_box(state.possible_moves_rescan());
+        }
+    });
+}
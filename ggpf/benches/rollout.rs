@@ -0,0 +1,58 @@
+//! Breakthrough random playouts on 8x8 boards, with validated moves and with moves
+//! applied without validation.
+//!
+//! Usage: `cargo bench --bench rollout`
+
+#![feature(test)]
+
+extern crate test;
+
+use ggpf::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+use ggpf::game::{Base, GameBuilder, Playable};
+
+use futures::executor::block_on;
+use rand::seq::SliceRandom;
+use test::Bencher;
+
+/// Number of playouts per iteration.
+const N_PLAYOUTS: usize = 10;
+
+fn initial_state() -> Breakthrough {
+    block_on(BreakthroughBuilder { size: 8 }.create(Color::Black))
+}
+
+#[bench]
+fn rollout_checked(b: &mut Bencher) {
+    let initial = initial_state();
+    b.iter(|| {
+        block_on(async {
+            for _ in 0..N_PLAYOUTS {
+                let mut state = initial.clone();
+                while !state.is_finished() {
+                    let action = *state
+                        .possible_moves()
+                        .choose(&mut rand::thread_rng())
+                        .unwrap();
+                    state.play(&action).await;
+                }
+                test::black_box(state);
+            }
+        })
+    });
+}
+
+#[bench]
+fn rollout_unchecked(b: &mut Bencher) {
+    let initial = initial_state();
+    b.iter(|| {
+        block_on(async {
+            for _ in 0..N_PLAYOUTS {
+                let mut state = initial.clone();
+                while !state.is_finished() {
+                    state.random_move().await;
+                }
+                test::black_box(state);
+            }
+        })
+    });
+}
@@ -0,0 +1,517 @@
+use crate::game::{
+    position_hash, simulate_players, turn_index, DrawDetector, DrawRules, Game, GameBuilder,
+    SingleWinner,
+};
+use crate::policies::{DynMultiplayerPolicyBuilder, MultiplayerPolicyBuilder};
+use crate::settings::{StrError, Tunable};
+
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// Conversion factor from natural logistic units to Elo points.
+const ELO_SCALE: f32 = 400. / std::f32::consts::LN_10;
+
+/// Number of virtual draws added between each pair of players, so that ratings stay
+/// finite when a player wins all of its games.
+const PRIOR_DRAWS: f32 = 1.;
+
+/// Named policy builder taking part in an arena.
+pub type Contender<G> = (
+    String,
+    Box<dyn DynMultiplayerPolicyBuilder<'static, G> + Sync + Send>,
+);
+
+/// Rating of a player after an arena.
+#[derive(Debug, Clone)]
+pub struct EloRating {
+    /// Player name.
+    pub name: String,
+    /// Elo rating, the mean rating of the players being zero.
+    pub elo: f32,
+    /// Half-width of the 95% confidence interval on the rating.
+    pub interval: f32,
+    /// Number of games won.
+    pub wins: usize,
+    /// Number of games drawn.
+    pub draws: usize,
+    /// Number of games lost.
+    pub losses: usize,
+}
+
+/// Ratings of all players of an arena, sorted by decreasing Elo.
+#[derive(Debug, Clone)]
+pub struct EloTable {
+    /// Players ratings.
+    pub ratings: Vec<EloRating>,
+}
+
+impl EloTable {
+    /// Rating of the given player.
+    pub fn get(&self, name: &str) -> Option<&EloRating> {
+        self.ratings.iter().find(|rating| rating.name == name)
+    }
+}
+
+impl fmt::Display for EloTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>8} {:>8} {:>6} {:>6} {:>6}",
+            "Player", "Elo", "+/-", "W", "D", "L"
+        )?;
+        for rating in self.ratings.iter() {
+            writeln!(
+                f,
+                "{:<20} {:>8.1} {:>8.1} {:>6} {:>6} {:>6}",
+                rating.name, rating.elo, rating.interval, rating.wins, rating.draws, rating.losses
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays a round-robin between the given players, `games_per_group` games for each group
+/// of as many players as the game has, and rates them.
+///
+/// In each group, the players rotate around the seats (`Game::players`) from one game to
+/// the next, and the starting seat changes after a full rotation. Each player creates one
+/// policy per seat and group, which is reset between games. The games of a group are
+/// played in sequence, groups are played concurrently.
+///
+/// Multiplayer games are rated as pairwise results: the winner beats each other player of
+/// the game, and the others draw against each other.
+pub async fn arena<GB>(
+    players: Vec<Contender<GB::G>>,
+    games_per_group: usize,
+    game_builder: GB,
+    rules: DrawRules,
+) -> EloTable
+where
+    GB: GameBuilder + 'static,
+    GB::G: SingleWinner + Hash + 'static,
+{
+    let colors = <GB::G as Game>::players();
+    let k = colors.len();
+    let n = players.len();
+    assert!(n >= k, "Not enough players for a game.");
+
+    let mut groups = vec![];
+    for group in combinations(n, k) {
+        // policies[i][s]: policy of the `i`-th player of the group for the `s`-th color.
+        let mut policies: Vec<Vec<Option<_>>> = group
+            .iter()
+            .map(|player| {
+                colors
+                    .iter()
+                    .map(|color| Some(players[*player].1.create(*color)))
+                    .collect()
+            })
+            .collect();
+        let colors = colors.clone();
+        let game_builder = game_builder.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut winners = vec![];
+            for g in 0..games_per_group {
+                let mut seated: Vec<_> = (0..k)
+                    .map(|s| policies[(s + g) % k][s].take().unwrap())
+                    .collect();
+                for policy in seated.iter_mut() {
+                    policy.reset();
+                }
+
+                let mut game = game_builder.create(colors[(g / k) % k]).await;
+                simulate_players(&mut seated, &mut game, rules).await;
+                winners.push(game.winner());
+
+                for (s, policy) in seated.into_iter().enumerate() {
+                    policies[(s + g) % k][s] = Some(policy);
+                }
+            }
+            winners
+        });
+        groups.push((group, handle));
+    }
+
+    // score[i][j]: points of i against j, a draw being worth half a point.
+    let mut score = vec![vec![0.; n]; n];
+    let mut count = vec![vec![0.; n]; n];
+    let mut results = vec![(0, 0, 0); n];
+    for (group, handle) in groups {
+        for (g, winner) in handle.await.unwrap().into_iter().enumerate() {
+            // seats[s]: player of the `s`-th color.
+            let seats: Vec<usize> = (0..k).map(|s| group[(s + g) % k]).collect();
+            let winner = winner.map(|winner| colors.iter().position(|c| *c == winner).unwrap());
+
+            for (s, player) in seats.iter().enumerate() {
+                let (wins, draws, losses) = &mut results[*player];
+                match winner {
+                    Some(w) if w == s => *wins += 1,
+                    Some(_) => *losses += 1,
+                    None => *draws += 1,
+                }
+
+                for (t, other) in seats.iter().enumerate().filter(|(t, _)| *t != s) {
+                    score[*player][*other] += match winner {
+                        Some(w) if w == s => 1.,
+                        Some(w) if w == t => 0.,
+                        _ => 0.5,
+                    };
+                    count[*player][*other] += 1.;
+                }
+            }
+        }
+    }
+
+    let (ratings, variances) = bradley_terry(&score, &count);
+    let mut ratings: Vec<EloRating> = players
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, _))| EloRating {
+            name,
+            elo: ratings[i] * ELO_SCALE,
+            interval: 1.96 * variances[i].sqrt() * ELO_SCALE,
+            wins: results[i].0,
+            draws: results[i].1,
+            losses: results[i].2,
+        })
+        .collect();
+    ratings.sort_by(|a, b| b.elo.partial_cmp(&a.elo).unwrap());
+    EloTable { ratings }
+}
+
+/// Results of a parameter value against the opponent of a tuning run.
+#[derive(Debug, Clone)]
+pub struct TuningResult {
+    /// Parameter value.
+    pub value: f32,
+    /// Number of games won.
+    pub wins: usize,
+    /// Number of games drawn.
+    pub draws: usize,
+    /// Number of games lost.
+    pub losses: usize,
+}
+
+impl TuningResult {
+    /// Points per game, a draw being worth half a point.
+    pub fn win_rate(&self) -> f32 {
+        let games = self.wins + self.draws + self.losses;
+        if games == 0 {
+            0.
+        } else {
+            (self.wins as f32 + 0.5 * self.draws as f32) / games as f32
+        }
+    }
+}
+
+/// Sweeps the parameter `parameter` of the base policy settings over `values`: for each
+/// value, an arena of `games` games is played against the opponent. Arenas are played
+/// concurrently.
+///
+/// Returns the results in the order of `values`, or an error if the parameter is unknown.
+pub async fn tune<GB, PB, PO>(
+    game_builder: GB,
+    base: PB,
+    parameter: &str,
+    values: &[f32],
+    opponent: PO,
+    games: usize,
+    rules: DrawRules,
+) -> Result<Vec<TuningResult>, StrError>
+where
+    GB: GameBuilder + 'static,
+    GB::G: SingleWinner + Hash + 'static,
+    PB: MultiplayerPolicyBuilder<GB::G> + Tunable + Sync + Send + 'static,
+    PB::P: Sync + Send + 'static,
+    PO: MultiplayerPolicyBuilder<GB::G> + Clone + Sync + Send + 'static,
+    PO::P: Sync + Send + 'static,
+{
+    let mut handles = vec![];
+    for value in values {
+        let players: Vec<Contender<GB::G>> = vec![
+            (
+                "candidate".to_owned(),
+                Box::new(base.with_parameter(parameter, *value)?),
+            ),
+            ("opponent".to_owned(), Box::new(opponent.clone())),
+        ];
+        handles.push((
+            *value,
+            tokio::spawn(arena(players, games, game_builder.clone(), rules)),
+        ));
+    }
+
+    let mut results = vec![];
+    for (value, handle) in handles {
+        let table = handle.await.unwrap();
+        let candidate = table.get("candidate").unwrap();
+        results.push(TuningResult {
+            value,
+            wins: candidate.wins,
+            draws: candidate.draws,
+            losses: candidate.losses,
+        });
+    }
+    Ok(results)
+}
+
+/// Self-play throughput of a policy.
+#[derive(Debug, Clone)]
+pub struct Throughput {
+    /// Number of games played.
+    pub games: usize,
+    /// Total number of moves played.
+    pub plies: usize,
+    /// Wall-clock duration of the benchmark.
+    pub elapsed: Duration,
+}
+
+impl Throughput {
+    /// Games played per second.
+    pub fn games_per_sec(&self) -> f32 {
+        self.games as f32 / self.elapsed.as_secs_f32()
+    }
+
+    /// Mean number of moves per game.
+    pub fn plies_per_game(&self) -> f32 {
+        self.plies as f32 / self.games as f32
+    }
+
+    /// Moves played per second.
+    pub fn plies_per_sec(&self) -> f32 {
+        self.plies as f32 / self.elapsed.as_secs_f32()
+    }
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} games in {:.2}s",
+            self.games,
+            self.elapsed.as_secs_f32()
+        )?;
+        writeln!(f, "|| games/s: {:.2}", self.games_per_sec())?;
+        writeln!(f, "|| plies/game: {:.1}", self.plies_per_game())?;
+        writeln!(f, "|| plies/s: {:.1}", self.plies_per_sec())
+    }
+}
+
+/// Plays `games` self-play games of the policy concurrently, the starting player
+/// alternating from one game to the next, and measures the throughput.
+pub async fn benchmark<GB>(
+    game_builder: GB,
+    policy: &(dyn DynMultiplayerPolicyBuilder<'static, GB::G> + Sync + Send),
+    games: usize,
+    rules: DrawRules,
+) -> Throughput
+where
+    GB: GameBuilder + 'static,
+    GB::G: Hash + 'static,
+{
+    let colors = <GB::G as Game>::players();
+    let start = Instant::now();
+
+    let handles: Vec<_> = (0..games)
+        .map(|g| {
+            let mut seated: Vec<_> = colors.iter().map(|color| policy.create(*color)).collect();
+            let starting = colors[g % colors.len()];
+            let game_builder = game_builder.clone();
+
+            tokio::spawn(async move {
+                let mut game = game_builder.create(starting).await;
+                let mut draw = DrawDetector::new(rules);
+                let mut plies = 0;
+                while !game.is_finished() && !draw.record(position_hash(&game)) {
+                    let action = seated[turn_index(&game)]
+                        .play(&game)
+                        .await
+                        .expect("The policy should play a move in an unfinished game.");
+                    game.play(&action).await;
+                    plies += 1;
+                }
+                plies
+            })
+        })
+        .collect();
+
+    let mut plies = 0;
+    for handle in handles {
+        plies += handle.await.unwrap();
+    }
+    Throughput {
+        games,
+        plies,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Subsets of `k` elements of `0..n`, each in increasing order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    (k - 1..n)
+        .flat_map(|last| {
+            combinations(last, k - 1).into_iter().map(move |mut c| {
+                c.push(last);
+                c
+            })
+        })
+        .collect()
+}
+
+/// Fits the logistic (Bradley-Terry) model `P(i beats j) = 1 / (1 + exp(r_j - r_i))` to the
+/// scores by maximum likelihood.
+///
+/// Returns the ratings, centered around zero, and their variances
+/// estimated from the diagonal of the Fisher information.
+fn bradley_terry(score: &[Vec<f32>], count: &[Vec<f32>]) -> (Vec<f32>, Vec<f32>) {
+    let n = score.len();
+    let mut ratings = vec![0.; n];
+    let mut information = vec![0.; n];
+
+    for _ in 0..1000 {
+        let mut max_step: f32 = 0.;
+        for i in 0..n {
+            let mut gradient = 0.;
+            information[i] = 0.;
+            for j in (0..n).filter(|j| *j != i) {
+                let games = count[i][j] + PRIOR_DRAWS;
+                let p = 1. / (1. + (ratings[j] - ratings[i]).exp());
+                gradient += score[i][j] + 0.5 * PRIOR_DRAWS - games * p;
+                information[i] += games * p * (1. - p);
+            }
+            if information[i] > 0. {
+                let step = gradient / information[i];
+                ratings[i] += step;
+                max_step = max_step.max(step.abs());
+            }
+        }
+
+        let mean = ratings.iter().sum::<f32>() / n as f32;
+        ratings.iter_mut().for_each(|r| *r -= mean);
+        if max_step < 1e-6 {
+            break;
+        }
+    }
+
+    let variances = information
+        .iter()
+        .map(|i| if *i > 0. { 1. / i } else { std::f32::INFINITY })
+        .collect();
+    (ratings, variances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tictactoe::{TicTacToe, TicTacToeBuilder};
+    use crate::policies::combinator::Verified;
+    use crate::policies::flat::Random;
+    use crate::policies::mcts::tests::uct_config;
+
+    #[test]
+    fn test_bradley_terry_symmetric() {
+        // half of the points each: equal ratings.
+        let score = vec![vec![0., 3.], vec![3., 0.]];
+        let count = vec![vec![0., 6.], vec![6., 0.]];
+        let (ratings, _) = bradley_terry(&score, &count);
+        assert!(ratings.iter().all(|r| r.abs() < 1e-4));
+
+        // all points: 3.5 out of 4 including the prior draw, that is odds of 7.
+        let score = vec![vec![0., 3.], vec![0., 0.]];
+        let count = vec![vec![0., 3.], vec![3., 0.]];
+        let (ratings, _) = bradley_terry(&score, &count);
+        assert!((ratings[0] - ratings[1] - 7f32.ln()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(combinations(3, 2), vec![vec![0, 1], vec![0, 2], vec![1, 2]]);
+        assert_eq!(combinations(5, 3).len(), 10);
+        assert!(combinations(5, 3)
+            .iter()
+            .all(|c| c.windows(2).all(|w| w[0] < w[1])));
+    }
+
+    #[tokio::test]
+    async fn test_dominant_policy_rated_higher() {
+        let uct = uct_config(500);
+        let players: Vec<Contender<TicTacToe>> = vec![
+            ("random".to_owned(), Box::new(Verified::new(Random {}))),
+            ("uct".to_owned(), Box::new(Verified::new(uct))),
+        ];
+
+        let table = arena(
+            players,
+            20,
+            TicTacToeBuilder::default(),
+            DrawRules::default(),
+        )
+        .await;
+        let uct = table.get("uct").unwrap();
+        let random = table.get("random").unwrap();
+        assert_eq!(table.ratings[0].name, "uct");
+        assert!(uct.elo > random.elo);
+        assert!(uct.wins > uct.losses);
+        assert_eq!(uct.wins + uct.draws + uct.losses, 20);
+        assert_eq!(uct.wins, random.losses);
+    }
+
+    #[tokio::test]
+    async fn test_benchmark() {
+        let throughput = benchmark(
+            TicTacToeBuilder::default(),
+            &Random {},
+            20,
+            DrawRules::default(),
+        )
+        .await;
+        assert_eq!(throughput.games, 20);
+        // a game lasts between 5 and 9 moves.
+        assert!(throughput.plies >= 100 && throughput.plies <= 180);
+        assert!(throughput.games_per_sec() > 0.);
+        assert!(throughput.plies_per_sec() > 0.);
+    }
+
+    #[tokio::test]
+    async fn test_tune_uct_weight() {
+        let uct = uct_config(100);
+        // with a huge weight, visits are spread evenly and the move played is arbitrary.
+        let values = [0.4, 100.];
+        let results = tune(
+            TicTacToeBuilder::default(),
+            uct,
+            "uct_weight",
+            &values,
+            Random {},
+            40,
+            DrawRules::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(results.len(), values.len());
+        for (result, value) in results.iter().zip(values.iter()) {
+            assert!((result.value - value).abs() < 1e-6);
+            assert_eq!(result.wins + result.draws + result.losses, 40);
+        }
+        assert!(results[0].win_rate() > results[1].win_rate());
+        assert!(results[0].win_rate() > 0.5);
+
+        let unknown = tune(
+            TicTacToeBuilder::default(),
+            uct,
+            "c_init",
+            &values,
+            Random {},
+            10,
+            DrawRules::default(),
+        )
+        .await;
+        assert!(unknown.is_err());
+    }
+}
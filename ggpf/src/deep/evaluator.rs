@@ -1,14 +1,17 @@
+use crate::deep::feature_cache::FeatureCache;
+use crate::deep::inference::{Inference, ThreadSafeModel};
+use crate::deep::metrics::MetricsRecorder;
 use crate::deep::tf;
 use crate::game;
 use crate::game::meta::simulated::DynamicsNetworkOutput;
+use crate::settings::StrError;
 
 use ndarray::Axis;
 use ndarray::{Array, ArrayBase, Dimension};
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::sync::{atomic::AtomicBool, RwLock};
 use std::{thread, time};
-use tensorflow::{Graph, Session, Tensor};
+use tensorflow::Tensor;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 use tokio::time::timeout_at;
@@ -16,6 +19,9 @@ use tokio::time::{Duration, Instant};
 
 const WARN_ON_GPU_UNDERUSAGE: bool = false;
 
+/// Maximum waiting time for a prediction, after which the evaluator is considered unavailable.
+const PREDICTION_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Takes a tensor and a way to send back the inference result for the prediction network.
 pub type PredictionEvaluatorChannel = (Tensor<f32>, oneshot::Sender<(Tensor<f32>, Tensor<f32>)>);
 /// Takes a tensor and a way to send back the inference result for the representation network.
@@ -28,7 +34,7 @@ pub type DynamicsEvaluatorChannel = (
 
 /*      HELPERS          */
 
-fn ndarray_to_tensor<D: Dimension>(arr: &Array<f32, D>) -> Tensor<f32> {
+pub(crate) fn ndarray_to_tensor<D: Dimension>(arr: &Array<f32, D>) -> Tensor<f32> {
     Tensor::new(&arr.shape().iter().map(|i| *i as u64).collect::<Vec<u64>>())
         .with_values(arr.as_slice().unwrap())
         .unwrap()
@@ -38,30 +44,90 @@ fn tensor_to_ndarray<D: Dimension>(tensor: Tensor<f32>, shape: D) -> Array<f32,
     ArrayBase::from_shape_vec(shape, tensor.to_vec()).unwrap()
 }
 
+/// Scalar network output, decoded from its support encoding if `support_size > 0`.
+/// `transform` is the `ε` of the value transform applied to the encoded scalar, if any.
+fn decode_scalar(tensor: &Tensor<f32>, support_size: usize, transform: Option<f32>) -> f32 {
+    if support_size > 0 {
+        tf::support_to_value(tensor, 1, support_size, transform)[0]
+    } else {
+        tensor[0]
+    }
+}
+
 /*      EVALUATORS       */
 
 /// Prediction evaluator
+///
+/// # Panics
+/// Panics if the evaluator is unavailable, see `try_prediction`.
 pub async fn prediction<G>(
-    mut sender: mpsc::Sender<PredictionEvaluatorChannel>,
+    sender: mpsc::Sender<PredictionEvaluatorChannel>,
     pov: G::Player,
     board: &G,
     support_size: usize,
+    transform: Option<f32>,
 ) -> (Array<f32, G::ActionDim>, f32)
 where
     G: game::Features,
 {
-    let board_tensor = ndarray_to_tensor(&board.state_to_feature(pov));
+    try_prediction(sender, pov, board, support_size, transform)
+        .await
+        .expect("The prediction evaluator is unavailable.")
+}
+
+/// Prediction evaluator, returning `None` if the evaluator task has stopped
+/// or doesn't answer in time.
+pub async fn try_prediction<G>(
+    sender: mpsc::Sender<PredictionEvaluatorChannel>,
+    pov: G::Player,
+    board: &G,
+    support_size: usize,
+    transform: Option<f32>,
+) -> Option<(Array<f32, G::ActionDim>, f32)>
+where
+    G: game::Features,
+{
+    let features = board.state_to_feature(pov);
+    prediction_from_features(sender, board, &features, support_size, transform).await
+}
+
+/// Prediction evaluator like `try_prediction`, reusing the features memoized in the cache.
+pub async fn try_cached_prediction<G>(
+    sender: mpsc::Sender<PredictionEvaluatorChannel>,
+    pov: G::Player,
+    board: &G,
+    support_size: usize,
+    transform: Option<f32>,
+    cache: &FeatureCache<G>,
+) -> Option<(Array<f32, G::ActionDim>, f32)>
+where
+    G: game::Features,
+{
+    let features = cache.get(board, pov);
+    prediction_from_features(sender, board, &features, support_size, transform).await
+}
+
+async fn prediction_from_features<G>(
+    mut sender: mpsc::Sender<PredictionEvaluatorChannel>,
+    board: &G,
+    features: &Array<f32, G::StateDim>,
+    support_size: usize,
+    transform: Option<f32>,
+) -> Option<(Array<f32, G::ActionDim>, f32)>
+where
+    G: game::Features,
+{
+    let board_tensor = ndarray_to_tensor(features);
     let (resp_tx, resp_rx) = oneshot::channel();
-    sender.send((board_tensor, resp_tx)).await.ok().unwrap();
-    let (policy_tensor, value_tensor) = resp_rx.await.unwrap();
+    sender.send((board_tensor, resp_tx)).await.ok()?;
+    let (policy_tensor, value_tensor) = tokio::time::timeout(PREDICTION_TIMEOUT, resp_rx)
+        .await
+        .ok()?
+        .ok()?;
     let ft = board.get_features();
     let policy = tensor_to_ndarray(policy_tensor, G::action_dimension(&ft));
-    let value = if support_size > 0 {
-        tf::support_to_value(&value_tensor, 1, support_size)[0]
-    } else {
-        value_tensor[0]
-    };
-    (policy, value)
+    let value = decode_scalar(&value_tensor, support_size, transform);
+    Some((policy, value))
 }
 
 /// Representation evaluator
@@ -89,6 +155,7 @@ pub async fn dynamics<G, H>(
     board: &Array<f32, H>,
     action: &Array<f32, G>,
     support_size: usize,
+    transform: Option<f32>,
 ) -> DynamicsNetworkOutput<H>
 where
     G: Dimension,
@@ -107,32 +174,126 @@ where
     let (next_board_tensor, reward) = resp_rx.await.unwrap();
 
     let repr_state = tensor_to_ndarray(next_board_tensor, board_dim);
-    let reward = if support_size > 0 {
-        tf::support_to_value(&reward, 1, support_size)[0]
-    } else {
-        reward[0]
-    };
+    let reward = decode_scalar(&reward, support_size, transform);
     DynamicsNetworkOutput { reward, repr_state }
 }
 
 use indicatif::ProgressBar;
 
+/// Smoothing factor of the batch statistics moving averages.
+const BATCH_STATS_SMOOTHING: f32 = 0.2;
+/// Average fill ratio above which the batch is considered too small.
+const BATCH_FULL_RATIO: f32 = 0.9;
+
+/// Batch size controller.
+///
+/// In adaptive mode, the batch grows while batches are filled and the round-trip latency
+/// stays below the target, and shrinks when the latency exceeds it.
+/// Otherwise the batch size is fixed to the capacity.
+#[derive(Debug, Clone)]
+pub struct BatchController {
+    capacity: usize,
+    target_latency: Option<Duration>,
+    batch_size: usize,
+    latency: f32,
+    fill_ratio: f32,
+}
+
+impl BatchController {
+    /// Create a controller for batches of at most `capacity` elements.
+    pub fn new(capacity: usize, target_latency: Option<Duration>) -> Self {
+        let batch_size = if target_latency.is_some() {
+            (capacity / 2).max(1)
+        } else {
+            capacity
+        };
+        BatchController {
+            capacity,
+            target_latency,
+            batch_size,
+            latency: 0.,
+            fill_ratio: 0.,
+        }
+    }
+
+    /// Number of requests after which the batch is evaluated.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Record an evaluated batch: its number of requests and the latency
+    /// between its first request and the answers.
+    pub fn update(&mut self, filled: usize, latency: Duration) {
+        let target = match self.target_latency {
+            Some(target) => target.as_secs_f32(),
+            None => return,
+        };
+
+        let alpha = BATCH_STATS_SMOOTHING;
+        self.latency = (1. - alpha) * self.latency + alpha * latency.as_secs_f32();
+        self.fill_ratio =
+            (1. - alpha) * self.fill_ratio + alpha * filled as f32 / self.batch_size as f32;
+
+        let step = (self.batch_size / 8).max(1);
+        if self.latency > target {
+            self.batch_size = self.batch_size.saturating_sub(step).max(1);
+        } else if self.fill_ratio > BATCH_FULL_RATIO {
+            self.batch_size = (self.batch_size + step).min(self.capacity);
+        }
+    }
+}
+
+/// Checks that the prediction network outputs `action_size` policy values per board,
+/// by evaluating a batch of null boards.
+pub fn check_prediction_shape(
+    model: &ThreadSafeModel,
+    batch_size: usize,
+    repr_size: usize,
+    action_size: usize,
+) -> Result<(), StrError> {
+    let (_, network) = model.as_ref();
+    let boards: Tensor<f32> = Tensor::new(&[batch_size as u64, repr_size as u64]);
+    let (policies, _) = network.read().unwrap().call_prediction(&boards);
+
+    if policies.len() == batch_size * action_size {
+        Ok(())
+    } else {
+        Err(StrError(format!(
+            "The prediction network outputs {} policy values for a batch of {} boards, \
+             but the game has {} actions ({} values expected).",
+            policies.len(),
+            batch_size,
+            action_size,
+            batch_size * action_size
+        )))
+    }
+}
+
 /// Prediction task
+///
+/// Stops when all the senders are dropped or when `shutdown` is set.
+/// If `target_latency` is set, the batch size adapts to reach it, up to `batch_size`.
+/// Batch fill ratios are recorded in `metrics` if given.
 pub async fn prediction_task(
     batch_size: usize,
     repr_size: usize,
     action_size: usize,
     support_size: usize,
-    tensorflow: Arc<(AtomicBool, RwLock<(Graph, Session)>)>,
+    target_latency: Option<Duration>,
+    model: ThreadSafeModel,
     mut receiver: mpsc::Receiver<PredictionEvaluatorChannel>,
+    shutdown: Arc<AtomicBool>,
     bb: Option<Arc<Box<ProgressBar>>>,
+    metrics: Option<Arc<MetricsRecorder>>,
 ) {
-    let (writer_lock, g_and_s) = tensorflow.as_ref();
+    let (writer_lock, network) = model.as_ref();
     log::info!("Starting prediction evaluator..");
 
     let mut repr_tensor: Tensor<f32> = Tensor::new(&[batch_size as u64, repr_size as u64]);
     let mut tx_buf = vec![];
     let mut idx = 0;
+    let mut controller = BatchController::new(batch_size, target_latency);
+    let mut batch_start = Instant::now();
 
     let mut last_time = Instant::now();
     let timeout = Duration::from_nanos(1_000_000_000 / 10_000);
@@ -141,14 +302,21 @@ pub async fn prediction_task(
     let last_warning_duration = Duration::from_secs(10);
 
     loop {
+        if shutdown.load(Ordering::Relaxed) {
+            log::info!("Stopping evaluator..");
+            return;
+        }
         let recv_result = timeout_at(last_time + timeout, receiver.recv()).await;
 
         let send_batch = match recv_result {
             Ok(Some((repr, tx))) => {
+                if idx == 0 {
+                    batch_start = Instant::now();
+                }
                 repr_tensor[idx * repr_size..(idx + 1) * repr_size].clone_from_slice(&repr);
                 tx_buf.push(tx);
                 idx += 1;
-                idx == batch_size
+                idx == controller.batch_size()
             }
             Err(_) => idx > 0,
             _ => return,
@@ -185,20 +353,21 @@ pub async fn prediction_task(
                 thread::sleep(time::Duration::from_millis(1));
             }
 
-            let (policies, values) = {
-                let (ref graph, ref session) = *g_and_s.read().unwrap();
-                tf::call_prediction(&session, &graph, &repr_tensor)
-            };
+            let (policies, values) = network.read().unwrap().call_prediction(&repr_tensor);
 
             if let Some(x) = bb.as_ref() {
                 x.inc(idx as u64);
             }
+            if let Some(metrics) = metrics.as_ref() {
+                metrics.record_batch(idx, controller.batch_size());
+            }
 
             for i in (0..idx).rev() {
                 let policy = Tensor::from(&policies[i * action_size..(i + 1) * action_size]);
                 let value = Tensor::from(&values[i * support_size..(i + 1) * support_size]);
                 tx_buf.pop().unwrap().send((policy, value)).unwrap();
             }
+            controller.update(idx, Instant::now() - batch_start);
             idx = 0;
             tx_buf.clear();
         }
@@ -207,15 +376,18 @@ pub async fn prediction_task(
 }
 
 /// Dynamics task
+///
+/// Stops when all the senders are dropped or when `shutdown` is set.
 pub async fn dynamics_task(
     batch_size: usize,
     repr_size: usize,
     action_size: usize,
     support_size: usize,
-    tensorflow: Arc<(AtomicBool, RwLock<(Graph, Session)>)>,
+    model: ThreadSafeModel,
     mut receiver: mpsc::Receiver<DynamicsEvaluatorChannel>,
+    shutdown: Arc<AtomicBool>,
 ) {
-    let (writer_lock, g_and_s) = tensorflow.as_ref();
+    let (writer_lock, network) = model.as_ref();
     log::info!("Starting dynamics evaluator..");
 
     let mut repr_tensor: Tensor<f32> = Tensor::new(&[batch_size as u64, repr_size as u64]);
@@ -232,6 +404,10 @@ pub async fn dynamics_task(
     let last_warning_duration = Duration::from_secs(10);
 
     loop {
+        if shutdown.load(Ordering::Relaxed) {
+            log::info!("Stopping evaluator..");
+            return;
+        }
         let recv_result = timeout_at(last_time + timeout, receiver.recv()).await;
 
         let send_batch = match recv_result {
@@ -278,10 +454,10 @@ pub async fn dynamics_task(
                 thread::sleep(time::Duration::from_millis(1));
             }
 
-            let (rewards, next_reprs) = {
-                let (ref graph, ref session) = *g_and_s.read().unwrap();
-                tf::call_dynamics(&session, &graph, &repr_tensor, &action_tensor)
-            };
+            let (rewards, next_reprs) = network
+                .read()
+                .unwrap()
+                .call_dynamics(&repr_tensor, &action_tensor);
 
             for i in (0..idx).rev() {
                 let next_repr = Tensor::from(&next_reprs[i * repr_size..(i + 1) * repr_size]);
@@ -296,14 +472,17 @@ pub async fn dynamics_task(
 }
 
 /// Representation task
+///
+/// Stops when all the senders are dropped or when `shutdown` is set.
 pub async fn representation_task(
     batch_size: usize,
     board_size: usize,
     repr_size: usize,
-    tensorflow: Arc<(AtomicBool, RwLock<(Graph, Session)>)>,
+    model: ThreadSafeModel,
     mut receiver: mpsc::Receiver<RepresentationEvaluatorChannel>,
+    shutdown: Arc<AtomicBool>,
 ) {
-    let (writer_lock, g_and_s) = tensorflow.as_ref();
+    let (writer_lock, network) = model.as_ref();
     log::info!(
         "Starting representation evaluator.. {}/{}",
         board_size,
@@ -321,6 +500,10 @@ pub async fn representation_task(
     let last_warning_duration = Duration::from_secs(10);
 
     loop {
+        if shutdown.load(Ordering::Relaxed) {
+            log::info!("Stopping evaluator..");
+            return;
+        }
         let recv_result = timeout_at(last_time + timeout, receiver.recv()).await;
 
         let send_batch = match recv_result {
@@ -365,10 +548,7 @@ pub async fn representation_task(
                 thread::sleep(time::Duration::from_millis(1));
             }
 
-            let reprs = {
-                let (ref graph, ref session) = *g_and_s.read().unwrap();
-                tf::call_representation(&session, &graph, &board_tensor)
-            };
+            let reprs = network.read().unwrap().call_representation(&board_tensor);
 
             for i in (0..idx).rev() {
                 let repr = Tensor::from(&reprs[i * repr_size..(i + 1) * repr_size]);
@@ -383,11 +563,11 @@ pub async fn representation_task(
 
 /// Evaluates a game state for PUCT - single batch
 pub fn prediction_evaluator_single<G: game::Features>(
-    session: &Session,
-    graph: &Graph,
+    model: &dyn Inference,
     pov: G::Player,
     board: &G,
     support_size: usize,
+    transform: Option<f32>,
 ) -> (Array<f32, G::ActionDim>, f32) {
     let ft = board.get_features();
     let input_dimensions = G::state_dimension(&ft);
@@ -405,25 +585,21 @@ pub fn prediction_evaluator_single<G: game::Features>(
     .with_values(&board.state_to_feature(pov).into_raw_vec())
     .unwrap();
 
-    let (policy_tensor, value_tensor) = tf::call_prediction(session, graph, &board_tensor);
+    let (policy_tensor, value_tensor) = model.call_prediction(&board_tensor);
 
     let policy = tensor_to_ndarray(policy_tensor, G::action_dimension(&ft));
-    let value = if support_size > 0 {
-        tf::support_to_value(&value_tensor, 1, support_size)[0]
-    } else {
-        value_tensor[0]
-    };
+    let value = decode_scalar(&value_tensor, support_size, transform);
     (policy, value)
 }
 
 /// Dynamics evaluator - single batch
 pub fn dynamics_evaluator_single<G: Dimension, H: Dimension>(
-    session: &Session,
-    graph: &Graph,
+    model: &dyn Inference,
     hidden_shape: H,
     board: Array<f32, H>,
     action: Array<f32, G>,
     support_size: usize,
+    transform: Option<f32>,
 ) -> DynamicsNetworkOutput<H> {
     let board_tensor = Tensor::new(
         &board
@@ -453,22 +629,16 @@ pub fn dynamics_evaluator_single<G: Dimension, H: Dimension>(
     .with_values(&action.into_raw_vec())
     .unwrap();
 
-    let (reward, next_board_tensor) =
-        tf::call_dynamics(session, graph, &board_tensor, &action_tensor);
+    let (reward, next_board_tensor) = model.call_dynamics(&board_tensor, &action_tensor);
 
     let repr_state = tensor_to_ndarray(next_board_tensor, hidden_shape);
-    let reward = if support_size > 0 {
-        tf::support_to_value(&reward, 1, support_size)[0]
-    } else {
-        reward[0]
-    };
+    let reward = decode_scalar(&reward, support_size, transform);
     DynamicsNetworkOutput { repr_state, reward }
 }
 
 /// State to representation for Muz - single batch
 pub fn representation_evaluator_single<G: Dimension, H: Dimension>(
-    session: &Session,
-    graph: &Graph,
+    model: &dyn Inference,
     hidden_shape: H,
     state: Array<f32, G>,
 ) -> Array<f32, H> {
@@ -486,6 +656,260 @@ pub fn representation_evaluator_single<G: Dimension, H: Dimension>(
     .with_values(&state.into_raw_vec())
     .unwrap();
 
-    let repr_board_tensor = tf::call_representation(session, graph, &board_tensor);
+    let repr_board_tensor = model.call_representation(&board_tensor);
     tensor_to_ndarray(repr_board_tensor, hidden_shape)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep::mock::{mock_model, MockModel};
+    use crate::policies::mcts::puct::tests::puct_config;
+    use crate::policies::mcts::puct::{AlphaZeroConfig, AlphaZeroEvaluators};
+    use crate::settings;
+    use futures::future::join_all;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Model with a policy head of `actions` outputs and a scalar value.
+    fn constant_model(actions: usize) -> ThreadSafeModel {
+        mock_model(move |_| (vec![0.; actions], 0.))
+    }
+
+    #[test]
+    fn test_check_prediction_shape() {
+        assert!(check_prediction_shape(&constant_model(75), 8, 100, 75).is_ok());
+
+        let error = check_prediction_shape(&constant_model(64), 8, 100, 75).unwrap_err();
+        assert!(error.0.contains("512 policy values"), "{}", error);
+        assert!(error.0.contains("75 actions"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_prediction_task_rectangular_board() {
+        // 7x6 board with 2 planes, one action per column.
+        let repr_size = ndarray::Ix3(7, 6, 2).size();
+        let action_size = ndarray::Ix1(7).size();
+        let (mut tx, rx) = mpsc::channel(4);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let task = tokio::spawn(prediction_task(
+            2,
+            repr_size,
+            action_size,
+            1,
+            None,
+            constant_model(action_size),
+            rx,
+            shutdown.clone(),
+            None,
+            None,
+        ));
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let board = Tensor::from(&vec![1.; 7 * 6 * 2][..]);
+        tx.send((board, resp_tx)).await.ok().unwrap();
+        let (policy, value) = resp_rx.await.unwrap();
+        assert_eq!(policy.len(), 7);
+        assert_eq!(value.len(), 1);
+
+        shutdown.store(true, Ordering::Relaxed);
+        task.await.unwrap();
+    }
+
+    /// Mean number of requests per batch evaluated by an adaptive `prediction_task` over
+    /// bursts of 64 requests separated by idle periods, after a warm-up of 10 bursts.
+    /// The model takes `model_latency` per batch of up to 16 boards.
+    async fn adaptive_batch_size(model_latency: Duration, target_latency: Duration) -> f32 {
+        let (capacity, repr_size) = (16, 4);
+        let boards = Arc::new(AtomicUsize::new(0));
+        let counter = boards.clone();
+        let model = mock_model(move |_| {
+            // the model evaluates the whole batch tensor, `capacity` boards per call.
+            if counter.fetch_add(1, Ordering::Relaxed) % capacity == 0 {
+                thread::sleep(model_latency);
+            }
+            (vec![0.25; 4], 0.)
+        });
+        let (tx, rx) = mpsc::channel(128);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let task = tokio::spawn(prediction_task(
+            capacity,
+            repr_size,
+            4,
+            1,
+            Some(target_latency),
+            model,
+            rx,
+            shutdown.clone(),
+            None,
+            None,
+        ));
+
+        let burst = || {
+            join_all((0..64).map(|_| {
+                let mut tx = tx.clone();
+                async move {
+                    let (resp_tx, resp_rx) = oneshot::channel();
+                    let board = Tensor::from(&[0.; 4][..]);
+                    tx.send((board, resp_tx)).await.ok().unwrap();
+                    resp_rx.await.unwrap();
+                }
+            }))
+        };
+        for _ in 0..10 {
+            burst().await;
+            tokio::time::delay_for(Duration::from_millis(5)).await;
+        }
+        boards.store(0, Ordering::Relaxed);
+        for _ in 0..4 {
+            burst().await;
+            tokio::time::delay_for(Duration::from_millis(5)).await;
+        }
+        let batches = boards.load(Ordering::Relaxed) / capacity;
+
+        shutdown.store(true, Ordering::Relaxed);
+        task.await.unwrap();
+        (4 * 64) as f32 / batches as f32
+    }
+
+    #[tokio::test]
+    async fn test_prediction_task_adaptive_batch() {
+        // a fast model: the batches grow to the capacity.
+        let batch_size =
+            adaptive_batch_size(Duration::from_micros(100), Duration::from_secs(1)).await;
+        assert!(batch_size > 12. && batch_size <= 16., "{}", batch_size);
+
+        // a model slower than the target latency: the batches shrink to a single request.
+        let batch_size =
+            adaptive_batch_size(Duration::from_millis(2), Duration::from_millis(1)).await;
+        assert!(batch_size >= 1. && batch_size < 2., "{}", batch_size);
+    }
+
+    #[test]
+    fn test_batch_controller_bursts() {
+        // simulated model: 0.5ms overhead and 0.1ms per request.
+        let model_latency =
+            |batch: usize| Duration::from_micros(500) + Duration::from_micros(100) * batch as u32;
+        let target = Duration::from_millis(5);
+        let mut controller = BatchController::new(128, Some(target));
+
+        for _ in 0..10 {
+            let mut sizes = vec![];
+            for _ in 0..50 {
+                let batch_size = controller.batch_size();
+                controller.update(batch_size, model_latency(batch_size));
+                sizes.push(batch_size);
+            }
+            // 45 requests per batch reach the target latency.
+            assert!(sizes[20..].iter().all(|size| *size >= 30 && *size <= 60));
+
+            // idle period: batches are flushed with a few requests.
+            for _ in 0..50 {
+                controller.update(3, model_latency(3));
+                assert!(controller.batch_size() >= 1 && controller.batch_size() <= 128);
+            }
+        }
+
+        let mut fixed = BatchController::new(128, None);
+        fixed.update(3, Duration::from_secs(1));
+        assert_eq!(fixed.batch_size(), 128);
+    }
+
+    #[tokio::test]
+    async fn test_evaluators_shutdown() {
+        let config = AlphaZeroConfig {
+            n_playouts: 1,
+            playouts_schedule: vec![],
+            puct: puct_config(1.),
+            value_target: settings::ValueTarget::RootValue,
+            network_path: concat!(env!("CARGO_MANIFEST_DIR"), "/src/deep/testdata/identity").into(),
+            board_shape: ndarray::Ix1(4),
+            action_shape: ndarray::Ix1(4),
+            watch_models: false,
+            batch_size: 1,
+            target_latency: None,
+            draw_rules: game::DrawRules::default(),
+            backend: settings::Backend::Onnx,
+            signature: settings::ModelSignature::default(),
+        };
+        let mut evaluators = AlphaZeroEvaluators::new(config, true);
+        let mut channel = evaluators.get_channel();
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let board = Tensor::from(&[1., 0., 0., 0.][..]);
+        channel.send((board, resp_tx)).await.ok().unwrap();
+        let (policy, _) = resp_rx.await.unwrap();
+        assert_eq!(&policy[..], &[1., 0., 0., 0.]);
+
+        evaluators.shutdown().await;
+        let (resp_tx, _) = oneshot::channel();
+        let board = Tensor::from(&[0.; 4][..]);
+        assert!(channel.send((board, resp_tx)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_swap_model() {
+        let network_path: String =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/src/deep/testdata/identity").into();
+        let config = AlphaZeroConfig {
+            n_playouts: 1,
+            playouts_schedule: vec![],
+            puct: puct_config(1.),
+            value_target: settings::ValueTarget::RootValue,
+            network_path: network_path.clone(),
+            board_shape: ndarray::Ix1(4),
+            action_shape: ndarray::Ix1(4),
+            watch_models: false,
+            batch_size: 1,
+            target_latency: None,
+            draw_rules: game::DrawRules::default(),
+            backend: settings::Backend::Onnx,
+            signature: settings::ModelSignature::default(),
+        };
+        let mut evaluators = AlphaZeroEvaluators::new(config, true);
+        let channel = evaluators.get_channel();
+
+        let predict = |board: [f32; 4]| {
+            let (resp_tx, resp_rx) = oneshot::channel();
+            let board = Tensor::from(&board[..]);
+            let mut channel = channel.clone();
+            async move {
+                channel.send((board, resp_tx)).await.ok().unwrap();
+                let (policy, _) = resp_rx.await.unwrap();
+                policy.to_vec()
+            }
+        };
+
+        assert_eq!(predict([1., 0., 0., 0.]).await, vec![1., 0., 0., 0.]);
+        // a model answering the reversed board as the policy.
+        evaluators.swap_network(Box::new(MockModel::new(|board: &[f32]| {
+            (board.iter().rev().cloned().collect(), 0.)
+        })));
+        assert_eq!(predict([1., 0., 0., 0.]).await, vec![0., 0., 0., 1.]);
+        evaluators.swap_model(&network_path);
+        assert_eq!(predict([1., 0., 0., 0.]).await, vec![1., 0., 0., 0.]);
+
+        evaluators.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_evaluators_shape_mismatch() {
+        // the identity model has 4 policy outputs.
+        let config = AlphaZeroConfig {
+            n_playouts: 1,
+            playouts_schedule: vec![],
+            puct: puct_config(1.),
+            value_target: settings::ValueTarget::RootValue,
+            network_path: concat!(env!("CARGO_MANIFEST_DIR"), "/src/deep/testdata/identity").into(),
+            board_shape: ndarray::Ix1(4),
+            action_shape: ndarray::Ix1(5),
+            watch_models: false,
+            batch_size: 1,
+            target_latency: None,
+            draw_rules: game::DrawRules::default(),
+            backend: settings::Backend::Onnx,
+            signature: settings::ModelSignature::default(),
+        };
+        let error = AlphaZeroEvaluators::try_new(config, true).err().unwrap();
+        assert!(error.0.contains("5 actions"), "{}", error);
+    }
+}
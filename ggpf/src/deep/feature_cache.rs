@@ -0,0 +1,164 @@
+use crate::game::{Features, Game};
+
+use ndarray::Array;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+type Key<G> = (u64, <G as Game>::Player);
+
+struct Lru<G: Features> {
+    entries: HashMap<Key<G>, (Arc<Array<f32, G::StateDim>>, u64)>,
+    /// Keys by last access.
+    order: BTreeMap<u64, Key<G>>,
+    tick: u64,
+    hits: usize,
+    misses: usize,
+}
+
+/// Least recently used cache of state features, keyed by `Features::state_hash` and
+/// point of view.
+///
+/// The cache can be shared between the workers of a search.
+pub struct FeatureCache<G: Features> {
+    capacity: usize,
+    lru: Mutex<Lru<G>>,
+}
+
+impl<G: Features> FeatureCache<G> {
+    /// Creates an empty cache holding at most `capacity` feature arrays.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "The cache capacity should be positive.");
+        FeatureCache {
+            capacity,
+            lru: Mutex::new(Lru {
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+                tick: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// Features of the state, computed with `state_to_feature` if they are not in the cache.
+    pub fn get(&self, board: &G, pov: G::Player) -> Arc<Array<f32, G::StateDim>> {
+        let key = (board.state_hash(), pov);
+        let mut guard = self.lru.lock().unwrap();
+        let lru = &mut *guard;
+        lru.tick += 1;
+        let tick = lru.tick;
+
+        if let Some((features, last_access)) = lru.entries.get_mut(&key) {
+            let features = features.clone();
+            let last_access = std::mem::replace(last_access, tick);
+            lru.order.remove(&last_access);
+            lru.order.insert(tick, key);
+            lru.hits += 1;
+            return features;
+        }
+
+        // the lock is held during featurization, so that concurrent requests for the
+        // same state don't featurize it twice.
+        let features = Arc::new(board.state_to_feature(pov));
+        if lru.entries.len() == self.capacity {
+            let (&oldest, _) = lru.order.iter().next().unwrap();
+            let evicted = lru.order.remove(&oldest).unwrap();
+            lru.entries.remove(&evicted);
+        }
+        lru.entries.insert(key, (features.clone(), tick));
+        lru.order.insert(tick, key);
+        lru.misses += 1;
+        features
+    }
+
+    /// Removes all the cached features. Hits and misses are still counted.
+    pub fn clear(&self) {
+        let mut lru = self.lru.lock().unwrap();
+        lru.entries.clear();
+        lru.order.clear();
+    }
+
+    /// Number of featurizations served from the cache.
+    pub fn hits(&self) -> usize {
+        self.lru.lock().unwrap().hits
+    }
+
+    /// Number of featurizations computed.
+    pub fn misses(&self) -> usize {
+        self.lru.lock().unwrap().misses
+    }
+
+    /// Number of cached feature arrays.
+    pub fn len(&self) -> usize {
+        self.lru.lock().unwrap().entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+    use crate::game::{Base, GameBuilder, Playable};
+    use rand::seq::SliceRandom;
+
+    /// States of a random game, from the initial position.
+    async fn random_game(size: usize) -> Vec<Breakthrough> {
+        let mut state = BreakthroughBuilder { size }.create(Color::Black).await;
+        let mut states = vec![state.clone()];
+        while !state.is_finished() {
+            let action = *state
+                .possible_moves()
+                .choose(&mut rand::thread_rng())
+                .unwrap();
+            state.play(&action).await;
+            states.push(state.clone());
+        }
+        states
+    }
+
+    #[tokio::test]
+    async fn test_cached_features_identical() {
+        let states = random_game(6).await;
+        let cache = FeatureCache::new(states.len() * 2);
+
+        for _ in 0..2 {
+            for state in states.iter() {
+                for pov in &[Color::Black, Color::White] {
+                    let cached = cache.get(state, *pov);
+                    let uncached = state.state_to_feature(*pov);
+                    assert_eq!(cached.shape(), uncached.shape());
+                    assert!(cached
+                        .iter()
+                        .zip(uncached.iter())
+                        .all(|(a, b)| a.to_bits() == b.to_bits()));
+                }
+            }
+        }
+        assert_eq!(cache.misses(), states.len() * 2);
+        assert_eq!(cache.hits(), states.len() * 2);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_evicted() {
+        let states = random_game(5).await;
+        let cache = FeatureCache::new(2);
+
+        cache.get(&states[0], Color::Black);
+        cache.get(&states[1], Color::Black);
+        cache.get(&states[0], Color::Black);
+        cache.get(&states[2], Color::Black);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.misses(), 3);
+
+        // states[1] was the least recently used.
+        cache.get(&states[0], Color::Black);
+        assert_eq!(cache.misses(), 3);
+        cache.get(&states[1], Color::Black);
+        assert_eq!(cache.misses(), 4);
+    }
+}
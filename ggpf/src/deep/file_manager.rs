@@ -1,6 +1,7 @@
+use crate::deep::inference;
 use crate::deep::self_play::GameHistoryEntry;
-use crate::deep::tf;
 use crate::game;
+use crate::settings::{Backend, ModelSignature};
 
 use nix::sys::stat;
 use nix::unistd::mkfifo;
@@ -10,13 +11,9 @@ use std::fs::create_dir_all;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::Path;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering;
 use std::sync::mpsc::channel;
-use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::Duration;
-use tensorflow::{Graph, Session};
 
 /// File manager.
 pub struct FileManager {
@@ -49,6 +46,7 @@ impl FileManager {
         result.insert("state", game.state.into_raw_vec());
         result.insert("policy", game.policy.into_raw_vec());
         result.insert("value", game.value.into_raw_vec());
+        result.insert("target_value", game.target_value.into_raw_vec());
         result.insert("action", game.action.into_raw_vec());
         result.insert("reward", game.reward.into_raw_vec());
 
@@ -59,7 +57,12 @@ impl FileManager {
 }
 
 /// Watch a path for changes and reload the model when content has been modified.
-pub fn watch_model(tf: Arc<(AtomicBool, RwLock<(Graph, Session)>)>, path: &str) {
+pub fn watch_model(
+    model: inference::ThreadSafeModel,
+    backend: Backend,
+    signature: ModelSignature,
+    path: &str,
+) {
     let p: String = path.into();
 
     thread::spawn(move || {
@@ -76,15 +79,8 @@ pub fn watch_model(tf: Arc<(AtomicBool, RwLock<(Graph, Session)>)>, path: &str)
         loop {
             match rx.recv() {
                 Ok(_) => {
-                    let (global_lock, tf_network) = tf.as_ref();
                     log::info!("Updating model.. {}", p);
-                    global_lock.store(true, Ordering::Relaxed);
-                    let mut tf_network = tf_network.write().unwrap();
-                    global_lock.store(false, Ordering::Relaxed);
-                    let (ref _graph, ref mut session_m) = *tf_network;
-                    session_m.close().expect("Unable to close the session.");
-
-                    *tf_network = tf::load_model(&p);
+                    inference::load_checkpoint(&model, backend, &signature, &p);
                     log::info!("Model successfully updated!");
                 }
                 Err(e) => println!("watch error: {:?}", e),
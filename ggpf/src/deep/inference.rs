@@ -0,0 +1,100 @@
+use crate::deep::{onnx, tf};
+use crate::settings::{Backend, ModelSignature, StrError};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tensorflow::Tensor;
+
+/// Neural network model inference, independently from the backend.
+///
+/// Tensors are batched: the first dimension is the batch size.
+pub trait Inference: Send + Sync {
+    /// Use prediction network inference: returns the policy and the value.
+    fn call_prediction(&self, board: &Tensor<f32>) -> (Tensor<f32>, Tensor<f32>);
+
+    /// Use dynamics network inference: returns the reward and the next hidden state.
+    fn call_dynamics(
+        &self,
+        board: &Tensor<f32>,
+        action: &Tensor<f32>,
+    ) -> (Tensor<f32>, Tensor<f32>);
+
+    /// Use representation network inference: returns the hidden state.
+    fn call_representation(&self, board: &Tensor<f32>) -> Tensor<f32>;
+}
+
+/// Access to a model behind Arc and RwLock
+/// the AtomicBool is here to indicate the file loader's intention
+/// to access the lock.
+pub type ThreadSafeModel = Arc<(AtomicBool, RwLock<Box<dyn Inference>>)>;
+
+/// Load a model using the given backend, with the inputs and outputs named by `signature`.
+///
+/// Fails if the model can't be found, or if an input or output of the signature is missing.
+pub fn try_load_model(
+    backend: Backend,
+    signature: &ModelSignature,
+    path: &str,
+) -> Result<Box<dyn Inference>, StrError> {
+    Ok(match backend {
+        Backend::TensorFlow => Box::new(tf::load_model(path, signature)?),
+        Backend::Onnx => Box::new(onnx::load_model(path, signature)?),
+    })
+}
+
+/// Load a model using the given backend, like `try_load_model`.
+///
+/// # Panics
+/// Panics if the model can't be loaded.
+pub fn load_model(backend: Backend, signature: &ModelSignature, path: &str) -> Box<dyn Inference> {
+    try_load_model(backend, signature, path).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Load a model using the given backend, ready to be shared between evaluators.
+pub fn try_load_thread_safe_model(
+    backend: Backend,
+    signature: &ModelSignature,
+    path: &str,
+) -> Result<ThreadSafeModel, StrError> {
+    Ok(Arc::new((
+        AtomicBool::new(false),
+        RwLock::new(try_load_model(backend, signature, path)?),
+    )))
+}
+
+/// Load a model like `try_load_thread_safe_model`.
+///
+/// # Panics
+/// Panics if the model can't be loaded.
+pub fn load_thread_safe_model(
+    backend: Backend,
+    signature: &ModelSignature,
+    path: &str,
+) -> ThreadSafeModel {
+    try_load_thread_safe_model(backend, signature, path).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Replace the shared model by `network`.
+///
+/// The writer flag is raised while waiting for the lock, so that evaluators stop
+/// taking read locks in the meantime.
+pub fn swap_model(model: &ThreadSafeModel, network: Box<dyn Inference>) {
+    let (writer_lock, current) = model.as_ref();
+    writer_lock.store(true, Ordering::Relaxed);
+    let mut current = current.write().unwrap();
+    writer_lock.store(false, Ordering::Relaxed);
+    *current = network;
+}
+
+/// Load the checkpoint at `path` in place of the shared model.
+///
+/// The checkpoint is loaded before taking the lock: evaluators keep using the previous
+/// model until then.
+pub fn load_checkpoint(
+    model: &ThreadSafeModel,
+    backend: Backend,
+    signature: &ModelSignature,
+    path: &str,
+) {
+    swap_model(model, load_model(backend, signature, path));
+}
@@ -0,0 +1,126 @@
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+/// Self-play statistics over a reporting period.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Metrics {
+    /// Number of games generated during the period.
+    pub games: usize,
+    /// Games generated per second.
+    pub games_per_sec: f32,
+    /// Mean length of the principal variation of the searches.
+    pub search_depth: f32,
+    /// Mean ratio of the prediction batches that was filled when sent to the model.
+    pub batch_fill: f32,
+    /// Mean squared error between the root values and the value targets.
+    pub value_error: f32,
+    /// Mean entropy of the root visit distributions.
+    pub visit_entropy: f32,
+    /// Mean KL divergence between the root visit distributions and the network priors.
+    pub prior_divergence: f32,
+}
+
+#[derive(Default)]
+struct Accumulator {
+    games: usize,
+    searches: usize,
+    depth: usize,
+    batches: usize,
+    fill: f32,
+    positions: usize,
+    squared_error: f32,
+    diagnosed: usize,
+    entropy: f32,
+    divergence: f32,
+}
+
+/// Statistics shared by the generator and evaluator tasks, until they are reported.
+#[derive(Default)]
+pub struct MetricsRecorder {
+    acc: Mutex<Accumulator>,
+}
+
+impl MetricsRecorder {
+    /// Records a generated game, given the principal variation length of each search,
+    /// the root values and the value targets.
+    pub fn record_game(&self, depths: &[usize], root_values: &[f32], targets: &[f32]) {
+        let mut acc = self.acc.lock().unwrap();
+        acc.games += 1;
+        acc.searches += depths.len();
+        acc.depth += depths.iter().sum::<usize>();
+        acc.positions += root_values.len();
+        acc.squared_error += root_values
+            .iter()
+            .zip(targets.iter())
+            .map(|(v, t)| (v - t) * (v - t))
+            .sum::<f32>();
+    }
+
+    /// Records the visit distribution entropy and the prior divergence of a search.
+    pub fn record_search(&self, entropy: f32, divergence: f32) {
+        let mut acc = self.acc.lock().unwrap();
+        acc.diagnosed += 1;
+        acc.entropy += entropy;
+        acc.divergence += divergence;
+    }
+
+    /// Records a prediction batch of `filled` requests out of `capacity`.
+    pub fn record_batch(&self, filled: usize, capacity: usize) {
+        let mut acc = self.acc.lock().unwrap();
+        acc.batches += 1;
+        acc.fill += filled as f32 / capacity as f32;
+    }
+
+    /// Metrics of the recorded statistics over `elapsed`, resetting them.
+    pub fn take(&self, elapsed: Duration) -> Metrics {
+        let acc = std::mem::take(&mut *self.acc.lock().unwrap());
+        let mean = |total: f32, n: usize| if n > 0 { total / n as f32 } else { 0. };
+        Metrics {
+            games: acc.games,
+            games_per_sec: acc.games as f32 / elapsed.as_secs_f32().max(1e-6),
+            search_depth: mean(acc.depth as f32, acc.searches),
+            batch_fill: mean(acc.fill, acc.batches),
+            value_error: mean(acc.squared_error, acc.positions),
+            visit_entropy: mean(acc.entropy, acc.diagnosed),
+            prior_divergence: mean(acc.divergence, acc.diagnosed),
+        }
+    }
+}
+
+/// Sends the metrics of `recorder` to `sender` every `period`.
+///
+/// Stops when the receiver is dropped.
+pub async fn metrics_task(
+    recorder: Arc<MetricsRecorder>,
+    period: Duration,
+    mut sender: mpsc::Sender<Metrics>,
+) {
+    let mut last_time = Instant::now();
+    loop {
+        tokio::time::delay_until(last_time + period).await;
+        let now = Instant::now();
+        let metrics = recorder.take(now - last_time);
+        last_time = now;
+        if sender.send(metrics).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Prints the received metrics on the standard output.
+pub async fn stdout_reporter(mut receiver: mpsc::Receiver<Metrics>) {
+    while let Some(metrics) = receiver.recv().await {
+        println!(
+            "{} games ({:.2}/s), search depth {:.1}, batch fill {:.0}%, value error {:.3}, \
+             visit entropy {:.2}, prior divergence {:.3}",
+            metrics.games,
+            metrics.games_per_sec,
+            metrics.search_depth,
+            100. * metrics.batch_fill,
+            metrics.value_error,
+            metrics.visit_entropy,
+            metrics.prior_divergence
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::deep::evaluator::{
+    ndarray_to_tensor, DynamicsEvaluatorChannel, PredictionEvaluatorChannel,
+    RepresentationEvaluatorChannel,
+};
+use crate::deep::inference::{Inference, ThreadSafeModel};
+use crate::game::Features;
+
+use ndarray::{Array, IxDyn};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock};
+use tensorflow::Tensor;
+use tokio::sync::mpsc;
+
+/// Capacity of the mock evaluators request queues.
+const MOCK_CHANNEL_CAPACITY: usize = 16;
+
+fn tensor_to_dyn(tensor: &Tensor<f32>) -> Array<f32, IxDyn> {
+    let shape: Vec<usize> = tensor.dims().iter().map(|i| *i as usize).collect();
+    Array::from_shape_vec(IxDyn(&shape), tensor.to_vec()).unwrap()
+}
+
+/// Model backed by `prediction_fn` instead of a neural network, to be run by the
+/// evaluator tasks.
+///
+/// `prediction_fn` maps the features of a board to its policy and value. The hidden
+/// state of a board is the board itself, and the dynamics keep it with a null reward.
+pub struct MockModel<F> {
+    prediction_fn: F,
+}
+
+impl<F> MockModel<F>
+where
+    F: Fn(&[f32]) -> (Vec<f32>, f32) + Send + Sync,
+{
+    /// Model evaluating each board of a batch with `prediction_fn`.
+    pub fn new(prediction_fn: F) -> Self {
+        MockModel { prediction_fn }
+    }
+}
+
+impl<F> Inference for MockModel<F>
+where
+    F: Fn(&[f32]) -> (Vec<f32>, f32) + Send + Sync,
+{
+    fn call_prediction(&self, board: &Tensor<f32>) -> (Tensor<f32>, Tensor<f32>) {
+        let batch_size = board.dims()[0];
+        let mut policies = vec![];
+        let mut values = vec![];
+        if batch_size > 0 {
+            for features in board.chunks(board.len() / batch_size as usize) {
+                let (policy, value) = (self.prediction_fn)(features);
+                policies.extend(policy);
+                values.push(value);
+            }
+        }
+        let action_size = policies.len() as u64 / batch_size.max(1);
+        (
+            Tensor::new(&[batch_size, action_size])
+                .with_values(&policies)
+                .unwrap(),
+            Tensor::new(&[batch_size, 1]).with_values(&values).unwrap(),
+        )
+    }
+
+    fn call_dynamics(&self, board: &Tensor<f32>, _: &Tensor<f32>) -> (Tensor<f32>, Tensor<f32>) {
+        (Tensor::new(&[board.dims()[0], 1]), copy_tensor(board))
+    }
+
+    fn call_representation(&self, board: &Tensor<f32>) -> Tensor<f32> {
+        copy_tensor(board)
+    }
+}
+
+fn copy_tensor(tensor: &Tensor<f32>) -> Tensor<f32> {
+    Tensor::new(tensor.dims()).with_values(tensor).unwrap()
+}
+
+/// Shared model backed by `prediction_fn`, as described by `MockModel`.
+pub fn mock_model<F>(prediction_fn: F) -> ThreadSafeModel
+where
+    F: Fn(&[f32]) -> (Vec<f32>, f32) + Send + Sync + 'static,
+{
+    Arc::new((
+        AtomicBool::new(false),
+        RwLock::new(Box::new(MockModel::new(prediction_fn))),
+    ))
+}
+
+/// Prediction evaluator backed by `eval_fn` instead of a neural network.
+///
+/// `eval_fn` maps the features of a state (as given by `state_to_feature`) to the
+/// policy over the actions and the value of the state for the player to move.
+/// The returned channel can be given to a `PUCT` builder.
+pub fn mock_alphazero_channel<G, F>(eval_fn: F) -> mpsc::Sender<PredictionEvaluatorChannel>
+where
+    G: Features + 'static,
+    F: Fn(&Array<f32, G::StateDim>) -> (Array<f32, G::ActionDim>, f32) + Send + 'static,
+{
+    spawn_prediction_channel::<G, _>(eval_fn, None)
+}
+
+/// Prediction evaluator answering with `eval_fn`, and stopping after `answered`
+/// requests if given.
+fn spawn_prediction_channel<G, F>(
+    eval_fn: F,
+    answered: Option<usize>,
+) -> mpsc::Sender<PredictionEvaluatorChannel>
+where
+    G: Features + 'static,
+    F: Fn(&Array<f32, G::StateDim>) -> (Array<f32, G::ActionDim>, f32) + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::channel::<PredictionEvaluatorChannel>(MOCK_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        let mut remaining = answered;
+        while remaining != Some(0) {
+            let (state, resp) = match rx.recv().await {
+                Some(request) => request,
+                None => break,
+            };
+            let features = tensor_to_dyn(&state)
+                .into_dimensionality::<G::StateDim>()
+                .expect("Features should have the game state dimension.");
+            let (policy, value) = eval_fn(&features);
+            let _ = resp.send((ndarray_to_tensor(&policy), Tensor::from(&[value][..])));
+            remaining = remaining.map(|n| n - 1);
+        }
+    });
+    tx
+}
+
+fn uniform_prediction<G: Features>(
+    descriptor: &G::Descriptor,
+) -> impl Fn(&Array<f32, G::StateDim>) -> (Array<f32, G::ActionDim>, f32) {
+    let action_dimension = G::action_dimension(descriptor);
+    let action_size = G::num_actions(descriptor);
+    move |_| {
+        (
+            Array::from_elem(action_dimension.clone(), 1. / action_size as f32),
+            0.,
+        )
+    }
+}
+
+/// Prediction evaluator answering a uniform policy and a null value.
+pub fn mock_uniform_channel<G: Features + 'static>(
+    descriptor: &G::Descriptor,
+) -> mpsc::Sender<PredictionEvaluatorChannel> {
+    spawn_prediction_channel::<G, _>(uniform_prediction::<G>(descriptor), None)
+}
+
+/// Prediction evaluator answering a uniform policy to the first `answered` requests,
+/// then stopping as if its task had died.
+pub fn mock_failing_channel<G: Features + 'static>(
+    descriptor: &G::Descriptor,
+    answered: usize,
+) -> mpsc::Sender<PredictionEvaluatorChannel> {
+    spawn_prediction_channel::<G, _>(uniform_prediction::<G>(descriptor), Some(answered))
+}
+
+/// Representation evaluator backed by `repr_fn`, mapping a state to its hidden state.
+pub fn mock_representation_channel<F>(repr_fn: F) -> mpsc::Sender<RepresentationEvaluatorChannel>
+where
+    F: Fn(&Array<f32, IxDyn>) -> Array<f32, IxDyn> + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::channel::<RepresentationEvaluatorChannel>(MOCK_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        while let Some((state, resp)) = rx.recv().await {
+            let hidden = repr_fn(&tensor_to_dyn(&state));
+            let _ = resp.send(ndarray_to_tensor(&hidden));
+        }
+    });
+    tx
+}
+
+/// Dynamics evaluator backed by `dynamics_fn`, mapping a hidden state and an action
+/// to the next hidden state and the reward.
+pub fn mock_dynamics_channel<F>(dynamics_fn: F) -> mpsc::Sender<DynamicsEvaluatorChannel>
+where
+    F: Fn(&Array<f32, IxDyn>, &Array<f32, IxDyn>) -> (Array<f32, IxDyn>, f32) + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::channel::<DynamicsEvaluatorChannel>(MOCK_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(((hidden, action), resp)) = rx.recv().await {
+            let (next_hidden, reward) =
+                dynamics_fn(&tensor_to_dyn(&hidden), &tensor_to_dyn(&action));
+            let _ = resp.send((ndarray_to_tensor(&next_hidden), Tensor::from(&[reward][..])));
+        }
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::Color;
+    use crate::game::tictactoe::{TicTacToe, TicTacToeBuilder};
+    use crate::game::{self, GameBuilder, SingleWinner};
+    use crate::policies::combinator::Verified;
+    use crate::policies::flat::RandomPolicy;
+    use crate::policies::mcts::puct::PUCT;
+    use crate::policies::MultiplayerPolicyBuilder;
+    use crate::settings;
+
+    #[tokio::test]
+    async fn test_uniform_puct_beats_random() {
+        let config = settings::PUCT {
+            discount: 1.,
+            c_base: 19652.,
+            c_init: 1.25,
+            root_dirichlet_alpha: 0.3,
+            root_exploration_fraction: 0.25,
+            add_exploration_noise: false,
+            scale_dirichlet_alpha: false,
+            value_support: None,
+            gumbel_scale: None,
+            gumbel_considered_actions: 16,
+            parallel_playouts: 1,
+            batch_expansions: false,
+            early_stop: None,
+            time_budget_ms: None,
+            feature_cache: 0,
+            value_transform: None,
+            fpu: 0.,
+            fpu_reduction: None,
+            log_search: false,
+        };
+        let puct = Verified::new(PUCT {
+            config,
+            n_playouts: 200,
+            playouts_schedule: vec![],
+            prediction_channel: mock_uniform_channel::<TicTacToe>(&()),
+        });
+        let players = [Color::Black, Color::White];
+
+        let n_games = 20;
+        let (mut puct_wins, mut random_wins) = (0, 0);
+        for i in 0..n_games {
+            let puct_color = players[i % 2];
+            let mut board = TicTacToeBuilder::default()
+                .create(players[(i / 2) % 2])
+                .await;
+
+            let p_puct = Box::new(MultiplayerPolicyBuilder::<TicTacToe>::create(
+                &puct, puct_color,
+            ));
+            let p_random = Box::new(RandomPolicy {});
+            if puct_color == Color::Black {
+                game::simulate(p_puct, p_random, &mut board, game::DrawRules::default()).await;
+            } else {
+                game::simulate(p_random, p_puct, &mut board, game::DrawRules::default()).await;
+            }
+
+            match board.winner() {
+                Some(winner) if winner == puct_color => puct_wins += 1,
+                Some(_) => random_wins += 1,
+                None => (),
+            }
+        }
+        assert!(
+            puct_wins > n_games / 2 && random_wins < puct_wins,
+            "PUCT won {}/{}, lost {}",
+            puct_wins,
+            n_games,
+            random_wins
+        );
+    }
+}
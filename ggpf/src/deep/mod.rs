@@ -1,7 +1,21 @@
 /// Neural networks evaluators.
 pub mod evaluator;
+/// Memoization of state features.
+pub mod feature_cache;
 /// File output channel.
 pub mod file_manager;
+/// Backend-independent model inference.
+pub mod inference;
+/// Self-play throughput metrics.
+pub mod metrics;
+/// Evaluators backed by closures, to run searches without a model.
+pub mod mock;
+/// ONNX Runtime helpers.
+pub mod onnx;
+/// Game histories serialization.
+pub mod replay;
+/// Bounded replay buffer with prioritized sampling.
+pub mod replay_buffer;
 /// Self-play generation.
 pub mod self_play;
 /// TensorFlow helpers.
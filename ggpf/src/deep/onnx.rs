@@ -0,0 +1,254 @@
+use crate::deep::inference::Inference;
+use crate::settings::{ModelSignature, StrError};
+
+use lazy_static::lazy_static;
+use ndarray::{Array, IxDyn};
+use onnxruntime::environment::Environment;
+use onnxruntime::session::Session;
+use onnxruntime::tensor::OrtOwnedTensor;
+use onnxruntime::GraphOptimizationLevel;
+use std::path::Path;
+use std::sync::Mutex;
+use tensorflow::Tensor;
+
+lazy_static! {
+    static ref ENVIRONMENT: Environment = Environment::builder()
+        .with_name("ggpf")
+        .build()
+        .expect("Unable to create the ONNX Runtime environment.");
+}
+
+/// ONNX model file name, when the model path is a directory.
+const MODEL_FILE: &str = "model.onnx";
+
+/// ONNX Runtime model.
+///
+/// Running the session requires a mutable access, so it is kept behind a mutex.
+pub struct OnnxModel {
+    session: Mutex<Session<'static>>,
+    /// Position of the board input in the graph.
+    board: usize,
+    /// Position of the action input in the graph, if any.
+    action: Option<usize>,
+    /// Positions of the outputs in the graph, in the signature order.
+    outputs: Vec<usize>,
+}
+
+// The session is only accessed through the mutex.
+unsafe impl Send for OnnxModel {}
+unsafe impl Sync for OnnxModel {}
+
+impl OnnxModel {
+    /// Run the model on the given batched inputs, given with their position in the graph.
+    /// Returns the outputs in the signature order.
+    fn run(&self, inputs: &[(usize, &Tensor<f32>)]) -> Vec<Tensor<f32>> {
+        let mut session = self.session.lock().unwrap();
+
+        let mut inputs = inputs.to_vec();
+        inputs.sort_by_key(|(position, _)| *position);
+        let arrays: Vec<Array<f32, IxDyn>> = inputs
+            .iter()
+            .map(|(position, tensor)| {
+                let input = &session.inputs[*position];
+                let batch_size = tensor.dims()[0] as usize;
+                let item_shape: Option<Vec<usize>> = input.dimensions().skip(1).collect();
+                let shape: Vec<usize> = match item_shape {
+                    Some(item_shape) => [batch_size].iter().chain(&item_shape).cloned().collect(),
+                    None => vec![batch_size, tensor.len() / batch_size],
+                };
+                Array::from_shape_vec(IxDyn(&shape), tensor.to_vec()).unwrap()
+            })
+            .collect();
+
+        let outputs: Vec<OrtOwnedTensor<f32, IxDyn>> = session.run(arrays).unwrap();
+        self.outputs
+            .iter()
+            .map(|position| {
+                let output = &outputs[*position];
+                let dims: Vec<u64> = output.shape().iter().map(|i| *i as u64).collect();
+                let values: Vec<f32> = output.iter().cloned().collect();
+                Tensor::new(&dims).with_values(&values).unwrap()
+            })
+            .collect()
+    }
+}
+
+impl Inference for OnnxModel {
+    fn call_prediction(&self, board: &Tensor<f32>) -> (Tensor<f32>, Tensor<f32>) {
+        let mut outputs = self.run(&[(self.board, board)]).into_iter();
+        let policy_tensor = outputs.next().unwrap();
+        let value_tensor = outputs.next().unwrap();
+        (policy_tensor, value_tensor)
+    }
+
+    fn call_dynamics(
+        &self,
+        board: &Tensor<f32>,
+        action: &Tensor<f32>,
+    ) -> (Tensor<f32>, Tensor<f32>) {
+        let action_position = self.action.expect("The model has no action input.");
+        let mut outputs = self
+            .run(&[(self.board, board), (action_position, action)])
+            .into_iter();
+        let next_board_tensor = outputs.next().unwrap();
+        let reward_tensor = outputs.next().unwrap();
+        (reward_tensor, next_board_tensor)
+    }
+
+    fn call_representation(&self, board: &Tensor<f32>) -> Tensor<f32> {
+        self.run(&[(self.board, board)]).into_iter().next().unwrap()
+    }
+}
+
+/// Position of the graph input or output named `name`, among the given names.
+fn position(names: &[&str], name: &str, kind: &str) -> Result<usize, StrError> {
+    names.iter().position(|n| *n == name).ok_or_else(|| {
+        StrError(format!(
+            "No {} named `{}` in the model. Available {}s: {}.",
+            kind,
+            name,
+            kind,
+            names.join(", ")
+        ))
+    })
+}
+
+/// Load an ONNX model: either a `.onnx` file, or a directory containing `model.onnx`,
+/// with the inputs and outputs named by `signature`.
+///
+/// Fails if the model can't be loaded or if an input or output of the signature is missing.
+pub fn load_model(path: &str, signature: &ModelSignature) -> Result<OnnxModel, StrError> {
+    let path = Path::new(path);
+    let file = if path.extension().map_or(false, |ext| ext == "onnx") {
+        path.to_path_buf()
+    } else {
+        path.join(MODEL_FILE)
+    };
+
+    /* check that model exists. */
+    if !file.exists() {
+        return Err(StrError(format!(
+            "Couldn't find model at {}",
+            file.display()
+        )));
+    };
+
+    let session = ENVIRONMENT
+        .new_session_builder()
+        .and_then(|builder| builder.with_optimization_level(GraphOptimizationLevel::Basic))
+        .and_then(|builder| builder.with_model_from_file(file.clone()))
+        .map_err(|e| {
+            StrError(format!(
+                "Unable to load the model at {}: {}",
+                file.display(),
+                e
+            ))
+        })?;
+
+    let inputs: Vec<&str> = session.inputs.iter().map(|i| i.name.as_str()).collect();
+    let outputs: Vec<&str> = session.outputs.iter().map(|o| o.name.as_str()).collect();
+    let resolve = |names: &[&str], name: &str, kind: &str| {
+        position(names, name, kind).map_err(|e| StrError(format!("{}: {}", file.display(), e)))
+    };
+
+    let board = match signature.board.as_deref() {
+        Some(name) => resolve(&inputs, name, "input")?,
+        None => 0,
+    };
+    let action = match signature.action.as_deref() {
+        Some(name) => Some(resolve(&inputs, name, "input")?),
+        None if inputs.len() > 1 => Some(1),
+        None => None,
+    };
+    let outputs = match signature.outputs.as_ref() {
+        Some(names) => names
+            .iter()
+            .map(|name| resolve(&outputs, name, "output"))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => (0..outputs.len()).collect(),
+    };
+
+    Ok(OnnxModel {
+        session: Mutex::new(session),
+        board,
+        action,
+        outputs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep::evaluator::{prediction_task, PredictionEvaluatorChannel};
+    use crate::deep::inference;
+    use crate::settings::Backend;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use tokio::sync::{mpsc, oneshot};
+
+    /// Model computing `policy = board` and `value = sum(board)`, for boards of size 4.
+    const IDENTITY_MODEL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/deep/testdata/identity");
+
+    #[tokio::test]
+    async fn test_onnx_prediction_batch() {
+        let model = inference::load_thread_safe_model(
+            Backend::Onnx,
+            &ModelSignature::default(),
+            IDENTITY_MODEL,
+        );
+        let (mut tx, rx) = mpsc::channel::<PredictionEvaluatorChannel>(2);
+        tokio::spawn(prediction_task(
+            2,
+            4,
+            4,
+            1,
+            None,
+            model,
+            rx,
+            Arc::new(AtomicBool::new(false)),
+            None,
+            None,
+        ));
+
+        let boards = [[1., 2., 3., 4.], [0., -1., 0.5, 0.]];
+        let mut responses = vec![];
+        for board in &boards {
+            let (resp_tx, resp_rx) = oneshot::channel();
+            tx.send((Tensor::from(&board[..]), resp_tx))
+                .await
+                .ok()
+                .unwrap();
+            responses.push(resp_rx);
+        }
+
+        for (board, resp_rx) in boards.iter().zip(responses) {
+            let (policy, value) = resp_rx.await.unwrap();
+            assert_eq!(&policy[..], &board[..]);
+            let sum: f32 = board.iter().sum();
+            assert!((value[0] - sum).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_onnx_signature() {
+        // outputs requested in the reverse graph order.
+        let signature = ModelSignature {
+            board: Some("board".to_owned()),
+            action: None,
+            outputs: Some(vec!["value".to_owned(), "policy".to_owned()]),
+        };
+        let model = load_model(IDENTITY_MODEL, &signature).ok().unwrap();
+        let board = Tensor::new(&[1, 4]).with_values(&[1., 2., 3., 4.]).unwrap();
+        let (value, policy) = model.call_prediction(&board);
+        assert_eq!(&policy[..], &[1., 2., 3., 4.]);
+        assert!((value[0] - 10.).abs() < 1e-5);
+
+        let signature = ModelSignature {
+            board: Some("observation".to_owned()),
+            ..ModelSignature::default()
+        };
+        let error = load_model(IDENTITY_MODEL, &signature).err().unwrap();
+        assert!(error.0.contains("`observation`"), "{}", error);
+        assert!(error.0.contains("Available inputs: board"), "{}", error);
+    }
+}
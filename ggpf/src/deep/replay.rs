@@ -0,0 +1,158 @@
+use crate::deep::self_play::GameHistoryEntry;
+use crate::game::Features;
+
+use ndarray::{Array, Dimension, IxDyn};
+use serde_derive::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+/// Array stored with its shape, independently from its dimension type.
+#[derive(Serialize, Deserialize)]
+struct SerializedArray {
+    shape: Vec<usize>,
+    data: Vec<f32>,
+}
+
+impl SerializedArray {
+    fn new<D: Dimension>(array: &Array<f32, D>) -> Self {
+        SerializedArray {
+            shape: array.shape().to_vec(),
+            data: array.iter().cloned().collect(),
+        }
+    }
+
+    fn into_array<D: Dimension>(self) -> io::Result<Array<f32, D>> {
+        Array::from_shape_vec(IxDyn(&self.shape), self.data)
+            .and_then(|array| array.into_dimensionality::<D>())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedEntry {
+    state: SerializedArray,
+    policy: SerializedArray,
+    action: SerializedArray,
+    value: SerializedArray,
+    target_value: SerializedArray,
+    reward: SerializedArray,
+    turn: Vec<f32>,
+}
+
+fn bincode_error(e: bincode::Error) -> io::Error {
+    match *e {
+        bincode::ErrorKind::Io(io) => io,
+        _ => io::Error::new(io::ErrorKind::Other, "non-io error."),
+    }
+}
+
+impl<G: Features> GameHistoryEntry<G> {
+    /// Serialize the game history using bincode.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let entry = SerializedEntry {
+            state: SerializedArray::new(&self.state),
+            policy: SerializedArray::new(&self.policy),
+            action: SerializedArray::new(&self.action),
+            value: SerializedArray::new(&self.value),
+            target_value: SerializedArray::new(&self.target_value),
+            reward: SerializedArray::new(&self.reward),
+            turn: self.turn.clone(),
+        };
+        bincode::serialize(&entry).map_err(bincode_error)
+    }
+
+    /// Deserialize a game history written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let entry: SerializedEntry = bincode::deserialize(bytes).map_err(bincode_error)?;
+        Ok(GameHistoryEntry {
+            state: entry.state.into_array()?,
+            policy: entry.policy.into_array()?,
+            action: entry.action.into_array()?,
+            value: entry.value.into_array()?,
+            target_value: entry.target_value.into_array()?,
+            reward: entry.reward.into_array()?,
+            turn: entry.turn,
+        })
+    }
+}
+
+/// Append a game history entry to the writer, prefixed by its size.
+pub fn write_history_entry<G: Features, W: Write>(
+    writer: &mut W,
+    entry: &GameHistoryEntry<G>,
+) -> io::Result<()> {
+    let bytes = entry.to_bytes()?;
+    writer.write_all(&(bytes.len() as u64).to_be_bytes())?;
+    writer.write_all(&bytes)
+}
+
+/// Read the next game history entry written by `write_history_entry`.
+pub fn read_history_entry<G: Features, R: Read>(reader: &mut R) -> io::Result<GameHistoryEntry<G>> {
+    let mut size = [0; 8];
+    reader.read_exact(&mut size)?;
+    let mut bytes = vec![0; u64::from_be_bytes(size) as usize];
+    reader.read_exact(&mut bytes)?;
+    GameHistoryEntry::from_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::Color;
+    use crate::game::tictactoe::{TicTacToe, TicTacToeBuilder};
+    use crate::game::{Base, Game, GameBuilder, Playable};
+    use futures::executor::block_on;
+    use ndarray::{Axis, RemoveAxis};
+
+    fn stack<D: RemoveAxis>(arrays: &[Array<f32, D>]) -> Array<f32, D> {
+        let views: Vec<_> = arrays.iter().map(|x| x.view()).collect();
+        ndarray::stack(Axis(0), &views).unwrap()
+    }
+
+    fn random_game() -> GameHistoryEntry<TicTacToe> {
+        let mut state = block_on(TicTacToeBuilder::default().create(Color::Black));
+        let ft = state.get_features();
+        let (mut states, mut actions, mut values, mut rewards, mut turn) =
+            (vec![], vec![], vec![], vec![], vec![]);
+
+        while !state.is_finished() {
+            turn.push(state.turn().into() as f32);
+            states.push(state.state_to_feature(state.turn()).insert_axis(Axis(0)));
+            let (action, reward) = block_on(state.random_move());
+            actions.push(TicTacToe::move_to_feature(&ft, action).insert_axis(Axis(0)));
+            values.push(Array::from_elem(ndarray::Ix1(1), 0.5));
+            rewards.push(Array::from_elem(ndarray::Ix1(1), reward));
+        }
+
+        GameHistoryEntry {
+            state: stack(&states),
+            policy: stack(&actions),
+            action: stack(&actions),
+            value: stack(&values),
+            target_value: stack(&values),
+            reward: stack(&rewards),
+            turn,
+        }
+    }
+
+    #[test]
+    fn test_history_entry_round_trip() {
+        let entry = random_game();
+        let mut buffer = vec![];
+        write_history_entry(&mut buffer, &entry).unwrap();
+        write_history_entry(&mut buffer, &entry).unwrap();
+
+        let mut reader = &buffer[..];
+        for _ in 0..2 {
+            let read: GameHistoryEntry<TicTacToe> = read_history_entry(&mut reader).unwrap();
+            assert_eq!(read.state, entry.state);
+            assert_eq!(read.state.shape(), entry.state.shape());
+            assert_eq!(read.policy, entry.policy);
+            assert_eq!(read.action, entry.action);
+            assert_eq!(read.value, entry.value);
+            assert_eq!(read.target_value, entry.target_value);
+            assert_eq!(read.reward, entry.reward);
+            assert_eq!(read.turn, entry.turn);
+        }
+        assert!(reader.is_empty());
+    }
+}
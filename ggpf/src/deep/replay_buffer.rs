@@ -0,0 +1,211 @@
+use crate::deep::self_play::GameHistoryEntry;
+use crate::game::Features;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+/// Smallest priority, so that every entry keeps a chance to be sampled.
+const MIN_PRIORITY: f32 = 1e-3;
+
+///
+/// Bounded window of the most recent game histories, sampled in minibatches for training.
+///
+/// Entries are identified by their insertion index. Once the buffer is full, a new entry
+/// evicts the oldest one. Sampling is uniform, or proportional to `priority^alpha` for
+/// prioritized replay, the priority of an entry being its last TD-error.
+///
+pub struct ReplayBuffer<G: Features> {
+    capacity: usize,
+    alpha: f32,
+    entries: Vec<GameHistoryEntry<G>>,
+    priorities: Vec<f32>,
+    max_priority: f32,
+    inserted: usize,
+}
+
+impl<G: Features> ReplayBuffer<G> {
+    /// Replay buffer with uniform sampling.
+    pub fn new(capacity: usize) -> Self {
+        Self::prioritized(capacity, 0.)
+    }
+
+    /// Replay buffer sampling entries proportionally to `priority^alpha`.
+    pub fn prioritized(capacity: usize, alpha: f32) -> Self {
+        assert!(capacity > 0, "Replay buffer capacity must be positive.");
+        ReplayBuffer {
+            capacity,
+            alpha,
+            entries: Vec::with_capacity(capacity),
+            priorities: Vec::with_capacity(capacity),
+            max_priority: 1.,
+            inserted: 0,
+        }
+    }
+
+    /// Number of stored entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds an entry, evicting the oldest one if the buffer is full. Returns the entry index.
+    ///
+    /// New entries get the highest priority seen so far, so that they are sampled at least once.
+    pub fn push(&mut self, entry: GameHistoryEntry<G>) -> usize {
+        let index = self.inserted;
+        if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+            self.priorities.push(self.max_priority);
+        } else {
+            let slot = index % self.capacity;
+            self.entries[slot] = entry;
+            self.priorities[slot] = self.max_priority;
+        }
+        self.inserted += 1;
+        index
+    }
+
+    fn slot(&self, index: usize) -> Option<usize> {
+        if index < self.inserted && index + self.entries.len() >= self.inserted {
+            Some(index % self.capacity)
+        } else {
+            None
+        }
+    }
+
+    /// Entry of given index, if it hasn't been evicted.
+    pub fn get(&self, index: usize) -> Option<&GameHistoryEntry<G>> {
+        self.slot(index).map(|slot| &self.entries[slot])
+    }
+
+    /// Sets the priority of an entry from its TD-error. Evicted entries are ignored.
+    pub fn update_priority(&mut self, index: usize, td_error: f32) {
+        if let Some(slot) = self.slot(index) {
+            let priority = td_error.abs().max(MIN_PRIORITY);
+            self.priorities[slot] = priority;
+            self.max_priority = self.max_priority.max(priority);
+        }
+    }
+
+    /// Samples a minibatch of entries with replacement, along with their indices.
+    pub fn sample(&self, batch: usize) -> Vec<(usize, &GameHistoryEntry<G>)> {
+        self.sample_with(&mut rand::thread_rng(), batch)
+    }
+
+    /// Samples a minibatch of entries with the given random number generator.
+    pub fn sample_with<R: Rng>(
+        &self,
+        rng: &mut R,
+        batch: usize,
+    ) -> Vec<(usize, &GameHistoryEntry<G>)> {
+        if self.is_empty() {
+            return vec![];
+        }
+
+        let slots: Vec<usize> = if self.alpha == 0. {
+            (0..batch)
+                .map(|_| rng.gen_range(0, self.entries.len()))
+                .collect()
+        } else {
+            let weights = WeightedIndex::new(self.priorities.iter().map(|p| p.powf(self.alpha)))
+                .expect("Invalid priorities.");
+            (0..batch).map(|_| weights.sample(rng)).collect()
+        };
+
+        // slot i holds the most recent index congruent to i.
+        let newest = self.inserted - 1;
+        slots
+            .into_iter()
+            .map(|slot| {
+                let index = newest - (newest + self.capacity - slot) % self.capacity;
+                (index, &self.entries[slot])
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tictactoe::TicTacToe;
+    use ndarray::{Array, Ix1};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn entry(marker: f32) -> GameHistoryEntry<TicTacToe> {
+        GameHistoryEntry {
+            state: Array::zeros((1, 3, 3, 3)),
+            policy: Array::zeros((1, 3, 3)),
+            action: Array::zeros((1, 3, 3)),
+            value: Array::from_elem(Ix1(1), marker),
+            target_value: Array::from_elem(Ix1(1), marker),
+            reward: Array::zeros(Ix1(1)),
+            turn: vec![0.],
+        }
+    }
+
+    fn marker(entry: &GameHistoryEntry<TicTacToe>) -> usize {
+        entry.value[0] as usize
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut buffer = ReplayBuffer::new(4);
+        for i in 0..10 {
+            assert_eq!(buffer.push(entry(i as f32)), i);
+        }
+        assert_eq!(buffer.len(), 4);
+        for i in 0..6 {
+            assert!(buffer.get(i).is_none());
+        }
+        for i in 6..10 {
+            assert_eq!(marker(buffer.get(i).unwrap()), i);
+        }
+        assert!(buffer.get(10).is_none());
+    }
+
+    #[test]
+    fn test_uniform_sampling() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut buffer = ReplayBuffer::new(8);
+        for i in 0..13 {
+            buffer.push(entry(i as f32));
+        }
+
+        let mut seen = [false; 13];
+        for (index, entry) in buffer.sample_with(&mut rng, 500) {
+            assert_eq!(marker(entry), index);
+            seen[index] = true;
+        }
+        let expected: Vec<bool> = (0..13).map(|i| i >= 5).collect();
+        assert_eq!(seen.to_vec(), expected);
+    }
+
+    #[test]
+    fn test_prioritized_sampling() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut buffer = ReplayBuffer::prioritized(10, 1.);
+        for i in 0..10 {
+            buffer.push(entry(i as f32));
+        }
+        for i in 0..10 {
+            buffer.update_priority(i, if i == 3 { 9. } else { 1. });
+        }
+
+        // entry 3 holds half of the total priority.
+        let samples = buffer.sample_with(&mut rng, 2000);
+        let hits = samples.iter().filter(|(index, _)| *index == 3).count();
+        assert!(hits > 850 && hits < 1150, "{} hits", hits);
+
+        // new entries get the maximal priority, evicting entry 0.
+        buffer.push(entry(10.));
+        let samples = buffer.sample_with(&mut rng, 2000);
+        let hits = samples.iter().filter(|(index, _)| *index == 10).count();
+        assert!(hits > 550 && hits < 850, "{} hits", hits);
+        assert!(samples.iter().all(|(index, _)| *index != 0));
+    }
+}
@@ -9,11 +9,13 @@
 
 
 use crate::deep::evaluator::PredictionEvaluatorChannel;
+use crate::deep::metrics::{metrics_task, Metrics, MetricsRecorder};
 use crate::game::GameBuilder;
 use crate::game::*;
 use crate::policies::mcts::puct::PUCT;
 use crate::policies::mcts::{muz, puct};
 use crate::policies::{
+    combinator::verify_move,
     mcts::muz::{Muz, MuzPolicy},
     MultiplayerPolicy, MultiplayerPolicyBuilder,
 };
@@ -22,10 +24,18 @@ use crate::settings;
 use indicatif::{ProgressBar, ProgressStyle};
 use ndarray::{Array, Axis, Dimension, Ix1};
 use rand::seq::SliceRandom;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+/// Period of the self-play metrics reports.
+const METRICS_PERIOD: Duration = Duration::from_secs(10);
 
 ///
 /// Game history data generated from self-play
@@ -42,12 +52,225 @@ where
     pub action: Array<f32, <G::ActionDim as Dimension>::Larger>,
     /// Value estimation of the root node.
     pub value: Array<f32, Ix1>,
+    /// Value target: n-step return for MuZero, the value estimation otherwise.
+    ///
+    /// Values are relative to the player to move in the recorded state: a final outcome
+    /// is labeled 1 for a win, -1 for a loss and 0 for a draw, for AlphaZero and MuZero alike.
+    pub target_value: Array<f32, Ix1>,
     /// Reward obtained after performing the action.
     pub reward: Array<f32, Ix1>,
     /// Whose turn.
     pub turn: Vec<f32>,
 }
 
+impl<G: Features> GameHistoryEntry<G> {
+    ///
+    /// Targets for `unroll_steps` moves starting from ply `index`: actions, rewards and values.
+    /// After the end of the game, actions are empty, rewards and values are 0.
+    ///
+    pub fn unroll(
+        &self,
+        index: usize,
+        unroll_steps: usize,
+    ) -> (
+        Array<f32, <G::ActionDim as Dimension>::Larger>,
+        Array<f32, Ix1>,
+        Array<f32, Ix1>,
+    ) {
+        let mut actions_shape = self.action.raw_dim();
+        actions_shape[0] = unroll_steps;
+        let mut actions = Array::zeros(actions_shape);
+        let mut rewards = Array::zeros(unroll_steps);
+        let mut values = Array::zeros(unroll_steps);
+
+        for t in 0..unroll_steps {
+            let i = index + t;
+            if i < self.turn.len() {
+                actions
+                    .index_axis_mut(Axis(0), t)
+                    .assign(&self.action.index_axis(Axis(0), i));
+                rewards[t] = self.reward[i];
+                values[t] = self.target_value[i];
+            }
+        }
+        (actions, rewards, values)
+    }
+}
+
+///
+/// Resignation state of a self-play game.
+///
+/// A player resigns when its root value stays below the threshold
+/// for `resign_plies` consecutive moves. In a fraction of the games,
+/// resignation is tracked but not applied, to measure false positives.
+/// No player resigns before the first move, as there would be no winning move to record.
+///
+struct Resignation<P> {
+    threshold: Option<f32>,
+    plies: usize,
+    enabled: bool,
+    below: HashMap<P, usize>,
+    resigned: Option<P>,
+    /// Number of recorded root values.
+    updates: usize,
+}
+
+impl<P> Resignation<P>
+where
+    P: std::hash::Hash + Eq + Copy,
+{
+    fn new(config: &settings::SelfPlay, multiplayer: bool) -> Self {
+        Resignation {
+            threshold: if multiplayer {
+                config.resign_threshold
+            } else {
+                None
+            },
+            plies: config.resign_plies,
+            enabled: rand::random::<f32>() >= config.resign_disabled_fraction,
+            below: HashMap::new(),
+            resigned: None,
+            updates: 0,
+        }
+    }
+
+    /// Record the root value for the player to move.
+    /// Returns true if the game has to be stopped by resignation.
+    fn update(&mut self, player: P, root_value: f32) -> bool {
+        self.updates += 1;
+        if let Some(threshold) = self.threshold {
+            let below = self.below.entry(player).or_insert(0);
+            *below = if root_value < threshold {
+                *below + 1
+            } else {
+                0
+            };
+
+            if *below >= self.plies && self.resigned.is_none() && self.updates > 1 {
+                self.resigned = Some(player);
+                return self.enabled;
+            }
+        }
+        false
+    }
+
+    /// In a game played until the end, whether the player that would have resigned won.
+    fn is_false_positive(&self, winner: P) -> bool {
+        self.resigned == Some(winner)
+    }
+}
+
+/// Number of games to generate, shared by the generator tasks.
+#[derive(Clone)]
+struct GameQuota {
+    target: Option<usize>,
+    started: Arc<AtomicUsize>,
+}
+
+impl GameQuota {
+    /// Quota of `target` games, or an unlimited one.
+    fn new(target: Option<usize>) -> Self {
+        GameQuota {
+            target,
+            started: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Reserves a new game, returning false once the target has been reached.
+    fn claim(&self) -> bool {
+        self.claim_game().is_some()
+    }
+
+    /// Reserves a new game, returning its number, or `None` once the target has been reached.
+    fn claim_game(&self) -> Option<usize> {
+        let game = self.started.fetch_add(1, Ordering::Relaxed);
+        match self.target {
+            Some(target) if game >= target => None,
+            _ => Some(game),
+        }
+    }
+}
+
+/// Position hash for draw detection, computed from the game features
+/// as game states are not required to be hashable.
+fn features_hash<G: Features>(state: &G) -> u64 {
+    let mut s = DefaultHasher::new();
+    state.turn().hash(&mut s);
+    for x in state.state_to_feature(state.turn()).iter() {
+        x.to_bits().hash(&mut s);
+    }
+    s.finish()
+}
+
+///
+/// Outcome of a self-play game, from who played each move and the rewards they got.
+/// The last player to move wins if its move was rewarded, otherwise the game is drawn.
+///
+fn final_outcome<P: Copy>(drawn: bool, turn: &[P], reward: &[f32]) -> Outcome<P> {
+    match (turn.last(), reward.last()) {
+        (Some(player), Some(r)) if !drawn && *r > 0. => Outcome::Win(*player),
+        _ => Outcome::Draw,
+    }
+}
+
+///
+/// Value targets of a self-play game, relative to the player to move at each ply.
+/// A drawn game has a final value of 0 for every player.
+///
+fn value_targets<P: PartialEq>(
+    value_target: settings::ValueTarget,
+    turn: &[P],
+    root_value: &[f32],
+    outcome: &Outcome<P>,
+) -> Vec<f32> {
+    let outcome = |player: &P| outcome.value(player);
+
+    match value_target {
+        settings::ValueTarget::FinalOutcome => turn.iter().map(outcome).collect(),
+        settings::ValueTarget::RootValue => root_value.to_vec(),
+        settings::ValueTarget::TdLambda { lambda } => {
+            let mut targets = vec![0.; turn.len()];
+            for i in (0..turn.len()).rev() {
+                targets[i] = if i + 1 == turn.len() {
+                    outcome(&turn[i])
+                } else {
+                    let sign = if turn[i + 1] == turn[i] { 1. } else { -1. };
+                    sign * ((1. - lambda) * root_value[i + 1] + lambda * targets[i + 1])
+                };
+            }
+            targets
+        }
+    }
+}
+
+///
+/// N-step value targets: for each ply, the discounted rewards of the next `td_steps` moves,
+/// bootstrapped with the root value `td_steps` moves ahead, relative to the player to move.
+///
+pub fn n_step_values<P: PartialEq>(
+    root_value: &[f32],
+    reward: &[f32],
+    turn: &[P],
+    td_steps: usize,
+    discount: f32,
+) -> Vec<f32> {
+    (0..turn.len())
+        .map(|i| {
+            let sign = |j: usize| if turn[j] == turn[i] { 1. } else { -1. };
+            let bootstrap = i + td_steps;
+
+            let mut value = 0.;
+            if bootstrap < turn.len() {
+                value += sign(bootstrap) * root_value[bootstrap] * discount.powi(td_steps as i32);
+            }
+            for j in i..bootstrap.min(turn.len()) {
+                value += sign(j) * reward[j] * discount.powi((j - i) as i32);
+            }
+            value
+        })
+        .collect()
+}
+
 //  /$$      /$$ /$$   /$$ /$$$$$$$$ /$$$$$$$$ /$$$$$$$   /$$$$$$
 // | $$$    /$$$| $$  | $$|_____ $$ | $$_____/| $$__  $$ /$$__  $$
 // | $$$$  /$$$$| $$  | $$     /$$/ | $$      | $$  \ $$| $$  \ $$
@@ -59,14 +282,17 @@ where
 //
 
 /*
- *  The game generator continuously generates self-play games using Muz policies.
+ *  The game generator generates self-play games using Muz policies, until the quota is reached.
  */
 async fn muzero_game_generator_task<GB, B, A>(
     config: muz::MuZeroConfig<B, A>,
+    config_selfplay: settings::SelfPlay,
     game_builder: GB,
     channels: muz::MuzEvaluatorChannels,
     mut output_chan: mpsc::Sender<GameHistoryEntry<GB::G>>,
     indicator_bar: Arc<Box<ProgressBar>>,
+    metrics: Option<Arc<MetricsRecorder>>,
+    quota: GameQuota,
 ) where
     GB::G: Features + Send + Sync + 'static,
     <GB::G as Base>::Move: Send + Sync,
@@ -77,11 +303,12 @@ async fn muzero_game_generator_task<GB, B, A>(
 {
     let muz = Muz {
         n_playouts: config.n_playouts,
+        playouts_schedule: config.playouts_schedule.clone(),
         muz: config.muz,
         channels,
     };
 
-    loop {
+    while quota.claim() {
         let mut policies: HashMap<<GB::G as Game>::Player, MuzPolicy<GB::G>> = HashMap::from_iter(
             <GB::G as Game>::players()
                 .iter()
@@ -92,6 +319,8 @@ async fn muzero_game_generator_task<GB, B, A>(
         let mut state: GB::G = game_builder.create(random_player).await;
 
         let ft = state.get_features();
+        // data augmentation: the whole game is recorded under a random symmetry.
+        let symmetry = *state.symmetries().choose(&mut rand::thread_rng()).unwrap();
 
         let mut history_state = vec![];
         let mut history_policy = vec![];
@@ -99,49 +328,103 @@ async fn muzero_game_generator_task<GB, B, A>(
         let mut history_action = vec![];
         let mut history_reward = vec![];
         let mut history_turn = vec![];
+        let mut search_depths = vec![];
+
+        let mut resignation = Resignation::new(&config_selfplay, GB::G::players().len() > 1);
+        let mut resigned = false;
+        let mut last_player = None;
+        let mut draw = DrawDetector::new(config.draw_rules);
+        let mut drawn = false;
 
         while !state.is_finished() {
+            if draw.record(features_hash(&state)) {
+                drawn = true;
+                break;
+            }
+
             let policy = policies.get_mut(&state.turn()).unwrap();
-            let action = policy.play(&state).await;
+            let action = policy
+                .play(&state)
+                .await
+                .expect("The policy should play a move in an unfinished game.");
+            if config_selfplay.verify_moves {
+                verify_move(&state, &action, &muz);
+            }
 
             /* Save search statistics */
             let mcts = policy.mcts.take().unwrap();
-            let game_node = mcts.root.as_ref().unwrap();
-            let visit_count = game_node.read().unwrap().info.node.count;
-
-            let monte_carlo_distribution: HashMap<<GB::G as Base>::Move, f32> = HashMap::from_iter(
-                game_node
-                    .read()
-                    .unwrap()
-                    .info
-                    .moves
-                    .iter()
-                    .map(|(k, v)| (*k, v.N_a / visit_count)),
-            );
+            let search_info = mcts.search_info();
+            search_depths.push(mcts.principal_variation(usize::MAX).len());
+            if let Some(metrics) = metrics.as_ref() {
+                metrics.record_search(search_info.entropy(), mcts.prior_divergence());
+            }
 
-            let root_value: f32 = game_node
-                .read()
-                .unwrap()
-                .info
-                .moves
-                .iter()
-                .map(|(_, v)| (v.reward + config.muz.puct.discount * v.Q * v.N_a / visit_count))
-                .sum();
+            let monte_carlo_distribution = if config.muz.puct.gumbel_scale.is_some() {
+                mcts.base_mcts
+                    .improved_policy(&mcts.root.as_ref().unwrap().read().unwrap().info.moves)
+            } else {
+                search_info.visit_distribution
+            };
+            let root_value = search_info.root_value;
+
+            if resignation.update(state.turn(), root_value) {
+                // the opponent's last move is recorded as winning.
+                *history_reward.last_mut().unwrap() = Array::from_elem(ndarray::Ix1(1), 1.);
+                resigned = true;
+                break;
+            }
 
             history_turn.push(state.turn().into() as f32);
-            history_state.push(state.state_to_feature(state.turn()).insert_axis(Axis(0)));
-            history_policy.push(
-                <GB::G as Features>::moves_to_feature(&ft, &monte_carlo_distribution)
+            history_state.push(
+                GB::G::state_symmetry(&ft, symmetry, &state.state_to_feature(state.turn()))
                     .insert_axis(Axis(0)),
             );
+            history_policy.push(
+                GB::G::action_symmetry(
+                    &ft,
+                    symmetry,
+                    &GB::G::moves_to_feature(&ft, &monte_carlo_distribution),
+                )
+                .insert_axis(Axis(0)),
+            );
             history_value.push(Array::from_elem(ndarray::Ix1(1), root_value));
-            history_action
-                .push(<GB::G as Features>::move_to_feature(&ft, action).insert_axis(Axis(0)));
+            history_action.push(
+                GB::G::action_symmetry(&ft, symmetry, &GB::G::move_to_feature(&ft, action))
+                    .insert_axis(Axis(0)),
+            );
 
+            last_player = Some(state.turn());
             let reward = state.play(&action).await;
             history_reward.push(Array::from_elem(ndarray::Ix1(1), reward));
         }
 
+        if drawn {
+            // no winner: the game is recorded with a value of 0 for both sides.
+            for value in history_value.iter_mut() {
+                value.fill(0.);
+            }
+        } else if !resigned {
+            // the last player to move wins.
+            if let Some(winner) = last_player {
+                if resignation.is_false_positive(winner) {
+                    log::info!("Resignation false positive.");
+                }
+            }
+        }
+
+        let root_values: Vec<f32> = history_value.iter().map(|v| v[0]).collect();
+        let rewards: Vec<f32> = history_reward.iter().map(|r| r[0]).collect();
+        let target_value = n_step_values(
+            &root_values,
+            &rewards,
+            &history_turn,
+            config.muz.td_steps,
+            config.muz.puct.discount,
+        );
+        if let Some(metrics) = metrics.as_ref() {
+            metrics.record_game(&search_depths, &root_values, &target_value);
+        }
+
         let history_state_view: Vec<_> = history_state.iter().map(|x| x.view()).collect();
         let history_policy_view: Vec<_> = history_policy.iter().map(|x| x.view()).collect();
         let history_action_view: Vec<_> = history_action.iter().map(|x| x.view()).collect();
@@ -154,6 +437,7 @@ async fn muzero_game_generator_task<GB, B, A>(
                 policy: ndarray::stack(Axis(0), &history_policy_view).unwrap(),
                 action: ndarray::stack(Axis(0), &history_action_view).unwrap(),
                 value: ndarray::stack(Axis(0), &history_value_view).unwrap(),
+                target_value: Array::from(target_value),
                 reward: ndarray::stack(Axis(0), &history_reward_view).unwrap(),
                 turn: history_turn,
             })
@@ -161,10 +445,52 @@ async fn muzero_game_generator_task<GB, B, A>(
             .ok()
             .unwrap();
 
-        indicator_bar.inc(1 as u64);
+        game_generated(&indicator_bar);
+    }
+}
+
+/// Progress of the self-play generation: a bar with an estimated time of arrival
+/// when a target number of games is set, a spinner otherwise.
+fn games_progress_bar(target_games: Option<usize>) -> ProgressBar {
+    let indicator_bar = match target_games {
+        Some(target) => {
+            let indicator_bar = ProgressBar::new(target as u64);
+            indicator_bar.set_style(ProgressStyle::default_bar().template(
+                "[{elapsed_precise}] {wide_bar} {pos}/{len} games generated (ETA {eta})",
+            ));
+            indicator_bar
+        }
+        None => {
+            let indicator_bar = ProgressBar::new_spinner();
+            indicator_bar.set_style(
+                ProgressStyle::default_spinner()
+                    .template("[{spinner}] {wide_bar} {pos} games generated ({elapsed_precise})"),
+            );
+            indicator_bar
+        }
+    };
+    indicator_bar.enable_steady_tick(200);
+    indicator_bar
+}
+
+/// Counts a generated game, finishing the progress bar once its length is reached.
+/// The bar is shared by the generator tasks.
+fn game_generated(indicator_bar: &ProgressBar) {
+    indicator_bar.inc(1);
+    if indicator_bar.position() >= indicator_bar.length() {
+        indicator_bar.finish();
     }
 }
 
+/// Spawns a task reporting the recorded metrics to `sender` periodically.
+fn spawn_metrics(sender: Option<mpsc::Sender<Metrics>>) -> Option<Arc<MetricsRecorder>> {
+    sender.map(|sender| {
+        let recorder = Arc::new(MetricsRecorder::default());
+        tokio::spawn(metrics_task(recorder.clone(), METRICS_PERIOD, sender));
+        recorder
+    })
+}
+
 ///
 /// MuZero self-play games generator
 /// Spawn several tasks (number according to settings) that performs self-play games
@@ -176,6 +502,8 @@ async fn muzero_game_generator_task<GB, B, A>(
 /// - `dynamics_tensorflow`: interface for the dynamics network.
 /// - `representation_tensorflow`: interface for the representation network.
 /// - `output_chan`: communication channel to emit the generated games.
+/// - `metrics`: channel to periodically emit self-play metrics, if given.
+/// - `target_games`: number of games to generate before returning, or `None` to run forever.
 /// # Panics
 /// This function will panic if the evaluator shapes doesn't fit,
 /// or if the CUDA executor goes out of memory.
@@ -185,6 +513,8 @@ pub async fn muzero_game_generator<GB, B, A>(
     config_selfplay: settings::SelfPlay,
     game_builder: GB,
     output_chan: mpsc::Sender<GameHistoryEntry<GB::G>>,
+    metrics: Option<mpsc::Sender<Metrics>>,
+    target_games: Option<usize>,
 ) where
     GB::G: Features + Send + Sync + 'static,
     <GB::G as Base>::Move: Send + Sync,
@@ -193,29 +523,38 @@ pub async fn muzero_game_generator<GB, B, A>(
     A: Dimension + 'static,
     B: Dimension + 'static,
 {
-    let indicator_bar = ProgressBar::new_spinner();
-    indicator_bar.set_style(
-        ProgressStyle::default_spinner()
-            .template("[{spinner}] {wide_bar} {pos} games generated ({elapsed_precise})"),
-    );
-    indicator_bar.enable_steady_tick(200);
-    let bar_box = Arc::new(Box::new(indicator_bar));
+    let bar_box = Arc::new(Box::new(games_progress_bar(target_games)));
 
     let mut muzero_evaluators = muz::MuzEvaluators::new(config.clone(), false);
+    let recorder = spawn_metrics(metrics);
+    if let Some(recorder) = recorder.as_ref() {
+        muzero_evaluators.record_metrics(recorder.clone());
+    }
+
+    let quota = GameQuota::new(target_games);
+    let mut workers: Vec<JoinHandle<()>> = vec![];
 
     for _ in 0..config_selfplay.evaluators {
         muzero_evaluators = muzero_evaluators.clone();
 
         for _ in 0..config_selfplay.generators {
-            tokio::spawn(muzero_game_generator_task(
+            workers.push(tokio::spawn(muzero_game_generator_task(
                 config.clone(),
+                config_selfplay,
                 game_builder.clone(),
                 muzero_evaluators.get_channels(),
                 output_chan.clone(),
                 bar_box.clone(),
-            ));
+                recorder.clone(),
+                quota.clone(),
+            )));
         }
     }
+
+    for worker in workers {
+        worker.await.unwrap();
+    }
+    bar_box.finish();
 }
 
 //   /$$$$$$  /$$       /$$$$$$$  /$$   /$$  /$$$$$$        /$$$$$$$$ /$$$$$$$$ /$$$$$$$   /$$$$$$
@@ -229,14 +568,17 @@ pub async fn muzero_game_generator<GB, B, A>(
 //
 
 /*
- *  The game generator continuously generates self-play games using PUCT policies.
+ *  The game generator generates self-play games using PUCT policies, until the quota is reached.
  */
 async fn alphazero_game_generator_task<GB, A, B>(
     config: puct::AlphaZeroConfig<A, B>,
+    config_selfplay: settings::SelfPlay,
     game_builder: GB,
     prediction_channel: mpsc::Sender<PredictionEvaluatorChannel>,
     mut output_chan: mpsc::Sender<GameHistoryEntry<GB::G>>,
     indicator_bar: Arc<Box<ProgressBar>>,
+    metrics: Option<Arc<MetricsRecorder>>,
+    quota: GameQuota,
 ) where
     GB::G: Features + Clone + Send + Sync + 'static,
     <GB::G as Base>::Move: Send + Sync,
@@ -248,11 +590,12 @@ async fn alphazero_game_generator_task<GB, A, B>(
     let puct = PUCT {
         config: config.puct,
         n_playouts: config.n_playouts,
+        playouts_schedule: config.playouts_schedule.clone(),
         prediction_channel,
     };
 
-    // Generate games indefinitely.
-    loop {
+    // Generate games until the quota is reached.
+    while quota.claim() {
         let mut p1 = puct.create(<GB::G as Game>::players()[0]);
         let mut p2 = puct.create(<GB::G as Game>::players()[1]);
         let random_player = *<GB::G as Game>::players()
@@ -261,6 +604,8 @@ async fn alphazero_game_generator_task<GB, A, B>(
         let mut state: GB::G = game_builder.create(random_player).await;
 
         let ft = state.get_features();
+        // data augmentation: the whole game is recorded under a random symmetry.
+        let symmetry = *state.symmetries().choose(&mut rand::thread_rng()).unwrap();
 
         let mut history_state = vec![];
         let mut history_policy = vec![];
@@ -268,52 +613,110 @@ async fn alphazero_game_generator_task<GB, A, B>(
         let mut history_action = vec![];
         let mut history_reward = vec![];
         let mut history_turn = vec![];
+        let mut search_depths = vec![];
+
+        let mut resignation = Resignation::new(&config_selfplay, GB::G::players().len() > 1);
+        let mut resigned = false;
+        let mut last_player = None;
+        let mut draw = DrawDetector::new(config.draw_rules);
+        let mut drawn = false;
 
         while !state.is_finished() {
+            if draw.record(features_hash(&state)) {
+                drawn = true;
+                break;
+            }
+
             let policy = if state.turn() == <GB::G as Game>::players()[0] {
                 &mut p1
             } else {
                 &mut p2
             };
-            let action = policy.play(&state).await;
+            let action = policy
+                .play(&state)
+                .await
+                .expect("The policy should play a move in an unfinished game.");
+            if config_selfplay.verify_moves {
+                verify_move(&state, &action, &puct);
+            }
 
             /* Save search statistics */
-            let game_node = policy.root.as_ref().unwrap();
-            let visit_count = game_node.read().unwrap().info.node.count;
-
-            let monte_carlo_distribution: HashMap<<GB::G as Base>::Move, f32> = HashMap::from_iter(
-                game_node
-                    .read()
-                    .unwrap()
-                    .info
-                    .moves
-                    .iter()
-                    .map(|(k, v)| (*k, v.N_a / visit_count)),
-            );
+            let search_info = policy.search_info();
+            search_depths.push(policy.principal_variation(usize::MAX).len());
+            if let Some(metrics) = metrics.as_ref() {
+                metrics.record_search(search_info.entropy(), policy.prior_divergence());
+            }
 
-            let root_value: f32 = game_node
-                .read()
-                .unwrap()
-                .info
-                .moves
-                .iter()
-                .map(|(_, v)| ((v.reward + config.puct.discount * v.Q) * v.N_a / visit_count))
-                .sum();
+            let monte_carlo_distribution = if config.puct.gumbel_scale.is_some() {
+                policy
+                    .base_mcts
+                    .improved_policy(&policy.root.as_ref().unwrap().read().unwrap().info.moves)
+            } else {
+                search_info.visit_distribution
+            };
+            let root_value = search_info.root_value;
+
+            if resignation.update(state.turn(), root_value) {
+                // the opponent's last move is recorded as winning.
+                *history_reward.last_mut().unwrap() = Array::from_elem(ndarray::Ix1(1), 1.);
+                resigned = true;
+                break;
+            }
 
             history_turn.push(state.turn().into() as f32);
-            history_state.push(state.state_to_feature(state.turn()).insert_axis(Axis(0)));
-            history_policy.push(
-                <GB::G as Features>::moves_to_feature(&ft, &monte_carlo_distribution)
+            history_state.push(
+                GB::G::state_symmetry(&ft, symmetry, &state.state_to_feature(state.turn()))
                     .insert_axis(Axis(0)),
             );
+            history_policy.push(
+                GB::G::action_symmetry(
+                    &ft,
+                    symmetry,
+                    &GB::G::moves_to_feature(&ft, &monte_carlo_distribution),
+                )
+                .insert_axis(Axis(0)),
+            );
             history_value.push(Array::from_elem(ndarray::Ix1(1), root_value));
-            history_action
-                .push(<GB::G as Features>::move_to_feature(&ft, action).insert_axis(Axis(0)));
+            history_action.push(
+                GB::G::action_symmetry(&ft, symmetry, &GB::G::move_to_feature(&ft, action))
+                    .insert_axis(Axis(0)),
+            );
 
+            last_player = Some(state.turn());
             let reward = state.play(&action).await;
             history_reward.push(Array::from_elem(ndarray::Ix1(1), reward));
         }
 
+        let root_values: Vec<f32> = history_value.iter().map(|v| v[0]).collect();
+
+        if drawn {
+            // no winner: the game is recorded with a value of 0 for both sides.
+            for value in history_value.iter_mut() {
+                value.fill(0.);
+            }
+        } else if !resigned {
+            // the last player to move wins.
+            if let Some(winner) = last_player {
+                if resignation.is_false_positive(winner) {
+                    log::info!("Resignation false positive.");
+                }
+            }
+        }
+
+        if !drawn {
+            let rewards: Vec<f32> = history_reward.iter().map(|r| r[0]).collect();
+            let outcome = final_outcome(drawn, &history_turn, &rewards);
+            let targets = value_targets(config.value_target, &history_turn, &root_values, &outcome);
+            for (value, target) in history_value.iter_mut().zip(targets) {
+                value.fill(target);
+            }
+        }
+
+        if let Some(metrics) = metrics.as_ref() {
+            let targets: Vec<f32> = history_value.iter().map(|v| v[0]).collect();
+            metrics.record_game(&search_depths, &root_values, &targets);
+        }
+
         let history_state_view: Vec<_> = history_state.iter().map(|x| x.view()).collect();
         let history_policy_view: Vec<_> = history_policy.iter().map(|x| x.view()).collect();
         let history_action_view: Vec<_> = history_action.iter().map(|x| x.view()).collect();
@@ -326,6 +729,7 @@ async fn alphazero_game_generator_task<GB, A, B>(
                 policy: ndarray::stack(Axis(0), &history_policy_view).unwrap(),
                 action: ndarray::stack(Axis(0), &history_action_view).unwrap(),
                 value: ndarray::stack(Axis(0), &history_value_view).unwrap(),
+                target_value: ndarray::stack(Axis(0), &history_value_view).unwrap(),
                 reward: ndarray::stack(Axis(0), &history_reward_view).unwrap(),
                 turn: history_turn,
             })
@@ -333,7 +737,7 @@ async fn alphazero_game_generator_task<GB, A, B>(
             .ok()
             .unwrap();
 
-        indicator_bar.inc(1 as u64);
+        game_generated(&indicator_bar);
     }
 }
 
@@ -349,6 +753,8 @@ async fn alphazero_game_generator_task<GB, A, B>(
 ///  - `game_builder`: game builder.
 ///  - `prediction_tensorflow`: interface for the prediction network.
 ///  - `output_chan`: communication channel to emit the generated games.
+///  - `metrics`: channel to periodically emit self-play metrics, if given.
+///  - `target_games`: number of games to generate before returning, or `None` to run forever.
 ///
 ///  # Panics
 ///
@@ -360,6 +766,8 @@ pub async fn alphazero_game_generator<GB, A, B>(
     config_selfplay: settings::SelfPlay,
     game_builder: GB,
     output_chan: mpsc::Sender<GameHistoryEntry<GB::G>>,
+    metrics: Option<mpsc::Sender<Metrics>>,
+    target_games: Option<usize>,
 ) where
     GB::G: Features + Clone + Send + Sync + 'static,
     <GB::G as Base>::Move: Send + Sync,
@@ -368,28 +776,678 @@ pub async fn alphazero_game_generator<GB, A, B>(
     A: Dimension + 'static,
     B: Dimension + 'static,
 {
-    let indicator_bar = ProgressBar::new_spinner();
-    indicator_bar.set_style(
-        ProgressStyle::default_spinner()
-            .template("[{spinner}] {wide_bar} {pos} games generated ({elapsed_precise})"),
-    );
-    indicator_bar.enable_steady_tick(200);
-    let bar_box = Arc::new(Box::new(indicator_bar));
+    let bar_box = Arc::new(Box::new(games_progress_bar(target_games)));
 
     let mut az = puct::AlphaZeroEvaluators::new(config.clone(), false);
+    let recorder = spawn_metrics(metrics);
+    if let Some(recorder) = recorder.as_ref() {
+        az.record_metrics(recorder.clone());
+    }
+
+    let quota = GameQuota::new(target_games);
+    let mut workers: Vec<JoinHandle<()>> = vec![];
 
     for _ in 0..config_selfplay.evaluators {
         // spawn new workers.
         az = az.clone();
 
         for _ in 0..config_selfplay.generators {
-            tokio::spawn(alphazero_game_generator_task(
+            workers.push(tokio::spawn(alphazero_game_generator_task(
                 config.clone(),
+                config_selfplay,
                 game_builder.clone(),
                 az.get_channel(),
                 output_chan.clone(),
                 bar_box.clone(),
-            ));
+                recorder.clone(),
+                quota.clone(),
+            )));
+        }
+    }
+
+    for worker in workers {
+        worker.await.unwrap();
+    }
+    bar_box.finish();
+}
+
+/// Results of arena games, from the point of view of the first contender.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArenaResults {
+    /// Games won by the first contender.
+    pub wins: usize,
+    /// Games won by the second contender.
+    pub losses: usize,
+    /// Drawn games.
+    pub draws: usize,
+}
+
+impl ArenaResults {
+    /// Number of played games.
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Score of the first contender, draws counting for half a win.
+    pub fn score(&self) -> f32 {
+        (self.wins as f32 + 0.5 * self.draws as f32) / self.games().max(1) as f32
+    }
+}
+
+impl std::ops::AddAssign for ArenaResults {
+    fn add_assign(&mut self, other: Self) {
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.draws += other.draws;
+    }
+}
+
+/*
+ *  The arena generator plays games between two PUCT policies, until the quota is reached.
+ *  The first contender plays the first player in even games and the second one in odd games.
+ *  Both search with their evaluation settings, without noise nor sampling.
+ */
+async fn arena_game_generator_task<GB, A, B>(
+    configs: (puct::AlphaZeroConfig<A, B>, puct::AlphaZeroConfig<A, B>),
+    prediction_channels: (
+        mpsc::Sender<PredictionEvaluatorChannel>,
+        mpsc::Sender<PredictionEvaluatorChannel>,
+    ),
+    game_builder: GB,
+    quota: GameQuota,
+) -> ArenaResults
+where
+    GB::G: Features + Clone + Send + Sync + 'static,
+    <GB::G as Base>::Move: Send + Sync,
+    <GB::G as Game>::Player: Send + Sync,
+    GB: GameBuilder,
+    A: Dimension,
+    B: Dimension,
+{
+    let draw_rules = configs.0.draw_rules;
+    let contenders = [
+        PUCT {
+            config: configs.0.puct.evaluation(),
+            n_playouts: configs.0.n_playouts,
+            playouts_schedule: configs.0.playouts_schedule.clone(),
+            prediction_channel: prediction_channels.0,
+        },
+        PUCT {
+            config: configs.1.puct.evaluation(),
+            n_playouts: configs.1.n_playouts,
+            playouts_schedule: configs.1.playouts_schedule.clone(),
+            prediction_channel: prediction_channels.1,
+        },
+    ];
+    let players = <GB::G as Game>::players();
+    let mut results = ArenaResults::default();
+
+    while let Some(game) = quota.claim_game() {
+        // colors are swapped every game.
+        let first = game % 2;
+        let mut p1 = contenders[first].create(players[0]);
+        let mut p2 = contenders[1 - first].create(players[1]);
+        let mut state: GB::G = game_builder.create(players[0]).await;
+
+        let mut draw = DrawDetector::new(draw_rules);
+        let mut drawn = false;
+        let mut last_move = None;
+
+        while !state.is_finished() {
+            if draw.record(features_hash(&state)) {
+                drawn = true;
+                break;
+            }
+
+            let turn = state.turn();
+            let action = if turn == players[0] {
+                p1.play(&state).await
+            } else {
+                p2.play(&state).await
+            }
+            .expect("The policy should play a move in an unfinished game.");
+            let reward = state.play(&action).await;
+            last_move = Some((turn, reward));
+        }
+
+        let (turn, reward): (Vec<_>, Vec<_>) = last_move.into_iter().unzip();
+        match final_outcome(drawn, &turn, &reward) {
+            Outcome::Win(winner) if (winner == players[0]) == (first == 0) => results.wins += 1,
+            Outcome::Win(_) => results.losses += 1,
+            _ => results.draws += 1,
+        }
+    }
+    results
+}
+
+///
+///  Arena games generator, to evaluate a model against another one.
+///
+///  Spawn several tasks (number according to settings) that play games between two
+///  AlphaZero policies, each one with its own network, alternating colors.
+///  Only the outcome of the games is recorded, no training targets are produced.
+///
+///  # Params
+///
+///  - `config`: configuration of the first contender.
+///  - `config_opponent`: configuration of the second contender.
+///  - `config_selfplay`: number of evaluators and generators.
+///  - `game_builder`: game builder.
+///  - `n_games`: number of games to play.
+///
+///  # Panics
+///
+///  This function will panic if the evaluator shapes doesn't fit,
+///  or if the CUDA executor goes out of memory.
+///
+pub async fn arena_game_generator<GB, A, B>(
+    config: puct::AlphaZeroConfig<A, B>,
+    config_opponent: puct::AlphaZeroConfig<A, B>,
+    config_selfplay: settings::SelfPlay,
+    game_builder: GB,
+    n_games: usize,
+) -> ArenaResults
+where
+    GB::G: Features + Clone + Send + Sync + 'static,
+    <GB::G as Base>::Move: Send + Sync,
+    <GB::G as Game>::Player: Send + Sync,
+    GB: GameBuilder + Clone + Sync + Send + 'static,
+    A: Dimension + 'static,
+    B: Dimension + 'static,
+{
+    let mut az = puct::AlphaZeroEvaluators::new(config.clone(), false);
+    let mut az_opponent = puct::AlphaZeroEvaluators::new(config_opponent.clone(), false);
+
+    let quota = GameQuota::new(Some(n_games));
+    let mut workers: Vec<JoinHandle<ArenaResults>> = vec![];
+
+    for _ in 0..config_selfplay.evaluators {
+        // spawn new workers.
+        az = az.clone();
+        az_opponent = az_opponent.clone();
+
+        for _ in 0..config_selfplay.generators {
+            workers.push(tokio::spawn(arena_game_generator_task(
+                (config.clone(), config_opponent.clone()),
+                (az.get_channel(), az_opponent.get_channel()),
+                game_builder.clone(),
+                quota.clone(),
+            )));
+        }
+    }
+
+    let mut results = ArenaResults::default();
+    for worker in workers {
+        results += worker.await.unwrap();
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep::mock::{mock_alphazero_channel, mock_uniform_channel};
+    use crate::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+    use crate::game::meta::opening_book::OpeningBookGB;
+    use crate::game::tictactoe::{Move, TicTacToe, TicTacToeBuilder};
+    use futures::executor::block_on;
+    use indicatif::ProgressDrawTarget;
+
+    fn config(resign_threshold: Option<f32>) -> settings::SelfPlay {
+        settings::SelfPlay {
+            batch_size: 1,
+            evaluators: 1,
+            generators: 1,
+            resign_threshold,
+            resign_plies: 2,
+            resign_disabled_fraction: 0.,
+            adaptive: false,
+            target_latency_ms: 5.,
+            verify_moves: true,
+        }
+    }
+
+    /// Perfect play, preferring quick wins and slow losses.
+    /// Returns the best move with its value for the player to move.
+    fn negamax(state: &TicTacToe) -> (Option<Move>, f32) {
+        if state.winner().is_some() {
+            return (None, -1.);
+        }
+        let mut best = (None, 0.);
+        for (i, action) in state.possible_moves().into_iter().enumerate() {
+            let mut next = state.clone();
+            block_on(next.play(&action));
+            let value = -0.9 * negamax(&next).1;
+            if i == 0 || value > best.1 {
+                best = (Some(action), value);
+            }
+        }
+        best
+    }
+
+    /// Self-play game between perfect players, returns who played each move.
+    fn play_game(mut state: TicTacToe, config: &settings::SelfPlay) -> Vec<Color> {
+        let mut resignation = Resignation::new(config, true);
+        let mut turns = vec![];
+        while !state.is_finished() {
+            let (action, value) = negamax(&state);
+            if resignation.update(state.turn(), value) {
+                break;
+            }
+            turns.push(state.turn());
+            block_on(state.play(&action.unwrap()));
+        }
+        turns
+    }
+
+    #[test]
+    fn test_final_outcome_value_targets() {
+        let mut state = block_on(TicTacToeBuilder::default().create(Color::Black));
+        let mut turns = vec![];
+        for action in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            turns.push(state.turn());
+            block_on(state.play(action));
+        }
+        let outcome = state.outcome();
+        assert_eq!(outcome, Outcome::Win(Color::Black));
+
+        let root_values = vec![0.5; turns.len()];
+        let targets = value_targets(
+            settings::ValueTarget::FinalOutcome,
+            &turns,
+            &root_values,
+            &outcome,
+        );
+        assert_eq!(targets, vec![1., -1., 1., -1., 1.]);
+
+        let targets = value_targets(
+            settings::ValueTarget::FinalOutcome,
+            &turns,
+            &root_values,
+            &Outcome::Draw,
+        );
+        assert!(targets.iter().all(|v| v.abs() < 1e-6));
+
+        let td_one = value_targets(
+            settings::ValueTarget::TdLambda { lambda: 1. },
+            &turns,
+            &root_values,
+            &outcome,
+        );
+        assert_eq!(td_one, vec![1., -1., 1., -1., 1.]);
+        let td_zero = value_targets(
+            settings::ValueTarget::TdLambda { lambda: 0. },
+            &turns,
+            &root_values,
+            &outcome,
+        );
+        assert_eq!(td_zero, vec![-0.5, -0.5, -0.5, -0.5, 1.]);
+    }
+
+    /// Turns and rewards of a tic-tac-toe game.
+    fn tictactoe_game(actions: &[(usize, usize)]) -> (Vec<Color>, Vec<f32>) {
+        let mut state = block_on(TicTacToeBuilder::default().create(Color::Black));
+        let (mut turns, mut rewards) = (vec![], vec![]);
+        for action in actions {
+            turns.push(state.turn());
+            rewards.push(block_on(state.play(action)));
         }
+        assert!(state.is_finished());
+        (turns, rewards)
+    }
+
+    #[test]
+    fn test_value_convention() {
+        let black_wins = [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)];
+        let white_wins = [(0, 0), (1, 0), (0, 1), (1, 1), (2, 2), (1, 2)];
+        let drawn = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (1, 1),
+            (0, 1),
+            (2, 1),
+            (1, 2),
+            (0, 2),
+            (2, 2),
+        ];
+
+        for (actions, winner) in &[
+            (&black_wins[..], Some(Color::Black)),
+            (&white_wins[..], Some(Color::White)),
+            (&drawn[..], None),
+        ] {
+            let (turns, rewards) = tictactoe_game(actions);
+            let outcome = final_outcome(false, &turns, &rewards);
+            let expected: Vec<f32> = turns
+                .iter()
+                .map(|turn| match winner {
+                    Some(winner) if winner == turn => 1.,
+                    Some(_) => -1.,
+                    None => 0.,
+                })
+                .collect();
+
+            // AlphaZero labels.
+            let root_values = vec![0.5; turns.len()];
+            let targets = value_targets(
+                settings::ValueTarget::FinalOutcome,
+                &turns,
+                &root_values,
+                &outcome,
+            );
+            assert_eq!(targets, expected);
+
+            // MuZero returns without bootstrap.
+            let targets = n_step_values(&root_values, &rewards, &turns, turns.len(), 1.);
+            assert_eq!(targets, expected);
+        }
+    }
+
+    #[test]
+    fn test_drawn_game_values() {
+        let mut state = block_on(TicTacToeBuilder::default().create(Color::Black));
+        let (mut turns, mut rewards) = (vec![], vec![]);
+        // X O X / X O O / O X X
+        for action in &[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (1, 1),
+            (0, 1),
+            (2, 1),
+            (1, 2),
+            (0, 2),
+            (2, 2),
+        ] {
+            turns.push(state.turn());
+            rewards.push(block_on(state.play(action)));
+        }
+        assert!(state.is_finished());
+        assert_eq!(state.outcome(), Outcome::Draw);
+
+        let outcome = final_outcome(false, &turns, &rewards);
+        assert_eq!(outcome, Outcome::Draw);
+        let root_values = vec![0.5; turns.len()];
+        let targets = value_targets(
+            settings::ValueTarget::FinalOutcome,
+            &turns,
+            &root_values,
+            &outcome,
+        );
+        assert_eq!(targets, vec![0.; turns.len()]);
+    }
+
+    #[test]
+    fn test_n_step_values() {
+        let mut state = block_on(TicTacToeBuilder::default().create(Color::Black));
+        let (mut turns, mut rewards) = (vec![], vec![]);
+        for action in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            turns.push(state.turn());
+            rewards.push(block_on(state.play(action)));
+        }
+        let root_values = [0.1, 0.2, 0.3, 0.4, 0.5];
+
+        let values = n_step_values(&root_values, &rewards, &turns, 2, 0.5);
+        // ply 3: white's reward is 0, black wins on the next move.
+        let expected = [0.3 * 0.5 * 0.5, 0.4 * 0.5 * 0.5, 0.5 * 0.5 * 0.5, -0.5, 1.];
+        for (value, expected) in values.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_resignation() {
+        let mut state = block_on(TicTacToeBuilder::default().create(Color::Black));
+        // corner then adjacent edge: forced win for black.
+        block_on(state.play(&(0, 0)));
+        block_on(state.play(&(1, 0)));
+
+        let full_game = play_game(state.clone(), &config(None));
+        let resigned_game = play_game(state, &config(Some(-0.5)));
+
+        assert!(resigned_game.len() < full_game.len());
+        // the last player to move is recorded as the winner.
+        assert_eq!(full_game.last(), Some(&Color::Black));
+        assert_eq!(resigned_game.last(), Some(&Color::Black));
+    }
+
+    #[tokio::test]
+    async fn test_resignation_after_first_move() {
+        // every root value is below the threshold: the first player can't resign as no
+        // move has been played, the second one resigns at once.
+        let config = settings::SelfPlay {
+            resign_plies: 1,
+            ..config(Some(1.))
+        };
+        let (output_tx, mut output_rx) = mpsc::channel(16);
+        alphazero_game_generator_task(
+            alpha_config(),
+            config,
+            TicTacToeBuilder::default(),
+            mock_uniform_channel::<TicTacToe>(&()),
+            output_tx,
+            Arc::new(Box::new(ProgressBar::hidden())),
+            None,
+            GameQuota::new(Some(1)),
+        )
+        .await;
+
+        let game = output_rx.recv().await.unwrap();
+        assert_eq!(game.turn.len(), 1);
+        assert!((game.reward[0] - 1.).abs() < 1e-6);
+    }
+
+    /// AlphaZero configuration for Tic-Tac-Toe, to be used with a mock evaluator.
+    fn alpha_config() -> puct::AlphaZeroConfig<ndarray::Ix2, ndarray::Ix3> {
+        game_alpha_config(ndarray::Ix2(3, 3), ndarray::Ix3(3, 3, 3))
+    }
+
+    /// AlphaZero configuration for a game of given shapes, to be used with a mock evaluator.
+    fn game_alpha_config<A: Dimension, B: Dimension>(
+        action_shape: A,
+        board_shape: B,
+    ) -> puct::AlphaZeroConfig<A, B> {
+        let puct = settings::PUCT {
+            discount: 1.,
+            c_base: 19652.,
+            c_init: 1.25,
+            root_dirichlet_alpha: 0.3,
+            root_exploration_fraction: 0.25,
+            add_exploration_noise: true,
+            scale_dirichlet_alpha: false,
+            value_support: None,
+            gumbel_scale: None,
+            gumbel_considered_actions: 16,
+            parallel_playouts: 1,
+            batch_expansions: false,
+            early_stop: None,
+            time_budget_ms: None,
+            feature_cache: 0,
+            value_transform: None,
+            fpu: 0.,
+            fpu_reduction: None,
+            log_search: false,
+        };
+        puct::AlphaZeroConfig {
+            n_playouts: 10,
+            playouts_schedule: vec![],
+            puct,
+            value_target: settings::ValueTarget::default(),
+            network_path: String::new(),
+            board_shape,
+            action_shape,
+            watch_models: false,
+            batch_size: 1,
+            target_latency: None,
+            draw_rules: DrawRules::default(),
+            backend: settings::Backend::default(),
+            signature: settings::ModelSignature::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let alpha_config = alpha_config();
+
+        let (output_tx, mut output_rx) = mpsc::channel(16);
+        let (metrics_tx, mut metrics_rx) = mpsc::channel(16);
+        let recorder = Arc::new(MetricsRecorder::default());
+        tokio::spawn(metrics_task(
+            recorder.clone(),
+            Duration::from_millis(100),
+            metrics_tx,
+        ));
+        tokio::spawn(alphazero_game_generator_task(
+            alpha_config,
+            config(None),
+            TicTacToeBuilder::default(),
+            mock_uniform_channel::<TicTacToe>(&()),
+            output_tx,
+            Arc::new(Box::new(ProgressBar::hidden())),
+            Some(recorder),
+            GameQuota::new(None),
+        ));
+        // consume the generated games.
+        tokio::spawn(async move { while output_rx.recv().await.is_some() {} });
+
+        let mut games = 0;
+        while games == 0 {
+            let metrics = tokio::time::timeout(Duration::from_secs(30), metrics_rx.recv())
+                .await
+                .expect("No metrics were reported.")
+                .unwrap();
+            games += metrics.games;
+            if metrics.games > 0 {
+                assert!(metrics.games_per_sec > 0.);
+                assert!(metrics.search_depth >= 1.);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_target_games() {
+        let (output_tx, mut output_rx) = mpsc::channel(16);
+        let quota = GameQuota::new(Some(5));
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                tokio::spawn(alphazero_game_generator_task(
+                    alpha_config(),
+                    config(None),
+                    TicTacToeBuilder::default(),
+                    mock_uniform_channel::<TicTacToe>(&()),
+                    output_tx.clone(),
+                    Arc::new(Box::new(ProgressBar::hidden())),
+                    None,
+                    quota.clone(),
+                ))
+            })
+            .collect();
+        drop(output_tx);
+
+        for worker in workers {
+            tokio::time::timeout(Duration::from_secs(30), worker)
+                .await
+                .expect("The generators should stop once the target is reached.")
+                .unwrap();
+        }
+
+        let mut games = 0;
+        while output_rx.recv().await.is_some() {
+            games += 1;
+        }
+        assert_eq!(games, 5);
+    }
+
+    #[tokio::test]
+    async fn test_games_progress_bar() {
+        let indicator_bar = games_progress_bar(Some(5));
+        indicator_bar.set_draw_target(ProgressDrawTarget::hidden());
+        assert_eq!(indicator_bar.length(), 5);
+        let indicator_bar = Arc::new(Box::new(indicator_bar));
+
+        let (output_tx, mut output_rx) = mpsc::channel(16);
+        let quota = GameQuota::new(Some(5));
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                tokio::spawn(alphazero_game_generator_task(
+                    alpha_config(),
+                    config(None),
+                    TicTacToeBuilder::default(),
+                    mock_uniform_channel::<TicTacToe>(&()),
+                    output_tx.clone(),
+                    indicator_bar.clone(),
+                    None,
+                    quota.clone(),
+                ))
+            })
+            .collect();
+        drop(output_tx);
+
+        for worker in workers {
+            worker.await.unwrap();
+        }
+        while output_rx.recv().await.is_some() {}
+
+        assert_eq!(indicator_bar.position(), 5);
+        assert!(indicator_bar.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_opening_book() {
+        let (output_tx, mut output_rx) = mpsc::channel(16);
+        let game_builder = OpeningBookGB::new(TicTacToeBuilder::default(), vec![vec![(1, 1)]]);
+        tokio::spawn(alphazero_game_generator_task(
+            alpha_config(),
+            config(None),
+            game_builder,
+            mock_uniform_channel::<TicTacToe>(&()),
+            output_tx,
+            Arc::new(Box::new(ProgressBar::hidden())),
+            None,
+            GameQuota::new(Some(5)),
+        ));
+
+        let mut games = 0;
+        while let Some(entry) = output_rx.recv().await {
+            // the opponent of the player to move owns the center, under any symmetry.
+            let first = entry.state.index_axis(Axis(0), 0);
+            assert_eq!(first.index_axis(Axis(2), 0).sum(), 0.);
+            assert_eq!(first.index_axis(Axis(2), 1).sum(), 1.);
+            assert_eq!(first[[1, 1, 1]], 1.);
+            games += 1;
+        }
+        assert_eq!(games, 5);
+    }
+
+    #[tokio::test]
+    async fn test_arena() {
+        // material balance for the player to move.
+        fn material(features: &Array<f32, ndarray::Ix3>) -> f32 {
+            let own = features.index_axis(Axis(2), 0).sum();
+            let opponent = features.index_axis(Axis(2), 1).sum();
+            (own - opponent) / 10.
+        }
+        let uniform = Array::from_elem(ndarray::Ix3(5, 5, 3), 1. / 75.);
+        let policy = uniform.clone();
+        let strong = mock_alphazero_channel::<Breakthrough, _>(move |features| {
+            (policy.clone(), material(features))
+        });
+        let weak = mock_alphazero_channel::<Breakthrough, _>(move |features| {
+            (uniform.clone(), -material(features))
+        });
+
+        let mut config = game_alpha_config(ndarray::Ix3(5, 5, 3), ndarray::Ix3(5, 5, 3));
+        config.n_playouts = 30;
+        let results = arena_game_generator_task(
+            (config.clone(), config),
+            (strong, weak),
+            BreakthroughBuilder { size: 5 },
+            GameQuota::new(Some(10)),
+        )
+        .await;
+
+        assert_eq!(results.games(), 10);
+        assert!(results.wins > 5, "{:?}", results);
     }
 }
@@ -1,12 +1,9 @@
-use std::path::Path;
-use std::sync::{atomic::AtomicBool, Arc, RwLock};
-use tensorflow::{Graph, Session, SessionOptions, SessionRunArgs, Tensor};
-
-/// Access to a TF model behind Arc and RwLock
-/// the AtomicBool is here to indicate the file loader's intention
-/// to access the lock.
+use crate::deep::inference::Inference;
+use crate::settings::{ModelSignature, StrError};
 
-pub type ThreadSafeModel = Arc<(AtomicBool, RwLock<(Graph, Session)>)>;
+use std::os::raw::c_int;
+use std::path::Path;
+use tensorflow::{Graph, Operation, Session, SessionOptions, SessionRunArgs, Tensor};
 
 fn sign(x: f32) -> f32 {
     if x > 0. {
@@ -18,11 +15,30 @@ fn sign(x: f32) -> f32 {
     }
 }
 
+/// Default `ε` of the value transform.
+pub const VALUE_TRANSFORM_EPSILON: f32 = 0.001;
+
+/// Value transform of MuZero: `h(x) = sign(x) (sqrt(|x| + 1) - 1) + ε x`.
+///
+/// Large values are compressed, so that they fit in a small support.
+pub fn value_transform(x: f32, epsilon: f32) -> f32 {
+    sign(x) * ((x.abs() + 1.).sqrt() - 1.) + epsilon * x
+}
+
+/// Inverse of the value transform `h`.
+pub fn inverse_value_transform(x: f32, epsilon: f32) -> f32 {
+    sign(x)
+        * ((((1. + 4. * epsilon * (x.abs() + 1. + epsilon)).sqrt() - 1.) / (2. * epsilon)).powi(2)
+            - 1.)
+}
+
 /// Converts a suport encoding of scalar to the corresponding value.
+/// The inverse value transform of parameter `ε` is applied if `transform` is `Some(ε)`.
 pub fn support_to_value(
     support: &Tensor<f32>,
     batch_size: usize,
     support_size: usize,
+    transform: Option<f32>,
 ) -> Tensor<f32> {
     let mut res = Tensor::new(&[batch_size as u64]);
 
@@ -31,12 +47,35 @@ pub fn support_to_value(
             .enumerate()
             .map(|(j, v)| support[(2 * support_size + 1) * i + j] * (v as f32))
             .sum();
-        let value: f32 = sign(value)
-            * ((((1. + 4. * 0.001 * (value.abs() + 1. + 0.001)).sqrt() - 1.) / (2. * 0.001))
-                .powi(2)
-                - 1.);
 
-        res[i] = value;
+        res[i] = match transform {
+            Some(epsilon) => inverse_value_transform(value, epsilon),
+            None => value,
+        };
+    }
+    res
+}
+
+/// Converts a value to its support encoding, of shape `[1, 2 * support_size + 1]`.
+///
+/// The value is scaled by the value transform if `transform` is `Some(ε)`, clamped to the
+/// support, and split between the two closest atoms (two-hot encoding).
+/// This is the inverse of `support_to_value`.
+pub fn value_to_support(value: f32, support_size: usize, transform: Option<f32>) -> Tensor<f32> {
+    let mut res = Tensor::new(&[1, 2 * support_size as u64 + 1]);
+
+    let scaled = match transform {
+        Some(epsilon) => value_transform(value, epsilon),
+        None => value,
+    };
+    let scaled = scaled.max(-(support_size as f32)).min(support_size as f32);
+    let floor = scaled.floor();
+    let index = (floor + support_size as f32) as usize;
+    let upper_weight = scaled - floor;
+
+    res[index] = 1. - upper_weight;
+    if index < 2 * support_size {
+        res[index + 1] = upper_weight;
     }
     res
 }
@@ -51,97 +90,213 @@ mod tests {
         support[0] = 1.0;
         support[1] = 0.;
         support[2] = 0.;
-        println!("=> {:?}", support_to_value(&support, 1, 1).to_vec());
+        println!(
+            "=> {:?}",
+            support_to_value(&support, 1, 1, Some(VALUE_TRANSFORM_EPSILON)).to_vec()
+        );
 
         support[0] = 0.;
         support[1] = 1.;
         support[2] = 0.;
-        println!("=> {:?}", support_to_value(&support, 1, 1).to_vec());
+        println!(
+            "=> {:?}",
+            support_to_value(&support, 1, 1, Some(VALUE_TRANSFORM_EPSILON)).to_vec()
+        );
 
         support[0] = 0.;
         support[1] = 0.;
         support[2] = 1.;
-        println!("=> {:?}", support_to_value(&support, 1, 1).to_vec());
+        println!(
+            "=> {:?}",
+            support_to_value(&support, 1, 1, Some(VALUE_TRANSFORM_EPSILON)).to_vec()
+        );
+    }
+
+    #[test]
+    fn test_value_support_round_trip() {
+        let support_size = 20;
+        for value in &[
+            -150., -12.5, -3.7, -1., -0.25, 0., 0.3, 1., 2.5, 7.75, 42., 123.4,
+        ] {
+            let support = value_to_support(*value, support_size, Some(VALUE_TRANSFORM_EPSILON));
+            assert_eq!(support.dims(), &[1, 2 * support_size as u64 + 1]);
+            assert!((support.iter().sum::<f32>() - 1.).abs() < 1e-6);
+            assert!(support.iter().filter(|x| **x > 0.).count() <= 2);
+
+            let decoded =
+                support_to_value(&support, 1, support_size, Some(VALUE_TRANSFORM_EPSILON))[0];
+            assert!(
+                (decoded - value).abs() < 1e-3 * value.abs().max(1.),
+                "{} decoded as {}",
+                value,
+                decoded
+            );
+        }
+    }
+
+    #[test]
+    fn test_value_support_clamp() {
+        let support = value_to_support(1e6, 2, None);
+        assert_eq!(support.to_vec(), vec![0., 0., 0., 0., 1.]);
+        let support = value_to_support(-1e6, 2, None);
+        assert_eq!(support.to_vec(), vec![1., 0., 0., 0., 0.]);
+    }
+
+    #[test]
+    fn test_value_transform() {
+        for epsilon in &[VALUE_TRANSFORM_EPSILON, 0.01] {
+            for value in &[-1000., -20.5, -1., -0.1, 0., 0.5, 3., 99.9, 1000.] {
+                let transformed = value_transform(*value, *epsilon);
+                let restored = inverse_value_transform(transformed, *epsilon);
+                assert!(
+                    (restored - value).abs() < 1e-3 * value.abs().max(1.),
+                    "{} restored as {}",
+                    value,
+                    restored
+                );
+            }
+        }
+
+        // large rewards are compressed to the order of their square root.
+        let compressed = value_transform(10000., VALUE_TRANSFORM_EPSILON);
+        assert!((compressed - 109.).abs() < 0.1);
+        assert_eq!(
+            value_transform(-10000., VALUE_TRANSFORM_EPSILON),
+            -compressed
+        );
+        assert!(value_transform(0.5, VALUE_TRANSFORM_EPSILON) < 0.5);
     }
 }
 
-/// Use prediction network inference.
-pub fn call_prediction(
-    session: &Session,
-    graph: &Graph,
-    board: &Tensor<f32>,
-) -> (Tensor<f32>, Tensor<f32>) {
-    let board_op = graph
-        .operation_by_name_required("serving_default_board")
-        .unwrap();
-    let output_op = graph
-        .operation_by_name_required("StatefulPartitionedCall")
-        .unwrap();
-    let mut args = SessionRunArgs::new();
-    args.add_feed(&board_op, 0, board);
-
-    let policy_req = args.request_fetch(&output_op, 0);
-    let value_req = args.request_fetch(&output_op, 1);
-    session.run(&mut args).unwrap();
-
-    let policy_tensor: Tensor<f32> = args.fetch(policy_req).unwrap();
-    let value_tensor: Tensor<f32> = args.fetch(value_req).unwrap();
-    (policy_tensor, value_tensor)
+/// Default name of the board input operation.
+const BOARD_INPUT: &str = "serving_default_board";
+/// Default name of the action input operation.
+const ACTION_INPUT: &str = "serving_default_action";
+/// Default name of the operation computing the outputs.
+const OUTPUT: &str = "StatefulPartitionedCall";
+
+/// Operation output: the operation and the index of the output.
+type OperationOutput = (Operation, c_int);
+
+/// TensorFlow saved model.
+pub struct TFModel {
+    // the operations refer to the graph, it is kept along with the session.
+    _graph: Graph,
+    session: Session,
+    board: OperationOutput,
+    action: Option<OperationOutput>,
+    outputs: Vec<OperationOutput>,
 }
 
-/// Use dynamics network inference.
-pub fn call_dynamics(
-    session: &Session,
-    graph: &Graph,
-    board: &Tensor<f32>,
-    action: &Tensor<f32>,
-) -> (Tensor<f32>, Tensor<f32>) {
-    let board_op = graph
-        .operation_by_name_required("serving_default_board")
-        .unwrap();
-    let action_op = graph
-        .operation_by_name_required("serving_default_action")
-        .unwrap();
-    let output_op = graph
-        .operation_by_name_required("StatefulPartitionedCall")
-        .unwrap();
-    let mut args = SessionRunArgs::new();
-    args.add_feed(&board_op, 0, board);
-    args.add_feed(&action_op, 0, action);
-
-    let reward_req = args.request_fetch(&output_op, 1);
-    let next_board_req = args.request_fetch(&output_op, 0);
-    session.run(&mut args).unwrap();
-
-    let reward_tensor: Tensor<f32> = args.fetch(reward_req).unwrap();
-    let next_board_tensor: Tensor<f32> = args.fetch(next_board_req).unwrap();
-    (reward_tensor, next_board_tensor)
+impl Drop for TFModel {
+    fn drop(&mut self) {
+        if self.session.close().is_err() {
+            log::warn!("Unable to close the session.");
+        }
+    }
 }
 
-/// Use representation network inference.
-pub fn call_representation(session: &Session, graph: &Graph, board: &Tensor<f32>) -> Tensor<f32> {
-    let board_op = graph
-        .operation_by_name_required("serving_default_board")
-        .unwrap();
-    let output_op = graph
-        .operation_by_name_required("StatefulPartitionedCall")
-        .unwrap();
-    let mut args = SessionRunArgs::new();
-    args.add_feed(&board_op, 0, board);
-
-    let repr_board_req = args.request_fetch(&output_op, 0);
-    session.run(&mut args).unwrap();
-
-    let repr_board_tensor: Tensor<f32> = args.fetch(repr_board_req).unwrap();
-    repr_board_tensor
+impl TFModel {
+    /// Output of the given index in the signature.
+    fn output(&self, index: usize) -> &OperationOutput {
+        self.outputs
+            .get(index)
+            .unwrap_or_else(|| panic!("The model signature has no output {}.", index))
+    }
 }
 
-/// Load a tensorflow model into a session.
-pub fn load_model(path: &str) -> (Graph, Session) {
+impl Inference for TFModel {
+    fn call_prediction(&self, board: &Tensor<f32>) -> (Tensor<f32>, Tensor<f32>) {
+        let mut args = SessionRunArgs::new();
+        args.add_feed(&self.board.0, self.board.1, board);
+
+        let (policy_op, policy_index) = self.output(0);
+        let (value_op, value_index) = self.output(1);
+        let policy_req = args.request_fetch(policy_op, *policy_index);
+        let value_req = args.request_fetch(value_op, *value_index);
+        self.session.run(&mut args).unwrap();
+
+        let policy_tensor: Tensor<f32> = args.fetch(policy_req).unwrap();
+        let value_tensor: Tensor<f32> = args.fetch(value_req).unwrap();
+        (policy_tensor, value_tensor)
+    }
+
+    fn call_dynamics(
+        &self,
+        board: &Tensor<f32>,
+        action: &Tensor<f32>,
+    ) -> (Tensor<f32>, Tensor<f32>) {
+        let (action_op, action_index) = self
+            .action
+            .as_ref()
+            .expect("The model has no action input.");
+        let mut args = SessionRunArgs::new();
+        args.add_feed(&self.board.0, self.board.1, board);
+        args.add_feed(action_op, *action_index, action);
+
+        let (next_board_op, next_board_index) = self.output(0);
+        let (reward_op, reward_index) = self.output(1);
+        let reward_req = args.request_fetch(reward_op, *reward_index);
+        let next_board_req = args.request_fetch(next_board_op, *next_board_index);
+        self.session.run(&mut args).unwrap();
+
+        let reward_tensor: Tensor<f32> = args.fetch(reward_req).unwrap();
+        let next_board_tensor: Tensor<f32> = args.fetch(next_board_req).unwrap();
+        (reward_tensor, next_board_tensor)
+    }
+
+    fn call_representation(&self, board: &Tensor<f32>) -> Tensor<f32> {
+        let mut args = SessionRunArgs::new();
+        args.add_feed(&self.board.0, self.board.1, board);
+
+        let (repr_board_op, repr_board_index) = self.output(0);
+        let repr_board_req = args.request_fetch(repr_board_op, *repr_board_index);
+        self.session.run(&mut args).unwrap();
+
+        let repr_board_tensor: Tensor<f32> = args.fetch(repr_board_req).unwrap();
+        repr_board_tensor
+    }
+}
+
+/// Names of the placeholder operations of the graph: the inputs of the model.
+fn input_names(graph: &Graph) -> Vec<String> {
+    graph
+        .operation_iter()
+        .filter(|op| {
+            op.op_type()
+                .map_or(false, |op_type| op_type == "Placeholder")
+        })
+        .filter_map(|op| op.name().ok())
+        .collect()
+}
+
+/// Operation output named `name`, written `operation` or `operation:index`.
+fn operation_output(graph: &Graph, name: &str) -> Result<OperationOutput, StrError> {
+    let (op_name, index) = match name.rfind(':') {
+        Some(i) => match name[i + 1..].parse() {
+            Ok(index) => (&name[..i], index),
+            Err(_) => (name, 0),
+        },
+        None => (name, 0),
+    };
+
+    match graph.operation_by_name(op_name) {
+        Ok(Some(op)) => Ok((op, index)),
+        _ => Err(StrError(format!(
+            "Operation `{}` not found in the model. Available inputs: {}.",
+            op_name,
+            input_names(graph).join(", ")
+        ))),
+    }
+}
+
+/// Load a tensorflow model into a session, with the inputs and outputs named by `signature`.
+///
+/// Fails if the model can't be loaded or if an operation of the signature is missing.
+pub fn load_model(path: &str, signature: &ModelSignature) -> Result<TFModel, StrError> {
     /* check that model exists. */
     if !Path::new(path).exists() {
-        log::error!("Couldn't find model at {}", path);
-        panic!("");
+        return Err(StrError(format!("Couldn't find model at {}", path)));
     };
 
     let mut graph = Graph::new();
@@ -153,6 +308,34 @@ pub fn load_model(path: &str) -> (Graph, Session) {
      */
     let configuration_buf = [50, 2, 32, 1];
     options.set_config(&configuration_buf).unwrap();
-    let session = Session::from_saved_model(&options, &["serve"], &mut graph, path).unwrap();
-    (graph, session)
+    let session = Session::from_saved_model(&options, &["serve"], &mut graph, path)
+        .map_err(|e| StrError(format!("Unable to load the model at {}: {}", path, e)))?;
+
+    let resolve = |name: &str| {
+        operation_output(&graph, name).map_err(|e| StrError(format!("{}: {}", path, e)))
+    };
+    let board = resolve(signature.board.as_deref().unwrap_or(BOARD_INPUT))?;
+    // only the dynamics network has an action input.
+    let action = match signature.action.as_deref() {
+        Some(name) => Some(resolve(name)?),
+        None => resolve(ACTION_INPUT).ok(),
+    };
+    let outputs = match signature.outputs.as_ref() {
+        Some(names) => names
+            .iter()
+            .map(|name| resolve(name))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => {
+            let (output, _) = resolve(OUTPUT)?;
+            vec![(output.clone(), 0), (output, 1)]
+        }
+    };
+
+    Ok(TFModel {
+        _graph: graph,
+        session,
+        board,
+        action,
+        outputs,
+    })
 }
@@ -1,10 +1,13 @@
 use crate::game::*;
+use crate::settings::StrError;
 
 use ansi_term::Colour::Fixed;
 use ansi_term::Style;
 use async_trait::async_trait;
 use ndarray::{Array, ArrayView, Axis, Ix2};
+use rand::seq::SliceRandom;
 use rand::Rng;
+use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::iter::FromIterator;
@@ -14,7 +17,7 @@ pub mod ui;
 /// Players
 ///
 /// Two colors: black and white.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Color {
     /// Black
     Black = 0,
@@ -71,7 +74,7 @@ impl fmt::Debug for Color {
 /// Game cell
 ///
 /// Represents a position on the board.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Cell {
     /// Empty cell.
     Empty,
@@ -93,7 +96,7 @@ impl fmt::Debug for Cell {
 /// Move direction
 ///
 /// Possible move directions relative to the pawn position.
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum MoveDirection {
     /// Front
     Front,
@@ -116,7 +119,7 @@ impl MoveDirection {
 /// Move
 ///
 /// Describes a potentially legal action on the board.
-#[derive(Hash, Eq, PartialEq, Copy, Clone)]
+#[derive(Hash, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub struct Move {
     /// Player
     pub color: Color,
@@ -195,8 +198,42 @@ impl Move {
     }
 }
 
+/// Weights of the Breakthrough static evaluation (see `Breakthrough::heuristic_value`).
+///
+/// The score of a position is the sum of the values of the pawns of the player, minus
+/// the values of the opponent's pawns, divided by the board size and squashed with `tanh`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeuristicParams {
+    /// Value of a pawn. Defaults to 1.
+    pub pawn: f32,
+    /// Bonus of a pawn on the opponent's home row side, scaled by the square of its
+    /// advancement (between 0 on its home row and 1 on the goal row). Defaults to 1.
+    pub advancement: f32,
+    /// Bonus of a pawn one move away from the goal row that the opponent can capture.
+    /// Defaults to 0.5.
+    pub threat: f32,
+    /// Value of a game decided in one move: the player to move has a pawn next to the
+    /// goal row, or the opponent has one that can't be captured. Defaults to 0.95,
+    /// `None` disables the detection.
+    pub decided: Option<f32>,
+}
+
+impl Default for HeuristicParams {
+    fn default() -> Self {
+        HeuristicParams {
+            pawn: 1.,
+            advancement: 1.,
+            threat: 0.5,
+            decided: Some(0.95),
+        }
+    }
+}
+
 /// Breakthrough game state instance
-#[derive(Clone, Eq)]
+///
+/// The transposition table is serialized along with the board, so that a restored game
+/// keeps the same hash.
+#[derive(Clone, Eq, Serialize, Deserialize)]
 pub struct Breakthrough {
     K: usize,
     content: ndarray::Array2<Cell>,
@@ -204,6 +241,9 @@ pub struct Breakthrough {
     transposition: ndarray::Array3<usize>,
     hash: usize,
     turn: Color,
+
+    /// Positions of the pawns of each color, kept in sync with `content`.
+    positions: [Vec<(usize, usize)>; 2],
 }
 
 impl PartialEq for Breakthrough {
@@ -214,48 +254,20 @@ impl PartialEq for Breakthrough {
 
 impl Hash for Breakthrough {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.transposition.hash(state)
+        self.hash.hash(state);
+        self.turn.hash(state)
     }
 }
 
+/// Whether boards are printed with ANSI colors: the standard output has to be a terminal,
+/// and the `NO_COLOR` environment variable must not be set.
+fn use_colors() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && nix::unistd::isatty(1).unwrap_or(false)
+}
+
 impl fmt::Debug for Breakthrough {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let style = Style::new().on(Fixed(0));
-        writeln!(f, "Turn: {:?}", self.turn)?;
-        writeln!(
-            f,
-            "{}{}{}",
-            style.paint("╔"),
-            style.paint("══╤".repeat(self.K - 1)),
-            style.paint("══╗")
-        )?;
-        for y in 0..self.K {
-            if y != 0 {
-                writeln!(
-                    f,
-                    "{}{}{}",
-                    style.paint("╟"),
-                    style.paint("──┼".repeat(self.K - 1)),
-                    style.paint("──╢")
-                )?;
-            }
-            write!(f, "{}", style.paint("║"))?;
-            for x in 0..self.K {
-                if x == 0 {
-                    write!(f, "{:?}", self.content[[x, y]])?;
-                } else {
-                    write!(f, "{}{:?}", style.paint("│"), self.content[[x, y]])?;
-                }
-            }
-            writeln!(f, "║")?;
-        }
-        writeln!(
-            f,
-            "{}{}{}",
-            style.paint("╚"),
-            style.paint("══╧".repeat(self.K - 1)),
-            style.paint("══╝")
-        )
+        self.write_board(f, use_colors())
     }
 }
 
@@ -272,57 +284,41 @@ impl GameBuilder for BreakthroughBuilder {
     type G = Breakthrough;
 
     async fn create(&self, turn: Color) -> Breakthrough {
-        let mut rng = rand::thread_rng();
         let K = self.size;
         let mut content = Array::from_elem([K, K], Cell::Empty);
-        let mut transposition = Array::from_elem([2, K, K], 0);
 
-        for (x, mut column) in content.axis_iter_mut(Axis(0)).enumerate() {
+        for mut column in content.axis_iter_mut(Axis(0)) {
             column[0] = Cell::C(Color::Black);
             column[1] = Cell::C(Color::Black);
             column[K - 2] = Cell::C(Color::White);
             column[K - 1] = Cell::C(Color::White);
-
-            for y in 0..K {
-                transposition[[Color::Black as usize, x, y]] = rng.gen::<usize>();
-                transposition[[Color::White as usize, x, y]] = rng.gen::<usize>()
-            }
         }
 
-        Breakthrough {
-            turn,
-            content,
-            transposition,
-            hash: 0,
-            K,
-        }
+        Breakthrough::with_content(content, turn)
+    }
+
+    /// Creates a game from the board part of the notation (see `Breakthrough::to_notation`).
+    async fn create_from(&self, position: &str, turn: Color) -> Result<Breakthrough, StrError> {
+        let side = match turn {
+            Color::Black => "b",
+            Color::White => "w",
+        };
+        Breakthrough::from_notation(&format!("{} {}", position, side), self.size)
     }
 }
 
 impl SingleWinner for Breakthrough {
     fn winner(&self) -> Option<Self::Player> {
-        let mut some_black = false;
-        let mut some_white = false;
-
-        for i in 0..self.K {
-            if self.content[[i, self.K - 1]] == Cell::C(Color::Black) {
-                return Some(Color::Black);
-            } else if self.content[[i, 0]] == Cell::C(Color::White) {
-                return Some(Color::White);
-            }
-
-            for j in 0..self.K {
-                if self.content[[i, j]] == Cell::C(Color::White) {
-                    some_white = true;
-                } else if self.content[[i, j]] == Cell::C(Color::Black) {
-                    some_black = true;
-                }
-            }
-        }
+        let black = &self.positions[Color::Black as usize];
+        let white = &self.positions[Color::White as usize];
 
-        if !some_white {
+        if black.iter().any(|&(_, y)| y == self.K - 1) {
+            Some(Color::Black)
+        } else if white.iter().any(|&(_, y)| y == 0) {
+            Some(Color::White)
+        } else if white.is_empty() {
             Some(Color::Black)
-        } else if !some_black {
+        } else if black.is_empty() {
             Some(Color::White)
         } else {
             None
@@ -343,6 +339,36 @@ impl Game for Breakthrough {
     fn turn(&self) -> Color {
         self.turn
     }
+
+    /// When white is to move, the board is rotated by a half-turn and the colors are
+    /// swapped, so that black is always to move. The transposition table is kept, so
+    /// that hashes of canonical positions can be compared.
+    fn canonical(&self) -> Breakthrough {
+        if self.turn == Color::Black {
+            return self.clone();
+        }
+        let K = self.K;
+        let content = Array::from_shape_fn([K, K], |(x, y)| {
+            match self.content[[K - 1 - x, K - 1 - y]] {
+                Cell::C(color) => Cell::C(color.adv()),
+                Cell::Empty => Cell::Empty,
+            }
+        });
+        Breakthrough::with_table(content, Color::Black, self.transposition.clone())
+    }
+
+    fn canonical_move(&self, action: &Move) -> Move {
+        if self.turn == Color::Black {
+            return *action;
+        }
+        // a half-turn keeps the directions relative to the player.
+        Move {
+            color: action.color.adv(),
+            x: self.K - 1 - action.x,
+            y: self.K - 1 - action.y,
+            direction: action.direction,
+        }
+    }
 }
 
 impl Base for Breakthrough {
@@ -353,26 +379,8 @@ impl Base for Breakthrough {
             return vec![];
         }
         let mut res = vec![];
-        for x in 0..self.K {
-            for y in 0..self.K {
-                if self.content[[x, y]] == Cell::C(self.turn) {
-                    for direction in &[
-                        MoveDirection::Front,
-                        MoveDirection::FrontLeft,
-                        MoveDirection::FrontRight,
-                    ] {
-                        let action = Move {
-                            color: self.turn,
-                            x,
-                            y,
-                            direction: *direction,
-                        };
-                        if action.is_valid(self.content.view()).is_some() {
-                            res.push(action)
-                        }
-                    }
-                }
-            }
+        for &(x, y) in self.positions[self.turn as usize].iter() {
+            self.push_valid_moves(x, y, &mut res);
         }
         res
     }
@@ -390,35 +398,31 @@ impl Playable for Breakthrough {
         }
         match m.is_valid(self.content.view()) {
             None => -1.,
-            Some((px, py)) => {
-                let mut c_hash = 0;
-                if let Cell::C(color) = self.content[[m.x, m.y]] {
-                    // remove cell from initial position
-                    c_hash ^= self.transposition[[color as usize, m.x, m.y]];
-                    // add cell to new position
-                    c_hash ^= self.transposition[[color as usize, px, py]];
-                }
-                if let Cell::C(color) = self.content[[px, py]] {
-                    // eat the other cell
-                    c_hash ^= self.transposition[[color as usize, px, py]];
-                }
-                self.hash ^= c_hash;
-                assert_eq!(self.content[[m.x, m.y]], Cell::C(self.turn));
-                assert_ne!(self.content[[px, py]], Cell::C(self.turn));
-
-                self.content[[px, py]] = self.content[[m.x, m.y]];
-                self.content[[m.x, m.y]] = Cell::Empty;
-
-                let reward = if self.winner() == Some(self.turn()) {
+            Some(target) => {
+                let player = self.turn;
+                self.apply_move_unchecked(m, target);
+                if self.winner() == Some(player) {
                     1.
                 } else {
                     0.
-                };
-                self.turn = self.turn.adv();
-                reward
+                }
             }
         }
     }
+
+    /// Random moves are drawn from the legal moves, so they are played without validation.
+    async fn random_move(&mut self) -> (Move, f32) {
+        let player = self.turn;
+        let actions = self.possible_moves();
+        let action = *actions.choose(&mut rand::thread_rng()).unwrap();
+        self.apply_move_unchecked(&action, action.target());
+        let reward = if self.winner() == Some(player) {
+            1.
+        } else {
+            0.
+        };
+        (action, reward)
+    }
 }
 
 impl Breakthrough {
@@ -426,6 +430,583 @@ impl Breakthrough {
     pub fn show(&self) {
         println!("{:?}", self);
     }
+
+    /// Plays `m`, moving the pawn to `target`, without checking that the move is legal.
+    ///
+    /// The caller guarantees that `m` is a legal move of the player to move, as given by
+    /// `possible_moves`, and that `target` is its target. Board, pawn lists, hash and turn
+    /// are updated.
+    pub fn apply_move_unchecked(&mut self, m: &Move, target: (usize, usize)) {
+        self.apply_with_undo(m, target);
+    }
+
+    /// Number of distinct move sequences of `depth` plies from the position (perft).
+    ///
+    /// Moves are played without validation and undone, on a single copy of the board.
+    pub fn perft(&self, depth: usize) -> u64 {
+        self.clone().perft_in_place(depth)
+    }
+
+    fn perft_in_place(&mut self, depth: usize) -> u64 {
+        let moves = self.possible_moves();
+        match depth {
+            0 => 1,
+            1 => moves.len() as u64,
+            _ => moves
+                .iter()
+                .map(|m| {
+                    let undo = self.apply_with_undo(m, m.target());
+                    let count = self.perft_in_place(depth - 1);
+                    self.undo(undo);
+                    count
+                })
+                .sum(),
+        }
+    }
+
+    /// Plays a legal move like `apply_move_unchecked`, returning how to undo it.
+    fn apply_with_undo(&mut self, m: &Move, target: (usize, usize)) -> BTUndo {
+        let (px, py) = target;
+        let mut c_hash = 0;
+        if let Cell::C(color) = self.content[[m.x, m.y]] {
+            // remove cell from initial position
+            c_hash ^= self.transposition[[color as usize, m.x, m.y]];
+            // add cell to new position
+            c_hash ^= self.transposition[[color as usize, px, py]];
+        }
+        let mut captured = None;
+        if let Cell::C(color) = self.content[[px, py]] {
+            // eat the other cell
+            c_hash ^= self.transposition[[color as usize, px, py]];
+
+            let eaten = &mut self.positions[color as usize];
+            let index = eaten.iter().position(|&p| p == (px, py)).unwrap();
+            eaten.swap_remove(index);
+            captured = Some((color, index));
+        }
+        self.hash ^= c_hash;
+
+        let moved = &mut self.positions[self.turn as usize];
+        let index = moved.iter().position(|&p| p == (m.x, m.y)).unwrap();
+        moved[index] = (px, py);
+
+        self.content[[px, py]] = self.content[[m.x, m.y]];
+        self.content[[m.x, m.y]] = Cell::Empty;
+        self.turn = self.turn.adv();
+
+        BTUndo {
+            from: (m.x, m.y),
+            to: target,
+            captured,
+            hash_delta: c_hash,
+        }
+    }
+
+    /// Cells of the pawns of `color`, in no particular order.
+    pub fn pieces(&self, color: Color) -> Vec<(usize, usize)> {
+        self.positions[color as usize].clone()
+    }
+
+    /// Number of pawns of `color`.
+    pub fn piece_count(&self, color: Color) -> usize {
+        self.positions[color as usize].len()
+    }
+
+    /// Cell of the pawn of `color` closest to the opponent's home row, if any pawn is left.
+    /// Among pawns on the same row, the one with the lowest column is returned.
+    pub fn most_advanced(&self, color: Color) -> Option<(usize, usize)> {
+        self.positions[color as usize]
+            .iter()
+            .copied()
+            .max_by_key(|&(x, y)| {
+                let advancement = match color {
+                    Color::Black => y,
+                    Color::White => self.K - 1 - y,
+                };
+                (advancement, std::cmp::Reverse(x))
+            })
+    }
+
+    /// Static evaluation of the position for `pov`, in [-1, 1], to be used as a
+    /// UCT leaf heuristic.
+    ///
+    /// Each pawn is worth 1, plus a bonus growing with the square of its advancement
+    /// towards the opponent's home row. Finished games are valued 1 or -1.
+    pub fn material_heuristic(&self, pov: Color) -> f32 {
+        self.heuristic_value_with(
+            pov,
+            &HeuristicParams {
+                threat: 0.,
+                decided: None,
+                ..HeuristicParams::default()
+            },
+        )
+    }
+
+    /// Static evaluation of the position for `pov`, in [-1, 1], with the default weights:
+    /// material, advancement and threats to reach the goal row.
+    pub fn heuristic_value(&self, pov: Color) -> f32 {
+        self.heuristic_value_with(pov, &HeuristicParams::default())
+    }
+
+    /// Static evaluation of the position for `pov`, in [-1, 1], with the given weights.
+    /// Finished games are valued 1 or -1.
+    pub fn heuristic_value_with(&self, pov: Color, params: &HeuristicParams) -> f32 {
+        if let Some(winner) = self.winner() {
+            return if winner == pov { 1. } else { -1. };
+        }
+
+        let sign = |color: Color| if color == pov { 1. } else { -1. };
+        if let Some(decided) = params.decided {
+            // pawns of the player to move next to the goal row always have a winning move.
+            if !self.threats(self.turn).is_empty() {
+                return sign(self.turn) * decided;
+            }
+            let opponent = self.turn.adv();
+            if self
+                .threats(opponent)
+                .iter()
+                .any(|&(x, y)| !self.is_attacked(x, y, self.turn))
+            {
+                return sign(opponent) * decided;
+            }
+        }
+
+        let mut score = 0.;
+        for ((_, y), cell) in self.content.indexed_iter() {
+            if let Cell::C(color) = cell {
+                let advancement = match color {
+                    Color::Black => y,
+                    Color::White => self.K - 1 - y,
+                } as f32
+                    / (self.K - 1) as f32;
+                let mut value = params.pawn + params.advancement * advancement * advancement;
+                if self.is_threat(y, *color) {
+                    value += params.threat;
+                }
+                score += sign(*color) * value;
+            }
+        }
+        (score / self.K as f32).tanh()
+    }
+
+    /// Whether a pawn of `color` on row `y` is one move away from the goal row.
+    fn is_threat(&self, y: usize, color: Color) -> bool {
+        match color {
+            Color::Black => y + 2 == self.K,
+            Color::White => y == 1,
+        }
+    }
+
+    /// Pawns of `color` one move away from the goal row.
+    fn threats(&self, color: Color) -> Vec<(usize, usize)> {
+        self.positions[color as usize]
+            .iter()
+            .copied()
+            .filter(|&(_, y)| self.is_threat(y, color))
+            .collect()
+    }
+
+    /// Whether a pawn of `color` can capture the cell `(x, y)`.
+    fn is_attacked(&self, x: usize, y: usize, color: Color) -> bool {
+        // attackers are one row behind the cell, from the point of view of `color`.
+        let from_y = match color {
+            Color::Black => y.checked_sub(1),
+            Color::White => Some(y + 1).filter(|y| *y < self.K),
+        };
+        from_y.map_or(false, |from_y| {
+            [x.checked_sub(1), Some(x + 1).filter(|x| *x < self.K)]
+                .iter()
+                .flatten()
+                .any(|&from_x| self.content[[from_x, from_y]] == Cell::C(color))
+        })
+    }
+
+    /// Board rendering without ANSI escape codes, for logs and files.
+    /// Black and white pawns are drawn `BB` and `WW`.
+    pub fn display_plain(&self) -> String {
+        let mut board = String::new();
+        self.write_board(&mut board, false).unwrap();
+        board
+    }
+
+    fn write_board<W: fmt::Write>(&self, f: &mut W, colored: bool) -> fmt::Result {
+        let style = if colored {
+            Style::new().on(Fixed(0))
+        } else {
+            Style::new()
+        };
+        let cell = |cell: Cell| match (cell, colored) {
+            (cell, true) => format!("{:?}", cell),
+            (Cell::Empty, false) => "  ".to_owned(),
+            (Cell::C(Color::Black), false) => "BB".to_owned(),
+            (Cell::C(Color::White), false) => "WW".to_owned(),
+        };
+
+        writeln!(f, "Turn: {:?}", self.turn)?;
+        writeln!(
+            f,
+            "{}{}{}",
+            style.paint("╔"),
+            style.paint("══╤".repeat(self.K - 1)),
+            style.paint("══╗")
+        )?;
+        for y in 0..self.K {
+            if y != 0 {
+                writeln!(
+                    f,
+                    "{}{}{}",
+                    style.paint("╟"),
+                    style.paint("──┼".repeat(self.K - 1)),
+                    style.paint("──╢")
+                )?;
+            }
+            write!(f, "{}", style.paint("║"))?;
+            for x in 0..self.K {
+                if x == 0 {
+                    write!(f, "{}", cell(self.content[[x, y]]))?;
+                } else {
+                    write!(f, "{}{}", style.paint("│"), cell(self.content[[x, y]]))?;
+                }
+            }
+            writeln!(f, "║")?;
+        }
+        writeln!(
+            f,
+            "{}{}{}",
+            style.paint("╚"),
+            style.paint("══╧".repeat(self.K - 1)),
+            style.paint("══╝")
+        )
+    }
+
+    /// Game with the given board, drawing a new transposition table.
+    fn with_content(content: ndarray::Array2<Cell>, turn: Color) -> Breakthrough {
+        let K = content.len_of(Axis(0));
+        let mut rng = rand::thread_rng();
+        let transposition = Array::from_shape_fn([2, K, K], |_| rng.gen::<usize>());
+        Breakthrough::with_table(content, turn, transposition)
+    }
+
+    /// Game with the given board and transposition table.
+    fn with_table(
+        content: ndarray::Array2<Cell>,
+        turn: Color,
+        transposition: ndarray::Array3<usize>,
+    ) -> Breakthrough {
+        let K = content.len_of(Axis(0));
+        let mut hash = 0;
+        let mut positions = [vec![], vec![]];
+        for ((x, y), cell) in content.indexed_iter() {
+            if let Cell::C(color) = cell {
+                hash ^= transposition[[*color as usize, x, y]];
+                positions[*color as usize].push((x, y));
+            }
+        }
+
+        Breakthrough {
+            turn,
+            content,
+            transposition,
+            hash,
+            K,
+            positions,
+        }
+    }
+
+    /// Pushes the valid moves of the pawn at `(x, y)`.
+    fn push_valid_moves(&self, x: usize, y: usize, res: &mut Vec<Move>) {
+        for direction in &[
+            MoveDirection::Front,
+            MoveDirection::FrontLeft,
+            MoveDirection::FrontRight,
+        ] {
+            let action = Move {
+                color: self.turn,
+                x,
+                y,
+                direction: *direction,
+            };
+            if action.is_valid(self.content.view()).is_some() {
+                res.push(action)
+            }
+        }
+    }
+
+    /// Legal moves found by scanning the whole board.
+    ///
+    /// Reference implementation of `possible_moves`, which only visits the pawns
+    /// of the player to move.
+    pub fn possible_moves_rescan(&self) -> Vec<Move> {
+        if self.is_finished() {
+            return vec![];
+        }
+        let mut res = vec![];
+        for x in 0..self.K {
+            for y in 0..self.K {
+                if self.content[[x, y]] == Cell::C(self.turn) {
+                    self.push_valid_moves(x, y, &mut res);
+                }
+            }
+        }
+        res
+    }
+
+    /// Compact position string, in the spirit of FEN.
+    ///
+    /// Ranks are listed from the last one (`y = size - 1`) to the first one, separated
+    /// by `/`. In each rank, `B` and `W` are black and white pawns, and digits count
+    /// consecutive empty cells. The side to move follows: `b` or `w`.
+    ///
+    /// The starting 5x5 position with black to move is `WWWWW/WWWWW/5/BBBBB/BBBBB b`.
+    pub fn to_notation(&self) -> String {
+        let ranks: Vec<String> = (0..self.K)
+            .rev()
+            .map(|y| {
+                let mut rank = String::new();
+                let mut empty = 0;
+                for x in 0..self.K {
+                    match self.content[[x, y]] {
+                        Cell::Empty => empty += 1,
+                        Cell::C(color) => {
+                            if empty > 0 {
+                                rank.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+                            rank.push(match color {
+                                Color::Black => 'B',
+                                Color::White => 'W',
+                            });
+                        }
+                    }
+                }
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                }
+                rank
+            })
+            .collect();
+
+        let turn = match self.turn {
+            Color::Black => 'b',
+            Color::White => 'w',
+        };
+        format!("{} {}", ranks.join("/"), turn)
+    }
+
+    /// Parses a position written by `to_notation` on a board of the given size.
+    pub fn from_notation(notation: &str, size: usize) -> Result<Breakthrough, StrError> {
+        let error = |message: String| {
+            StrError(format!(
+                "Invalid breakthrough position `{}`: {}.",
+                notation, message
+            ))
+        };
+
+        let fields: Vec<&str> = notation.split_whitespace().collect();
+        if fields.len() != 2 {
+            return Err(error("expected the board and the side to move".into()));
+        }
+
+        let turn = match fields[1] {
+            "b" => Color::Black,
+            "w" => Color::White,
+            turn => return Err(error(format!("unknown side to move `{}`", turn))),
+        };
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != size {
+            return Err(error(format!(
+                "expected {} ranks, found {}",
+                size,
+                ranks.len()
+            )));
+        }
+
+        let mut content = Array::from_elem([size, size], Cell::Empty);
+        for (i, rank) in ranks.iter().enumerate() {
+            let y = size - 1 - i;
+            // number of cells described so far, and pending empty cells.
+            let mut x = 0;
+            let mut empty = 0;
+            for c in rank.chars() {
+                if let Some(digit) = c.to_digit(10) {
+                    empty = 10 * empty + digit as usize;
+                    continue;
+                }
+                x += empty;
+                empty = 0;
+
+                let color = match c {
+                    'B' => Color::Black,
+                    'W' => Color::White,
+                    c => {
+                        return Err(error(format!(
+                            "unexpected character `{}` on rank {}",
+                            c,
+                            y + 1
+                        )))
+                    }
+                };
+                if x >= size {
+                    return Err(error(format!("too many cells on rank {}", y + 1)));
+                }
+                content[[x, y]] = Cell::C(color);
+                x += 1;
+            }
+            x += empty;
+
+            if x != size {
+                return Err(error(format!(
+                    "rank {} has {} cells instead of {}",
+                    y + 1,
+                    x,
+                    size
+                )));
+            }
+        }
+
+        Ok(Breakthrough::with_content(content, turn))
+    }
+}
+
+/// Reads a square such as `b3`, as written by `Move::name`.
+fn parse_square(square: &str, size: usize) -> Option<(usize, usize)> {
+    let mut chars = square.chars();
+    let file = chars.next()?;
+    if !file.is_ascii_lowercase() {
+        return None;
+    }
+    let x = file as usize - 'a' as usize;
+    let y = chars.as_str().parse::<usize>().ok()?.checked_sub(1)?;
+    if x < size && y < size {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+/// Moves are written as in `Move::name`, such as `a2->b3`. The color is optional.
+impl ParseMove for Breakthrough {
+    fn parse_move(&self, notation: &str) -> Result<Move, StrError> {
+        let error = |message: &str| {
+            StrError(format!(
+                "Invalid breakthrough move `{}`: {}.",
+                notation, message
+            ))
+        };
+
+        let squares = notation.split_whitespace().last().unwrap_or("");
+        let squares: Vec<&str> = squares.split("->").collect();
+        if squares.len() != 2 {
+            return Err(error("expected two squares such as `a2->b3`"));
+        }
+        let from = parse_square(squares[0], self.K).ok_or_else(|| error("invalid origin"))?;
+        let to = parse_square(squares[1], self.K).ok_or_else(|| error("invalid target"))?;
+
+        self.possible_moves()
+            .into_iter()
+            .find(|m| (m.x, m.y) == from && m.target() == to)
+            .ok_or_else(|| error("illegal move"))
+    }
+}
+
+/// Effects of a Breakthrough move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BTMoveOutcome {
+    /// Initial position of the pawn.
+    pub from: (usize, usize),
+    /// Final position of the pawn.
+    pub to: (usize, usize),
+    /// Color of the captured pawn, if any.
+    pub captured: Option<Color>,
+    /// Whether the pawn reached the last row of the opponent.
+    pub promotion: bool,
+}
+
+#[async_trait]
+impl DetailedPlayable for Breakthrough {
+    /// `None` if the move was invalid and the board left unchanged.
+    type Outcome = Option<BTMoveOutcome>;
+
+    async fn play_detailed(&mut self, m: &Move) -> (f32, Option<BTMoveOutcome>) {
+        let outcome = m.is_valid(self.content.view()).map(|to| {
+            let captured = match self.content[[to.0, to.1]] {
+                Cell::C(color) => Some(color),
+                Cell::Empty => None,
+            };
+            let last_row = if m.color == Color::Black {
+                self.K - 1
+            } else {
+                0
+            };
+            BTMoveOutcome {
+                from: (m.x, m.y),
+                to,
+                captured,
+                promotion: to.1 == last_row,
+            }
+        });
+        (self.play(m).await, outcome)
+    }
+}
+
+/// Information needed to undo a Breakthrough move.
+#[derive(Debug, Clone, Copy)]
+pub struct BTUndo {
+    from: (usize, usize),
+    to: (usize, usize),
+    /// Color of the captured pawn, and its index in the pawn list.
+    captured: Option<(Color, usize)>,
+    hash_delta: usize,
+}
+
+impl UndoableGame for Breakthrough {
+    type Undo = BTUndo;
+
+    fn play_with_undo(&mut self, action: &Move) -> (f32, BTUndo) {
+        let player = self.turn;
+        let undo = self.apply_with_undo(action, action.target());
+        let reward = if self.winner() == Some(player) {
+            1.
+        } else {
+            0.
+        };
+        (reward, undo)
+    }
+
+    fn undo(&mut self, undo: BTUndo) {
+        self.turn = self.turn.adv();
+        self.hash ^= undo.hash_delta;
+        self.content[[undo.from.0, undo.from.1]] = self.content[[undo.to.0, undo.to.1]];
+        self.content[[undo.to.0, undo.to.1]] = Cell::Empty;
+
+        let moved = &mut self.positions[self.turn as usize];
+        let index = moved.iter().position(|&p| p == undo.to).unwrap();
+        moved[index] = undo.from;
+
+        if let Some((color, index)) = undo.captured {
+            self.content[[undo.to.0, undo.to.1]] = Cell::C(color);
+            // reverts the `swap_remove`, keeping the pawn list order.
+            let eaten = &mut self.positions[color as usize];
+            eaten.push(undo.to);
+            let last = eaten.len() - 1;
+            eaten.swap(index, last);
+        }
+    }
+}
+
+/// Move encoder distinguishing captures (code 1) from other moves (code 0).
+pub struct BTCapture {}
+
+impl MoveCode<Breakthrough> for BTCapture {
+    fn code(game: &Breakthrough, action: &Move) -> usize {
+        let (px, py) = action.target();
+        match game.content[[px, py]] {
+            Cell::C(color) if color != action.color => 1,
+            _ => 0,
+        }
+    }
 }
 
 impl Features for Breakthrough {
@@ -467,6 +1048,13 @@ impl Features for Breakthrough {
         features
     }
 
+    /// Zobrist hash of the board, maintained incrementally.
+    /// The transposition table is drawn when the game is created: hashes of states
+    /// coming from different games can't be compared.
+    fn state_hash(&self) -> u64 {
+        (self.hash as u64) ^ (self.turn as u64)
+    }
+
     fn moves_to_feature(
         descr: &Self::Descriptor,
         moves: &HashMap<Self::Move, f32>,
@@ -510,6 +1098,38 @@ impl Features for Breakthrough {
         )
     }
 
+    fn symmetries(&self) -> Vec<Symmetry> {
+        vec![
+            Symmetry::default(),
+            Symmetry {
+                flip: true,
+                rotations: 0,
+            },
+        ]
+    }
+
+    fn action_symmetry(
+        _: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> Array<f32, Self::ActionDim> {
+        let mut res = symmetry.apply(features);
+        if symmetry.flip {
+            // front left and front right moves are exchanged.
+            let left = res
+                .index_axis(Axis(2), MoveDirection::FrontLeft as usize)
+                .to_owned();
+            let right = res
+                .index_axis(Axis(2), MoveDirection::FrontRight as usize)
+                .to_owned();
+            res.index_axis_mut(Axis(2), MoveDirection::FrontLeft as usize)
+                .assign(&right);
+            res.index_axis_mut(Axis(2), MoveDirection::FrontRight as usize)
+                .assign(&left);
+        }
+        res
+    }
+
     fn all_possible_moves(K: &Self::Descriptor) -> Vec<Self::Move> {
         let mut res = vec![];
         for x in 0..*K {
@@ -532,4 +1152,553 @@ impl Features for Breakthrough {
         }
         res
     }
+
+    /// Moves of a color are indexed by `3 * (x + K * y) + direction`, white moves
+    /// come after the `3 * K * K` black moves.
+    fn action_index(K: &Self::Descriptor, action: &Self::Move) -> usize {
+        let color: u8 = action.color.into();
+        3 * (K * K * color as usize + action.x + K * action.y) + action.direction as usize
+    }
+
+    fn action_from_index(K: &Self::Descriptor, index: usize) -> Option<Self::Move> {
+        let (cell, direction) = (index / 3, index % 3);
+        let (color, cell) = (cell / (K * K), cell % (K * K));
+        let color = match color {
+            0 => Color::Black,
+            1 => Color::White,
+            _ => return None,
+        };
+        let direction = match direction {
+            0 => MoveDirection::Front,
+            1 => MoveDirection::FrontLeft,
+            _ => MoveDirection::FrontRight,
+        };
+        Some(Move {
+            color,
+            x: cell % K,
+            y: cell / K,
+            direction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 5x5 board with the given pawns, black to play.
+    async fn position(black: &[(usize, usize)], white: &[(usize, usize)]) -> Breakthrough {
+        let mut content = Array::from_elem([5, 5], Cell::Empty);
+        for (x, y) in black {
+            content[[*x, *y]] = Cell::C(Color::Black);
+        }
+        for (x, y) in white {
+            content[[*x, *y]] = Cell::C(Color::White);
+        }
+        Breakthrough::with_content(content, Color::Black)
+    }
+
+    #[tokio::test]
+    async fn test_decisive_move_wins() {
+        let state = position(&[(0, 3), (2, 0)], &[(4, 1), (4, 4)]).await;
+        for _ in 0..20 {
+            let action = decisive_move(&state).await;
+            let mut next = state.clone();
+            next.play(&action).await;
+            assert_eq!(next.winner(), Some(Color::Black));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decisive_move_blocks() {
+        // white threatens to reach the last row: black has to take the pawn.
+        let state = position(&[(0, 1), (2, 0)], &[(3, 1), (4, 4)]).await;
+        let block = Move {
+            color: Color::Black,
+            x: 2,
+            y: 0,
+            direction: MoveDirection::FrontLeft,
+        };
+        for _ in 0..20 {
+            assert_eq!(decisive_move(&state).await, block);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flip_symmetry() {
+        let state = position(&[(0, 1), (1, 0), (3, 0)], &[(2, 4), (4, 3)]).await;
+        let ft = state.get_features();
+        let flip = Symmetry {
+            flip: true,
+            rotations: 0,
+        };
+        assert!(state.symmetries().contains(&flip));
+
+        let features = state.state_to_feature(Color::Black);
+        let flipped = Breakthrough::state_symmetry(&ft, flip, &features);
+        assert_ne!(flipped, features);
+        assert_eq!(
+            Breakthrough::state_symmetry(&ft, flip.inverse(), &flipped),
+            features
+        );
+
+        // the pawn is mirrored and its left diagonal becomes the right one.
+        let action = Move {
+            color: Color::Black,
+            x: 0,
+            y: 1,
+            direction: MoveDirection::FrontLeft,
+        };
+        let mirrored = Move {
+            color: Color::Black,
+            x: 4,
+            y: 1,
+            direction: MoveDirection::FrontRight,
+        };
+        assert_eq!(
+            Breakthrough::action_symmetry(&ft, flip, &Breakthrough::move_to_feature(&ft, action)),
+            Breakthrough::move_to_feature(&ft, mirrored)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_legal_mask() {
+        let state = position(&[(0, 3), (1, 1), (2, 0)], &[(1, 2), (4, 4)]).await;
+        let ft = state.get_features();
+        let moves = state.possible_moves();
+        let mask = state.legal_mask();
+        assert_eq!(mask.iter().filter(|x| **x != 0.).count(), moves.len());
+
+        // masked softmax of uniform logits.
+        let logits: Array<f32, _> = Array::zeros(Breakthrough::action_dimension(&ft));
+        let policy = state.masked_policy(&logits.mapv(f32::exp));
+        let uniform = Array::from_elem(Breakthrough::action_dimension(&ft), 1.);
+        let expected = state.feature_to_moves(&uniform);
+        for m in moves.iter() {
+            let p = policy[[m.x, m.y, m.direction as usize]];
+            assert!((p - expected[m]).abs() < 1e-6);
+        }
+        assert!((policy.sum() - 1.).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_serde_round_trip() {
+        let mut state = BreakthroughBuilder { size: 6 }.create(Color::Black).await;
+        // three moves each: no pawn can reach the last row yet.
+        for _ in 0..6 {
+            state.random_move().await;
+        }
+        assert!(!state.is_finished());
+
+        let bytes = bincode::serialize(&state).unwrap();
+        let json = serde_json::to_string(&state).unwrap();
+        for restored in vec![
+            bincode::deserialize::<Breakthrough>(&bytes).unwrap(),
+            serde_json::from_str::<Breakthrough>(&json).unwrap(),
+        ] {
+            assert_eq!(restored, state);
+            assert_eq!(restored.possible_moves(), state.possible_moves());
+            assert_eq!(restored.state_hash(), state.state_hash());
+            assert_eq!(position_hash(&restored), position_hash(&state));
+
+            // the hash is still maintained after the restored game is played on.
+            let mut next = state.clone();
+            let mut restored_next = restored;
+            let action = state.possible_moves()[0];
+            next.play(&action).await;
+            restored_next.play(&action).await;
+            assert_eq!(restored_next.state_hash(), next.state_hash());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notation_start_position() {
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let notation = state.to_notation();
+        assert_eq!(notation, "WWWWW/WWWWW/5/BBBBB/BBBBB b");
+
+        let parsed = Breakthrough::from_notation(&notation, 5).unwrap();
+        assert_eq!(parsed, state);
+        assert_eq!(parsed.possible_moves(), state.possible_moves());
+        assert_eq!(parsed.to_notation(), notation);
+    }
+
+    #[tokio::test]
+    async fn test_notation_capture() {
+        let mut state = position(&[(1, 2)], &[(2, 3), (4, 4)]).await;
+        assert_eq!(state.to_notation(), "4W/2W2/1B3/5/5 b");
+
+        let capture = Move {
+            color: Color::Black,
+            x: 1,
+            y: 2,
+            direction: MoveDirection::FrontLeft,
+        };
+        state.play(&capture).await;
+        let notation = state.to_notation();
+        assert_eq!(notation, "4W/2B2/5/5/5 w");
+
+        let parsed = Breakthrough::from_notation(&notation, 5).unwrap();
+        assert_eq!(parsed, state);
+        assert_eq!(parsed.possible_moves(), state.possible_moves());
+    }
+
+    #[test]
+    fn test_notation_invalid() {
+        let error = Breakthrough::from_notation("WWWWW/WWWWW/5/BBBBBB/BBBBB b", 5).unwrap_err();
+        assert!(error.0.contains("too many cells on rank 2"));
+
+        assert!(Breakthrough::from_notation("WWWWW/WWWWW/5/BBBB/BBBBB b", 5).is_err());
+        assert!(Breakthrough::from_notation("WWWWW/WWWWW/5/BBBBB/BBBBB x", 5).is_err());
+        assert!(Breakthrough::from_notation("WWWWW/WWWWW/BBBBB/BBBBB b", 5).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_from() {
+        let builder = BreakthroughBuilder { size: 5 };
+        for turn in &[Color::Black, Color::White] {
+            let start = builder.create(*turn).await;
+            let from = builder
+                .create_from("WWWWW/WWWWW/5/BBBBB/BBBBB", *turn)
+                .await
+                .unwrap();
+            assert_eq!(from, start);
+        }
+
+        // endgame: a black pawn next to a white one, white to move.
+        let state = builder
+            .create_from("5/1W3/1B3/5/5", Color::White)
+            .await
+            .unwrap();
+        let mut moves = state.possible_moves();
+        moves.sort_by_key(|m| m.name());
+        let mut expected = vec![
+            Move {
+                color: Color::White,
+                x: 1,
+                y: 3,
+                direction: MoveDirection::FrontLeft,
+            },
+            Move {
+                color: Color::White,
+                x: 1,
+                y: 3,
+                direction: MoveDirection::FrontRight,
+            },
+        ];
+        expected.sort_by_key(|m| m.name());
+        assert_eq!(moves, expected);
+
+        assert!(builder.create_from("5/5/5", Color::Black).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_moves_match_rescan() {
+        use rand::seq::SliceRandom;
+        use std::collections::HashSet;
+
+        let mut n_positions = 0;
+        while n_positions < 100 {
+            let mut state = BreakthroughBuilder { size: 8 }.create(Color::Black).await;
+            while !state.is_finished() && n_positions < 100 {
+                let moves = state.possible_moves();
+                let incremental: HashSet<Move> = moves.iter().copied().collect();
+                let rescan: HashSet<Move> = state.possible_moves_rescan().into_iter().collect();
+                assert_eq!(moves.len(), incremental.len());
+                assert_eq!(incremental, rescan);
+                n_positions += 1;
+
+                let action = *moves.choose(&mut rand::thread_rng()).unwrap();
+                state.play(&action).await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unchecked_matches_play() {
+        for _ in 0..10 {
+            let mut state = BreakthroughBuilder { size: 8 }.create(Color::Black).await;
+            while !state.is_finished() {
+                let action = *state
+                    .possible_moves()
+                    .choose(&mut rand::thread_rng())
+                    .unwrap();
+                let mut unchecked = state.clone();
+                unchecked.apply_move_unchecked(&action, action.target());
+                state.play(&action).await;
+
+                assert_eq!(unchecked, state);
+                assert_eq!(unchecked.state_hash(), state.state_hash());
+                assert_eq!(unchecked.possible_moves(), state.possible_moves());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_undo_restores_state() {
+        let initial = BreakthroughBuilder { size: 8 }.create(Color::Black).await;
+        let mut state = initial.clone();
+
+        let mut undos = vec![];
+        while !state.is_finished() {
+            let action = *state
+                .possible_moves()
+                .choose(&mut rand::thread_rng())
+                .unwrap();
+            undos.push(state.play_with_undo(&action).1);
+        }
+        while let Some(undo) = undos.pop() {
+            state.undo(undo);
+        }
+
+        assert_eq!(state, initial);
+        assert_eq!(state.hash, initial.hash);
+        assert_eq!(state.positions, initial.positions);
+
+        let (winner, _) = playout_in_place(&mut state, Color::Black, |s| s.winner());
+        assert!(winner.is_some());
+        assert_eq!(state.positions, initial.positions);
+        assert_eq!(state.state_hash(), initial.state_hash());
+    }
+
+    #[tokio::test]
+    async fn test_parse_move() {
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        for action in state.possible_moves() {
+            assert_eq!(state.parse_move(&action.name()).unwrap(), action);
+        }
+        assert_eq!(
+            state.parse_move("b2->c3").unwrap(),
+            Move {
+                color: Color::Black,
+                x: 1,
+                y: 1,
+                direction: MoveDirection::FrontLeft,
+            }
+        );
+
+        // malformed notation, out of the board and illegal moves.
+        for notation in &[
+            "", "b2", "b2-c3", "22->c3", "b2->c0", "b2->f3", "a1->a2", "b2->b4",
+        ] {
+            assert!(state.parse_move(notation).is_err());
+        }
+    }
+
+    #[test]
+    fn test_action_index_round_trip() {
+        let moves = Breakthrough::all_possible_moves(&5);
+        let mut indices: Vec<usize> = moves
+            .iter()
+            .map(|m| Breakthrough::action_index(&5, m))
+            .collect();
+        for (action, index) in moves.iter().zip(indices.iter()) {
+            assert_eq!(Breakthrough::action_from_index(&5, *index), Some(*action));
+        }
+        indices.sort_unstable();
+        assert_eq!(indices, (0..moves.len()).collect::<Vec<_>>());
+        assert_eq!(Breakthrough::action_from_index(&5, moves.len()), None);
+    }
+
+    #[tokio::test]
+    async fn test_perft() {
+        // reference counts from an independent implementation of the rules.
+        let expected: [(usize, [u64; 3]); 3] = [
+            (4, [6, 42, 334]),
+            (5, [13, 156, 1924]),
+            (6, [16, 256, 4308]),
+        ];
+        for (size, counts) in expected.iter() {
+            let state = BreakthroughBuilder { size: *size }
+                .create(Color::Black)
+                .await;
+            for (depth, count) in counts.iter().enumerate() {
+                assert_eq!(state.perft(depth + 1), *count, "size {}", size);
+            }
+            assert_eq!(state.perft(0), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_value() {
+        let builder = BreakthroughBuilder { size: 5 };
+        for turn in &[Color::Black, Color::White] {
+            let start = builder.create(*turn).await;
+            assert!(start.heuristic_value(Color::Black).abs() < 1e-6);
+            assert!(start.heuristic_value(Color::White).abs() < 1e-6);
+        }
+
+        // black wins by taking the pawn on b5.
+        let state = builder
+            .create_from("1WWWW/B4/5/5/BBBBB", Color::Black)
+            .await
+            .unwrap();
+        assert!(state.heuristic_value(Color::Black) > 0.9);
+        assert!(state.heuristic_value(Color::White) < -0.9);
+        // with white to move, the pawn can be taken.
+        let state = builder
+            .create_from("1WWWW/B4/5/5/BBBBB", Color::White)
+            .await
+            .unwrap();
+        assert!(state.heuristic_value(Color::Black).abs() < 0.9);
+        // unless it is out of reach.
+        let state = builder
+            .create_from("3WW/B4/5/5/BBBBB", Color::White)
+            .await
+            .unwrap();
+        assert!(state.heuristic_value(Color::Black) > 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_canonical() {
+        let state = position(&[(0, 1), (1, 0), (3, 2)], &[(2, 4), (4, 3)]).await;
+        assert_eq!(state.canonical(), state);
+
+        // half-turn rotation with swapped colors, white to move.
+        let mut content = Array::from_elem([5, 5], Cell::Empty);
+        for (x, y) in &[(0, 1), (1, 0), (3, 2)] {
+            content[[4 - x, 4 - y]] = Cell::C(Color::White);
+        }
+        for (x, y) in &[(2, 4), (4, 3)] {
+            content[[4 - x, 4 - y]] = Cell::C(Color::Black);
+        }
+        let swapped = Breakthrough::with_table(content, Color::White, state.transposition.clone());
+        assert_ne!(swapped, state);
+
+        let canonical = swapped.canonical();
+        assert_eq!(canonical, state);
+        assert_eq!(canonical.state_hash(), state.state_hash());
+
+        let mut mapped: Vec<Move> = swapped
+            .possible_moves()
+            .iter()
+            .map(|action| swapped.canonical_move(action))
+            .collect();
+        let mut expected = canonical.possible_moves();
+        mapped.sort_by_key(|action| action.name());
+        expected.sort_by_key(|action| action.name());
+        assert_eq!(mapped, expected);
+
+        // playing a move commutes with the canonical form.
+        for action in swapped.possible_moves() {
+            let mut next = swapped.clone();
+            next.play(&action).await;
+            let mut canonical_next = canonical.clone();
+            canonical_next.play(&swapped.canonical_move(&action)).await;
+            assert_eq!(canonical_next.canonical(), next);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_play_detailed() {
+        let state = position(&[(1, 1), (0, 3)], &[(2, 2), (4, 4)]).await;
+
+        let capture = Move {
+            color: Color::Black,
+            x: 1,
+            y: 1,
+            direction: MoveDirection::FrontLeft,
+        };
+        let mut next = state.clone();
+        let (reward, outcome) = next.play_detailed(&capture).await;
+        assert_eq!(reward, 0.);
+        assert_eq!(
+            outcome,
+            Some(BTMoveOutcome {
+                from: (1, 1),
+                to: (2, 2),
+                captured: Some(Color::White),
+                promotion: false,
+            })
+        );
+        assert_eq!(next.positions[Color::White as usize], vec![(4, 4)]);
+
+        let advance = Move {
+            color: Color::Black,
+            x: 0,
+            y: 3,
+            direction: MoveDirection::Front,
+        };
+        let mut next = state.clone();
+        let (reward, outcome) = next.play_detailed(&advance).await;
+        assert_eq!(reward, 1.);
+        let outcome = outcome.unwrap();
+        assert_eq!(outcome.captured, None);
+        assert!(outcome.promotion);
+
+        // the front cell is taken: the move is invalid.
+        let blocked = Move {
+            color: Color::Black,
+            x: 1,
+            y: 1,
+            direction: MoveDirection::Front,
+        };
+        let mut blocked_state = position(&[(1, 1)], &[(1, 2), (4, 4)]).await;
+        assert_eq!(blocked_state.play_detailed(&blocked).await, (-1., None));
+    }
+
+    #[tokio::test]
+    async fn test_display_plain() {
+        let state = position(&[(0, 1), (1, 0), (3, 0)], &[(2, 4), (4, 3)]).await;
+        let plain = state.display_plain();
+        assert!(!plain.contains('\x1b'));
+
+        let mut colored = String::new();
+        state.write_board(&mut colored, true).unwrap();
+        assert!(colored.contains('\x1b'));
+        // strip the escape sequences, of the form `ESC [ ... m`.
+        let mut stripped = String::new();
+        let mut escape = false;
+        for c in colored.chars() {
+            match c {
+                '\x1b' => escape = true,
+                'm' if escape => escape = false,
+                c if !escape => stripped.push(c),
+                _ => (),
+            }
+        }
+
+        assert_eq!(stripped.chars().count(), plain.chars().count());
+        for (c, p) in stripped.chars().zip(plain.chars()) {
+            assert!(
+                c == p || (c == '▓' && (p == 'B' || p == 'W')),
+                "{:?} {:?}",
+                c,
+                p
+            );
+        }
+        assert_eq!(plain.matches("BB").count(), 3);
+        assert_eq!(plain.matches("WW").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pieces() {
+        let mut state = BreakthroughBuilder { size: 6 }.create(Color::Black).await;
+        for color in &[Color::Black, Color::White] {
+            assert_eq!(state.piece_count(*color), 2 * 6);
+            assert_eq!(state.pieces(*color).len(), 2 * 6);
+        }
+        assert!(state.pieces(Color::Black).iter().all(|&(_, y)| y < 2));
+        assert_eq!(state.most_advanced(Color::Black), Some((0, 1)));
+        assert_eq!(state.most_advanced(Color::White), Some((0, 4)));
+
+        let front = |color, x, y| Move {
+            color,
+            x,
+            y,
+            direction: MoveDirection::Front,
+        };
+        state.play(&front(Color::Black, 2, 1)).await;
+        state.play(&front(Color::White, 4, 4)).await;
+        state.play(&front(Color::Black, 2, 2)).await;
+
+        assert_eq!(state.most_advanced(Color::Black), Some((2, 3)));
+        assert_eq!(state.most_advanced(Color::White), Some((4, 3)));
+        assert_eq!(state.piece_count(Color::Black), 2 * 6);
+
+        let empty = position(&[(1, 1)], &[]).await;
+        assert_eq!(empty.piece_count(Color::White), 0);
+        assert_eq!(empty.most_advanced(Color::White), None);
+        assert_eq!(empty.pieces(Color::Black), vec![(1, 1)]);
+    }
 }
@@ -0,0 +1,275 @@
+use crate::game::{Base, Features, Playable, Singleplayer, SingleplayerGameBuilder};
+
+use async_trait::async_trait;
+use ndarray::{Array, Ix1, Ix3};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+
+const GRAVITY: f32 = 9.8;
+const CART_MASS: f32 = 1.0;
+const POLE_MASS: f32 = 0.1;
+const TOTAL_MASS: f32 = CART_MASS + POLE_MASS;
+/// Half of the pole length.
+const POLE_LENGTH: f32 = 0.5;
+const POLE_MASS_LENGTH: f32 = POLE_MASS * POLE_LENGTH;
+const FORCE: f32 = 10.0;
+/// Duration of a step, in seconds.
+const TAU: f32 = 0.02;
+/// The episode ends once the pole leans more than 12 degrees.
+const THETA_THRESHOLD: f32 = 12. * std::f32::consts::PI / 180.;
+/// The episode ends once the cart leaves the track.
+const X_THRESHOLD: f32 = 2.4;
+
+/// Action pushing the cart to the left.
+pub const PUSH_LEFT: usize = 0;
+/// Action pushing the cart to the right.
+pub const PUSH_RIGHT: usize = 1;
+
+/// Cart-pole balancing, with the dynamics of the `CartPole-v1` Gym environment.
+///
+/// The state is the cart position and velocity, and the pole angle and angular velocity.
+/// Each step the cart is pushed to the left or to the right, yielding a reward of one,
+/// until the pole falls, the cart leaves the track or the step limit is reached.
+#[derive(Clone, Debug)]
+pub struct CartPole {
+    state: [f32; 4],
+    steps: usize,
+    max_steps: usize,
+}
+
+impl CartPole {
+    /// Cart position, cart velocity, pole angle and pole angular velocity.
+    pub fn state(&self) -> [f32; 4] {
+        self.state
+    }
+
+    /// Number of steps played.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Whether the pole has fallen or the cart has left the track.
+    pub fn out_of_bounds(&self) -> bool {
+        let [x, _, theta, _] = self.state;
+        x.abs() > X_THRESHOLD || theta.abs() > THETA_THRESHOLD
+    }
+}
+
+impl Hash for CartPole {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for x in self.state.iter() {
+            x.to_bits().hash(state);
+        }
+        self.steps.hash(state);
+    }
+}
+
+impl PartialEq for CartPole {
+    fn eq(&self, other: &CartPole) -> bool {
+        self.state == other.state && self.steps == other.steps
+    }
+}
+
+impl Eq for CartPole {}
+
+impl Base for CartPole {
+    type Move = usize;
+
+    fn possible_moves(&self) -> Vec<usize> {
+        if self.is_finished() {
+            vec![]
+        } else {
+            vec![PUSH_LEFT, PUSH_RIGHT]
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.out_of_bounds() || self.steps >= self.max_steps
+    }
+}
+
+#[async_trait]
+impl Playable for CartPole {
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    async fn play(&mut self, action: &usize) -> f32 {
+        let [x, x_dot, theta, theta_dot] = self.state;
+        let force = if *action == PUSH_RIGHT { FORCE } else { -FORCE };
+        let (cos, sin) = (theta.cos(), theta.sin());
+
+        let temp = (force + POLE_MASS_LENGTH * theta_dot * theta_dot * sin) / TOTAL_MASS;
+        let theta_acc = (GRAVITY * sin - cos * temp)
+            / (POLE_LENGTH * (4. / 3. - POLE_MASS * cos * cos / TOTAL_MASS));
+        let x_acc = temp - POLE_MASS_LENGTH * theta_acc * cos / TOTAL_MASS;
+
+        self.state = [
+            x + TAU * x_dot,
+            x_dot + TAU * x_acc,
+            theta + TAU * theta_dot,
+            theta_dot + TAU * theta_acc,
+        ];
+        self.steps += 1;
+        1.
+    }
+}
+
+impl Singleplayer for CartPole {}
+
+impl Features for CartPole {
+    type StateDim = Ix3;
+    type ActionDim = Ix1;
+
+    type Descriptor = ();
+
+    fn get_features(&self) -> Self::Descriptor {}
+
+    fn state_dimension(_: &Self::Descriptor) -> Self::StateDim {
+        ndarray::Dim([1, 1, 4])
+    }
+
+    fn action_dimension(_: &Self::Descriptor) -> Self::ActionDim {
+        ndarray::Dim(2)
+    }
+
+    fn state_to_feature(&self, _pov: Self::Player) -> Array<f32, Self::StateDim> {
+        Array::from_shape_fn((1, 1, 4), |(_, _, i)| self.state[i])
+    }
+
+    fn all_possible_moves(_: &Self::Descriptor) -> Vec<Self::Move> {
+        vec![PUSH_LEFT, PUSH_RIGHT]
+    }
+
+    fn moves_to_feature(
+        _: &Self::Descriptor,
+        moves: &HashMap<Self::Move, f32>,
+    ) -> Array<f32, Self::ActionDim> {
+        Array::from_shape_fn(2, |i| *moves.get(&i).unwrap_or(&0.))
+    }
+
+    fn all_feature_to_moves(
+        _: &Self::Descriptor,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> HashMap<Self::Move, f32> {
+        HashMap::from_iter(features.iter().cloned().enumerate())
+    }
+
+    fn feature_to_moves(&self, features: &Array<f32, Self::ActionDim>) -> HashMap<Self::Move, f32> {
+        self.possible_moves()
+            .into_iter()
+            .map(|action| (action, features[action]))
+            .collect()
+    }
+}
+
+/// Cart-pole builder.
+#[derive(Clone, Debug)]
+pub struct CartPoleBuilder {
+    /// Initial cart position, cart velocity, pole angle and pole angular velocity.
+    pub initial_state: [f32; 4],
+    /// The episode ends after this number of steps.
+    pub max_steps: usize,
+}
+
+impl Default for CartPoleBuilder {
+    fn default() -> Self {
+        CartPoleBuilder {
+            initial_state: [0., 0., 0.05, 0.],
+            max_steps: 500,
+        }
+    }
+}
+
+#[async_trait]
+impl SingleplayerGameBuilder for CartPoleBuilder {
+    type G = CartPole;
+
+    async fn create(&self) -> CartPole {
+        CartPole {
+            state: self.initial_state,
+            steps: 0,
+            max_steps: self.max_steps,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays until the end of the episode, returning the collected reward.
+    async fn episode<F: FnMut(&CartPole) -> usize>(mut game: CartPole, mut policy: F) -> f32 {
+        let mut total = 0.;
+        while !game.is_finished() {
+            let action = policy(&game);
+            total += game.play(&action).await;
+        }
+        total
+    }
+
+    #[tokio::test]
+    async fn test_balance_beats_random() {
+        let builder = CartPoleBuilder::default();
+
+        // push towards the side the pole is falling to.
+        let balanced = episode(builder.create().await, |game| {
+            let [_, _, theta, theta_dot] = game.state();
+            if theta + theta_dot > 0. {
+                PUSH_RIGHT
+            } else {
+                PUSH_LEFT
+            }
+        })
+        .await;
+        assert!(balanced > 200., "{} steps", balanced);
+
+        let mut random = 0.;
+        for _ in 0..20 {
+            random += episode(builder.create().await, |_| rand::random::<usize>() % 2).await;
+        }
+        assert!(random / 20. < balanced);
+    }
+
+    #[tokio::test]
+    async fn test_boundaries() {
+        // the cart leaves the track.
+        let mut game = CartPoleBuilder {
+            initial_state: [2.39, 1., 0., 0.],
+            max_steps: 500,
+        }
+        .create()
+        .await;
+        assert!(!game.is_finished());
+        game.play(&PUSH_RIGHT).await;
+        assert!(game.out_of_bounds());
+        assert!(game.possible_moves().is_empty());
+
+        // the pole falls.
+        let mut game = CartPoleBuilder {
+            initial_state: [0., 0., 0.2, 0.],
+            max_steps: 500,
+        }
+        .create()
+        .await;
+        let mut steps = 0;
+        while !game.is_finished() {
+            game.play(&PUSH_LEFT).await;
+            steps += 1;
+        }
+        assert!(game.out_of_bounds());
+        assert!(steps < 10);
+
+        // the step limit is reached.
+        let mut game = CartPoleBuilder {
+            initial_state: [0., 0., 0., 0.],
+            max_steps: 3,
+        }
+        .create()
+        .await;
+        let reward = episode(game.clone(), |_| PUSH_LEFT).await;
+        assert!((reward - 3.).abs() < 1e-6);
+        for _ in 0..3 {
+            game.play(&PUSH_RIGHT).await;
+        }
+        assert!(game.is_finished() && !game.out_of_bounds());
+    }
+}
@@ -0,0 +1,567 @@
+use crate::game::breakthrough::Color;
+use crate::game::*;
+
+use async_trait::async_trait;
+use ndarray::Array;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+
+/// Board size.
+const SIZE: usize = 8;
+/// Maximum number of steps in a move: a capture sequence can't take more than 12 pieces.
+const MAX_STEPS: usize = 12;
+
+/// Game piece.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Piece {
+    /// Moves and captures forward only.
+    Man(Color),
+    /// Moves and captures in the four diagonal directions.
+    King(Color),
+}
+
+impl Piece {
+    /// Owner of the piece.
+    pub fn color(self) -> Color {
+        match self {
+            Piece::Man(color) | Piece::King(color) => color,
+        }
+    }
+
+    fn directions(self) -> &'static [Direction] {
+        match self {
+            Piece::Man(Color::Black) => &[Direction::UpLeft, Direction::UpRight],
+            Piece::Man(Color::White) => &[Direction::DownLeft, Direction::DownRight],
+            Piece::King(_) => &DIRECTIONS,
+        }
+    }
+}
+
+/// Diagonal direction. Black moves up and white moves down.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    /// -x, +y
+    UpLeft = 0,
+    /// +x, +y
+    UpRight = 1,
+    /// -x, -y
+    DownLeft = 2,
+    /// +x, -y
+    DownRight = 3,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::UpLeft,
+    Direction::UpRight,
+    Direction::DownLeft,
+    Direction::DownRight,
+];
+
+impl Direction {
+    /// Square reached after `distance` steps in this direction, if it is on the board.
+    fn step(self, (x, y): (usize, usize), distance: usize) -> Option<(usize, usize)> {
+        let (dx, dy) = match self {
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        };
+        let px = x as i32 + dx * distance as i32;
+        let py = y as i32 + dy * distance as i32;
+        if px >= 0 && py >= 0 && (px as usize) < SIZE && (py as usize) < SIZE {
+            Some((px as usize, py as usize))
+        } else {
+            None
+        }
+    }
+}
+
+/// Move: a simple diagonal step, or a sequence of jumps played as a single move.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    from: (usize, usize),
+    capture: bool,
+    len: usize,
+    steps: [Direction; MAX_STEPS],
+}
+
+impl Move {
+    fn simple(from: (usize, usize), direction: Direction) -> Self {
+        let mut steps = [Direction::UpLeft; MAX_STEPS];
+        steps[0] = direction;
+        Move {
+            from,
+            capture: false,
+            len: 1,
+            steps,
+        }
+    }
+
+    fn jumps(from: (usize, usize), directions: &[Direction]) -> Self {
+        let mut steps = [Direction::UpLeft; MAX_STEPS];
+        steps[..directions.len()].copy_from_slice(directions);
+        Move {
+            from,
+            capture: true,
+            len: directions.len(),
+            steps,
+        }
+    }
+
+    /// Starting square.
+    pub fn from(&self) -> (usize, usize) {
+        self.from
+    }
+
+    /// Whether the move is a capture sequence.
+    pub fn is_capture(&self) -> bool {
+        self.capture
+    }
+
+    /// Squares reached after each step, the last one being the destination.
+    pub fn path(&self) -> Vec<(usize, usize)> {
+        let distance = if self.capture { 2 } else { 1 };
+        let mut square = self.from;
+        self.steps[..self.len]
+            .iter()
+            .map(|direction| {
+                square = direction.step(square, distance).unwrap();
+                square
+            })
+            .collect()
+    }
+
+    /// Index of the move in the action features: starting square and first direction.
+    fn feature_index(&self) -> [usize; 3] {
+        [self.from.0, self.from.1, self.steps[0] as usize]
+    }
+}
+
+fn square_name((x, y): (usize, usize)) -> String {
+    format!("{}{}", ('a' as usize + x) as u8 as char, 1 + y)
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if self.capture { "x" } else { "-" };
+        write!(f, "{}", square_name(self.from))?;
+        for square in self.path() {
+            write!(f, "{}{}", separator, square_name(square))?;
+        }
+        Ok(())
+    }
+}
+
+/// Checkers (English draughts) game state instance.
+///
+/// Captures are mandatory, and a capture sequence has to be continued
+/// as long as possible. A man reaching the last row is promoted to king,
+/// which ends the move.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Checkers {
+    board: [[Option<Piece>; SIZE]; SIZE],
+    turn: Color,
+}
+
+impl fmt::Debug for Checkers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Turn: {:?}", self.turn)?;
+        for y in (0..SIZE).rev() {
+            for column in self.board.iter() {
+                match column[y] {
+                    None => write!(f, ".")?,
+                    Some(Piece::Man(Color::Black)) => write!(f, "b")?,
+                    Some(Piece::Man(Color::White)) => write!(f, "w")?,
+                    Some(Piece::King(Color::Black)) => write!(f, "B")?,
+                    Some(Piece::King(Color::White)) => write!(f, "W")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Checkers {
+    /// Piece at given position.
+    pub fn piece(&self, (x, y): (usize, usize)) -> Option<Piece> {
+        self.board[x][y]
+    }
+
+    fn promotion_row(color: Color) -> usize {
+        match color {
+            Color::Black => SIZE - 1,
+            Color::White => 0,
+        }
+    }
+
+    /// Extend the capture sequence of `piece` (that started from `from`) from `square`.
+    /// Only complete sequences are added to `res`.
+    fn capture_sequences(
+        &self,
+        piece: Piece,
+        from: (usize, usize),
+        square: (usize, usize),
+        captured: &mut Vec<(usize, usize)>,
+        directions: &mut Vec<Direction>,
+        res: &mut Vec<Move>,
+    ) {
+        let mut extended = false;
+        for direction in piece.directions() {
+            let (over, target) = match (direction.step(square, 1), direction.step(square, 2)) {
+                (Some(over), Some(target)) => (over, target),
+                _ => continue,
+            };
+            let takes_opponent = match self.piece(over) {
+                Some(p) => p.color() != piece.color() && !captured.contains(&over),
+                None => false,
+            };
+            // the moving piece has left its starting square.
+            let lands = self.piece(target).is_none() || target == from;
+            if !(takes_opponent && lands) {
+                continue;
+            }
+
+            extended = true;
+            captured.push(over);
+            directions.push(*direction);
+            let promoted = match piece {
+                Piece::Man(color) => target.1 == Self::promotion_row(color),
+                Piece::King(_) => false,
+            };
+            if promoted {
+                res.push(Move::jumps(from, directions));
+            } else {
+                self.capture_sequences(piece, from, target, captured, directions, res);
+            }
+            captured.pop();
+            directions.pop();
+        }
+
+        if !extended && !directions.is_empty() {
+            res.push(Move::jumps(from, directions));
+        }
+    }
+}
+
+/// Game builder for Checkers.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct CheckersBuilder {}
+
+#[async_trait]
+impl GameBuilder for CheckersBuilder {
+    type G = Checkers;
+
+    async fn create(&self, turn: Color) -> Checkers {
+        let mut board = [[None; SIZE]; SIZE];
+        for (x, column) in board.iter_mut().enumerate() {
+            for (y, cell) in column.iter_mut().enumerate() {
+                if (x + y) % 2 == 0 {
+                    if y < 3 {
+                        *cell = Some(Piece::Man(Color::Black));
+                    } else if y >= SIZE - 3 {
+                        *cell = Some(Piece::Man(Color::White));
+                    }
+                }
+            }
+        }
+        Checkers { board, turn }
+    }
+}
+
+impl SingleWinner for Checkers {
+    fn winner(&self) -> Option<Color> {
+        // a player without pieces has no legal moves either.
+        if self.possible_moves().is_empty() {
+            Some(self.turn.adv())
+        } else {
+            None
+        }
+    }
+}
+
+impl Game for Checkers {
+    type Player = Color;
+
+    fn players() -> Vec<Color> {
+        vec![Color::Black, Color::White]
+    }
+
+    fn player_after(player: Color) -> Color {
+        player.adv()
+    }
+
+    fn turn(&self) -> Color {
+        self.turn
+    }
+}
+
+impl Base for Checkers {
+    type Move = Move;
+
+    fn possible_moves(&self) -> Vec<Move> {
+        let mut captures = vec![];
+        let mut simple_moves = vec![];
+
+        for (x, column) in self.board.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                let piece = match cell {
+                    Some(piece) if piece.color() == self.turn => *piece,
+                    _ => continue,
+                };
+                self.capture_sequences(
+                    piece,
+                    (x, y),
+                    (x, y),
+                    &mut vec![],
+                    &mut vec![],
+                    &mut captures,
+                );
+                for direction in piece.directions() {
+                    if let Some(target) = direction.step((x, y), 1) {
+                        if self.piece(target).is_none() {
+                            simple_moves.push(Move::simple((x, y), *direction));
+                        }
+                    }
+                }
+            }
+        }
+
+        if captures.is_empty() {
+            simple_moves
+        } else {
+            captures
+        }
+    }
+}
+
+#[async_trait]
+impl Playable for Checkers {
+    async fn play(&mut self, m: &Move) -> f32 {
+        let (x, y) = m.from;
+        let piece = match self.board[x][y] {
+            Some(piece) if piece.color() == self.turn => piece,
+            _ => return -1.,
+        };
+
+        self.board[x][y] = None;
+        let mut square = m.from;
+        for direction in &m.steps[..m.len] {
+            if m.capture {
+                let (ox, oy) = direction.step(square, 1).unwrap();
+                self.board[ox][oy] = None;
+                square = direction.step(square, 2).unwrap();
+            } else {
+                square = direction.step(square, 1).unwrap();
+            }
+        }
+
+        let (px, py) = square;
+        self.board[px][py] = if py == Self::promotion_row(self.turn) {
+            Some(Piece::King(self.turn))
+        } else {
+            Some(piece)
+        };
+
+        let player = self.turn;
+        self.turn = self.turn.adv();
+        if self.winner() == Some(player) {
+            1.
+        } else {
+            0.
+        }
+    }
+}
+
+impl Features for Checkers {
+    type StateDim = ndarray::Ix3;
+    type ActionDim = ndarray::Ix3;
+
+    type Descriptor = ();
+
+    fn get_features(&self) -> Self::Descriptor {}
+
+    fn state_dimension(_: &Self::Descriptor) -> Self::StateDim {
+        ndarray::Dim([SIZE, SIZE, 5])
+    }
+
+    fn action_dimension(_: &Self::Descriptor) -> Self::ActionDim {
+        ndarray::Dim([SIZE, SIZE, 4])
+    }
+
+    fn state_to_feature(&self, pov: Self::Player) -> Array<f32, Self::StateDim> {
+        let mut features = ndarray::Array::zeros(Self::state_dimension(&()));
+
+        for ((x, y, z), row) in features.indexed_iter_mut() {
+            let plane = match self.board[x][y] {
+                Some(Piece::Man(c)) if c == pov => Some(0),
+                Some(Piece::King(c)) if c == pov => Some(1),
+                Some(Piece::Man(_)) => Some(2),
+                Some(Piece::King(_)) => Some(3),
+                None => None,
+            };
+            if plane == Some(z) {
+                *row = 1.0
+            } else if z == 4 {
+                if self.turn() == Color::White {
+                    *row = 1.0
+                } else {
+                    *row = -1.0
+                }
+            }
+        }
+
+        features
+    }
+
+    fn moves_to_feature(
+        descr: &Self::Descriptor,
+        moves: &HashMap<Self::Move, f32>,
+    ) -> Array<f32, Self::ActionDim> {
+        let mut features = ndarray::Array::zeros(Self::action_dimension(descr));
+
+        // capture sequences sharing their first jump share the same index.
+        for (action, proba) in moves.iter() {
+            features[action.feature_index()] += *proba;
+        }
+
+        features
+    }
+
+    fn feature_to_moves(&self, features: &Array<f32, Self::ActionDim>) -> HashMap<Self::Move, f32> {
+        let possible_moves = self.possible_moves();
+
+        let mut shared: HashMap<[usize; 3], f32> = HashMap::new();
+        for m in possible_moves.iter() {
+            *shared.entry(m.feature_index()).or_insert(0.) += 1.;
+        }
+
+        let z: f32 = shared.keys().map(|index| features[*index]).sum();
+        HashMap::from_iter(possible_moves.iter().map(|m| {
+            let index = m.feature_index();
+            (*m, features[index] / (shared[&index] * z))
+        }))
+    }
+
+    fn all_feature_to_moves(
+        descr: &Self::Descriptor,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> HashMap<Self::Move, f32> {
+        let possible_moves = Self::all_possible_moves(descr);
+        let z: f32 = possible_moves
+            .iter()
+            .map(|m| features[m.feature_index()])
+            .sum();
+        HashMap::from_iter(
+            possible_moves
+                .iter()
+                .map(|m| (*m, features[m.feature_index()] / z)),
+        )
+    }
+
+    /// Capture sequences can't be enumerated: the action space is made of one move per
+    /// action feature, that is a simple step from each dark square in each direction.
+    fn all_possible_moves(_: &Self::Descriptor) -> Vec<Self::Move> {
+        let mut res = vec![];
+        for x in 0..SIZE {
+            for y in 0..SIZE {
+                if (x + y) % 2 == 0 {
+                    for direction in DIRECTIONS.iter() {
+                        res.push(Move::simple((x, y), *direction));
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(pieces: &[((usize, usize), Piece)], turn: Color) -> Checkers {
+        let mut board = [[None; SIZE]; SIZE];
+        for ((x, y), piece) in pieces {
+            board[*x][*y] = Some(*piece);
+        }
+        Checkers { board, turn }
+    }
+
+    const BLACK: Piece = Piece::Man(Color::Black);
+    const WHITE: Piece = Piece::Man(Color::White);
+
+    #[tokio::test]
+    async fn test_single_capture() {
+        let mut state = position(
+            &[
+                ((2, 2), BLACK),
+                ((6, 0), BLACK),
+                ((3, 3), WHITE),
+                ((7, 7), WHITE),
+            ],
+            Color::Black,
+        );
+        // the capture is forced, although simple moves are available.
+        let moves = state.possible_moves();
+        assert_eq!(moves.len(), 1);
+        assert!(moves[0].is_capture());
+        assert_eq!(moves[0].path(), vec![(4, 4)]);
+
+        assert_eq!(state.play(&moves[0]).await, 0.);
+        assert_eq!(state.piece((3, 3)), None);
+        assert_eq!(state.piece((4, 4)), Some(BLACK));
+        assert_eq!(state.turn(), Color::White);
+    }
+
+    #[tokio::test]
+    async fn test_double_capture() {
+        let mut state = position(
+            &[((2, 2), BLACK), ((3, 3), WHITE), ((5, 5), WHITE)],
+            Color::Black,
+        );
+        let moves = state.possible_moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].path(), vec![(4, 4), (6, 6)]);
+        assert_eq!(format!("{:?}", moves[0]), "c3xe5xg7");
+
+        // white has no pieces left.
+        assert_eq!(state.play(&moves[0]).await, 1.);
+        assert_eq!(state.winner(), Some(Color::Black));
+        assert!(state.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_promotion() {
+        let mut state = position(&[((2, 6), BLACK), ((7, 1), WHITE)], Color::Black);
+        let action = *state
+            .possible_moves()
+            .iter()
+            .find(|m| m.path() == vec![(3, 7)])
+            .unwrap();
+        state.play(&action).await;
+        assert_eq!(state.piece((3, 7)), Some(Piece::King(Color::Black)));
+
+        // the king moves backwards.
+        let reply = state.possible_moves()[0];
+        state.play(&reply).await;
+        let moves = state.possible_moves();
+        assert!(moves.iter().any(|m| m.path() == vec![(2, 6)]));
+        assert!(moves.iter().any(|m| m.path() == vec![(4, 6)]));
+    }
+
+    #[tokio::test]
+    async fn test_feature_moves_round_trip() {
+        let state = CheckersBuilder::default().create(Color::Black).await;
+        let moves = state.possible_moves();
+        assert_eq!(moves.len(), 7);
+
+        let distribution = HashMap::from_iter(moves.iter().map(|m| (*m, 1. / 7.)));
+        let features = Checkers::moves_to_feature(&(), &distribution);
+        for (m, p) in state.feature_to_moves(&features) {
+            assert!((p - distribution[&m]).abs() < 1e-6);
+        }
+        assert_eq!(Checkers::all_possible_moves(&()).len(), 128);
+    }
+}
@@ -0,0 +1,134 @@
+use ndarray::{Array, Ix2};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Move placing a piece on the cell `(x, y)` of a grid.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CellMove {
+    /// Column.
+    pub x: usize,
+    /// Row.
+    pub y: usize,
+}
+
+impl CellMove {
+    /// Move on the cell `(x, y)`.
+    pub fn new(x: usize, y: usize) -> Self {
+        CellMove { x, y }
+    }
+
+    /// Index of the cell on a board with `height` rows, unique among the cells of the board.
+    pub fn index(&self, height: usize) -> usize {
+        self.x * height + self.y
+    }
+
+    /// Move on the cell of given index, on a board with `height` rows.
+    pub fn from_index(index: usize, height: usize) -> Self {
+        CellMove {
+            x: index / height,
+            y: index % height,
+        }
+    }
+}
+
+impl From<(usize, usize)> for CellMove {
+    fn from((x, y): (usize, usize)) -> Self {
+        CellMove { x, y }
+    }
+}
+
+impl fmt::Display for CellMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// Move of a piece from a cell of a grid to another.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FromToMove {
+    /// Cell the piece leaves.
+    pub from: (usize, usize),
+    /// Cell the piece reaches.
+    pub to: (usize, usize),
+}
+
+impl FromToMove {
+    /// Move from `from` to `to`.
+    pub fn new(from: (usize, usize), to: (usize, usize)) -> Self {
+        FromToMove { from, to }
+    }
+
+    /// Index of the move on a `width` x `height` board, unique among the moves of the board.
+    pub fn index(&self, width: usize, height: usize) -> usize {
+        let cells = width * height;
+        CellMove::from(self.from).index(height) * cells + CellMove::from(self.to).index(height)
+    }
+}
+
+impl fmt::Display for FromToMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}) -> ({}, {})",
+            self.from.0, self.from.1, self.to.0, self.to.1
+        )
+    }
+}
+
+/// Action features of cell moves: the value of each move on its cell.
+pub fn cell_moves_to_feature(dim: Ix2, moves: &HashMap<CellMove, f32>) -> Array<f32, Ix2> {
+    let mut features = Array::zeros(dim);
+    for (m, value) in moves.iter() {
+        features[[m.x, m.y]] = *value;
+    }
+    features
+}
+
+/// Distribution over the given cell moves, proportional to the action features of their cells.
+pub fn feature_to_cell_moves(
+    moves: Vec<CellMove>,
+    features: &Array<f32, Ix2>,
+) -> HashMap<CellMove, f32> {
+    let z: f32 = moves.iter().map(|m| features[[m.x, m.y]]).sum();
+    moves
+        .into_iter()
+        .map(|m| (m, features[[m.x, m.y]] / z))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_cell_move() {
+        let moves: HashSet<CellMove> = [(0, 1), (1, 0), (0, 1)]
+            .iter()
+            .map(|m| CellMove::from(*m))
+            .collect();
+        assert_eq!(moves.len(), 2);
+        assert!(moves.contains(&CellMove::new(1, 0)));
+        assert_ne!(CellMove::new(0, 1), CellMove::new(1, 0));
+        assert_eq!(format!("{}", CellMove::new(2, 3)), "(2, 3)");
+
+        // 4 columns of 3 rows.
+        let indices: HashSet<usize> = (0..4)
+            .flat_map(|x| (0..3).map(move |y| CellMove::new(x, y).index(3)))
+            .collect();
+        assert_eq!(indices, (0..12).collect());
+        for index in 0..12 {
+            assert_eq!(CellMove::from_index(index, 3).index(3), index);
+        }
+
+        let moves: HashSet<usize> = (0..12)
+            .flat_map(|from| {
+                (0..12).map(move |to| {
+                    let (from, to) = (CellMove::from_index(from, 3), CellMove::from_index(to, 3));
+                    FromToMove::new((from.x, from.y), (to.x, to.y)).index(4, 3)
+                })
+            })
+            .collect();
+        assert_eq!(moves.len(), 144);
+    }
+}
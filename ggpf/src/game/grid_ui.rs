@@ -0,0 +1,372 @@
+use super::*;
+
+use cursive::direction::Direction;
+use cursive::event::{Event, EventResult, Key};
+use cursive::theme;
+use cursive::theme::ColorStyle;
+use cursive::Printer;
+use cursive::Vec2;
+use float_ord::FloatOrd;
+
+/// Interactive interface for games played on a grid.
+///
+/// The board size is given by the first two axes of the state features, and a move is
+/// attached to the cell of the largest component of its action features.
+pub struct IGrid<G: Features + 'static> {
+    game: G,
+    width: usize,
+    height: usize,
+    /// Selected cell, and index of the selected move among the moves of this cell.
+    selection: Option<(usize, usize, usize)>,
+    choosing_move_cb: Option<Box<dyn FnOnce(G::Move, &mut IGrid<G>) + Send + Sync>>,
+}
+
+impl<G: Features + 'static> IGrid<G> {
+    /// Instanciates a view with given state.
+    pub fn new(initial_state: G) -> Self {
+        let dimension = G::state_dimension(&initial_state.get_features());
+        let dimension = dimension.slice();
+        IGrid {
+            game: initial_state,
+            width: dimension[0],
+            height: dimension.get(1).copied().unwrap_or(1),
+            selection: None,
+            choosing_move_cb: None,
+        }
+    }
+
+    /// Starts the selection of a move, the callback being called once the move is confirmed.
+    pub fn choose_move(&mut self, cb: Box<dyn FnOnce(G::Move, &mut IGrid<G>) + Send + Sync>) {
+        self.selection = self.game.possible_moves().first().map(|action| {
+            let (x, y) = self.move_cell(action);
+            (x, y, 0)
+        });
+        self.choosing_move_cb = Some(cb);
+    }
+
+    /// Cell the move is attached to.
+    fn move_cell(&self, action: &G::Move) -> (usize, usize) {
+        let features = G::move_to_feature(&self.game.get_features(), *action).into_dyn();
+        let (index, _) = features
+            .indexed_iter()
+            .max_by_key(|(_, value)| FloatOrd(**value))
+            .expect("Empty action features.");
+        let index = index.slice();
+        (index[0], index.get(1).copied().unwrap_or(0))
+    }
+
+    /// Possible moves attached to the given cell.
+    fn cell_moves(&self, x: usize, y: usize) -> Vec<G::Move> {
+        self.game
+            .possible_moves()
+            .into_iter()
+            .filter(|action| self.move_cell(action) == (x, y))
+            .collect()
+    }
+
+    fn handle_move(&mut self, dx: isize, dy: isize) -> EventResult {
+        if let Some((x, y, _)) = self.selection {
+            let possible_moves = self.game.possible_moves();
+            let new_cell = possible_moves
+                .iter()
+                .map(|action| self.move_cell(action))
+                .filter(|(mx, my)| {
+                    (dx != 0 && (*mx as isize - x as isize) * dx > 0)
+                        || (dy != 0 && (*my as isize - y as isize) * dy > 0)
+                })
+                .min_by_key(|(mx, my)| {
+                    let a = *mx as isize - x as isize;
+                    let b = *my as isize - y as isize;
+                    if dx == 0 {
+                        (b * dy, a.abs())
+                    } else {
+                        (a * dx, b.abs())
+                    }
+                });
+            if let Some((nx, ny)) = new_cell {
+                self.selection = Some((nx, ny, 0))
+            }
+            EventResult::Consumed(None)
+        } else {
+            EventResult::Ignored
+        }
+    }
+
+    fn selected_move(&self) -> Option<G::Move> {
+        self.selection
+            .and_then(|(x, y, index)| self.cell_moves(x, y).get(index).copied())
+    }
+}
+
+impl<G: Features + 'static> cursive::view::View for IGrid<G> {
+    fn draw(&self, printer: &Printer) {
+        let palette = [
+            ColorStyle::new(
+                theme::Color::RgbLowRes(0, 0, 0),
+                theme::Color::TerminalDefault,
+            ),
+            ColorStyle::new(
+                theme::Color::RgbLowRes(5, 3, 5),
+                theme::Color::TerminalDefault,
+            ),
+            ColorStyle::new(
+                theme::Color::RgbLowRes(5, 1, 1),
+                theme::Color::TerminalDefault,
+            ),
+            ColorStyle::new(
+                theme::Color::RgbLowRes(1, 3, 5),
+                theme::Color::TerminalDefault,
+            ),
+        ];
+        let (w, h) = (self.width, self.height);
+        // print letters
+        for x in 0..w {
+            printer.print(
+                (2 + 3 * x, 0),
+                &(('a' as usize + x) as u8 as char).to_string(),
+            );
+        }
+        for y in 0..h {
+            printer.print((0, 2 + 2 * y), &format!("{}", 1 + y));
+        }
+        printer.print((1, 1), &format!("╔{}══╗", "══╤".repeat(w - 1)));
+        for y in 0..h {
+            if y != 0 {
+                printer.print((1, 1 + 2 * y), &format!("╟{}──╢", "──┼".repeat(w - 1)));
+            }
+            printer.print((1, 2 + 2 * y), "║");
+            for x in 0..w {
+                if x != 0 {
+                    printer.print((1 + 3 * x, 2 + 2 * y), "│")
+                };
+
+                if let CellGlyph::Piece(player) = self.game.render_cell(x, y) {
+                    printer.with_color(palette[player % palette.len()], |printer| {
+                        printer.print((2 + 3 * x, 2 + 2 * y), "▓▓")
+                    })
+                }
+            }
+            printer.print((1 + 3 * w, 2 + 2 * y), "║");
+        }
+        printer.print((1, 1 + 2 * h), &format!("╚{}══╝", "══╧".repeat(w - 1)));
+
+        let select_color = ColorStyle::new(
+            theme::Color::RgbLowRes(1, 1, 1),
+            theme::Color::RgbLowRes(4, 4, 4),
+        );
+
+        if let Some((x, y, _)) = self.selection {
+            printer.with_color(select_color, |printer| {
+                printer.print((1 + 3 * x, 1 + 2 * y), "┏━━┓");
+                printer.print((1 + 3 * x, 2 + 2 * y), "┣");
+                printer.print((4 + 3 * x, 2 + 2 * y), "┫");
+                printer.print((1 + 3 * x, 3 + 2 * y), "┗━━┛");
+            });
+        }
+        if let Some(action) = self.selected_move() {
+            printer.print((1, 2 + 2 * h), &format!("Move: {:?}", action));
+        }
+    }
+
+    fn take_focus(&mut self, _: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Key(Key::Right) => self.handle_move(1, 0),
+            Event::Key(Key::Left) => self.handle_move(-1, 0),
+            Event::Key(Key::Up) => self.handle_move(0, -1),
+            Event::Key(Key::Down) => self.handle_move(0, 1),
+            Event::Key(Key::Tab) => {
+                if let Some((x, y, index)) = self.selection {
+                    let n_moves = self.cell_moves(x, y).len().max(1);
+                    self.selection = Some((x, y, (index + 1) % n_moves));
+                    EventResult::Consumed(None)
+                } else {
+                    EventResult::Ignored
+                }
+            }
+            Event::Key(Key::Enter) => {
+                if let Some(action) = self.selected_move() {
+                    if let Some(f) = self.choosing_move_cb.take() {
+                        f(action, self);
+                    }
+                    self.selection = None;
+                    EventResult::Consumed(None)
+                } else {
+                    EventResult::Ignored
+                }
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+
+    fn required_size(&mut self, _: Vec2) -> Vec2 {
+        Vec2 {
+            x: self.width * 3 + 3,
+            y: self.height * 2 + 4,
+        }
+    }
+}
+
+impl<G: Features + 'static> GameView for IGrid<G> {
+    type G = G;
+
+    fn set_state(&mut self, state: Self::G) {
+        self.game = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::Color;
+    use cursive::view::View;
+    use ndarray::Dimension;
+
+    /// Connect Four board, pieces falling to the bottom of the columns.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct ConnectFour {
+        cells: [[Option<Color>; 6]; 7],
+        turn: Color,
+    }
+
+    impl Base for ConnectFour {
+        type Move = usize;
+
+        fn possible_moves(&self) -> Vec<usize> {
+            (0..7).filter(|x| self.cells[*x][0].is_none()).collect()
+        }
+    }
+
+    #[async_trait]
+    impl Playable for ConnectFour {
+        async fn play(&mut self, action: &usize) -> f32 {
+            let column = &mut self.cells[*action];
+            let y = column.iter().rposition(Option::is_none).unwrap();
+            column[y] = Some(self.turn);
+            self.turn = self.turn.adv();
+            0.
+        }
+    }
+
+    impl Game for ConnectFour {
+        type Player = Color;
+
+        fn players() -> Vec<Color> {
+            vec![Color::Black, Color::White]
+        }
+
+        fn player_after(player: Color) -> Color {
+            player.adv()
+        }
+
+        fn turn(&self) -> Color {
+            self.turn
+        }
+    }
+
+    impl Features for ConnectFour {
+        type StateDim = ndarray::Ix3;
+        type ActionDim = ndarray::Ix1;
+        type Descriptor = ();
+
+        fn get_features(&self) {}
+
+        fn state_dimension(_: &()) -> ndarray::Ix3 {
+            ndarray::Dim([7, 6, 2])
+        }
+
+        fn action_dimension(_: &()) -> ndarray::Ix1 {
+            ndarray::Dim(7)
+        }
+
+        fn state_to_feature(&self, pov: Color) -> Array<f32, ndarray::Ix3> {
+            Array::from_shape_fn((7, 6, 2), |(x, y, p)| {
+                let player = if p == 0 { pov } else { pov.adv() };
+                if self.cells[x][y] == Some(player) {
+                    1.
+                } else {
+                    0.
+                }
+            })
+        }
+
+        fn moves_to_feature(_: &(), moves: &HashMap<usize, f32>) -> Array<f32, ndarray::Ix1> {
+            Array::from_shape_fn(7, |x| *moves.get(&x).unwrap_or(&0.))
+        }
+
+        fn feature_to_moves(&self, features: &Array<f32, ndarray::Ix1>) -> HashMap<usize, f32> {
+            self.possible_moves()
+                .into_iter()
+                .map(|x| (x, features[x]))
+                .collect()
+        }
+
+        fn all_feature_to_moves(
+            _: &(),
+            features: &Array<f32, ndarray::Ix1>,
+        ) -> HashMap<usize, f32> {
+            (0..7).map(|x| (x, features[x])).collect()
+        }
+
+        fn all_possible_moves(_: &()) -> Vec<usize> {
+            (0..7).collect()
+        }
+
+        fn symmetries(&self) -> Vec<Symmetry> {
+            // columns can be mirrored, pieces still fall to the bottom.
+            vec![
+                Symmetry::default(),
+                Symmetry {
+                    flip: true,
+                    rotations: 0,
+                },
+            ]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grid_view() {
+        let mut state = ConnectFour {
+            cells: [[None; 6]; 7],
+            turn: Color::Black,
+        };
+        state.play(&3).await;
+        state.play(&3).await;
+
+        let mut view = IGrid::new(state);
+        assert_eq!(view.required_size(Vec2::zero()), Vec2::new(24, 16));
+        assert_eq!(view.move_cell(&2), (2, 0));
+        assert_eq!(view.game.render_cell(3, 5), CellGlyph::Piece(0));
+        assert_eq!(view.game.render_cell(3, 4), CellGlyph::Piece(1));
+        assert_eq!(view.game.render_cell(3, 3), CellGlyph::Empty);
+
+        view.choose_move(Box::new(|_, _| ()));
+        assert_eq!(view.selected_move(), Some(0));
+        view.on_event(Event::Key(Key::Right));
+        assert_eq!(view.selected_move(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_rectangular_features() {
+        let mut state = ConnectFour {
+            cells: [[None; 6]; 7],
+            turn: Color::Black,
+        };
+        state.play(&0).await;
+
+        assert_eq!(ConnectFour::state_dimension(&()).size(), 7 * 6 * 2);
+        assert_eq!(ConnectFour::num_actions(&()), 7);
+        let features = state.state_to_feature(Color::Black);
+        assert_eq!(features.shape(), &[7, 6, 2]);
+
+        for symmetry in state.symmetries() {
+            let image = ConnectFour::state_symmetry(&(), symmetry, &features);
+            assert_eq!(image.shape(), features.shape());
+        }
+        let mirrored = ConnectFour::state_symmetry(&(), state.symmetries()[1], &features);
+        assert!(mirrored[[6, 5, 0]] > 0.);
+    }
+}
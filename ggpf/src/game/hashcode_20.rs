@@ -117,9 +117,36 @@ pub enum Move {
 }
 
 impl Hashcode20 {
+    /// Total value of the scanned books.
+    pub fn score(&self) -> usize {
+        self.scanned_books
+            .iter()
+            .map(|book| self.rules.books[*book])
+            .sum()
+    }
+
+    /// Current day.
+    pub fn day(&self) -> usize {
+        self.day
+    }
+
+    /// Libraries that completed their signup.
+    pub fn signedup_libraries(&self) -> &BTreeSet<usize> {
+        &self.signedup_libraries
+    }
+
+    /// Books that have been scanned.
+    pub fn scanned_books(&self) -> &BTreeSet<usize> {
+        &self.scanned_books
+    }
+
     fn compute_possible_moves(&self) -> Vec<Move> {
         let mut result = vec![];
 
+        if self.is_finished() {
+            return result;
+        }
+
         if self.pending_sign_up.is_none() {
             for library in self.unsignedup_libraries.iter() {
                 let (_, tts, _) = self.rules.libraries[*library];
@@ -171,19 +198,63 @@ impl SingleplayerGameBuilder for Hashcode20Settings {
     }
 }
 
-impl Singleplayer for Hashcode20 {
-    /*    fn score(&self) -> f32 {
-        let score: usize = self.scanned_books.iter().map(|book| self.rules.books[*book]).sum();
-        score as f32
-    }*/
+/// Hashcode 2020 game builder, reading the problem from a file.
+#[derive(Clone, Debug)]
+pub struct HashcodeBuilder {
+    settings: Hashcode20Settings,
+}
+
+impl HashcodeBuilder {
+    /// Create a game builder given the problem file.
+    pub fn new(filename: &str) -> HashcodeBuilder {
+        HashcodeBuilder {
+            settings: Hashcode20Settings::new_from_file(filename),
+        }
+    }
+
+    /// Problem settings.
+    pub fn settings(&self) -> &Hashcode20Settings {
+        &self.settings
+    }
+}
+
+#[async_trait]
+impl SingleplayerGameBuilder for HashcodeBuilder {
+    type G = Hashcode20;
+
+    async fn create(&self) -> Hashcode20 {
+        SingleplayerGameBuilder::create(&self.settings).await
+    }
 }
 
+/// The reward of a move is the score increment, so the total reward is the score of the solution.
+impl Singleplayer for Hashcode20 {}
+
 impl Base for Hashcode20 {
     type Move = Move;
 
     fn possible_moves(&self) -> Vec<Move> {
         self.compute_possible_moves()
     }
+
+    /// The game ends on the deadline.
+    fn is_finished(&self) -> bool {
+        self.day >= self.rules.D
+    }
+}
+
+/// Move encoder for Hashcode 2020: the move alone, regardless of the day.
+pub struct HashcodeCode {}
+
+impl MoveCode<Hashcode20> for HashcodeCode {
+    fn code(game: &Hashcode20, action: &Move) -> usize {
+        let L = game.rules.L;
+        match action {
+            Move::Skip => 0,
+            Move::Signup(library) => 1 + library,
+            Move::Scan(book, library) => 1 + L + book * L + library,
+        }
+    }
 }
 
 impl Hash for Hashcode20 {
@@ -228,3 +299,72 @@ impl Playable for Hashcode20 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::nmcs::NMCS;
+    use crate::policies::nrpa::NRPA;
+    use crate::policies::{SingleplayerPolicy, SingleplayerPolicyBuilder};
+    use crate::settings;
+
+    const EXAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/game/testdata/hashcode_20");
+
+    /// Plays the solution, checking that each move satisfies the problem constraints.
+    /// Returns the total reward.
+    async fn check_solution(state: &mut Hashcode20, solution: Vec<Move>) -> f32 {
+        let mut scanned_today = BTreeMap::new();
+        let mut total = 0.;
+
+        for action in solution {
+            assert!(state.possible_moves().contains(&action));
+            match action {
+                Move::Scan(book, library) => {
+                    let (books, _, max_per_day) = &state.rules.libraries[library];
+                    assert!(books.contains(&book));
+                    assert!(state.signedup_libraries().contains(&library));
+                    assert!(!state.scanned_books().contains(&book));
+
+                    let count = scanned_today.entry(library).or_insert(0);
+                    *count += 1;
+                    assert!(*count <= *max_per_day);
+                }
+                Move::Skip => scanned_today.clear(),
+                Move::Signup(_) => assert!(state.pending_sign_up.is_none()),
+            }
+            total += state.play(&action).await;
+            assert!(state.day() <= state.rules.D);
+        }
+        assert!(state.is_finished());
+        total
+    }
+
+    #[tokio::test]
+    async fn test_nmcs_schedule() {
+        let game_builder = HashcodeBuilder::new(EXAMPLE);
+        let mut state = SingleplayerGameBuilder::create(&game_builder).await;
+        let solution = NMCS::new(1).create().solve(&state).await;
+
+        let total = check_solution(&mut state, solution).await;
+        assert!(state.score() > 0);
+        assert!((total - state.score() as f32).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_nrpa_schedule() {
+        let game_builder = HashcodeBuilder::new(EXAMPLE);
+        let mut state = SingleplayerGameBuilder::create(&game_builder).await;
+        let solution = NRPA::<Hashcode20, HashcodeCode>::new(settings::NRPA {
+            level: 2,
+            iterations: 10,
+            alpha: 1.,
+        })
+        .create()
+        .solve(&state)
+        .await;
+
+        check_solution(&mut state, solution).await;
+        // all the books can be scanned before the deadline.
+        assert_eq!(state.score(), 21);
+    }
+}
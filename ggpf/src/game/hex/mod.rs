@@ -0,0 +1,348 @@
+use crate::game::breakthrough::Color;
+use crate::game::common::{cell_moves_to_feature, feature_to_cell_moves, CellMove};
+use crate::game::*;
+
+use async_trait::async_trait;
+use ndarray::{Array, Array2};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Move: the cell where the stone is placed.
+pub type Move = CellMove;
+
+/// Neighbors offsets on the rhombic board.
+const NEIGHBORS: [(isize, isize); 6] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, -1), (-1, 1)];
+
+/// Disjoint-set forest with union by rank.
+#[derive(Clone, Debug)]
+struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        UnionFind {
+            parent: (0..size).collect(),
+            rank: vec![0; size],
+        }
+    }
+
+    fn find(&self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.rank[a] < self.rank[b] {
+            self.parent[a] = b;
+        } else {
+            self.parent[b] = a;
+            if self.rank[a] == self.rank[b] {
+                self.rank[a] += 1;
+            }
+        }
+    }
+}
+
+/// Hex game state instance.
+///
+/// Black connects the top (`y = 0`) and bottom sides, white connects the left (`x = 0`)
+/// and right sides. Groups are tracked with a union-find, with one virtual node per side.
+#[derive(Clone)]
+pub struct Hex {
+    size: usize,
+    content: Array2<Option<Color>>,
+    turn: Color,
+    groups: UnionFind,
+}
+
+impl PartialEq for Hex {
+    fn eq(&self, other: &Self) -> bool {
+        self.content == other.content && self.turn == other.turn
+    }
+}
+
+impl Eq for Hex {}
+
+impl Hash for Hex {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.content.iter().for_each(|cell| cell.hash(state));
+        self.turn.hash(state);
+    }
+}
+
+impl fmt::Debug for Hex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Turn: {:?}", self.turn)?;
+        for y in 0..self.size {
+            write!(f, "{}", " ".repeat(y))?;
+            for x in 0..self.size {
+                match self.content[[x, y]] {
+                    None => write!(f, ". ")?,
+                    Some(c) => write!(f, "{:?} ", c)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Hex {
+    /// Board size.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Content of the cell at given position.
+    pub fn cell(&self, m: Move) -> Option<Color> {
+        self.content[[m.x, m.y]]
+    }
+
+    /// Virtual nodes of the two sides a color has to connect.
+    fn sides(&self, color: Color) -> (usize, usize) {
+        let cells = self.size * self.size;
+        match color {
+            Color::Black => (cells, cells + 1),
+            Color::White => (cells + 2, cells + 3),
+        }
+    }
+
+    /// Whether the stones of the given color connect its two sides.
+    fn connected(&self, color: Color) -> bool {
+        let (a, b) = self.sides(color);
+        self.groups.find(a) == self.groups.find(b)
+    }
+
+    /// Place a stone and merge it with its neighbors and sides.
+    fn place(&mut self, m: Move, color: Color) {
+        let (x, y) = (m.x, m.y);
+        self.content[[x, y]] = Some(color);
+        let index = m.index(self.size);
+        let last = self.size - 1;
+
+        let (first_side, second_side) = self.sides(color);
+        let position = match color {
+            Color::Black => y,
+            Color::White => x,
+        };
+        if position == 0 {
+            self.groups.union(index, first_side);
+        }
+        if position == last {
+            self.groups.union(index, second_side);
+        }
+
+        for (dx, dy) in NEIGHBORS.iter() {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            if nx < 0 || ny < 0 || nx > last as isize || ny > last as isize {
+                continue;
+            }
+            let (nx, ny) = (nx as usize, ny as usize);
+            if self.content[[nx, ny]] == Some(color) {
+                self.groups.union(index, nx * self.size + ny);
+            }
+        }
+    }
+}
+
+/// Game builder for Hex.
+#[derive(Copy, Clone, Debug)]
+pub struct HexBuilder {
+    /// Board size.
+    pub size: usize,
+}
+
+#[async_trait]
+impl GameBuilder for HexBuilder {
+    type G = Hex;
+
+    async fn create(&self, turn: Color) -> Hex {
+        Hex {
+            size: self.size,
+            content: Array2::from_elem((self.size, self.size), None),
+            turn,
+            groups: UnionFind::new(self.size * self.size + 4),
+        }
+    }
+}
+
+impl SingleWinner for Hex {
+    fn winner(&self) -> Option<Color> {
+        Self::players().into_iter().find(|c| self.connected(*c))
+    }
+}
+
+impl Game for Hex {
+    type Player = Color;
+
+    fn players() -> Vec<Color> {
+        vec![Color::Black, Color::White]
+    }
+
+    fn player_after(player: Color) -> Color {
+        player.adv()
+    }
+
+    fn turn(&self) -> Color {
+        self.turn
+    }
+}
+
+impl Base for Hex {
+    type Move = Move;
+
+    fn possible_moves(&self) -> Vec<Move> {
+        if self.winner().is_some() {
+            return vec![];
+        }
+        Self::all_possible_moves(&self.size)
+            .into_iter()
+            .filter(|m| self.cell(*m).is_none())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Playable for Hex {
+    async fn play(&mut self, m: &Move) -> f32 {
+        if m.x >= self.size || m.y >= self.size || self.content[[m.x, m.y]].is_some() {
+            return -1.;
+        }
+        self.place(*m, self.turn);
+        let reward = if self.connected(self.turn) { 1. } else { 0. };
+        self.turn = self.turn.adv();
+        reward
+    }
+}
+
+impl Features for Hex {
+    type StateDim = ndarray::Ix3;
+    type ActionDim = ndarray::Ix2;
+
+    type Descriptor = usize;
+
+    fn get_features(&self) -> Self::Descriptor {
+        self.size
+    }
+
+    fn state_dimension(size: &Self::Descriptor) -> Self::StateDim {
+        ndarray::Dim([*size, *size, 3])
+    }
+
+    fn action_dimension(size: &Self::Descriptor) -> Self::ActionDim {
+        ndarray::Dim([*size, *size])
+    }
+
+    fn state_to_feature(&self, pov: Self::Player) -> Array<f32, Self::StateDim> {
+        let mut features = ndarray::Array::zeros(Self::state_dimension(&self.size));
+
+        for ((x, y, z), row) in features.indexed_iter_mut() {
+            let cell = self.content[[x, y]];
+            if (z == 0 && cell == Some(pov)) || (z == 1 && cell == Some(pov.adv())) {
+                *row = 1.0
+            } else if z == 2 {
+                if self.turn() == Color::White {
+                    *row = 1.0
+                } else {
+                    *row = -1.0
+                }
+            }
+        }
+
+        features
+    }
+
+    fn moves_to_feature(
+        descr: &Self::Descriptor,
+        moves: &HashMap<Self::Move, f32>,
+    ) -> Array<f32, Self::ActionDim> {
+        cell_moves_to_feature(Self::action_dimension(descr), moves)
+    }
+
+    fn feature_to_moves(&self, features: &Array<f32, Self::ActionDim>) -> HashMap<Self::Move, f32> {
+        feature_to_cell_moves(self.possible_moves(), features)
+    }
+
+    fn all_feature_to_moves(
+        descr: &Self::Descriptor,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> HashMap<Self::Move, f32> {
+        feature_to_cell_moves(Self::all_possible_moves(descr), features)
+    }
+
+    fn symmetries(&self) -> Vec<Symmetry> {
+        // the half turn keeps the neighborhoods and each player's sides.
+        vec![
+            Symmetry::default(),
+            Symmetry {
+                flip: false,
+                rotations: 2,
+            },
+        ]
+    }
+
+    fn all_possible_moves(size: &Self::Descriptor) -> Vec<Self::Move> {
+        let mut res = vec![];
+        for x in 0..*size {
+            for y in 0..*size {
+                res.push(CellMove::new(x, y));
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[tokio::test]
+    async fn test_winning_bridge() {
+        let mut state = HexBuilder { size: 5 }.create(Color::Black).await;
+        // black chain from top to bottom, using the (1, -1) diagonal.
+        let black = [(2, 0), (2, 1), (1, 2), (1, 3), (0, 4)];
+        let white = [(4, 0), (4, 1), (4, 2), (4, 3)];
+
+        for (i, m) in black.iter().enumerate() {
+            let reward = state.play(&CellMove::from(*m)).await;
+            assert_eq!(state.winner().is_some(), i == black.len() - 1);
+            if i < white.len() {
+                assert_eq!(reward, 0.);
+                assert_eq!(state.play(&CellMove::from(white[i])).await, 0.);
+            } else {
+                assert_eq!(reward, 1.);
+            }
+        }
+        assert_eq!(state.winner(), Some(Color::Black));
+        assert!(state.possible_moves().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_full_board_single_winner() {
+        let mut rng = rand::thread_rng();
+        for size in 1..8 {
+            for _ in 0..20 {
+                let mut state = HexBuilder { size }.create(Color::Black).await;
+                for m in Hex::all_possible_moves(&size) {
+                    let color = if rng.gen() {
+                        Color::Black
+                    } else {
+                        Color::White
+                    };
+                    state.place(m, color);
+                }
+                assert_ne!(state.connected(Color::Black), state.connected(Color::White));
+            }
+        }
+    }
+}
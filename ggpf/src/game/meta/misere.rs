@@ -0,0 +1,237 @@
+use crate::game::*;
+use async_trait::async_trait;
+
+/// Misère variant of a two-player game: the player who would win the underlying
+/// game loses instead. Everything else is delegated to the underlying game.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Misere<G> {
+    /// Current game state.
+    pub state: G,
+}
+
+impl<G: Base> Base for Misere<G> {
+    type Move = G::Move;
+
+    fn possible_moves(&self) -> Vec<Self::Move> {
+        self.state.possible_moves()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
+}
+
+/// Rewards are those of the underlying game, negated.
+#[async_trait]
+impl<G: Playable> Playable for Misere<G> {
+    async fn play(&mut self, action: &Self::Move) -> f32 {
+        -self.state.play(action).await
+    }
+
+    async fn random_move(&mut self) -> (Self::Move, f32) {
+        let (action, reward) = self.state.random_move().await;
+        (action, -reward)
+    }
+}
+
+impl<G: Game> Game for Misere<G> {
+    type Player = G::Player;
+
+    fn players() -> Vec<Self::Player> {
+        G::players()
+    }
+
+    fn player_after(player: Self::Player) -> Self::Player {
+        G::player_after(player)
+    }
+
+    fn turn(&self) -> Self::Player {
+        self.state.turn()
+    }
+}
+
+impl<G: SingleWinner> SingleWinner for Misere<G> {
+    fn winner(&self) -> Option<G::Player> {
+        self.state.winner().map(G::player_after)
+    }
+}
+
+/* GAME BUILDER */
+/// Builder for the misère variant of a game.
+#[derive(Clone, Copy)]
+pub struct MisereGB<GB>(GB);
+
+impl<GB> MisereGB<GB> {
+    /// Creates a misère game builder, given the corresponding standard game builder.
+    pub fn new(gb: GB) -> Self {
+        Self(gb)
+    }
+}
+
+#[async_trait]
+impl<GB> GameBuilder for MisereGB<GB>
+where
+    GB::G: 'static,
+    GB: GameBuilder + Send + Sync,
+{
+    type G = Misere<GB::G>;
+
+    async fn create(&self, starting: <Self::G as Game>::Player) -> Misere<GB::G> {
+        Misere {
+            state: self.0.create(starting).await,
+        }
+    }
+
+    async fn create_from(
+        &self,
+        position: &str,
+        starting: <Self::G as Game>::Player,
+    ) -> Result<Misere<GB::G>, StrError> {
+        let state = self.0.create_from(position, starting).await?;
+        Ok(Misere { state })
+    }
+}
+
+impl<G: Features> Features for Misere<G> {
+    type StateDim = G::StateDim;
+    type ActionDim = G::ActionDim;
+
+    type Descriptor = G::Descriptor;
+
+    fn get_features(&self) -> Self::Descriptor {
+        self.state.get_features()
+    }
+
+    fn state_dimension(descr: &Self::Descriptor) -> Self::StateDim {
+        G::state_dimension(descr)
+    }
+
+    fn action_dimension(descr: &Self::Descriptor) -> Self::ActionDim {
+        G::action_dimension(descr)
+    }
+
+    fn state_to_feature(&self, pov: Self::Player) -> Array<f32, Self::StateDim> {
+        self.state.state_to_feature(pov)
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.state.state_hash()
+    }
+
+    fn moves_to_feature(
+        descr: &Self::Descriptor,
+        moves: &HashMap<Self::Move, f32>,
+    ) -> Array<f32, Self::ActionDim> {
+        G::moves_to_feature(descr, moves)
+    }
+
+    fn feature_to_moves(&self, features: &Array<f32, Self::ActionDim>) -> HashMap<Self::Move, f32> {
+        self.state.feature_to_moves(features)
+    }
+
+    fn all_feature_to_moves(
+        descr: &Self::Descriptor,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> HashMap<Self::Move, f32> {
+        G::all_feature_to_moves(descr, features)
+    }
+
+    fn all_possible_moves(descr: &Self::Descriptor) -> Vec<Self::Move> {
+        G::all_possible_moves(descr)
+    }
+
+    fn action_index(descr: &Self::Descriptor, action: &Self::Move) -> usize {
+        G::action_index(descr, action)
+    }
+
+    fn action_from_index(descr: &Self::Descriptor, index: usize) -> Option<Self::Move> {
+        G::action_from_index(descr, index)
+    }
+
+    fn render_cell(&self, x: usize, y: usize) -> CellGlyph {
+        self.state.render_cell(x, y)
+    }
+
+    fn symmetries(&self) -> Vec<Symmetry> {
+        self.state.symmetries()
+    }
+
+    fn state_symmetry(
+        descr: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::StateDim>,
+    ) -> Array<f32, Self::StateDim> {
+        G::state_symmetry(descr, symmetry, features)
+    }
+
+    fn action_symmetry(
+        descr: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> Array<f32, Self::ActionDim> {
+        G::action_symmetry(descr, symmetry, features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::{BreakthroughBuilder, Color};
+    use crate::game::nim::{Nim, NimBuilder};
+    use futures::executor::block_on;
+
+    /// Best move with its value for the player to move, under perfect play.
+    fn negamax<G: SingleWinner + Clone>(state: &G) -> (Option<G::Move>, f32) {
+        if state.is_finished() {
+            let value = match state.winner() {
+                Some(player) if player == state.turn() => 1.,
+                Some(_) => -1.,
+                None => 0.,
+            };
+            return (None, value);
+        }
+        let mut best = (None, -f32::MAX);
+        for action in state.possible_moves() {
+            let mut next = state.clone();
+            block_on(next.play(&action));
+            let value = -negamax(&next).1;
+            if value > best.1 {
+                best = (Some(action), value);
+            }
+        }
+        best
+    }
+
+    #[tokio::test]
+    async fn test_misere_winner() {
+        let builder = MisereGB::new(BreakthroughBuilder { size: 5 });
+        let mut state = builder.create(Color::Black).await;
+        while !state.is_finished() {
+            state.random_move().await;
+        }
+        let winner = state.state.winner().unwrap();
+        assert_eq!(state.winner(), Some(winner.adv()));
+    }
+
+    #[allow(clippy::float_cmp)]
+    #[tokio::test]
+    async fn test_misere_negamax() {
+        // taking the last object wins in the standard game and loses in misère.
+        let builder = NimBuilder {
+            initial_heaps: vec![2],
+        };
+        let state: Nim = builder.create(Color::Black).await;
+        assert_eq!(negamax(&state), (Some((0, 2)), 1.));
+
+        let state = MisereGB::new(builder).create(Color::Black).await;
+        assert_eq!(negamax(&state), (Some((0, 1)), 1.));
+
+        let builder = NimBuilder {
+            initial_heaps: vec![1, 1],
+        };
+        let state: Nim = builder.create(Color::Black).await;
+        assert_eq!(negamax(&state).1, -1.);
+        let state = MisereGB::new(builder).create(Color::Black).await;
+        assert_eq!(negamax(&state).1, 1.);
+    }
+}
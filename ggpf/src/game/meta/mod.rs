@@ -1,4 +1,10 @@
+/// Misère variant of a game, where the winner loses
+pub mod misere;
+/// Starting games with a line of an opening book
+pub mod opening_book;
 /// Simulation of a game
 pub mod simulated;
 /// Wrapping a game with its history
 pub mod with_history;
+/// Wrapping a two-player game with the swap rule
+pub mod with_swap;
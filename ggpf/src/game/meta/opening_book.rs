@@ -0,0 +1,57 @@
+use crate::game::*;
+
+use async_trait::async_trait;
+use rand::seq::SliceRandom;
+
+/// Builder starting each game with a line of an opening book.
+///
+/// The game is created by the wrapped builder, then a randomly chosen line is replayed
+/// before handing the game over, so self-play records start after the book moves.
+#[derive(Clone)]
+pub struct OpeningBookGB<GB: GameBuilder> {
+    gb: GB,
+    book: Vec<Vec<<GB::G as Base>::Move>>,
+}
+
+impl<GB: GameBuilder> OpeningBookGB<GB> {
+    /// Creates an opening book game builder, given the standard game builder and the opening lines.
+    pub fn new(gb: GB, book: Vec<Vec<<GB::G as Base>::Move>>) -> Self {
+        Self { gb, book }
+    }
+}
+
+#[async_trait]
+impl<GB> GameBuilder for OpeningBookGB<GB>
+where
+    GB: GameBuilder + Send + Sync,
+{
+    type G = GB::G;
+
+    async fn create(&self, starting: <Self::G as Game>::Player) -> GB::G {
+        let mut state = self.gb.create(starting).await;
+        let line = self
+            .book
+            .choose(&mut rand::thread_rng())
+            .cloned()
+            .unwrap_or_default();
+        for action in line.iter() {
+            debug_assert!(
+                state.possible_moves().contains(action),
+                "Illegal opening move {:?} on board:\n{:?}",
+                action,
+                state
+            );
+            state.play(action).await;
+        }
+        state
+    }
+
+    /// Positions are created as is, without replaying an opening line.
+    async fn create_from(
+        &self,
+        position: &str,
+        starting: <Self::G as Game>::Player,
+    ) -> Result<GB::G, StrError> {
+        self.gb.create_from(position, starting).await
+    }
+}
@@ -26,6 +26,7 @@ where
     repr_dimension: Ix3,
     game_descriptor: G::Descriptor,
     support_size: usize,
+    transform: Option<f32>,
 }
 
 impl<G> Clone for Simulated<G>
@@ -42,6 +43,20 @@ where
             repr_dimension: self.repr_dimension,
             game_descriptor: self.game_descriptor.clone(),
             support_size: self.support_size,
+            transform: self.transform,
+        }
+    }
+}
+
+/// Hashes the representation, which is all the features depend on.
+impl<G> Hash for Simulated<G>
+where
+    G: Features,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.turn.hash(state);
+        for x in self.repr_state.iter() {
+            x.to_bits().hash(state);
         }
     }
 }
@@ -58,6 +73,8 @@ where
     /// - `repr_state`: initial repr state.
     /// - `initial_possible_moves`: available moves for the initial state.
     /// - `dynamics_evaluator`: evaluator for the dynamics network.
+    /// - `support_size`: reward support size, zero for scalar rewards.
+    /// - `transform`: `ε` of the value transform of the rewards, if any.
     pub fn new(
         turn: G::Player,
         repr_state: Array<f32, Ix3>,
@@ -65,6 +82,7 @@ where
         initial_possible_moves: Vec<G::Move>,
         dynamics_evaluator: mpsc::Sender<DynamicsEvaluatorChannel>,
         support_size: usize,
+        transform: Option<f32>,
     ) -> Self {
         let repr_dimension = repr_state.raw_dim();
         Simulated {
@@ -76,6 +94,7 @@ where
             repr_dimension,
             game_descriptor,
             support_size,
+            transform,
         }
     }
 }
@@ -121,6 +140,7 @@ where
             &self.repr_state,
             &move_encoded,
             self.support_size,
+            self.transform,
         )
         .await;
         self.repr_state = network_output.repr_state;
@@ -194,6 +214,14 @@ where
         G::all_possible_moves(&descr.1)
     }
 
+    fn action_index(descr: &Self::Descriptor, action: &Self::Move) -> usize {
+        G::action_index(&descr.1, action)
+    }
+
+    fn action_from_index(descr: &Self::Descriptor, index: usize) -> Option<Self::Move> {
+        G::action_from_index(&descr.1, index)
+    }
+
     fn all_feature_to_moves(
         descr: &Self::Descriptor,
         features: &Array<f32, Self::ActionDim>,
@@ -1,13 +1,19 @@
 use crate::game::*;
 use async_trait::async_trait;
+use ndarray::Ix3;
 use std::sync::Arc;
 
 /// A game with its history.
+///
+/// The features are the last `history_len` frames of the game, stacked along the
+/// channel axis: the wrapped game features should be `(x, y, channels)` arrays, and
+/// the stacked features have `history_len` times more channels, from the oldest frame
+/// to the current one. Frames before the start of the game are zeros.
 #[derive(Clone, Debug)]
 pub struct WithHistory<G: Base> {
     prec: Option<Arc<Self>>,
     /// Current game state.
-    pub state: G, // TODO: create accessor
+    pub state: G,
     history_len: usize,
 }
 
@@ -17,6 +23,10 @@ impl<G: Base + Clone> Base for WithHistory<G> {
     fn possible_moves(&self) -> Vec<Self::Move> {
         self.state.possible_moves()
     }
+
+    fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
 }
 
 #[async_trait]
@@ -67,6 +77,24 @@ impl<G: Base + Hash> Hash for WithHistory<G> {
         self.state.hash(state)
     }
 }
+
+impl<G: Base> WithHistory<G> {
+    /// Game states of the history window, from the current one to the oldest one.
+    /// There are less than `history_len` states at the beginning of the game.
+    fn frames(&self) -> Vec<&G> {
+        let mut frames = vec![];
+        let mut current = Some(self);
+        while let Some(node) = current {
+            if frames.len() == self.history_len {
+                break;
+            }
+            frames.push(&node.state);
+            current = node.prec.as_ref().map(|b| b.as_ref());
+        }
+        frames
+    }
+}
+
 /* GAME BUILDER */
 /// Builder for a game with history.
 #[derive(Clone, Copy)]
@@ -75,6 +103,7 @@ pub struct WithHistoryGB<GB>(GB, usize);
 impl<GB> WithHistoryGB<GB> {
     /// Creates a game builder with history, given a correspond standard game builder.
     pub fn new(gb: GB, history_len: usize) -> Self {
+        assert!(history_len > 0, "At least one frame should be stacked.");
         Self(gb, history_len)
     }
 }
@@ -95,30 +124,39 @@ where
             history_len: self.1,
         }
     }
+
+    async fn create_from(
+        &self,
+        position: &str,
+        starting: <Self::G as Game>::Player,
+    ) -> Result<WithHistory<GB::G>, StrError> {
+        let state = self.0.create_from(position, starting).await?;
+        Ok(WithHistory {
+            prec: None,
+            state,
+            history_len: self.1,
+        })
+    }
 }
 
-impl<G: Features + Clone + Sync + Send> Features for WithHistory<G> {
-    // one dimension larger to store history
-    type StateDim = <G::StateDim as Dimension>::Larger;
+impl<G> Features for WithHistory<G>
+where
+    G: Features<StateDim = Ix3> + Clone + Sync + Send,
+{
+    type StateDim = Ix3;
     type ActionDim = G::ActionDim;
 
-    type Descriptor = (<G::StateDim as Dimension>::Larger, G::Descriptor);
+    /// Number of stacked frames, and descriptor of the wrapped game.
+    type Descriptor = (usize, G::Descriptor);
 
     fn get_features(&self) -> Self::Descriptor {
-        let ft = self.state.get_features();
-
-        let state_dimension = {
-            let game_state_dimension = G::state_dimension(&ft);
-            let mut new_dim = game_state_dimension.insert_axis(Axis(0));
-            new_dim[0] = self.history_len;
-            new_dim
-        };
-
-        (state_dimension, ft)
+        (self.history_len, self.state.get_features())
     }
 
     fn state_dimension(descr: &Self::Descriptor) -> Self::StateDim {
-        descr.0.clone()
+        let mut dimension = G::state_dimension(&descr.1);
+        dimension[2] *= descr.0;
+        dimension
     }
 
     fn action_dimension(descr: &Self::Descriptor) -> Self::ActionDim {
@@ -126,21 +164,30 @@ impl<G: Features + Clone + Sync + Send> Features for WithHistory<G> {
     }
 
     fn state_to_feature(&self, pov: Self::Player) -> Array<f32, Self::StateDim> {
-        let mut states_ref = vec![];
-        (0..self.history_len).fold(self, |current, _| {
-            states_ref.push(&current.state);
-            let res: &Self = current.prec.as_ref().map(|b| b.as_ref()).unwrap_or(current);
-            res
-        });
-        let features_array: Vec<ndarray::Array<f32, Self::StateDim>> = states_ref
-            .iter()
+        let frames = self.frames();
+        let frame_dimension = G::state_dimension(&self.state.get_features());
+        let padding = Array::zeros(frame_dimension);
+
+        let features_array: Vec<Array<f32, Ix3>> = (0..self.history_len)
             .rev()
-            .map(|g| g.state_to_feature(pov).insert_axis(Axis(0)))
+            .map(|age| match frames.get(age) {
+                Some(frame) => frame.state_to_feature(pov),
+                None => padding.clone(),
+            })
             .collect();
-        let features_array_view: Vec<ndarray::ArrayView<f32, Self::StateDim>> =
+        let features_array_view: Vec<ndarray::ArrayView<f32, Ix3>> =
             features_array.iter().map(|x| x.view()).collect();
-        ndarray::stack(Axis(0), &features_array_view)
-            .expect("All features should have the same shape.")
+        ndarray::stack(Axis(2), &features_array_view)
+            .expect("All frames should have the same shape.")
+    }
+
+    /// Combines the hashes of the stacked frames.
+    fn state_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for frame in self.frames() {
+            hasher.write_u64(frame.state_hash());
+        }
+        hasher.finish()
     }
 
     fn moves_to_feature(
@@ -161,9 +208,50 @@ impl<G: Features + Clone + Sync + Send> Features for WithHistory<G> {
         G::all_feature_to_moves(&descr.1, features)
     }
 
+    fn symmetries(&self) -> Vec<Symmetry> {
+        self.state.symmetries()
+    }
+
+    fn state_symmetry(
+        descr: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::StateDim>,
+    ) -> Array<f32, Self::StateDim> {
+        // each frame is transformed independently.
+        let channels = G::state_dimension(&descr.1)[2];
+        let features_array: Vec<Array<f32, Ix3>> = features
+            .axis_chunks_iter(Axis(2), channels)
+            .map(|frame| G::state_symmetry(&descr.1, symmetry, &frame.to_owned()))
+            .collect();
+        let features_array_view: Vec<ndarray::ArrayView<f32, Ix3>> =
+            features_array.iter().map(|x| x.view()).collect();
+        ndarray::stack(Axis(2), &features_array_view)
+            .expect("All frames should have the same shape.")
+    }
+
+    fn action_symmetry(
+        descr: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> Array<f32, Self::ActionDim> {
+        G::action_symmetry(&descr.1, symmetry, features)
+    }
+
     fn all_possible_moves(descr: &Self::Descriptor) -> Vec<Self::Move> {
         G::all_possible_moves(&descr.1)
     }
+
+    fn action_index(descr: &Self::Descriptor, action: &Self::Move) -> usize {
+        G::action_index(&descr.1, action)
+    }
+
+    fn action_from_index(descr: &Self::Descriptor, index: usize) -> Option<Self::Move> {
+        G::action_from_index(&descr.1, index)
+    }
+
+    fn render_cell(&self, x: usize, y: usize) -> CellGlyph {
+        self.state.render_cell(x, y)
+    }
 }
 
 /// Interface wrapper for WithHistory.
@@ -221,3 +309,122 @@ where
         self.view.set_state(state.state)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// Single-player game counting its steps, with features filled with the step count.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Counter(usize);
+
+    impl Base for Counter {
+        type Move = ();
+
+        fn possible_moves(&self) -> Vec<()> {
+            if self.0 < 10 {
+                vec![()]
+            } else {
+                vec![]
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Playable for Counter {
+        async fn play(&mut self, _action: &()) -> f32 {
+            self.0 += 1;
+            0.
+        }
+    }
+
+    impl Singleplayer for Counter {}
+
+    #[derive(Clone)]
+    struct CounterBuilder;
+
+    #[async_trait]
+    impl SingleplayerGameBuilder for CounterBuilder {
+        type G = Counter;
+
+        async fn create(&self) -> Counter {
+            Counter(0)
+        }
+    }
+
+    impl Features for Counter {
+        type StateDim = Ix3;
+        type ActionDim = ndarray::Ix1;
+        type Descriptor = ();
+
+        fn get_features(&self) {}
+
+        fn state_dimension(_descr: &()) -> Ix3 {
+            ndarray::Dim([2, 2, 3])
+        }
+
+        fn action_dimension(_descr: &()) -> ndarray::Ix1 {
+            ndarray::Dim([1])
+        }
+
+        fn state_to_feature(&self, _pov: u8) -> Array<f32, Ix3> {
+            Array::from_elem(Self::state_dimension(&()), (self.0 + 1) as f32)
+        }
+
+        fn moves_to_feature(_descr: &(), moves: &HashMap<(), f32>) -> Array<f32, ndarray::Ix1> {
+            Array::from_elem(1, *moves.get(&()).unwrap_or(&0.))
+        }
+
+        fn feature_to_moves(&self, features: &Array<f32, ndarray::Ix1>) -> HashMap<(), f32> {
+            Self::all_feature_to_moves(&(), features)
+        }
+
+        fn all_feature_to_moves(
+            _descr: &(),
+            features: &Array<f32, ndarray::Ix1>,
+        ) -> HashMap<(), f32> {
+            vec![((), features[0])].into_iter().collect()
+        }
+
+        fn all_possible_moves(_descr: &()) -> Vec<()> {
+            vec![()]
+        }
+    }
+
+    /// Value of each stacked frame, from the oldest one.
+    fn frame_values(features: &Array3<f32>) -> Vec<f32> {
+        features
+            .axis_chunks_iter(Axis(2), 3)
+            .map(|frame| {
+                assert!(frame.iter().all(|x| *x == frame[[0, 0, 0]]));
+                frame[[0, 0, 0]]
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_with_history() {
+        let mut state = WithHistoryGB::new(CounterBuilder, 4).create(0).await;
+        let dimension = WithHistory::<Counter>::state_dimension(&state.get_features());
+        assert_eq!(dimension, ndarray::Dim([2, 2, 12]));
+
+        let features = state.state_to_feature(0);
+        assert_eq!(features.raw_dim(), dimension);
+        assert_eq!(frame_values(&features), vec![0., 0., 0., 1.]);
+
+        state.play(&()).await;
+        state.play(&()).await;
+        assert_eq!(
+            frame_values(&state.state_to_feature(0)),
+            vec![0., 1., 2., 3.]
+        );
+
+        state.play(&()).await;
+        state.play(&()).await;
+        assert_eq!(
+            frame_values(&state.state_to_feature(0)),
+            vec![2., 3., 4., 5.]
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use crate::game::*;
+use async_trait::async_trait;
+
+/// Move of a game with the swap rule.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SwapMove<M> {
+    /// Move of the underlying game.
+    Play(M),
+    /// Take over the first move: the two players exchange their colors.
+    Swap,
+}
+
+/// A two-player game with the swap (pie) rule: after the first move, the second
+/// player may swap instead of playing, taking over the first player's color.
+///
+/// Players are external: once swapped, each player controls the color of the underlying
+/// game that belonged to the other one.
+#[derive(Clone, Debug)]
+pub struct WithSwap<G: Base> {
+    /// Current game state.
+    pub state: G,
+    swapped: bool,
+    plies: usize,
+}
+
+impl<G: Game> WithSwap<G> {
+    /// Whether the players have exchanged their colors.
+    pub fn swapped(&self) -> bool {
+        self.swapped
+    }
+
+    /// Whether the player to move can swap.
+    pub fn swap_available(&self) -> bool {
+        self.plies == 1 && !self.swapped
+    }
+
+    /// Color of the underlying game played by the given player, and conversely.
+    fn owner(&self, player: G::Player) -> G::Player {
+        if self.swapped {
+            G::player_after(player)
+        } else {
+            player
+        }
+    }
+}
+
+impl<G: Game> Base for WithSwap<G> {
+    type Move = SwapMove<G::Move>;
+
+    fn possible_moves(&self) -> Vec<Self::Move> {
+        let mut moves: Vec<Self::Move> = self
+            .state
+            .possible_moves()
+            .into_iter()
+            .map(SwapMove::Play)
+            .collect();
+        if self.swap_available() && !moves.is_empty() {
+            moves.push(SwapMove::Swap);
+        }
+        moves
+    }
+
+    fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
+}
+
+#[async_trait]
+impl<G: Game> Playable for WithSwap<G> {
+    async fn play(&mut self, action: &<Self as Base>::Move) -> f32 {
+        match action {
+            SwapMove::Play(m) => {
+                self.plies += 1;
+                self.state.play(m).await
+            }
+            SwapMove::Swap if self.swap_available() => {
+                self.plies += 1;
+                self.swapped = true;
+                0.
+            }
+            SwapMove::Swap => -1.,
+        }
+    }
+}
+
+impl<G: Game> Game for WithSwap<G> {
+    type Player = G::Player;
+
+    fn players() -> Vec<Self::Player> {
+        G::players()
+    }
+
+    fn player_after(player: Self::Player) -> Self::Player {
+        G::player_after(player)
+    }
+
+    fn turn(&self) -> Self::Player {
+        self.owner(self.state.turn())
+    }
+}
+
+impl<G: SingleWinner> SingleWinner for WithSwap<G> {
+    fn winner(&self) -> Option<G::Player> {
+        self.state.winner().map(|color| self.owner(color))
+    }
+}
+
+impl<G: Base + PartialEq> PartialEq for WithSwap<G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.state.eq(&other.state)
+            && self.swapped == other.swapped
+            && (self.plies == 1) == (other.plies == 1)
+    }
+}
+impl<G: Base + Eq> Eq for WithSwap<G> {}
+
+impl<G: Base + Hash> Hash for WithSwap<G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state.hash(state);
+        self.swapped.hash(state);
+        (self.plies == 1).hash(state);
+    }
+}
+
+/* GAME BUILDER */
+/// Builder for a game with the swap rule.
+#[derive(Clone, Copy)]
+pub struct WithSwapGB<GB>(GB);
+
+impl<GB> WithSwapGB<GB> {
+    /// Creates a game builder with the swap rule, given a correspond standard game builder.
+    pub fn new(gb: GB) -> Self {
+        Self(gb)
+    }
+}
+
+#[async_trait]
+impl<GB> GameBuilder for WithSwapGB<GB>
+where
+    GB::G: 'static,
+    GB: GameBuilder + Send + Sync,
+{
+    type G = WithSwap<GB::G>;
+
+    async fn create(&self, starting: <Self::G as Game>::Player) -> WithSwap<GB::G> {
+        let state = self.0.create(starting).await;
+        WithSwap {
+            state,
+            swapped: false,
+            plies: 0,
+        }
+    }
+
+    async fn create_from(
+        &self,
+        position: &str,
+        starting: <Self::G as Game>::Player,
+    ) -> Result<WithSwap<GB::G>, StrError> {
+        let state = self.0.create_from(position, starting).await?;
+        // the swap is only offered after the first move of the game, not from a
+        // given position.
+        Ok(WithSwap {
+            state,
+            swapped: false,
+            plies: 2,
+        })
+    }
+}
+
+/// The swap move is stored after the flattened actions of the underlying game.
+impl<G: Features> Features for WithSwap<G> {
+    type StateDim = G::StateDim;
+    type ActionDim = ndarray::Ix1;
+
+    type Descriptor = G::Descriptor;
+
+    fn get_features(&self) -> Self::Descriptor {
+        self.state.get_features()
+    }
+
+    fn state_dimension(descr: &Self::Descriptor) -> Self::StateDim {
+        G::state_dimension(descr)
+    }
+
+    fn action_dimension(descr: &Self::Descriptor) -> Self::ActionDim {
+        ndarray::Dim(G::action_dimension(descr).size() + 1)
+    }
+
+    fn state_to_feature(&self, pov: Self::Player) -> Array<f32, Self::StateDim> {
+        self.state.state_to_feature(self.owner(pov))
+    }
+
+    fn moves_to_feature(
+        descr: &Self::Descriptor,
+        moves: &HashMap<Self::Move, f32>,
+    ) -> Array<f32, Self::ActionDim> {
+        let inner_moves: HashMap<G::Move, f32> = moves
+            .iter()
+            .filter_map(|(m, p)| match m {
+                SwapMove::Play(m) => Some((*m, *p)),
+                SwapMove::Swap => None,
+            })
+            .collect();
+        let inner = G::moves_to_feature(descr, &inner_moves);
+        let swap = moves.get(&SwapMove::Swap).cloned().unwrap_or(0.);
+
+        inner.iter().cloned().chain(std::iter::once(swap)).collect()
+    }
+
+    fn feature_to_moves(&self, features: &Array<f32, Self::ActionDim>) -> HashMap<Self::Move, f32> {
+        let descr = self.get_features();
+        let inner = inner_features::<G>(&descr, features);
+        let moves = self.state.feature_to_moves(&inner);
+        if !self.swap_available() {
+            return moves
+                .into_iter()
+                .map(|(m, p)| (SwapMove::Play(m), p))
+                .collect();
+        }
+
+        // mass of the legal moves of the underlying game.
+        let legal: HashMap<G::Move, f32> = self
+            .state
+            .possible_moves()
+            .into_iter()
+            .map(|m| (m, 1.))
+            .collect();
+        let z: f32 = (G::moves_to_feature(&descr, &legal) * &inner).sum();
+        with_swap(moves, z, features[features.len() - 1])
+    }
+
+    fn all_feature_to_moves(
+        descr: &Self::Descriptor,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> HashMap<Self::Move, f32> {
+        let inner = inner_features::<G>(descr, features);
+        let z = inner.sum();
+        let moves = G::all_feature_to_moves(descr, &inner);
+        with_swap(moves, z, features[features.len() - 1])
+    }
+
+    fn symmetries(&self) -> Vec<Symmetry> {
+        self.state.symmetries()
+    }
+
+    fn state_symmetry(
+        descr: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::StateDim>,
+    ) -> Array<f32, Self::StateDim> {
+        G::state_symmetry(descr, symmetry, features)
+    }
+
+    fn action_symmetry(
+        descr: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> Array<f32, Self::ActionDim> {
+        let inner = inner_features::<G>(descr, features);
+        let swap = features[features.len() - 1];
+        G::action_symmetry(descr, symmetry, &inner)
+            .iter()
+            .cloned()
+            .chain(std::iter::once(swap))
+            .collect()
+    }
+
+    fn all_possible_moves(descr: &Self::Descriptor) -> Vec<Self::Move> {
+        G::all_possible_moves(descr)
+            .into_iter()
+            .map(SwapMove::Play)
+            .chain(std::iter::once(SwapMove::Swap))
+            .collect()
+    }
+}
+
+/// Actions features of the underlying game.
+fn inner_features<G: Features>(
+    descr: &G::Descriptor,
+    features: &Array<f32, ndarray::Ix1>,
+) -> Array<f32, G::ActionDim> {
+    let shape = G::action_dimension(descr);
+    let values = features.iter().take(shape.size()).cloned().collect();
+    Array::from_shape_vec(shape, values).unwrap()
+}
+
+/// Add the swap move to the normalized distribution `moves` of mass `z` before normalization.
+fn with_swap<M: MoveTrait>(moves: HashMap<M, f32>, z: f32, swap: f32) -> HashMap<SwapMove<M>, f32> {
+    let total = z + swap;
+    let mut res: HashMap<_, _> = moves
+        .into_iter()
+        .map(|(m, p)| (SwapMove::Play(m), p * z / total))
+        .collect();
+    res.insert(SwapMove::Swap, swap / total);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::Color;
+    use crate::game::tictactoe::{TicTacToe, TicTacToeBuilder};
+
+    #[tokio::test]
+    async fn test_swap_once() {
+        let mut state = WithSwapGB::new(TicTacToeBuilder::default())
+            .create(Color::Black)
+            .await;
+        assert!(!state.possible_moves().contains(&SwapMove::Swap));
+
+        state.play(&SwapMove::Play((1, 1))).await;
+        assert_eq!(state.turn(), Color::White);
+        assert!(state.possible_moves().contains(&SwapMove::Swap));
+
+        assert_eq!(state.play(&SwapMove::Swap).await, 0.);
+        assert!(state.swapped());
+        // the first player now plays the other color, the center is owned by white.
+        assert_eq!(state.turn(), Color::Black);
+        assert_eq!(state.state.turn(), Color::White);
+        assert!(!state.possible_moves().contains(&SwapMove::Swap));
+        assert_eq!(state.play(&SwapMove::Swap).await, -1.);
+
+        for m in &[(1, 0), (0, 0), (2, 0), (2, 2)] {
+            state.play(&SwapMove::Play(*m)).await;
+            assert!(!state.possible_moves().contains(&SwapMove::Swap));
+        }
+        // diagonal (0, 0) - (1, 1) - (2, 2) completed by black in the underlying game.
+        assert_eq!(state.state.winner(), Some(Color::Black));
+        assert_eq!(state.winner(), Some(Color::White));
+    }
+
+    #[tokio::test]
+    async fn test_declined_swap() {
+        for _ in 0..20 {
+            let mut inner: TicTacToe = TicTacToeBuilder::default().create(Color::Black).await;
+            let mut state = WithSwapGB::new(TicTacToeBuilder::default())
+                .create(Color::Black)
+                .await;
+
+            while !inner.is_finished() {
+                assert_eq!(state.turn(), inner.turn());
+                assert_eq!(
+                    state.state_to_feature(Color::Black),
+                    inner.state_to_feature(Color::Black)
+                );
+                let expected: Vec<_> = inner
+                    .possible_moves()
+                    .into_iter()
+                    .map(SwapMove::Play)
+                    .collect();
+                let moves: Vec<_> = state
+                    .possible_moves()
+                    .into_iter()
+                    .filter(|m| *m != SwapMove::Swap)
+                    .collect();
+                assert_eq!(moves, expected);
+
+                let (m, reward) = inner.random_move().await;
+                assert_eq!(state.play(&SwapMove::Play(m)).await, reward);
+            }
+            assert!(state.is_finished());
+            assert_eq!(state.winner(), inner.winner());
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use crate::policies::MultiplayerPolicy;
+use crate::settings::StrError;
 
 use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt};
 use ndarray::{Array, Axis, Dimension};
 use rand::seq::SliceRandom;
 use std::collections::hash_map::DefaultHasher;
 use std::convert::TryFrom;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::hash::{Hash, Hasher};
 
 ///
@@ -16,22 +18,46 @@ use std::hash::{Hash, Hasher};
 ///
 pub mod breakthrough;
 ///
+/// Cart-pole balancing, a native reinforcement learning environment.
+///
+pub mod cartpole;
+///
+/// Checkers (English draughts), with mandatory captures.
+///
+pub mod checkers;
+///
+/// Move types shared by grid games.
+///
+pub mod common;
+///
+/// Generic terminal interface for games played on a grid.
+///
+pub mod grid_ui;
+///
 /// Google Hashcode 2020 problem.
 ///
 pub mod hashcode_20;
 ///
+/// Hex: players connect their two sides of a rhombic board, draws are impossible.
+///
+pub mod hex;
+///
 /// Games that takes other games as an input.
 ///
 pub mod meta;
 ///
-/// Misère breakthrough
+/// Nim: players take objects from heaps, the last to move wins.
 ///
-pub mod misere_breakthrough;
+pub mod nim;
 ///
 /// Open AI Gym interface.
 ///
 pub mod openai;
 ///
+/// Tic-Tac-Toe.
+///
+pub mod tictactoe;
+///
 /// Weak schur number.
 ///
 pub mod weak_schur;
@@ -105,6 +131,25 @@ pub trait Game: Playable {
     /// Returns whose turn it is.
     ///
     fn turn(&self) -> Self::Player;
+
+    ///
+    /// Position seen from the player to move, for games where the players are
+    /// interchangeable: positions that only differ by a color swap have the same
+    /// canonical form. Defaults to the position itself.
+    ///
+    fn canonical(&self) -> Self
+    where
+        Self: Clone,
+    {
+        self.clone()
+    }
+
+    ///
+    /// Move of the canonical position corresponding to the given move.
+    ///
+    fn canonical_move(&self, action: &Self::Move) -> Self::Move {
+        *action
+    }
 }
 
 ///
@@ -117,22 +162,34 @@ pub trait Playout: Game + Clone + Send {
     /// It stores moves and state history, along with the total reward and the final state.
     ///
     async fn playout_history(&self, pov: Self::Player) -> (Self, Vec<(Self, Self::Move)>, f32) {
+        let (s, hist, rewards) = self.playout_history_multi().await;
+        let total_reward = rewards.get(&pov).copied().unwrap_or(0.);
+        (s, hist, total_reward)
+    }
+
+    ///
+    /// Simulate a game execution using random moves until reaching a final state.
+    /// It stores moves and state history, along with the total reward of each player
+    /// and the final state. Useful for cooperative games, where every player's reward matters.
+    ///
+    async fn playout_history_multi(
+        &self,
+    ) -> (Self, Vec<(Self, Self::Move)>, HashMap<Self::Player, f32>) {
         let mut s = self.clone();
         let mut hist = Vec::new();
 
-        let mut total_reward = 0.;
+        let mut rewards: HashMap<Self::Player, f32> =
+            Self::players().into_iter().map(|p| (p, 0.)).collect();
 
         while !s.is_finished() {
             let s_cloned = s.clone();
             let player = s.turn();
             let (m, r) = s.random_move().await;
-            if player == pov {
-                total_reward += r;
-            }
+            *rewards.entry(player).or_insert(0.) += r;
 
             hist.push((s_cloned, m));
         }
-        (s, hist, total_reward)
+        (s, hist, rewards)
     }
 
     ///
@@ -143,15 +200,207 @@ pub trait Playout: Game + Clone + Send {
         let (s, _, total_reward) = self.playout_history(pov).await;
         (s, total_reward)
     }
+
+    ///
+    /// Simulate a game execution like `playout_history`, using `choose` to
+    /// select the move played in each state.
+    ///
+    async fn playout_history_with<F>(
+        &self,
+        pov: Self::Player,
+        choose: F,
+    ) -> (Self, Vec<(Self, Self::Move)>, f32)
+    where
+        F: for<'a> Fn(&'a Self) -> BoxFuture<'a, Self::Move> + Send + Sync,
+    {
+        let mut s = self.clone();
+        let mut hist = Vec::new();
+
+        let mut total_reward = 0.;
+
+        while !s.is_finished() {
+            let s_cloned = s.clone();
+            let player = s.turn();
+            let m = choose(&s).await;
+            let r = s.play(&m).await;
+            if player == pov {
+                total_reward += r;
+            }
+
+            hist.push((s_cloned, m));
+        }
+        (s, hist, total_reward)
+    }
 }
 impl<G: Game + Clone + Send> Playout for G {}
 
+///
+/// Games whose moves can be undone, so that a single state can be searched in place
+/// instead of being cloned for each move.
+///
+pub trait UndoableGame: Game {
+    ///
+    /// Information needed to undo a move.
+    ///
+    type Undo;
+
+    ///
+    /// Plays a legal move, yielding its reward and the information needed to undo it.
+    ///
+    fn play_with_undo(&mut self, action: &Self::Move) -> (f32, Self::Undo);
+
+    ///
+    /// Undoes a move. Moves have to be undone in the reverse order they were played.
+    ///
+    fn undo(&mut self, undo: Self::Undo);
+}
+
+///
+/// Games describing what a move changed on the board, besides its reward.
+///
+#[async_trait]
+pub trait DetailedPlayable: Playable {
+    ///
+    /// Description of the effects of a move.
+    ///
+    type Outcome: Send;
+
+    ///
+    /// Plays a move like `Playable::play`, yielding its reward and its effects.
+    ///
+    async fn play_detailed(&mut self, action: &Self::Move) -> (f32, Self::Outcome);
+}
+
+///
+/// Plays random moves on the board until reaching a final state, like `playout_board`,
+/// then undoes them: the board is restored instead of being cloned.
+/// Returns the evaluation of the final state along with the total reward of `pov`.
+///
+pub fn playout_in_place<G, T, F>(board: &mut G, pov: G::Player, evaluate: F) -> (T, f32)
+where
+    G: UndoableGame,
+    F: FnOnce(&G) -> T,
+{
+    let mut undos = vec![];
+    let mut total_reward = 0.;
+
+    while !board.is_finished() {
+        let player = board.turn();
+        let action = *board
+            .possible_moves()
+            .choose(&mut rand::thread_rng())
+            .unwrap();
+        let (reward, undo) = board.play_with_undo(&action);
+        if player == pov {
+            total_reward += reward;
+        }
+        undos.push(undo);
+    }
+
+    let result = evaluate(board);
+    while let Some(undo) = undos.pop() {
+        board.undo(undo);
+    }
+    (result, total_reward)
+}
+
+///
+/// Move chooser for `playout_history_with`: plays an immediately winning move if
+/// there is one, otherwise a random move among the ones after which the opponent
+/// can't win immediately.
+///
+pub fn decisive_move<G: SingleWinner + Clone>(state: &G) -> BoxFuture<'_, G::Move> {
+    async move {
+        let player = state.turn();
+        let moves = state.possible_moves();
+
+        let mut next_states = Vec::with_capacity(moves.len());
+        for m in moves.iter() {
+            let mut next = state.clone();
+            next.play(m).await;
+            if next.winner() == Some(player) {
+                return *m;
+            }
+            next_states.push(next);
+        }
+
+        let mut safe_moves = vec![];
+        for (m, next) in moves.iter().zip(next_states.iter()) {
+            let opponent = next.turn();
+            let mut opponent_wins = false;
+            for reply in next.possible_moves() {
+                let mut after = next.clone();
+                after.play(&reply).await;
+                if after.winner() == Some(opponent) {
+                    opponent_wins = true;
+                    break;
+                }
+            }
+            if !opponent_wins {
+                safe_moves.push(*m);
+            }
+        }
+
+        let candidates = if safe_moves.is_empty() {
+            &moves
+        } else {
+            &safe_moves
+        };
+        *candidates.choose(&mut rand::thread_rng()).unwrap()
+    }
+    .boxed()
+}
+
+///
+/// Simulates a game execution using `decisive_move` until reaching a final state.
+/// It returns the total reward with the final state.
+///
+pub async fn decisive_playout<G: SingleWinner + Clone>(state: &G, pov: G::Player) -> (G, f32) {
+    let (s, _, total_reward) = state.playout_history_with(pov, decisive_move::<G>).await;
+    (s, total_reward)
+}
+
 ///
 /// Non-cooperative games.
 ///
 pub trait SingleWinner: Game {
     /// Returns the winner of the game, or None if no one has won yet.
     fn winner(&self) -> Option<Self::Player>;
+
+    /// Returns the outcome of the game, telling drawn games apart from ongoing ones.
+    /// A finished game without winner is a draw.
+    fn outcome(&self) -> Outcome<Self::Player> {
+        match self.winner() {
+            Some(player) => Outcome::Win(player),
+            None if self.is_finished() => Outcome::Draw,
+            None => Outcome::Ongoing,
+        }
+    }
+}
+
+///
+/// Outcome of a game with a single winner.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome<P> {
+    /// The game was won by the given player.
+    Win(P),
+    /// The game ended without winner.
+    Draw,
+    /// The game is not finished.
+    Ongoing,
+}
+
+impl<P: PartialEq> Outcome<P> {
+    /// Value of the outcome from the point of view of `player`:
+    /// 1 for a win, -1 for a loss and 0 for a draw or an ongoing game.
+    pub fn value(&self, player: &P) -> f32 {
+        match self {
+            Outcome::Win(winner) if winner == player => 1.,
+            Outcome::Win(_) => -1.,
+            Outcome::Draw | Outcome::Ongoing => 0.,
+        }
+    }
 }
 
 ///
@@ -190,6 +439,22 @@ pub trait GameBuilder: Sync + Send + Clone {
     /// Create a new game starting for player `starting`.
     ///
     async fn create(&self, starting: <Self::G as Game>::Player) -> Self::G;
+
+    ///
+    /// Create a game from the serialized `position`, with player `starting` to move.
+    /// The position format depends on the game, builders that don't support it return
+    /// an error.
+    ///
+    async fn create_from(
+        &self,
+        position: &str,
+        _starting: <Self::G as Game>::Player,
+    ) -> Result<Self::G, StrError> {
+        Err(StrError(format!(
+            "Unable to create a game from position `{}`: not supported by this game.",
+            position
+        )))
+    }
 }
 
 ///
@@ -203,6 +468,16 @@ pub trait SingleplayerGameBuilder: Clone + Send + Sync {
     /// Create a new single-player game instance.
     ///
     async fn create(&self) -> Self::G;
+
+    ///
+    /// Create a single-player game instance from the serialized `position`.
+    ///
+    async fn create_from(&self, position: &str) -> Result<Self::G, StrError> {
+        Err(StrError(format!(
+            "Unable to create a game from position `{}`: not supported by this game.",
+            position
+        )))
+    }
 }
 ///
 /// Single-player game builder is an instance of GameBuilder
@@ -217,6 +492,14 @@ where
     async fn create(&self, _starting: <Self::G as Game>::Player) -> Self::G {
         self.create().await
     }
+
+    async fn create_from(
+        &self,
+        position: &str,
+        _starting: <Self::G as Game>::Player,
+    ) -> Result<Self::G, StrError> {
+        SingleplayerGameBuilder::create_from(self, position).await
+    }
 }
 
 use std::collections::HashMap;
@@ -227,7 +510,7 @@ use std::collections::HashMap;
 ///These games are the ones that can be played by neural network-based policies,
 ///such as PUCT or Muz.
 ///
-pub trait Features: Game {
+pub trait Features: Game + Hash {
     ///
     /// Type dimension of the game state feature space.
     ///
@@ -250,6 +533,11 @@ pub trait Features: Game {
     /// Deduce action shape from game descriptor.
     fn action_dimension(descr: &Self::Descriptor) -> Self::ActionDim;
 
+    /// Number of actions encoded in the action features, which is the size of the policy head.
+    fn num_actions(descr: &Self::Descriptor) -> usize {
+        Self::action_dimension(descr).size()
+    }
+
     ///
     /// Converts the game state to features (multi-dimensional array).
     ///
@@ -258,6 +546,18 @@ pub trait Features: Game {
     ///
     fn state_to_feature(&self, pov: Self::Player) -> Array<f32, Self::StateDim>;
 
+    ///
+    /// Hash identifying the state features, used to memoize `state_to_feature`.
+    ///
+    /// States with the same hash must have the same features. Defaults to the
+    /// `Hash` implementation of the game.
+    ///
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
     ///
     /// Converts an action probability distribution to the action features.
     ///
@@ -277,6 +577,33 @@ pub trait Features: Game {
         hash.insert(action, 1.);
         Self::moves_to_feature(descr, &hash)
     }
+
+    ///
+    /// Legality mask of the action features: 1 at the features of the possible moves,
+    /// 0 elsewhere.
+    ///
+    fn legal_mask(&self) -> Array<f32, Self::ActionDim> {
+        let moves: HashMap<Self::Move, f32> =
+            self.possible_moves().into_iter().map(|m| (m, 1.)).collect();
+        Self::moves_to_feature(&self.get_features(), &moves).mapv(|x| if x > 0. { 1. } else { 0. })
+    }
+
+    ///
+    /// Multiplies a policy by the legality mask and renormalizes it.
+    ///
+    /// `feature_to_moves` already discards invalid moves, this exposes the masked
+    /// policy as features, e.g. for training targets.
+    ///
+    fn masked_policy(&self, policy: &Array<f32, Self::ActionDim>) -> Array<f32, Self::ActionDim> {
+        let masked = policy * &self.legal_mask();
+        let z = masked.sum();
+        if z > 0. {
+            masked / z
+        } else {
+            masked
+        }
+    }
+
     ///
     /// Converts a move distribution feature to the corresponding set of move probabilities, relative to the game state.
     ///
@@ -290,6 +617,25 @@ pub trait Features: Game {
     ///
     fn all_possible_moves(descr: &Self::Descriptor) -> Vec<Self::Move>;
 
+    ///
+    /// Index of a move in the flat action space.
+    ///
+    /// The default implementation is the position of the move in `all_possible_moves`.
+    ///
+    fn action_index(descr: &Self::Descriptor, action: &Self::Move) -> usize {
+        Self::all_possible_moves(descr)
+            .iter()
+            .position(|m| m == action)
+            .expect("Move is not part of the action space.")
+    }
+
+    ///
+    /// Move at the given index of the flat action space, inverse of `action_index`.
+    ///
+    fn action_from_index(descr: &Self::Descriptor, index: usize) -> Option<Self::Move> {
+        Self::all_possible_moves(descr).get(index).copied()
+    }
+
     ///
     /// Converts a move distribution feature to the corresponding set of move probabilities, independently from the game state.
     ///
@@ -299,6 +645,150 @@ pub trait Features: Game {
         descr: &Self::Descriptor,
         features: &Array<f32, Self::ActionDim>,
     ) -> HashMap<Self::Move, f32>;
+
+    ///
+    /// Board symmetries that leave the game unchanged, used for data augmentation.
+    /// Defaults to the identity only.
+    ///
+    fn symmetries(&self) -> Vec<Symmetry> {
+        vec![Symmetry::default()]
+    }
+
+    ///
+    /// Applies a symmetry to state features.
+    /// The default implementation transforms the first two axes.
+    ///
+    fn state_symmetry(
+        _descr: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::StateDim>,
+    ) -> Array<f32, Self::StateDim> {
+        symmetry.apply(features)
+    }
+
+    ///
+    /// Applies a symmetry to action features.
+    /// The default implementation transforms the first two axes.
+    ///
+    fn action_symmetry(
+        _descr: &Self::Descriptor,
+        symmetry: Symmetry,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> Array<f32, Self::ActionDim> {
+        symmetry.apply(features)
+    }
+
+    ///
+    /// Content of the cell `(x, y)` of a game played on a grid, the cells being the first
+    /// two axes of the state features.
+    ///
+    /// The default implementation assumes one feature plane per player, from the point of
+    /// view of the first player: the cell holds a piece of the first player whose plane is set.
+    ///
+    fn render_cell(&self, x: usize, y: usize) -> CellGlyph {
+        let players = Self::players();
+        let features = self.state_to_feature(players[0]).into_dyn();
+        (0..players.len())
+            .find(|p| features.get(&[x, y, *p][..]).map_or(false, |v| *v > 0.))
+            .map_or(CellGlyph::Empty, CellGlyph::Piece)
+    }
+}
+
+/// Content of a board cell, as drawn by user interfaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellGlyph {
+    /// Empty cell.
+    Empty,
+    /// Piece of the player of the given index in `Game::players()`.
+    Piece(usize),
+}
+
+/// Symmetry of a board: a reflection of the first axis if `flip` is set,
+/// followed by `rotations` quarter turns. Odd numbers of quarter turns swap the
+/// width and the height of the board, so they only apply to square boards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Symmetry {
+    /// Reflection of the first axis (`x` becomes `width - 1 - x`).
+    pub flip: bool,
+    /// Number of quarter turns.
+    pub rotations: u8,
+}
+
+impl Symmetry {
+    /// The eight symmetries of the square (dihedral group).
+    pub fn dihedral() -> Vec<Symmetry> {
+        let mut res = vec![];
+        for flip in &[false, true] {
+            for rotations in 0..4 {
+                res.push(Symmetry {
+                    flip: *flip,
+                    rotations,
+                });
+            }
+        }
+        res
+    }
+
+    /// The four symmetries of the rectangle: reflections of each axis and the half turn.
+    pub fn rectangle() -> Vec<Symmetry> {
+        Symmetry::dihedral()
+            .into_iter()
+            .filter(|symmetry| symmetry.rotations % 2 == 0)
+            .collect()
+    }
+
+    /// Whether the symmetry maps a `width` x `height` board onto itself.
+    pub fn keeps_shape(self, width: usize, height: usize) -> bool {
+        width == height || self.rotations % 2 == 0
+    }
+
+    /// Symmetry cancelling this one.
+    pub fn inverse(self) -> Symmetry {
+        if self.flip {
+            // reflections are their own inverse.
+            self
+        } else {
+            Symmetry {
+                flip: false,
+                rotations: (4 - self.rotations % 4) % 4,
+            }
+        }
+    }
+
+    /// Applies the symmetry to the first two axes of the features.
+    pub fn apply<D: Dimension>(self, features: &Array<f32, D>) -> Array<f32, D> {
+        if self == Symmetry::default() {
+            return features.clone();
+        }
+
+        let shape = features.shape();
+        debug_assert!(
+            shape.len() < 2 || self.keeps_shape(shape[0], shape[1]),
+            "{:?} doesn't apply to a {:?} board",
+            self,
+            shape
+        );
+
+        let mut view = features.view();
+        if self.flip {
+            view.invert_axis(Axis(0));
+        }
+        for _ in 0..self.rotations % 4 {
+            view.swap_axes(0, 1);
+            view.invert_axis(Axis(0));
+        }
+        Array::from_shape_vec(view.raw_dim(), view.iter().cloned().collect()).unwrap()
+    }
+}
+
+///
+/// Games whose moves can be read from a textual notation.
+///
+pub trait ParseMove: Base {
+    ///
+    /// Parses a legal move of the current state.
+    ///
+    fn parse_move(&self, notation: &str) -> Result<Self::Move, StrError>;
 }
 
 ///
@@ -324,6 +814,20 @@ impl<T: Base> MoveCode<T> for NoFeatures {
     }
 }
 
+///
+/// A move encoder hashing the move along with the state features, so that
+/// the same move gets different codes in different positions.
+///
+pub struct StateMoveCode {}
+impl<T: Features> MoveCode<T> for StateMoveCode {
+    fn code(game: &T, action: &T::Move) -> usize {
+        let mut s = DefaultHasher::new();
+        game.state_hash().hash(&mut s);
+        action.hash(&mut s);
+        usize::try_from(s.finish()).unwrap()
+    }
+}
+
 ///
 /// Games with an user interface.
 ///
@@ -342,22 +846,612 @@ pub trait GameView: cursive::view::View {
     fn set_state(&mut self, state: Self::G);
 }
 
+/// Limits after which a game that hasn't ended is declared a draw.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DrawRules {
+    /// Maximum number of plies.
+    pub max_plies: Option<usize>,
+    /// Number of occurrences of the same position.
+    pub max_repetitions: Option<usize>,
+}
+
+/// Counts plies and position repetitions of a game, following the draw rules.
+#[derive(Debug, Clone)]
+pub struct DrawDetector {
+    rules: DrawRules,
+    plies: usize,
+    positions: HashMap<u64, usize>,
+}
+
+impl DrawDetector {
+    /// Create a detector for a new game.
+    pub fn new(rules: DrawRules) -> Self {
+        DrawDetector {
+            rules,
+            plies: 0,
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Record the position hash before a move is played.
+    /// Returns true if the game has to be stopped as a draw.
+    pub fn record(&mut self, position: u64) -> bool {
+        let count = self.positions.entry(position).or_insert(0);
+        *count += 1;
+
+        let repeated = self
+            .rules
+            .max_repetitions
+            .map_or(false, |max| *count >= max);
+        let capped = self.rules.max_plies.map_or(false, |max| self.plies >= max);
+        self.plies += 1;
+        repeated || capped
+    }
+}
+
+/// Hash of a game position.
+pub fn position_hash<G: Hash>(state: &G) -> u64 {
+    let mut s = DefaultHasher::new();
+    state.hash(&mut s);
+    s.finish()
+}
+
+/// Policy taking part in a simulated match.
+pub type MatchPolicy<'a, G> = Box<dyn MultiplayerPolicy<G> + Sync + Send + 'a>;
+
+/// Index of the player whose turn it is, in the `Game::players` order.
+pub(crate) fn turn_index<G: Game>(board: &G) -> usize {
+    let turn = board.turn();
+    G::players()
+        .iter()
+        .position(|player| *player == turn)
+        .expect("The player to move should be one of the game players.")
+}
+
+/// Simulate a match by executing the policies on a given game,
+/// `policies[i]` playing for `G::players()[i]`.
+///
+/// Returns true if the game was stopped as a draw by the draw rules.
+pub async fn simulate_players<'a, G: Game + Hash>(
+    policies: &mut [MatchPolicy<'a, G>],
+    board: &mut G,
+    rules: DrawRules,
+) -> bool {
+    assert_eq!(policies.len(), G::players().len());
+    let mut draw = DrawDetector::new(rules);
+    while !board.is_finished() {
+        if draw.record(position_hash(board)) {
+            return true;
+        }
+        let action = policies[turn_index(board)]
+            .play(&board)
+            .await
+            .expect("The policy should play a move in an unfinished game.");
+        board.play(&action).await;
+    }
+    false
+}
+
 /// Simulate a match by executing the two policies on
 /// a given game.
-pub async fn simulate<'a, 'b, G: Game>(
-    mut p1: Box<dyn MultiplayerPolicy<G> + Sync + Send + 'a>,
-    mut p2: Box<dyn MultiplayerPolicy<G> + Sync + Send + 'b>,
+///
+/// Returns true if the game was stopped as a draw by the draw rules.
+pub async fn simulate<'a, 'b, G: Game + Hash>(
+    p1: Box<dyn MultiplayerPolicy<G> + Sync + Send + 'a>,
+    p2: Box<dyn MultiplayerPolicy<G> + Sync + Send + 'b>,
     board: &mut G,
-) {
-    while {
-        let action = if board.turn() == G::players()[0] {
-            p1.play(&board).await
+    rules: DrawRules,
+) -> bool {
+    simulate_players(&mut [p1, p2], board, rules).await
+}
+
+/// Transcript of a played game: one line per move.
+///
+/// Moves are written using their `Display` implementation
+/// (which is `Move::name` for Breakthrough).
+#[derive(Debug, Clone, Default)]
+pub struct MoveLog {
+    lines: Vec<String>,
+}
+
+impl MoveLog {
+    /// Create an empty transcript.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a move, given the state resulting from the move.
+    pub fn record<G: Game>(&mut self, action: &G::Move, state: &G)
+    where
+        G::Move: Display,
+    {
+        let line = if state.is_finished() {
+            format!("{}. {} (end)", self.lines.len() + 1, action)
         } else {
-            p2.play(&board).await
+            format!(
+                "{}. {} ({:?} to play)",
+                self.lines.len() + 1,
+                action,
+                state.turn()
+            )
         };
+        self.lines.push(line);
+    }
+
+    /// Recorded lines.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Newline-joined transcript.
+    pub fn transcript(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+/// Simulate a match like `simulate_players`, recording the moves played.
+pub async fn simulate_players_with_log<'a, G: Game + Hash>(
+    policies: &mut [MatchPolicy<'a, G>],
+    board: &mut G,
+    rules: DrawRules,
+) -> MoveLog
+where
+    G::Move: Display,
+{
+    assert_eq!(policies.len(), G::players().len());
+    let mut log = MoveLog::new();
+    let mut draw = DrawDetector::new(rules);
+    while !board.is_finished() {
+        if draw.record(position_hash(board)) {
+            break;
+        }
+        let action = policies[turn_index(board)]
+            .play(&board)
+            .await
+            .expect("The policy should play a move in an unfinished game.");
         board.play(&action).await;
-        //println!("{:?} => {:?}", action, board);
-        let game_has_ended = board.is_finished();
-        !game_has_ended
-    } {}
+        log.record(&action, board);
+    }
+    log
+}
+
+/// Simulate a match like `simulate`, recording the moves played.
+pub async fn simulate_with_log<'a, 'b, G: Game + Hash>(
+    p1: Box<dyn MultiplayerPolicy<G> + Sync + Send + 'a>,
+    p2: Box<dyn MultiplayerPolicy<G> + Sync + Send + 'b>,
+    board: &mut G,
+    rules: DrawRules,
+) -> MoveLog
+where
+    G::Move: Display,
+{
+    simulate_players_with_log(&mut [p1, p2], board, rules).await
+}
+
+/// Record of a ply of a simulated match.
+#[derive(Debug, Clone)]
+pub struct PlyRecord<G: Game> {
+    /// State before the move.
+    pub state: G,
+    /// Player who chose the move.
+    pub player: G::Player,
+    /// Chosen move.
+    pub action: G::Move,
+    /// Reward obtained by the player for the move.
+    pub reward: f32,
+    /// Value estimate of the state from the player's point of view, if the policy gives one.
+    pub value: Option<f32>,
+}
+
+/// Simulate a match like `simulate_players`, recording each ply.
+///
+/// The final state is left in `board`, it is not part of the records.
+pub async fn simulate_players_recorded<'a, G: Game + Hash + Clone>(
+    policies: &mut [MatchPolicy<'a, G>],
+    board: &mut G,
+    rules: DrawRules,
+) -> Vec<PlyRecord<G>> {
+    assert_eq!(policies.len(), G::players().len());
+    let mut records = vec![];
+    let mut draw = DrawDetector::new(rules);
+    while !board.is_finished() {
+        if draw.record(position_hash(board)) {
+            break;
+        }
+        let state = board.clone();
+        let (action, stats) = policies[turn_index(board)]
+            .play_with_stats(&board)
+            .await
+            .expect("The policy should play a move in an unfinished game.");
+        let reward = board.play(&action).await;
+        records.push(PlyRecord {
+            player: state.turn(),
+            state,
+            action,
+            reward,
+            value: stats.value,
+        });
+    }
+    records
+}
+
+/// Simulate a match like `simulate`, recording each ply.
+pub async fn simulate_recorded<'a, 'b, G: Game + Hash + Clone>(
+    p1: Box<dyn MultiplayerPolicy<G> + Sync + Send + 'a>,
+    p2: Box<dyn MultiplayerPolicy<G> + Sync + Send + 'b>,
+    board: &mut G,
+    rules: DrawRules,
+) -> Vec<PlyRecord<G>> {
+    simulate_players_recorded(&mut [p1, p2], board, rules).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::breakthrough::{BreakthroughBuilder, Color};
+    use super::*;
+    use crate::policies::flat::RandomPolicy;
+    use crate::policies::mcts::tests::uct_config;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_breakthrough_transcript() {
+        let mut board = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let log = simulate_with_log(
+            Box::new(RandomPolicy {}),
+            Box::new(RandomPolicy {}),
+            &mut board,
+            DrawRules::default(),
+        )
+        .await;
+
+        assert!(board.is_finished());
+        // a pawn needs at least three moves to cross a 5x5 board.
+        assert!(log.lines().len() >= 5);
+        let transcript = log.transcript();
+        assert_eq!(transcript.lines().count(), log.lines().len());
+        assert!(transcript.lines().last().unwrap().ends_with("(end)"));
+        assert_eq!(
+            transcript
+                .lines()
+                .filter(|line| line.ends_with("to play)"))
+                .count(),
+            log.lines().len() - 1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simulate_recorded() {
+        let uct = uct_config(50);
+        let initial = tictactoe::TicTacToeBuilder::default()
+            .create(Color::Black)
+            .await;
+        let mut board = initial.clone();
+        let records = simulate_recorded(
+            Box::new(crate::policies::MultiplayerPolicyBuilder::create(
+                &uct,
+                Color::Black,
+            )),
+            Box::new(RandomPolicy {}),
+            &mut board,
+            DrawRules::default(),
+        )
+        .await;
+        assert!(board.is_finished());
+
+        let mut replayed = initial;
+        for record in records.iter() {
+            assert!(record.state == replayed);
+            assert_eq!(record.player, replayed.turn());
+            assert_eq!(record.value.is_some(), record.player == Color::Black);
+            assert_eq!(replayed.play(&record.action).await, record.reward);
+        }
+        assert!(replayed == board);
+        // at least five plies are needed to finish a game.
+        assert!(records.len() >= 5);
+    }
+
+    /// Players move a token back and forth: the game never ends.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Shuffle {
+        left: bool,
+        turn: Color,
+    }
+
+    impl Base for Shuffle {
+        /// Side the token is moved to.
+        type Move = bool;
+
+        fn possible_moves(&self) -> Vec<bool> {
+            vec![!self.left]
+        }
+    }
+
+    #[async_trait]
+    impl Playable for Shuffle {
+        async fn play(&mut self, left: &bool) -> f32 {
+            self.left = *left;
+            self.turn = self.turn.adv();
+            0.
+        }
+    }
+
+    impl Game for Shuffle {
+        type Player = Color;
+
+        fn players() -> Vec<Color> {
+            vec![Color::Black, Color::White]
+        }
+
+        fn player_after(player: Color) -> Color {
+            player.adv()
+        }
+
+        fn turn(&self) -> Color {
+            self.turn
+        }
+    }
+
+    fn shuffle() -> Shuffle {
+        Shuffle {
+            left: true,
+            turn: Color::Black,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ply_cap_draw() {
+        let rules = DrawRules {
+            max_plies: Some(50),
+            max_repetitions: None,
+        };
+        let mut board = shuffle();
+        let draw = simulate(
+            Box::new(RandomPolicy {}),
+            Box::new(RandomPolicy {}),
+            &mut board,
+            rules,
+        )
+        .await;
+        assert!(draw);
+        assert!(!board.is_finished());
+
+        let mut board = shuffle();
+        let log = simulate_with_log(
+            Box::new(RandomPolicy {}),
+            Box::new(RandomPolicy {}),
+            &mut board,
+            rules,
+        )
+        .await;
+        assert_eq!(log.lines().len(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_repetition_draw() {
+        let rules = DrawRules {
+            max_plies: Some(50),
+            max_repetitions: Some(3),
+        };
+        let mut board = shuffle();
+        let log = simulate_with_log(
+            Box::new(RandomPolicy {}),
+            Box::new(RandomPolicy {}),
+            &mut board,
+            rules,
+        )
+        .await;
+        // the initial position occurs again after 2 and 4 plies.
+        assert_eq!(log.lines().len(), 4);
+        assert_eq!(board, shuffle());
+    }
+
+    /// Three players take turns until no ply is left.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Rotation {
+        plies: usize,
+        turn: u8,
+    }
+
+    impl Base for Rotation {
+        type Move = ();
+
+        fn possible_moves(&self) -> Vec<()> {
+            if self.plies == 0 {
+                vec![]
+            } else {
+                vec![()]
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Playable for Rotation {
+        async fn play(&mut self, _: &()) -> f32 {
+            self.plies -= 1;
+            self.turn = Self::player_after(self.turn);
+            0.
+        }
+    }
+
+    impl Game for Rotation {
+        type Player = u8;
+
+        fn players() -> Vec<u8> {
+            vec![0, 1, 2]
+        }
+
+        fn player_after(player: u8) -> u8 {
+            (player + 1) % 3
+        }
+
+        fn turn(&self) -> u8 {
+            self.turn
+        }
+    }
+
+    /// Two players relay each other until no ply is left.
+    /// The player to move is rewarded with the number of plies left.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Relay {
+        plies: usize,
+        turn: u8,
+    }
+
+    impl Base for Relay {
+        type Move = ();
+
+        fn possible_moves(&self) -> Vec<()> {
+            if self.plies == 0 {
+                vec![]
+            } else {
+                vec![()]
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Playable for Relay {
+        async fn play(&mut self, _: &()) -> f32 {
+            let reward = self.plies as f32;
+            self.plies -= 1;
+            self.turn = Self::player_after(self.turn);
+            reward
+        }
+    }
+
+    impl Game for Relay {
+        type Player = u8;
+
+        fn players() -> Vec<u8> {
+            vec![0, 1]
+        }
+
+        fn player_after(player: u8) -> u8 {
+            1 - player
+        }
+
+        fn turn(&self) -> u8 {
+            self.turn
+        }
+    }
+
+    #[tokio::test]
+    async fn test_playout_history_multi() {
+        let board = Relay { plies: 5, turn: 1 };
+        let (s, hist, rewards) = board.playout_history_multi().await;
+        assert!(s.is_finished());
+        assert_eq!(hist.len(), 5);
+        assert_eq!(hist[0].0, board);
+        assert_eq!(rewards[&1], 5. + 3. + 1.);
+        assert_eq!(rewards[&0], 4. + 2.);
+
+        let (_, _, reward) = board.playout_history(0).await;
+        assert_eq!(reward, rewards[&0]);
+
+        let finished = Relay { plies: 0, turn: 0 };
+        let (_, _, rewards) = finished.playout_history_multi().await;
+        assert_eq!(rewards.len(), 2);
+        assert!(rewards.values().all(|r| *r == 0.));
+    }
+
+    /// Records the player it plays for, each time it is asked for a move.
+    struct Recorder {
+        player: u8,
+        calls: Arc<Mutex<Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl MultiplayerPolicy<Rotation> for Recorder {
+        async fn play(&mut self, board: &Rotation) -> Option<()> {
+            assert_eq!(board.turn(), self.player);
+            self.calls.lock().unwrap().push(self.player);
+            Some(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_three_players_order() {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let mut policies: Vec<MatchPolicy<Rotation>> = Rotation::players()
+            .into_iter()
+            .map(|player| {
+                Box::new(Recorder {
+                    player,
+                    calls: calls.clone(),
+                }) as MatchPolicy<Rotation>
+            })
+            .collect();
+
+        let mut board = Rotation { plies: 7, turn: 1 };
+        let draw = simulate_players(&mut policies, &mut board, DrawRules::default()).await;
+        assert!(!draw);
+        assert!(board.is_finished());
+        assert_eq!(*calls.lock().unwrap(), vec![1, 2, 0, 1, 2, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_state_move_code() {
+        let initial = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let mut board = initial.clone();
+        for _ in 0..2 {
+            let action = board.possible_moves()[0];
+            board.play(&action).await;
+        }
+        let initial_moves = initial.possible_moves();
+        let action = board
+            .possible_moves()
+            .into_iter()
+            .find(|action| initial_moves.contains(action))
+            .unwrap();
+
+        assert_ne!(
+            StateMoveCode::code(&initial, &action),
+            StateMoveCode::code(&board, &action)
+        );
+        assert_eq!(
+            StateMoveCode::code(&board, &action),
+            StateMoveCode::code(&board.clone(), &action)
+        );
+        assert_eq!(
+            NoFeatures::code(&initial, &action),
+            NoFeatures::code(&board, &action)
+        );
+    }
+
+    #[test]
+    fn test_dihedral_symmetries() {
+        let features =
+            Array::from_shape_vec((4, 4, 2), (0..32).map(|i| i as f32).collect()).unwrap();
+        let images: Vec<_> = Symmetry::dihedral()
+            .into_iter()
+            .map(|symmetry| symmetry.apply(&features))
+            .collect();
+
+        for (i, symmetry) in Symmetry::dihedral().into_iter().enumerate() {
+            assert_eq!(symmetry.inverse().apply(&images[i]), features);
+            for j in 0..i {
+                assert_ne!(images[i], images[j]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rectangle_symmetries() {
+        let features =
+            Array::from_shape_vec((7, 6, 2), (0..84).map(|i| i as f32).collect()).unwrap();
+        let symmetries = Symmetry::rectangle();
+        assert_eq!(symmetries.len(), 4);
+        for symmetry in symmetries {
+            let image = symmetry.apply(&features);
+            assert_eq!(image.shape(), features.shape());
+            assert_eq!(symmetry.inverse().apply(&image), features);
+        }
+        assert_eq!(
+            Symmetry::dihedral()
+                .into_iter()
+                .filter(|symmetry| symmetry.keeps_shape(4, 4))
+                .count(),
+            8
+        );
+    }
 }
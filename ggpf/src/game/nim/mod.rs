@@ -0,0 +1,181 @@
+use crate::game::breakthrough::Color;
+use crate::game::{Base, Game, GameBuilder, Playable, SingleWinner};
+
+use async_trait::async_trait;
+use std::fmt;
+
+/// Move: (heap index, number of objects removed from the heap).
+pub type Move = (usize, usize);
+
+/// Nim game state instance.
+///
+/// Players alternately remove objects from one heap,
+/// the player taking the last object wins.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Nim {
+    heaps: Vec<usize>,
+    turn: Color,
+}
+
+impl fmt::Debug for Nim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Turn: {:?}", self.turn)?;
+        for (i, heap) in self.heaps.iter().enumerate() {
+            writeln!(f, "{}: {}", i, "|".repeat(*heap))?;
+        }
+        Ok(())
+    }
+}
+
+impl Nim {
+    /// Heaps sizes.
+    pub fn heaps(&self) -> &[usize] {
+        &self.heaps
+    }
+
+    /// Nim-sum of the position: XOR of the heaps sizes.
+    /// The player to move wins with optimal play if and only if it is not zero.
+    pub fn nim_value(&self) -> usize {
+        self.heaps.iter().fold(0, |acc, heap| acc ^ heap)
+    }
+
+    /// Returns a move leading to a zero nim-sum position, if the position is winning.
+    pub fn optimal_move(&self) -> Option<Move> {
+        let value = self.nim_value();
+        if value == 0 {
+            return None;
+        }
+        self.heaps
+            .iter()
+            .enumerate()
+            .find(|(_, heap)| (*heap ^ value) < **heap)
+            .map(|(i, heap)| (i, heap - (heap ^ value)))
+    }
+}
+
+/// Game builder for Nim.
+#[derive(Clone, Debug)]
+pub struct NimBuilder {
+    /// Initial heaps sizes.
+    pub initial_heaps: Vec<usize>,
+}
+
+#[async_trait]
+impl GameBuilder for NimBuilder {
+    type G = Nim;
+
+    async fn create(&self, turn: Color) -> Nim {
+        Nim {
+            heaps: self.initial_heaps.clone(),
+            turn,
+        }
+    }
+}
+
+impl Base for Nim {
+    type Move = Move;
+
+    fn possible_moves(&self) -> Vec<Move> {
+        self.heaps
+            .iter()
+            .enumerate()
+            .flat_map(|(i, heap)| (1..=*heap).map(move |take| (i, take)))
+            .collect()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.heaps.iter().all(|heap| *heap == 0)
+    }
+}
+
+#[async_trait]
+impl Playable for Nim {
+    async fn play(&mut self, m: &Move) -> f32 {
+        let (heap, take) = *m;
+        if take == 0 || heap >= self.heaps.len() || self.heaps[heap] < take {
+            return -1.;
+        }
+        self.heaps[heap] -= take;
+        let reward = if self.is_finished() { 1. } else { 0. };
+        self.turn = self.turn.adv();
+        reward
+    }
+}
+
+impl Game for Nim {
+    type Player = Color;
+
+    fn players() -> Vec<Color> {
+        vec![Color::Black, Color::White]
+    }
+
+    fn player_after(player: Color) -> Color {
+        player.adv()
+    }
+
+    fn turn(&self) -> Color {
+        self.turn
+    }
+}
+
+impl SingleWinner for Nim {
+    fn winner(&self) -> Option<Color> {
+        if self.is_finished() {
+            // the last player to move took the last object.
+            Some(self.turn.adv())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::mcts::tests::uct_config;
+    use crate::policies::mcts::uct::UCTPolicy;
+    use crate::policies::{MultiplayerPolicy, MultiplayerPolicyBuilder};
+    use crate::settings;
+    use rand::seq::SliceRandom;
+
+    #[tokio::test]
+    async fn test_uct_finds_optimal_move() {
+        let builder = NimBuilder {
+            initial_heaps: vec![3, 4, 5],
+        };
+        let state = builder.create(Color::Black).await;
+        assert_eq!(state.optimal_move(), Some((0, 2)));
+
+        let uct = settings::UCT {
+            uct_weight: 1.,
+            ..uct_config(100_000)
+        };
+        let mut policy: UCTPolicy<Nim> = uct.create(Color::Black);
+        assert_eq!(policy.play(&state).await, Some((0, 2)));
+    }
+
+    #[tokio::test]
+    async fn test_optimal_play_wins() {
+        let builder = NimBuilder {
+            initial_heaps: vec![3, 4, 5],
+        };
+
+        for _ in 0..20 {
+            let mut state = builder.create(Color::Black).await;
+            assert_ne!(state.nim_value(), 0);
+
+            while !state.is_finished() {
+                let action = if state.turn() == Color::Black {
+                    state.optimal_move().unwrap()
+                } else {
+                    *state
+                        .possible_moves()
+                        .choose(&mut rand::thread_rng())
+                        .unwrap()
+                };
+                state.play(&action).await;
+            }
+            assert_eq!(state.winner(), Some(Color::Black));
+        }
+    }
+}
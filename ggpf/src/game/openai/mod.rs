@@ -1,8 +1,12 @@
 use crate::game::*;
 
+use futures::future::{BoxFuture, FutureExt};
 use ndarray::s;
 use std::fmt;
+use std::io;
 use std::iter::FromIterator;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 use ggpf_gym::*;
 
@@ -11,10 +15,13 @@ use ggpf_gym::*;
 /// 
 /// Each instance is connected to a remote runner. This is because of Rust limitations
 /// somehow it's not possible to have both `tensorflow` and `pyo3` in the same crate..
+/// Experiments without `tensorflow` can use a `LocalGym` runner instead.
 pub struct Gym {
-    env: GymRunnerClient,
+    runner: Runner,
     game: String,
     possible_moves: Vec<usize>,
+    discretization: Option<Discretization>,
+    frame_skip: usize,
     is_done: bool,
     current_state: Array<f32, Ix3>,
     features: (Vec<usize>, Ix3, Ix1),
@@ -71,19 +78,288 @@ fn interpolate(image: &Array<f32, Ix3>, x_t: usize, y_t: usize) -> Array<f32, Ix
     result
 }
 
+/// Discretization of a continuous action space: each dimension takes `bins` evenly
+/// spaced values between its bounds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Discretization {
+    low: Vec<f64>,
+    high: Vec<f64>,
+    bins: usize,
+}
+
+impl Discretization {
+    /// Discretizes the box with the given bounds.
+    pub fn new(low: Vec<f64>, high: Vec<f64>, bins: usize) -> Self {
+        assert!(bins > 0, "The number of bins should be positive.");
+        assert_eq!(
+            low.len(),
+            high.len(),
+            "Bounds should have the same dimension."
+        );
+        assert!(
+            low.iter().chain(high.iter()).all(|x| x.is_finite()),
+            "Unbounded action spaces can't be discretized."
+        );
+        Self { low, high, bins }
+    }
+
+    /// Number of discrete actions.
+    pub fn n_actions(&self) -> usize {
+        self.bins.pow(self.low.len() as u32)
+    }
+
+    /// Continuous action of the given index. The first dimension varies the fastest.
+    pub fn action(&self, index: usize) -> Vec<f64> {
+        let mut index = index;
+        self.low
+            .iter()
+            .zip(self.high.iter())
+            .map(|(low, high)| {
+                let bin = index % self.bins;
+                index /= self.bins;
+                if self.bins == 1 {
+                    (low + high) / 2.
+                } else {
+                    low + (high - low) * bin as f64 / (self.bins - 1) as f64
+                }
+            })
+            .collect()
+    }
+}
+
 use tarpc::context;
 
+/// Calls `call` with the client, reconnecting with `connect` and calling again up to
+/// `max_retries` times on errors. The client is replaced by the new connection.
+async fn with_retries<C, T, Connect, Call>(
+    client: &Mutex<C>,
+    max_retries: usize,
+    mut connect: Connect,
+    mut call: Call,
+) -> io::Result<T>
+where
+    C: Clone,
+    Connect: FnMut() -> BoxFuture<'static, io::Result<C>>,
+    Call: FnMut(C) -> BoxFuture<'static, io::Result<T>>,
+{
+    let current = client.lock().unwrap().clone();
+    let mut result = call(current).await;
+    let mut retries = 0;
+    while let Err(error) = &result {
+        if retries == max_retries {
+            break;
+        }
+        retries += 1;
+        log::warn!(
+            "Gym runner request failed ({}), reconnecting ({}/{})..",
+            error,
+            retries,
+            max_retries
+        );
+        result = match connect().await {
+            Ok(new_client) => {
+                *client.lock().unwrap() = new_client.clone();
+                call(new_client).await
+            }
+            Err(error) => Err(error),
+        };
+    }
+    result
+}
+
+/// Connection to a remote Gym runner, shared by the clones of a game.
+#[derive(Clone)]
+struct Connection {
+    client: Arc<Mutex<GymRunnerClient>>,
+    builder: GymBuilder,
+}
+
+impl Connection {
+    /// Sends a request to the runner, with the builder deadline and retries. Each
+    /// attempt gets a new deadline.
+    ///
+    /// A reconnection restarts the episode: requests playing in the episode should
+    /// use `request_once` instead.
+    /// Panics if the runner can't be reached.
+    async fn request<T, F>(&self, mut call: F) -> T
+    where
+        F: FnMut(GymRunnerClient, context::Context) -> BoxFuture<'static, io::Result<T>>,
+    {
+        let builder = self.builder.clone();
+        let connect = move || {
+            let builder = builder.clone();
+            async move { builder.connect().await }.boxed()
+        };
+        with_retries(&self.client, self.builder.max_retries, connect, |client| {
+            call(client, self.builder.context())
+        })
+        .await
+        .expect("Unable to reach the Gym runner.")
+    }
+
+    /// Sends a request to the runner with the builder deadline, without retries.
+    async fn request_once<T, F>(&self, call: F) -> io::Result<T>
+    where
+        F: FnOnce(GymRunnerClient, context::Context) -> BoxFuture<'static, io::Result<T>>,
+    {
+        let client = self.client.lock().unwrap().clone();
+        call(client, self.builder.context()).await
+    }
+}
+
+/// In-process Gym environment, calling Python on the current thread instead of
+/// going through a remote runner.
+///
+/// Every call holds the Python GIL and blocks the thread until the environment
+/// answers: games are effectively played one at a time, so this runner is meant for
+/// single-threaded generation (e.g. a basic scheduler and one generator).
+#[derive(Clone)]
+pub struct LocalGym {
+    env: Arc<gym::Environment>,
+    render: bool,
+}
+
+impl LocalGym {
+    /// Creates the `game_name` environment.
+    pub fn new(game_name: &str, render: bool) -> Self {
+        let env = gym::GymClient::default().make(game_name);
+        Self {
+            env: Arc::new(env),
+            render,
+        }
+    }
+
+    fn reset(&self) -> gym::SpaceData {
+        let res = self.env.reset().expect("Unable to reset the environment.");
+        if self.render {
+            self.env.render();
+        }
+        res
+    }
+
+    fn step(&self, action: &gym::SpaceData) -> gym::State {
+        let res = self.env.step(action).expect("Invalid action.");
+        if self.render {
+            self.env.render();
+        }
+        res
+    }
+}
+
+/// Executor of the Gym environment.
+#[derive(Clone)]
+enum Runner {
+    Remote(Connection),
+    Local(LocalGym),
+}
+
+impl Runner {
+    async fn action_space(&self) -> gym::SpaceTemplate {
+        match self {
+            Runner::Remote(connection) => {
+                connection
+                    .request(|mut env, ctx| async move { env.action_space(ctx).await }.boxed())
+                    .await
+            }
+            Runner::Local(local) => local.env.action_space().clone(),
+        }
+    }
+
+    async fn observation_space(&self) -> gym::SpaceTemplate {
+        match self {
+            Runner::Remote(connection) => {
+                connection
+                    .request(|mut env, ctx| async move { env.observation_space(ctx).await }.boxed())
+                    .await
+            }
+            Runner::Local(local) => local.env.observation_space().clone(),
+        }
+    }
+
+    async fn reset(&self) -> gym::SpaceData {
+        match self {
+            Runner::Remote(connection) => {
+                connection
+                    .request(|mut env, ctx| async move { env.reset(ctx).await }.boxed())
+                    .await
+            }
+            Runner::Local(local) => local.reset(),
+        }
+    }
+
+    /// Plays `action` in the current episode. A failed step isn't retried, as the
+    /// episode can't be resumed after a reconnection.
+    fn step(&self, action: gym::SpaceData) -> BoxFuture<'static, io::Result<gym::State>> {
+        match self {
+            Runner::Remote(connection) => {
+                let connection = connection.clone();
+                async move {
+                    connection
+                        .request_once(move |mut env, ctx| match &action {
+                            gym::SpaceData::DISCRETE(n) => {
+                                let n = *n;
+                                async move { env.play(ctx, n).await }.boxed()
+                            }
+                            gym::SpaceData::BOX(v) => {
+                                let v = v.to_vec();
+                                async move { env.play_continuous(ctx, v).await }.boxed()
+                            }
+                            gym::SpaceData::TUPLE(_) => async {
+                                Err(io::Error::new(
+                                    io::ErrorKind::InvalidInput,
+                                    "Tuple actions are not supported by the Gym runner.",
+                                ))
+                            }
+                            .boxed(),
+                        })
+                        .await
+                }
+                .boxed()
+            }
+            Runner::Local(local) => {
+                let state = local.step(&action);
+                async move { Ok(state) }.boxed()
+            }
+        }
+    }
+}
+
 impl Gym {
     /// Given a connected client, build a game based on Gym.
-    pub async fn new(mut env: GymRunnerClient, game: String) -> Self {
-        let possible_moves = match env.action_space(context::current()).await.unwrap() {
-            gym::SpaceTemplate::DISCRETE { n } => (0..n).collect::<Vec<_>>(),
+    pub async fn new(env: GymRunnerClient, builder: GymBuilder) -> Self {
+        let connection = Connection {
+            client: Arc::new(Mutex::new(env)),
+            builder: builder.clone(),
+        };
+        Self::with_runner(Runner::Remote(connection), builder).await
+    }
+
+    /// Given an in-process environment, build a game based on Gym.
+    pub async fn new_local(env: LocalGym, builder: GymBuilder) -> Self {
+        Self::with_runner(Runner::Local(env), builder).await
+    }
+
+    /// Continuous action spaces are discretized with `action_bins` values per dimension,
+    /// and each action is repeated for `frame_skip` environment steps.
+    async fn with_runner(runner: Runner, builder: GymBuilder) -> Self {
+        assert!(
+            builder.frame_skip > 0,
+            "Actions should be played at least once."
+        );
+
+        let (possible_moves, discretization) = match runner.action_space().await {
+            gym::SpaceTemplate::DISCRETE { n } => ((0..n).collect::<Vec<_>>(), None),
+            gym::SpaceTemplate::BOX { low, high, .. } => {
+                let discretization = Discretization::new(low, high, builder.action_bins);
+                let moves = (0..discretization.n_actions()).collect::<Vec<_>>();
+                (moves, Some(discretization))
+            }
             x => panic!("Unsupported action space. {:?}", x),
         };
 
-        let init_state = env.reset(context::current()).await.unwrap();
+        let init_state = runner.reset().await;
 
-        let state_dimension = match env.observation_space(context::current()).await.unwrap() {
+        let state_dimension = match runner.observation_space().await {
             gym::SpaceTemplate::BOX { shape, .. } => convert_to_3D(&shape),
             _ => panic!("..."),
         };
@@ -98,12 +374,23 @@ impl Gym {
         let action_dimension = Ix1(possible_moves.len());
 
         Self {
-            env,
+            runner,
             possible_moves: possible_moves.clone(),
+            discretization,
+            frame_skip: builder.frame_skip,
             is_done: false,
             current_state: obs_state,
             features: (possible_moves, state_dimension, action_dimension),
-            game,
+            game: builder.game_name,
+        }
+    }
+}
+
+impl Hash for Gym {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.game.hash(state);
+        for x in self.current_state.iter() {
+            x.to_bits().hash(state);
         }
     }
 }
@@ -122,14 +409,46 @@ impl Base for Gym {
 
 use async_trait::async_trait;
 
+/// Plays `step` `frame_skip` times, or until the end of the episode.
+/// Yields the accumulated reward and the last state, or the first error.
+async fn skip_frames<F>(frame_skip: usize, mut step: F) -> io::Result<(f64, gym::State)>
+where
+    F: FnMut() -> BoxFuture<'static, io::Result<gym::State>>,
+{
+    let mut reward = 0.;
+    let mut state = step().await?;
+    reward += state.reward;
+    for _ in 1..frame_skip {
+        if state.is_done {
+            break;
+        }
+        state = step().await?;
+        reward += state.reward;
+    }
+    Ok((reward, state))
+}
+
 #[async_trait]
 impl Playable for Gym {
     #[allow(clippy::trivially_copy_pass_by_ref)]
     async fn play(&mut self, action: &usize) -> f32 {
-        //let env = self.env.lock().unwrap();
-        let next_state = self.env.play(context::current(), *action).await.unwrap();
+        let action = match &self.discretization {
+            None => gym::SpaceData::DISCRETE(*action),
+            Some(discretization) => gym::SpaceData::BOX(discretization.action(*action).into()),
+        };
+        let runner = self.runner.clone();
+        // the trajectory would be inconsistent if the episode went on with another runner.
+        let (reward, next_state) = skip_frames(self.frame_skip, || runner.step(action.clone()))
+            .await
+            .unwrap_or_else(|error| panic!("The Gym episode can't be resumed: {}", error));
         self.is_done = next_state.is_done;
-        next_state.reward as f32
+        if let Ok(observation) = next_state.observation.get_box() {
+            self.current_state = observation
+                .mapv(|x| x as f32)
+                .into_shape(self.features.1)
+                .expect("Unable to reshape observation.");
+        }
+        reward as f32
     }
 }
 
@@ -201,33 +520,421 @@ impl Features for Gym {
 #[derive(Clone, Debug)]
 /// Builder for Gym games.
 pub struct GymBuilder {
-    /// Executor remote address.
-    pub address: String,
+    /// Executor remote address. Without address, the environment is run in-process by
+    /// a `LocalGym`.
+    pub address: Option<String>,
     /// Gym game name.
     pub game_name: String,
     /// Whether the game should be rendered.
     pub render: bool,
+    /// Number of values of each dimension of a continuous action space.
+    pub action_bins: usize,
+    /// Number of environment steps each action is repeated for.
+    pub frame_skip: usize,
+    /// Deadline of each request to the runner, in milliseconds.
+    pub timeout_ms: u64,
+    /// Number of reconnections to the runner after a failed request. A reconnection
+    /// restarts the episode, so steps are never retried.
+    pub max_retries: usize,
 }
 
 use tarpc::client;
 
+impl GymBuilder {
+    /// Request context with the configured deadline.
+    fn context(&self) -> context::Context {
+        let mut ctx = context::current();
+        ctx.deadline = SystemTime::now() + Duration::from_millis(self.timeout_ms);
+        ctx
+    }
+
+    /// Connects to the runner and initializes the environment.
+    async fn connect(&self) -> io::Result<GymRunnerClient> {
+        let address = self.address.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "No remote runner address.")
+        })?;
+        let conn = tarpc::serde_transport::tcp::connect(address, BinCodec::default());
+        let conn = conn.await?;
+
+        let mut runner = GymRunnerClient::new(client::Config::default(), conn).spawn()?;
+        runner
+            .init(self.context(), self.game_name.clone(), self.render)
+            .await?;
+        runner.reset(self.context()).await?;
+        Ok(runner)
+    }
+}
+
 #[async_trait]
 impl SingleplayerGameBuilder for GymBuilder {
     type G = Gym;
 
     async fn create(&self) -> Gym {
-        let conn = tarpc::serde_transport::tcp::connect(&self.address, BinCodec::default());
-        let conn = conn.await.unwrap();
+        if self.address.is_none() {
+            let env = LocalGym::new(&self.game_name, self.render);
+            return Gym::new_local(env, self.clone()).await;
+        }
 
-        let mut runner = GymRunnerClient::new(client::Config::default(), conn)
-            .spawn()
-            .unwrap();
-        runner
-            .init(context::current(), self.game_name.clone(), self.render)
+        let mut attempts = 0;
+        let runner = loop {
+            match self.connect().await {
+                Ok(runner) => break runner,
+                Err(error) if attempts < self.max_retries => {
+                    attempts += 1;
+                    log::warn!("Unable to connect to the Gym runner: {}, retrying..", error);
+                }
+                Err(error) => panic!("Unable to connect to the Gym runner: {}", error),
+            }
+        };
+
+        Gym::new(runner, self.clone()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use tarpc::server::{BaseChannel, Channel};
+
+    #[test]
+    fn test_box_discretization() {
+        // BipedalWalker-like action space.
+        let discretization = Discretization::new(vec![-1.; 4], vec![1.; 4], 3);
+        assert_eq!(discretization.n_actions(), 81);
+
+        let actions: Vec<Vec<f64>> = (0..81).map(|i| discretization.action(i)).collect();
+        for action in actions.iter() {
+            assert_eq!(action.len(), 4);
+            assert!(action.iter().all(|x| (-1. ..=1.).contains(x)));
+        }
+        assert_eq!(actions[0], vec![-1.; 4]);
+        assert_eq!(actions[1], vec![0., -1., -1., -1.]);
+        assert_eq!(actions[80], vec![1.; 4]);
+        for (i, a) in actions.iter().enumerate() {
+            assert!(actions[..i].iter().all(|b| a != b));
+        }
+
+        let single = Discretization::new(vec![0., 2.], vec![1., 4.], 1);
+        assert_eq!(single.n_actions(), 1);
+        assert_eq!(single.action(0), vec![0.5, 3.]);
+    }
+
+    /// Steps of a scripted episode: the observation is the step index.
+    fn scripted_steps(
+        rewards: &[f64],
+        done_at: usize,
+    ) -> (
+        Arc<Mutex<VecDeque<gym::State>>>,
+        impl FnMut() -> BoxFuture<'static, io::Result<gym::State>>,
+    ) {
+        let steps: VecDeque<gym::State> = rewards
+            .iter()
+            .enumerate()
+            .map(|(i, reward)| gym::State {
+                observation: gym::SpaceData::BOX(ndarray::arr1(&[i as f64])),
+                reward: *reward,
+                is_done: i >= done_at,
+            })
+            .collect();
+        let steps = Arc::new(Mutex::new(steps));
+        let remaining = steps.clone();
+        let step = move || {
+            let state = remaining.lock().unwrap().pop_front().unwrap();
+            async move { Ok(state) }.boxed()
+        };
+        (steps, step)
+    }
+
+    #[tokio::test]
+    async fn test_frame_skip() {
+        let rewards = [1., 2., 3., 4., 5., 6.];
+
+        let (steps, step) = scripted_steps(&rewards, 10);
+        let (reward, state) = skip_frames(4, step).await.unwrap();
+        assert_eq!(reward, 1. + 2. + 3. + 4.);
+        assert!(!state.is_done);
+        assert_eq!(state.observation.get_box().unwrap()[0], 3.);
+        assert_eq!(steps.lock().unwrap().len(), 2);
+
+        // the episode ends at the second step: the action is not repeated anymore.
+        let (steps, step) = scripted_steps(&rewards, 1);
+        let (reward, state) = skip_frames(4, step).await.unwrap();
+        assert_eq!(reward, 1. + 2.);
+        assert!(state.is_done);
+        assert_eq!(state.observation.get_box().unwrap()[0], 1.);
+        assert_eq!(steps.lock().unwrap().len(), 4);
+
+        let (_, step) = scripted_steps(&rewards, 10);
+        assert_eq!(skip_frames(1, step).await.unwrap().0, 1.);
+    }
+
+    /// Client of a mock transport, failing once disconnected.
+    #[derive(Clone)]
+    struct MockClient {
+        connected: Arc<AtomicBool>,
+    }
+
+    impl MockClient {
+        fn call(self, request: usize) -> BoxFuture<'static, io::Result<usize>> {
+            let connected = self.connected.load(Ordering::SeqCst);
+            async move {
+                if connected {
+                    Ok(request + 1)
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::ConnectionReset,
+                        "disconnected",
+                    ))
+                }
+            }
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let first = MockClient {
+            connected: Arc::new(AtomicBool::new(true)),
+        };
+        let client = Mutex::new(first.clone());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let connect = {
+            let connections = connections.clone();
+            move || {
+                connections.fetch_add(1, Ordering::SeqCst);
+                async {
+                    Ok(MockClient {
+                        connected: Arc::new(AtomicBool::new(true)),
+                    })
+                }
+                .boxed()
+            }
+        };
+
+        let res = with_retries(&client, 2, connect.clone(), |c| c.call(1)).await;
+        assert_eq!(res.unwrap(), 2);
+        assert_eq!(connections.load(Ordering::SeqCst), 0);
+
+        // the connection drops: the next request reconnects and resumes.
+        first.connected.store(false, Ordering::SeqCst);
+        let res = with_retries(&client, 2, connect.clone(), |c| c.call(2)).await;
+        assert_eq!(res.unwrap(), 3);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        let res = with_retries(&client, 2, connect, |c| c.call(3)).await;
+        assert_eq!(res.unwrap(), 4);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        // the runner is unreachable: the error is returned once retries are exhausted.
+        let current = client.lock().unwrap().clone();
+        current.connected.store(false, Ordering::SeqCst);
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let unreachable = {
+            let attempts = attempts.clone();
+            move || {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async { Err(io::Error::new(io::ErrorKind::TimedOut, "timeout")) }.boxed()
+            }
+        };
+        let res = with_retries(&client, 2, unreachable, |c| c.call(4)).await;
+        assert!(res.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    /// Gym runner with a one-dimensional observation, counting the connections and
+    /// the steps it receives, and recording the continuous actions.
+    #[derive(Clone)]
+    struct MockRunner {
+        action_space: gym::SpaceTemplate,
+        connections: Arc<AtomicUsize>,
+        steps: Arc<AtomicUsize>,
+        continuous_actions: Arc<Mutex<Vec<Vec<f64>>>>,
+        /// Whether the next request for the action space or a step hangs.
+        hang: Arc<AtomicBool>,
+    }
+
+    impl MockRunner {
+        fn new(action_space: gym::SpaceTemplate) -> Self {
+            MockRunner {
+                action_space,
+                connections: Arc::new(AtomicUsize::new(0)),
+                steps: Arc::new(AtomicUsize::new(0)),
+                continuous_actions: Arc::new(Mutex::new(vec![])),
+                hang: Arc::new(AtomicBool::new(false)),
+            }
+        }
+
+        /// Waits long enough for the request to time out, if the runner should hang.
+        async fn maybe_hang(hang: bool) {
+            if hang {
+                tokio::time::delay_for(Duration::from_secs(5)).await;
+            }
+        }
+
+        fn step(self) -> BoxFuture<'static, gym::State> {
+            let step = self.steps.fetch_add(1, Ordering::SeqCst) + 1;
+            let hang = self.hang.swap(false, Ordering::SeqCst);
+            async move {
+                Self::maybe_hang(hang).await;
+                gym::State {
+                    observation: gym::SpaceData::BOX(ndarray::arr1(&[step as f64])),
+                    reward: 1.,
+                    is_done: false,
+                }
+            }
+            .boxed()
+        }
+    }
+
+    impl GymRunner for MockRunner {
+        type InitFut = BoxFuture<'static, ()>;
+
+        fn init(self, _: context::Context, _name: String, _render: bool) -> Self::InitFut {
+            async {}.boxed()
+        }
+
+        type ResetFut = BoxFuture<'static, gym::SpaceData>;
+
+        fn reset(self, _: context::Context) -> Self::ResetFut {
+            async { gym::SpaceData::BOX(ndarray::arr1(&[0.])) }.boxed()
+        }
+
+        type PlayFut = BoxFuture<'static, gym::State>;
+
+        fn play(self, _: context::Context, _action: usize) -> Self::PlayFut {
+            self.step()
+        }
+
+        type PlayContinuousFut = BoxFuture<'static, gym::State>;
+
+        fn play_continuous(self, _: context::Context, action: Vec<f64>) -> Self::PlayContinuousFut {
+            self.continuous_actions.lock().unwrap().push(action);
+            self.step()
+        }
+
+        type ActionSpaceFut = BoxFuture<'static, gym::SpaceTemplate>;
+
+        fn action_space(self, _: context::Context) -> Self::ActionSpaceFut {
+            let hang = self.hang.swap(false, Ordering::SeqCst);
+            async move {
+                Self::maybe_hang(hang).await;
+                self.action_space
+            }
+            .boxed()
+        }
+
+        type ObservationSpaceFut = BoxFuture<'static, gym::SpaceTemplate>;
+
+        fn observation_space(self, _: context::Context) -> Self::ObservationSpaceFut {
+            async {
+                gym::SpaceTemplate::BOX {
+                    high: vec![1.],
+                    low: vec![0.],
+                    shape: vec![1],
+                }
+            }
+            .boxed()
+        }
+    }
+
+    /// Serves `runner` over TCP on a local port, returning its address.
+    async fn serve_runner(runner: MockRunner) -> String {
+        let incoming = tarpc::serde_transport::tcp::listen("localhost:0", BinCodec::default)
             .await
             .unwrap();
-        runner.reset(context::current()).await.unwrap();
+        let address = incoming.local_addr().to_string();
+        let mut incoming = Box::pin(incoming);
+        tokio::spawn(async move {
+            while let Some(Ok(transport)) = incoming.next().await {
+                runner.connections.fetch_add(1, Ordering::SeqCst);
+                let channel = BaseChannel::with_defaults(transport);
+                tokio::spawn(channel.respond_with(runner.clone().serve()).execute());
+            }
+        });
+        address
+    }
 
-        Gym::new(runner, self.game_name.clone()).await
+    fn remote_builder(address: String) -> GymBuilder {
+        GymBuilder {
+            address: Some(address),
+            game_name: "Mock-v0".into(),
+            render: false,
+            action_bins: 3,
+            frame_skip: 1,
+            timeout_ms: 200,
+            max_retries: 2,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_timeout() {
+        let runner = MockRunner::new(gym::SpaceTemplate::DISCRETE { n: 2 });
+        let address = serve_runner(runner.clone()).await;
+
+        // the runner hangs while setting up the game: the request is sent again on a
+        // new connection, with a new deadline.
+        runner.hang.store(true, Ordering::SeqCst);
+        let game = SingleplayerGameBuilder::create(&remote_builder(address)).await;
+        assert_eq!(game.possible_moves(), vec![0, 1]);
+        assert_eq!(runner.connections.load(Ordering::SeqCst), 2);
+
+        // the runner hangs during the episode: the step is not played in a new episode.
+        runner.hang.store(true, Ordering::SeqCst);
+        assert!(game.runner.step(gym::SpaceData::DISCRETE(0)).await.is_err());
+        assert_eq!(runner.steps.load(Ordering::SeqCst), 1);
+        assert_eq!(runner.connections.load(Ordering::SeqCst), 2);
+
+        // tuples are rejected before reaching the runner.
+        let tuple = gym::SpaceData::TUPLE(ndarray::arr1(&[gym::SpaceData::DISCRETE(0)]));
+        assert!(game.runner.step(tuple).await.is_err());
+        assert_eq!(runner.steps.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_remote_continuous_actions() {
+        let runner = MockRunner::new(gym::SpaceTemplate::BOX {
+            high: vec![1., 1.],
+            low: vec![-1., -1.],
+            shape: vec![2],
+        });
+        let address = serve_runner(runner.clone()).await;
+
+        let mut game = SingleplayerGameBuilder::create(&remote_builder(address)).await;
+        assert_eq!(game.possible_moves().len(), 9);
+        let reward = game.play(&5).await;
+        assert!((reward - 1.).abs() < 1e-6);
+        game.play(&0).await;
+        assert_eq!(
+            *runner.continuous_actions.lock().unwrap(),
+            vec![vec![1., 0.], vec![-1., -1.]]
+        );
+        assert!((game.state_to_feature(0)[[0, 0, 0]] - 2.).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "python")]
+    #[tokio::test]
+    async fn test_local_cartpole() {
+        let builder = GymBuilder {
+            address: None,
+            game_name: "CartPole-v1".into(),
+            render: false,
+            action_bins: 3,
+            frame_skip: 1,
+            timeout_ms: 1000,
+            max_retries: 0,
+        };
+        let mut game = SingleplayerGameBuilder::create(&builder).await;
+        assert_eq!(game.possible_moves(), vec![0, 1]);
+
+        let dimension = Gym::state_dimension(&game.get_features());
+        assert_eq!(dimension, Ix3(1, 1, 4));
+        let reward = game.play(&0).await;
+        assert_eq!(reward, 1.);
+        let features = game.state_to_feature(0);
+        assert_eq!(features.raw_dim(), dimension);
+        assert!(features.iter().all(|x| x.is_finite()));
+        assert!(!game.is_finished());
     }
 }
@@ -0,0 +1,216 @@
+use crate::game::breakthrough::Color;
+use crate::game::*;
+
+use async_trait::async_trait;
+use ndarray::Array;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+
+/// Move: (x, y) coordinates of the marked cell.
+pub type Move = (usize, usize);
+
+const LINES: [[Move; 3]; 8] = [
+    [(0, 0), (1, 0), (2, 0)],
+    [(0, 1), (1, 1), (2, 1)],
+    [(0, 2), (1, 2), (2, 2)],
+    [(0, 0), (0, 1), (0, 2)],
+    [(1, 0), (1, 1), (1, 2)],
+    [(2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 1), (2, 2)],
+    [(0, 2), (1, 1), (2, 0)],
+];
+
+/// Tic-Tac-Toe game state instance.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TicTacToe {
+    content: [[Option<Color>; 3]; 3],
+    turn: Color,
+}
+
+impl fmt::Debug for TicTacToe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Turn: {:?}", self.turn)?;
+        for y in 0..3 {
+            for column in self.content.iter() {
+                match column[y] {
+                    None => write!(f, ".")?,
+                    Some(c) => write!(f, "{:?}", c)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl TicTacToe {
+    /// Content of the cell at given position.
+    pub fn cell(&self, (x, y): Move) -> Option<Color> {
+        self.content[x][y]
+    }
+}
+
+/// Game builder for Tic-Tac-Toe.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct TicTacToeBuilder {}
+
+#[async_trait]
+impl GameBuilder for TicTacToeBuilder {
+    type G = TicTacToe;
+
+    async fn create(&self, turn: Color) -> TicTacToe {
+        TicTacToe {
+            content: [[None; 3]; 3],
+            turn,
+        }
+    }
+}
+
+impl SingleWinner for TicTacToe {
+    fn winner(&self) -> Option<Color> {
+        LINES.iter().find_map(|line| {
+            let c = self.cell(line[0]);
+            if c.is_some() && c == self.cell(line[1]) && c == self.cell(line[2]) {
+                c
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl Game for TicTacToe {
+    type Player = Color;
+
+    fn players() -> Vec<Color> {
+        vec![Color::Black, Color::White]
+    }
+
+    fn player_after(player: Color) -> Color {
+        player.adv()
+    }
+
+    fn turn(&self) -> Color {
+        self.turn
+    }
+}
+
+impl Base for TicTacToe {
+    type Move = Move;
+
+    fn possible_moves(&self) -> Vec<Move> {
+        if self.winner().is_some() {
+            return vec![];
+        }
+        Self::all_possible_moves(&())
+            .into_iter()
+            .filter(|m| self.cell(*m).is_none())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Playable for TicTacToe {
+    async fn play(&mut self, m: &Move) -> f32 {
+        let (x, y) = *m;
+        if x >= 3 || y >= 3 || self.content[x][y].is_some() {
+            return -1.;
+        }
+        self.content[x][y] = Some(self.turn);
+        let reward = if self.winner() == Some(self.turn) {
+            1.
+        } else {
+            0.
+        };
+        self.turn = self.turn.adv();
+        reward
+    }
+}
+
+impl Features for TicTacToe {
+    type StateDim = ndarray::Ix3;
+    type ActionDim = ndarray::Ix2;
+
+    type Descriptor = ();
+
+    fn get_features(&self) -> Self::Descriptor {}
+
+    fn state_dimension(_: &Self::Descriptor) -> Self::StateDim {
+        ndarray::Dim([3, 3, 3])
+    }
+
+    fn action_dimension(_: &Self::Descriptor) -> Self::ActionDim {
+        ndarray::Dim([3, 3])
+    }
+
+    fn state_to_feature(&self, pov: Self::Player) -> Array<f32, Self::StateDim> {
+        let mut features = ndarray::Array::zeros(Self::state_dimension(&()));
+
+        for ((x, y, z), row) in features.indexed_iter_mut() {
+            if (z == 0 && self.content[x][y] == Some(pov))
+                || (z == 1 && self.content[x][y] == Some(pov.adv()))
+            {
+                *row = 1.0
+            } else if z == 2 {
+                if self.turn() == Color::White {
+                    *row = 1.0
+                } else {
+                    *row = -1.0
+                }
+            }
+        }
+
+        features
+    }
+
+    fn moves_to_feature(
+        descr: &Self::Descriptor,
+        moves: &HashMap<Self::Move, f32>,
+    ) -> Array<f32, Self::ActionDim> {
+        let mut features = ndarray::Array::zeros(Self::action_dimension(descr));
+
+        for ((x, y), proba) in moves.iter() {
+            features[[*x, *y]] = *proba;
+        }
+
+        features
+    }
+
+    fn feature_to_moves(&self, features: &Array<f32, Self::ActionDim>) -> HashMap<Self::Move, f32> {
+        let possible_moves = self.possible_moves();
+        let z: f32 = possible_moves.iter().map(|(x, y)| features[[*x, *y]]).sum();
+        HashMap::from_iter(
+            possible_moves
+                .iter()
+                .map(|(x, y)| ((*x, *y), features[[*x, *y]] / z)),
+        )
+    }
+
+    fn all_feature_to_moves(
+        descr: &Self::Descriptor,
+        features: &Array<f32, Self::ActionDim>,
+    ) -> HashMap<Self::Move, f32> {
+        let possible_moves = Self::all_possible_moves(descr);
+        let z: f32 = possible_moves.iter().map(|(x, y)| features[[*x, *y]]).sum();
+        HashMap::from_iter(
+            possible_moves
+                .iter()
+                .map(|(x, y)| ((*x, *y), features[[*x, *y]] / z)),
+        )
+    }
+
+    fn symmetries(&self) -> Vec<Symmetry> {
+        Symmetry::dihedral()
+    }
+
+    fn all_possible_moves(_: &Self::Descriptor) -> Vec<Self::Move> {
+        let mut res = vec![];
+        for x in 0..3 {
+            for y in 0..3 {
+                res.push((x, y));
+            }
+        }
+        res
+    }
+}
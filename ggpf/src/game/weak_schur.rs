@@ -1,4 +1,4 @@
-use crate::game::{Base, Playable, Singleplayer, SingleplayerGameBuilder};
+use crate::game::{Base, MoveCode, Playable, Singleplayer, SingleplayerGameBuilder};
 
 use async_trait::async_trait;
 use std::cmp::Ordering;
@@ -6,36 +6,48 @@ use std::fmt;
 use std::hash::Hasher;
 use std::hash::*;
 
-const K: usize = 9;
-const WS_RULE: bool = true;
-
 /// Weak schur number game.
+///
+/// Integers `1, 2, 3, ...` are placed one after the other in partitions, such that
+/// no partition contains `x`, `y` and `x + y` with `x != y`.
+/// Each placed integer yields a reward of one.
 #[derive(Clone, Eq)]
 pub struct WeakSchurNumber {
-    partitions: [Vec<usize>; K],
+    partitions: Vec<Vec<usize>>,
     last_value: usize,
+    bound: Option<usize>,
+    consecutive_rule: bool,
 }
 
 impl fmt::Debug for WeakSchurNumber {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Partitions")?;
-        for i in 0..K {
-            writeln!(f, "{:?}", self.partitions[i])?;
+        for partition in self.partitions.iter() {
+            writeln!(f, "{:?}", partition)?;
         }
         write!(f, "")
     }
 }
 
 impl WeakSchurNumber {
+    /// Partitions built so far.
+    pub fn partitions(&self) -> &[Vec<usize>] {
+        &self.partitions
+    }
+
+    /// Last placed integer.
+    pub fn last_value(&self) -> usize {
+        self.last_value
+    }
+
     /* assumes values is in increasing order. */
-    fn is_valid(last_value: usize, values: &[usize]) -> bool {
+    fn is_valid(value: usize, values: &[usize]) -> bool {
         let mut begin = 0;
         let mut end = values.len() as isize - 1;
-        let target = last_value + 1;
 
         while begin < end {
             let sum = values[begin as usize] + values[end as usize];
-            match sum.cmp(&target) {
+            match sum.cmp(&value) {
                 Ordering::Equal => return false,
                 Ordering::Less => begin += 1,
                 Ordering::Greater => end -= 1,
@@ -43,49 +55,41 @@ impl WeakSchurNumber {
         }
         true
     }
-
-    fn best_sequence(values: &[usize]) -> usize {
-        let mut best_length = 0;
-        let mut cur_length = 0;
-
-        for i in 1..values.len() {
-            if values[i] == values[i - 1] + 1 {
-                cur_length += 1;
-                if cur_length + 1 > best_length {
-                    best_length = cur_length + 1
-                };
-            } else {
-                cur_length = 0
-            }
-        }
-        best_length
-    }
-
-    fn score(&self) -> f32 {
-        self.partitions
-            .iter()
-            .map(|p| Self::best_sequence(&p))
-            .max()
-            .unwrap() as f32
-    }
 }
 
 impl Singleplayer for WeakSchurNumber {}
 
 /// Weak schur number game builder
-#[derive(Clone)]
-pub struct WeakSchurNumberBuilder {}
+#[derive(Clone, Debug)]
+pub struct WeakSchurNumberBuilder {
+    /// Number of partitions.
+    pub partitions: usize,
+    /// The game ends once this integer has been placed.
+    pub bound: Option<usize>,
+    /// Place an integer in the partition of its predecessor whenever possible.
+    /// This prunes the search, but some partitions can't be reached anymore.
+    pub consecutive_rule: bool,
+}
+
+impl Default for WeakSchurNumberBuilder {
+    fn default() -> Self {
+        WeakSchurNumberBuilder {
+            partitions: 9,
+            bound: None,
+            consecutive_rule: true,
+        }
+    }
+}
 
 #[async_trait]
 impl SingleplayerGameBuilder for WeakSchurNumberBuilder {
     type G = WeakSchurNumber;
     async fn create(&self) -> WeakSchurNumber {
-        let partitions = Default::default();
-        let last_value = 0;
-
         WeakSchurNumber {
-            partitions,
-            last_value,
+            partitions: vec![vec![]; self.partitions],
+            last_value: 0,
+            bound: self.bound,
+            consecutive_rule: self.consecutive_rule,
         }
     }
 }
@@ -94,6 +98,10 @@ impl Base for WeakSchurNumber {
     type Move = usize;
 
     fn possible_moves(&self) -> Vec<Self::Move> {
+        if self.bound == Some(self.last_value) {
+            return vec![];
+        }
+
         let valid_moves = self
             .partitions
             .iter()
@@ -102,7 +110,7 @@ impl Base for WeakSchurNumber {
                 WeakSchurNumber::is_valid(self.last_value + 1, partition)
             });
 
-        if WS_RULE {
+        if self.consecutive_rule {
             if let Some((idx, _)) = valid_moves
                 .clone()
                 .find(|(_, partition)| partition.last() == Some(&self.last_value))
@@ -133,10 +141,58 @@ impl Playable for WeakSchurNumber {
     async fn play(&mut self, m: &<Self as Base>::Move) -> f32 {
         self.last_value += 1;
         self.partitions[*m].push(self.last_value);
-        if self.is_finished() {
-            self.score()
-        } else {
-            0.
+        1.
+    }
+}
+
+/// Move encoder for weak schur: the placed integer and its partition.
+pub struct WeakSchurCode {}
+
+impl MoveCode<WeakSchurNumber> for WeakSchurCode {
+    fn code(game: &WeakSchurNumber, action: &usize) -> usize {
+        (game.last_value + 1) * game.partitions.len() + action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::nrpa::NRPA;
+    use crate::policies::{SingleplayerPolicy, SingleplayerPolicyBuilder};
+    use crate::settings;
+
+    #[tokio::test]
+    async fn test_nrpa_partition() {
+        let game_builder = WeakSchurNumberBuilder {
+            partitions: 3,
+            bound: Some(23),
+            consecutive_rule: false,
+        };
+        let mut state = SingleplayerGameBuilder::create(&game_builder).await;
+        let mut policy = NRPA::<WeakSchurNumber, WeakSchurCode>::new(settings::NRPA {
+            level: 3,
+            iterations: 20,
+            alpha: 1.,
+        })
+        .create();
+
+        for action in policy.solve(&state).await {
+            assert!(state.possible_moves().contains(&action));
+            state.play(&action).await;
+        }
+        assert!(state.is_finished());
+        // 23 is optimal but not always reached: a random rollout places 20 integers
+        // about once in 170 tries, and the search performs 8000 rollouts.
+        assert!(state.last_value() >= 20);
+
+        let placed: usize = state.partitions().iter().map(|p| p.len()).sum();
+        assert_eq!(placed, state.last_value());
+        for partition in state.partitions() {
+            for x in partition {
+                for y in partition.iter().filter(|y| *y != x) {
+                    assert!(!partition.contains(&(x + y)));
+                }
+            }
         }
     }
 }
@@ -23,6 +23,10 @@
 //! * [`ui`](../ui/index.html): visualize PUCT-based policies (AlphaZero/MuZero) in a duel against PPA.
 //! * [`perf`](../perf/index.html): test raw games generation performance.
 
+///
+/// Round-robin tournaments and Elo ratings of policies.
+///
+pub mod arena;
 ///
 /// Features for neural network-based policies.
 ///
@@ -0,0 +1,383 @@
+use crate::game::Game;
+use crate::policies::{MultiplayerPolicy, MultiplayerPolicyBuilder, PlayStats};
+
+use async_trait::async_trait;
+use rand::Rng;
+use std::fmt;
+
+/// Mixture policy
+///
+/// Delegates each move to the first policy with probability `p`, to the second one otherwise.
+pub struct MixturePolicy<PA, PB> {
+    a: PA,
+    b: PB,
+    p: f32,
+}
+
+impl<PA, PB> MixturePolicy<PA, PB> {
+    fn pick_a(&self) -> bool {
+        rand::thread_rng().gen::<f32>() < self.p
+    }
+}
+
+#[async_trait]
+impl<G, PA, PB> MultiplayerPolicy<G> for MixturePolicy<PA, PB>
+where
+    G: Game,
+    PA: MultiplayerPolicy<G> + Send,
+    PB: MultiplayerPolicy<G> + Send,
+{
+    async fn play(&mut self, board: &G) -> Option<G::Move> {
+        if self.pick_a() {
+            self.a.play(board).await
+        } else {
+            self.b.play(board).await
+        }
+    }
+
+    async fn play_with_stats(&mut self, board: &G) -> Option<(G::Move, PlayStats<G>)> {
+        if self.pick_a() {
+            self.a.play_with_stats(board).await
+        } else {
+            self.b.play_with_stats(board).await
+        }
+    }
+
+    fn reset(&mut self) {
+        self.a.reset();
+        self.b.reset();
+    }
+}
+
+/// Mixture policy builder.
+pub struct Mixture<A, B> {
+    /// Policy builder used with probability `p`.
+    pub a: A,
+    /// Policy builder used with probability `1 - p`.
+    pub b: B,
+    /// Probability of using `a` for a move.
+    pub p: f32,
+}
+
+impl<A: fmt::Display, B: fmt::Display> fmt::Display for Mixture<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Mixture")?;
+        writeln!(f, "|| p = {}:", self.p)?;
+        write!(f, "{}", self.a)?;
+        writeln!(f, "|| 1 - p:")?;
+        write!(f, "{}", self.b)
+    }
+}
+
+impl<G, A, B> MultiplayerPolicyBuilder<G> for Mixture<A, B>
+where
+    G: Game,
+    A: MultiplayerPolicyBuilder<G>,
+    B: MultiplayerPolicyBuilder<G>,
+    A::P: Send,
+    B::P: Send,
+{
+    type P = MixturePolicy<A::P, B::P>;
+
+    fn create(&self, color: G::Player) -> Self::P {
+        MixturePolicy {
+            a: self.a.create(color),
+            b: self.b.create(color),
+            p: self.p,
+        }
+    }
+}
+
+/// Sequential policy
+///
+/// Delegates its first `plies` moves to the first policy, then the next ones to the second.
+pub struct SequentialPolicy<PA, PB> {
+    a: PA,
+    b: PB,
+    plies: usize,
+    /// Number of moves played since the creation or the last reset.
+    ply: usize,
+}
+
+#[async_trait]
+impl<G, PA, PB> MultiplayerPolicy<G> for SequentialPolicy<PA, PB>
+where
+    G: Game,
+    PA: MultiplayerPolicy<G> + Send,
+    PB: MultiplayerPolicy<G> + Send,
+{
+    async fn play(&mut self, board: &G) -> Option<G::Move> {
+        self.play_with_stats(board).await.map(|(action, _)| action)
+    }
+
+    async fn play_with_stats(&mut self, board: &G) -> Option<(G::Move, PlayStats<G>)> {
+        self.ply += 1;
+        if self.ply <= self.plies {
+            self.a.play_with_stats(board).await
+        } else {
+            self.b.play_with_stats(board).await
+        }
+    }
+
+    fn reset(&mut self) {
+        self.a.reset();
+        self.b.reset();
+        self.ply = 0;
+    }
+}
+
+/// Sequential policy builder.
+pub struct Sequential<A, B> {
+    /// Policy builder used for the first moves.
+    pub a: A,
+    /// Policy builder used for the next moves.
+    pub b: B,
+    /// Number of moves played by `a`.
+    pub plies: usize,
+}
+
+impl<A: fmt::Display, B: fmt::Display> fmt::Display for Sequential<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Sequential")?;
+        writeln!(f, "|| first {} plies:", self.plies)?;
+        write!(f, "{}", self.a)?;
+        writeln!(f, "|| then:")?;
+        write!(f, "{}", self.b)
+    }
+}
+
+impl<G, A, B> MultiplayerPolicyBuilder<G> for Sequential<A, B>
+where
+    G: Game,
+    A: MultiplayerPolicyBuilder<G>,
+    B: MultiplayerPolicyBuilder<G>,
+    A::P: Send,
+    B::P: Send,
+{
+    type P = SequentialPolicy<A::P, B::P>;
+
+    fn create(&self, color: G::Player) -> Self::P {
+        SequentialPolicy {
+            a: self.a.create(color),
+            b: self.b.create(color),
+            plies: self.plies,
+            ply: 0,
+        }
+    }
+}
+
+/// Verified policy
+///
+/// Checks that each move of the inner policy is legal, panicking with the board,
+/// the move and the policy otherwise.
+pub struct VerifiedPolicy<PA> {
+    a: PA,
+    enabled: bool,
+    name: String,
+}
+
+impl<PA> VerifiedPolicy<PA> {
+    fn verify<G: Game>(&self, board: &G, action: &G::Move) {
+        if self.enabled {
+            verify_move(board, action, &self.name);
+        }
+    }
+}
+
+/// Panics with the board, the move and the policy if the move is not legal on the board.
+pub fn verify_move<G: Game>(board: &G, action: &G::Move, policy: &impl fmt::Display) {
+    if !board.possible_moves().contains(action) {
+        panic!(
+            "Illegal move {:?} played by policy:\n{}\non board:\n{:?}",
+            action, policy, board
+        );
+    }
+}
+
+#[async_trait]
+impl<G, PA> MultiplayerPolicy<G> for VerifiedPolicy<PA>
+where
+    G: Game,
+    PA: MultiplayerPolicy<G> + Send,
+{
+    async fn play(&mut self, board: &G) -> Option<G::Move> {
+        let action = self.a.play(board).await?;
+        self.verify(board, &action);
+        Some(action)
+    }
+
+    async fn play_with_stats(&mut self, board: &G) -> Option<(G::Move, PlayStats<G>)> {
+        let (action, stats) = self.a.play_with_stats(board).await?;
+        self.verify(board, &action);
+        Some((action, stats))
+    }
+
+    fn reset(&mut self) {
+        self.a.reset();
+    }
+}
+
+/// Verified policy builder.
+pub struct Verified<A> {
+    /// Policy builder whose moves are checked.
+    pub a: A,
+    /// Check the moves, otherwise the policy is played as is.
+    pub enabled: bool,
+}
+
+impl<A> Verified<A> {
+    /// Checks the moves of the policies built by `a`.
+    pub fn new(a: A) -> Self {
+        Verified { a, enabled: true }
+    }
+}
+
+impl<A: fmt::Display> fmt::Display for Verified<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Verified")?;
+        writeln!(f, "|| enabled: {}", self.enabled)?;
+        write!(f, "{}", self.a)
+    }
+}
+
+impl<G, A> MultiplayerPolicyBuilder<G> for Verified<A>
+where
+    G: Game,
+    A: MultiplayerPolicyBuilder<G>,
+    A::P: Send,
+{
+    type P = VerifiedPolicy<A::P>;
+
+    fn create(&self, color: G::Player) -> Self::P {
+        VerifiedPolicy {
+            a: self.a.create(color),
+            enabled: self.enabled,
+            name: format!("{}", self.a),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::Color;
+    use crate::game::tictactoe::{TicTacToe, TicTacToeBuilder};
+    use crate::game::{Base, GameBuilder, Playable, SingleWinner};
+    use crate::policies::flat::{outcome, Greedy, Random};
+
+    /// Greedy policy playing the winning moves.
+    fn forced_win() -> Greedy<TicTacToe, fn(&TicTacToe, Color) -> f32> {
+        Greedy::new(outcome)
+    }
+
+    /// Black wins by playing (2, 0), among 5 legal moves.
+    async fn one_move_win() -> TicTacToe {
+        let mut state = TicTacToeBuilder::default().create(Color::Black).await;
+        for action in &[(0, 0), (0, 1), (1, 0), (1, 1)] {
+            state.play(action).await;
+        }
+        state
+    }
+
+    async fn wins(policy: &mut impl MultiplayerPolicy<TicTacToe>, state: &TicTacToe) -> bool {
+        let mut after_move = state.clone();
+        after_move.play(&policy.play(state).await.unwrap()).await;
+        after_move.winner() == Some(Color::Black)
+    }
+
+    #[tokio::test]
+    async fn test_mixture_win_rate() {
+        let state = one_move_win().await;
+        let mixture = Mixture {
+            a: forced_win(),
+            b: Random {},
+            p: 0.5,
+        };
+        let mut policy = mixture.create(Color::Black);
+
+        let n_trials = 2000;
+        let mut n_wins = 0;
+        for _ in 0..n_trials {
+            if wins(&mut policy, &state).await {
+                n_wins += 1;
+            }
+        }
+        // the greedy policy always wins, the random one with probability 1/5.
+        let win_rate = n_wins as f32 / n_trials as f32;
+        assert!((win_rate - 0.6).abs() < 0.05, "Win rate: {}", win_rate);
+    }
+
+    #[tokio::test]
+    async fn test_sequential() {
+        let state = one_move_win().await;
+        let sequential = Sequential {
+            a: Random {},
+            b: forced_win(),
+            plies: 2,
+        };
+        let mut policy = sequential.create(Color::Black);
+
+        policy.play(&state).await;
+        policy.play(&state).await;
+        for _ in 0..10 {
+            assert!(wins(&mut policy, &state).await);
+        }
+    }
+
+    /// Policy always playing in the top-left corner, whether it's free or not.
+    struct Corner {}
+
+    #[async_trait]
+    impl MultiplayerPolicy<TicTacToe> for Corner {
+        async fn play(&mut self, _board: &TicTacToe) -> Option<(usize, usize)> {
+            Some((0, 0))
+        }
+    }
+
+    impl fmt::Display for Corner {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "Corner")
+        }
+    }
+
+    impl MultiplayerPolicyBuilder<TicTacToe> for Corner {
+        type P = Corner;
+
+        fn create(&self, _color: Color) -> Corner {
+            Corner {}
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verified_legal() {
+        let mut state = TicTacToeBuilder::default().create(Color::Black).await;
+        let mut black = Verified::new(Random {}).create(Color::Black);
+        let mut white = Verified::new(Random {}).create(Color::White);
+        while !state.is_finished() {
+            let action = if state.turn() == Color::Black {
+                black.play(&state).await
+            } else {
+                white.play(&state).await
+            }
+            .unwrap();
+            state.play(&action).await;
+        }
+
+        // the first move is legal.
+        let state = TicTacToeBuilder::default().create(Color::Black).await;
+        Verified::new(Corner {})
+            .create(Color::Black)
+            .play(&state)
+            .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Illegal move (0, 0) played by policy:\nCorner")]
+    async fn test_verified_illegal() {
+        let state = one_move_win().await;
+        Verified::new(Corner {})
+            .create(Color::Black)
+            .play(&state)
+            .await;
+    }
+}
@@ -1,12 +1,16 @@
-use crate::game::{Game, Playout, SingleWinner, Singleplayer};
+use crate::deep::evaluator::{prediction, PredictionEvaluatorChannel};
+use crate::game::{Features, Game, Playout, SingleWinner, Singleplayer};
 use crate::policies::{
-    MultiplayerPolicy, MultiplayerPolicyBuilder, SingleplayerPolicy, SingleplayerPolicyBuilder,
+    rollout, MultiplayerPolicy, MultiplayerPolicyBuilder, SingleplayerPolicy,
+    SingleplayerPolicyBuilder,
 };
 use crate::settings;
 
 use async_trait::async_trait;
 use rand::seq::SliceRandom;
-use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use tokio::sync::mpsc;
 
 /// Random policy
 ///
@@ -15,9 +19,9 @@ pub struct RandomPolicy {}
 
 #[async_trait]
 impl<G: Game> MultiplayerPolicy<G> for RandomPolicy {
-    async fn play(self: &mut RandomPolicy, board: &G) -> G::Move {
+    async fn play(self: &mut RandomPolicy, board: &G) -> Option<G::Move> {
         let moves = board.possible_moves();
-        moves.choose(&mut rand::thread_rng()).copied().unwrap()
+        moves.choose(&mut rand::thread_rng()).copied()
     }
 }
 
@@ -31,7 +35,7 @@ impl<G: Singleplayer + Clone> SingleplayerPolicy<G> for RandomPolicy {
 }
 
 /// Random policy builder.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Random {}
 
 use std::fmt;
@@ -61,12 +65,16 @@ impl<G: Singleplayer + Clone> SingleplayerPolicyBuilder<G> for Random {
 pub struct FlatMonteCarloPolicy<G: Game> {
     color: G::Player,
     playouts: usize,
+    rollout: settings::RolloutKind,
 }
 
 #[async_trait]
 impl<G: Game + SingleWinner + Clone> MultiplayerPolicy<G> for FlatMonteCarloPolicy<G> {
-    async fn play(self: &mut FlatMonteCarloPolicy<G>, board: &G) -> G::Move {
+    async fn play(self: &mut FlatMonteCarloPolicy<G>, board: &G) -> Option<G::Move> {
         let moves = board.possible_moves();
+        if moves.is_empty() {
+            return None;
+        }
 
         let n_playouts_per_move = self.playouts / moves.len();
 
@@ -78,7 +86,11 @@ impl<G: Game + SingleWinner + Clone> MultiplayerPolicy<G> for FlatMonteCarloPoli
             b_after_move.play(&m).await;
             let mut success = 0;
             for _ in 0..n_playouts_per_move {
-                if b_after_move.playout_board(self.color).await.0.winner() == Some(self.color) {
+                if rollout(&b_after_move, self.color, self.rollout)
+                    .await
+                    .winner()
+                    == Some(self.color)
+                {
                     success += 1;
                 }
             }
@@ -89,7 +101,7 @@ impl<G: Game + SingleWinner + Clone> MultiplayerPolicy<G> for FlatMonteCarloPoli
             }
         }
 
-        best_move.unwrap()
+        best_move
     }
 }
 
@@ -98,7 +110,8 @@ type FlatMonteCarlo = settings::FlatMonteCarlo;
 
 impl fmt::Display for FlatMonteCarlo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "FlatMonteCarlo")
+        writeln!(f, "FlatMonteCarlo")?;
+        writeln!(f, "|| rollout: {:?}", self.rollout)
     }
 }
 
@@ -109,83 +122,121 @@ impl<G: Game + SingleWinner + Clone> MultiplayerPolicyBuilder<G> for FlatMonteCa
         FlatMonteCarloPolicy {
             color,
             playouts: self.playouts,
+            rollout: self.rollout,
         }
     }
 }
 
-/// Flat Monte Carlo with UCB policy
-pub struct FlatUCBMonteCarloPolicy<G: Game> {
-    color: G::Player,
-    playouts: usize,
-    ucb_weight: f32,
+/// Playout statistics of a move.
+#[derive(Debug, Clone, Copy, Default)]
+struct ArmStats {
+    count: f32,
+    sum: f32,
+    sum_squares: f32,
 }
 
-#[async_trait]
-impl<G: Game + SingleWinner + Clone> MultiplayerPolicy<G> for FlatUCBMonteCarloPolicy<G> {
-    async fn play(self: &mut FlatUCBMonteCarloPolicy<G>, board: &G) -> G::Move {
-        let moves = board.possible_moves();
-        let n_moves = moves.len();
+impl ArmStats {
+    fn add(&mut self, reward: f32) {
+        self.count += 1.;
+        self.sum += reward;
+        self.sum_squares += reward * reward;
+    }
 
-        let mut move_success: HashMap<&G::Move, i32> = HashMap::new();
-        let mut move_count: HashMap<&G::Move, i32> = HashMap::new();
-        let mut move_board: HashMap<&G::Move, G> = HashMap::new();
+    fn mean(&self) -> f32 {
+        self.sum / self.count
+    }
 
-        for m in moves.iter() {
-            let mut b_after_move = board.clone();
-            b_after_move.play(&m).await;
-            if b_after_move.playout_board(self.color).await.0.winner() == Some(self.color) {
-                move_success.insert(m, 1);
-            } else {
-                move_success.insert(m, 0);
-            }
-            move_count.insert(m, 1);
-            move_board.insert(m, b_after_move);
-        }
+    fn variance(&self) -> f32 {
+        self.sum_squares / self.count - self.mean() * self.mean()
+    }
+}
 
-        for i in 0..(self.playouts - n_moves) {
-            let mut max_ucb = 0f32;
-            let mut max_move = None;
+/// UCB1 bound of a move, `total` playouts having been performed.
+fn ucb1(stats: &ArmStats, total: f32, ucb_weight: f32) -> f32 {
+    stats.mean() + ucb_weight * (total.ln() / stats.count).sqrt()
+}
 
-            for m in moves.iter() {
-                let count = *move_count.get(&m).unwrap() as f32;
-                let succ = *move_success.get(&m).unwrap() as f32;
-                let mean = succ / count;
-                let ucb = mean + self.ucb_weight * (((n_moves + i) as f32).ln() / count).sqrt();
+/// UCB1-Tuned bound of a move: the exploration term is bounded using the reward variance.
+fn ucb1_tuned(stats: &ArmStats, total: f32) -> f32 {
+    let exploration = total.ln() / stats.count;
+    let variance = stats.variance() + (2. * exploration).sqrt();
+    stats.mean() + (exploration * variance.min(0.25)).sqrt()
+}
 
-                if ucb >= max_ucb {
-                    max_move = Some(m);
-                    max_ucb = ucb;
+/// Flat bandit search: each move is played once, then `playouts` are distributed between
+/// moves maximizing the bound. Returns moves with their statistics.
+async fn flat_bandit<G, F>(
+    board: &G,
+    color: G::Player,
+    playouts: usize,
+    bound: F,
+) -> Vec<(G::Move, ArmStats)>
+where
+    G: Game + SingleWinner + Clone,
+    F: Fn(&ArmStats, f32) -> f32,
+{
+    let mut arms = vec![];
+    for m in board.possible_moves() {
+        let mut b_after_move = board.clone();
+        b_after_move.play(&m).await;
+        arms.push((m, b_after_move, ArmStats::default()));
+    }
+    let n_moves = arms.len();
+
+    for i in 0..playouts.max(n_moves) {
+        let index = if i < n_moves {
+            i
+        } else {
+            let mut max_bound = 0f32;
+            let mut max_index = 0;
+            for (index, (_, _, stats)) in arms.iter().enumerate() {
+                let value = bound(stats, i as f32);
+                if value >= max_bound {
+                    max_index = index;
+                    max_bound = value;
                 }
             }
+            max_index
+        };
 
-            if let Some(max_move) = max_move {
-                *move_count.get_mut(&max_move).unwrap() += 1;
-                if move_board
-                    .get(&max_move)
-                    .unwrap()
-                    .playout_board(self.color)
-                    .await
-                    .0
-                    .winner()
-                    == Some(self.color)
-                {
-                    *move_success.get_mut(&max_move).unwrap() += 1;
-                }
-            }
-        }
+        let (_, b_after_move, stats) = &mut arms[index];
+        let winner = b_after_move.playout_board(color).await.0.winner();
+        stats.add(if winner == Some(color) { 1. } else { 0. });
+    }
 
-        let mut max_count = 0;
-        let mut max_move = None;
+    arms.into_iter().map(|(m, _, stats)| (m, stats)).collect()
+}
 
-        for m in moves.iter() {
-            let count = *move_count.get(&m).unwrap();
+/// Most visited move, if any.
+fn most_visited<M: Copy>(arms: &[(M, ArmStats)]) -> Option<M> {
+    let mut max_count = 0.;
+    let mut max_move = None;
 
-            if count >= max_count {
-                max_move = Some(m);
-                max_count = count;
-            }
+    for (m, stats) in arms.iter() {
+        if stats.count >= max_count {
+            max_move = Some(m);
+            max_count = stats.count;
         }
-        max_move.copied().unwrap()
+    }
+    max_move.copied()
+}
+
+/// Flat Monte Carlo with UCB policy
+pub struct FlatUCBMonteCarloPolicy<G: Game> {
+    color: G::Player,
+    playouts: usize,
+    ucb_weight: f32,
+}
+
+#[async_trait]
+impl<G: Game + SingleWinner + Clone> MultiplayerPolicy<G> for FlatUCBMonteCarloPolicy<G> {
+    async fn play(self: &mut FlatUCBMonteCarloPolicy<G>, board: &G) -> Option<G::Move> {
+        let ucb_weight = self.ucb_weight;
+        let arms = flat_bandit(board, self.color, self.playouts, |stats, total| {
+            ucb1(stats, total, ucb_weight)
+        })
+        .await;
+        most_visited(&arms)
     }
 }
 
@@ -210,3 +261,307 @@ impl<G: Game + SingleWinner + Clone> MultiplayerPolicyBuilder<G> for FlatUCBMont
         }
     }
 }
+
+/// Flat Monte Carlo with UCB1-Tuned policy
+pub struct FlatUCB1TunedPolicy<G: Game> {
+    color: G::Player,
+    playouts: usize,
+}
+
+#[async_trait]
+impl<G: Game + SingleWinner + Clone> MultiplayerPolicy<G> for FlatUCB1TunedPolicy<G> {
+    async fn play(self: &mut FlatUCB1TunedPolicy<G>, board: &G) -> Option<G::Move> {
+        let arms = flat_bandit(board, self.color, self.playouts, ucb1_tuned).await;
+        most_visited(&arms)
+    }
+}
+
+/// Flat Monte Carlo with UCB1-Tuned policy builder
+type FlatUCB1Tuned = settings::FlatUCB1Tuned;
+
+impl fmt::Display for FlatUCB1Tuned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "FlatUCB1Tuned")
+    }
+}
+
+impl<G: Game + SingleWinner + Clone> MultiplayerPolicyBuilder<G> for FlatUCB1Tuned {
+    type P = FlatUCB1TunedPolicy<G>;
+
+    fn create(&self, color: G::Player) -> Self::P {
+        FlatUCB1TunedPolicy {
+            color,
+            playouts: self.playouts,
+        }
+    }
+}
+
+/// One of the moves with the best score, chosen at random between ties, if any.
+fn best_scored<M: Copy>(scored: &[(M, f32)]) -> Option<M> {
+    let best_score = scored
+        .iter()
+        .map(|(_, score)| *score)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let best_moves: Vec<M> = scored
+        .iter()
+        .filter(|(_, score)| *score >= best_score)
+        .map(|(m, _)| *m)
+        .collect();
+    best_moves.choose(&mut rand::thread_rng()).copied()
+}
+
+/// Value of a finished game for `pov`: 1 for a win, -1 for a loss and 0 otherwise.
+pub fn outcome<G: SingleWinner>(board: &G, pov: G::Player) -> f32 {
+    match board.winner() {
+        Some(winner) if winner == pov => 1.,
+        Some(_) => -1.,
+        None => 0.,
+    }
+}
+
+/// Greedy policy
+///
+/// Plays the move leading to the position with the best evaluation, without search.
+pub struct GreedyPolicy<G: Game, F> {
+    color: G::Player,
+    evaluate: Arc<F>,
+}
+
+#[async_trait]
+impl<G, F> MultiplayerPolicy<G> for GreedyPolicy<G, F>
+where
+    G: Game + Clone,
+    F: Fn(&G, G::Player) -> f32 + Send + Sync,
+{
+    async fn play(self: &mut GreedyPolicy<G, F>, board: &G) -> Option<G::Move> {
+        let mut scored = vec![];
+        for m in board.possible_moves() {
+            let mut b_after_move = board.clone();
+            b_after_move.play(&m).await;
+            scored.push((m, (self.evaluate)(&b_after_move, self.color)));
+        }
+        best_scored(&scored)
+    }
+}
+
+/// Greedy policy builder.
+///
+/// `evaluate` gives the value of a position, from the point of view of the given player.
+pub struct Greedy<G, F> {
+    evaluate: Arc<F>,
+    game: PhantomData<fn() -> G>,
+}
+
+impl<G, F> Greedy<G, F> {
+    /// Creates a greedy policy builder from an evaluation function.
+    pub fn new(evaluate: F) -> Self {
+        Greedy {
+            evaluate: Arc::new(evaluate),
+            game: PhantomData,
+        }
+    }
+}
+
+impl<G, F> fmt::Display for Greedy<G, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Greedy")
+    }
+}
+
+impl<G, F> MultiplayerPolicyBuilder<G> for Greedy<G, F>
+where
+    G: Game + Clone,
+    F: Fn(&G, G::Player) -> f32 + Send + Sync,
+{
+    type P = GreedyPolicy<G, F>;
+
+    fn create(&self, color: G::Player) -> Self::P {
+        GreedyPolicy {
+            color,
+            evaluate: self.evaluate.clone(),
+        }
+    }
+}
+
+/// Greedy network policy
+///
+/// Plays the move leading to the position with the best value predicted by the network.
+pub struct GreedyNetworkPolicy<G: Game> {
+    color: G::Player,
+    support_size: usize,
+    transform: Option<f32>,
+    prediction_channel: mpsc::Sender<PredictionEvaluatorChannel>,
+}
+
+#[async_trait]
+impl<G: Features + SingleWinner + Clone> MultiplayerPolicy<G> for GreedyNetworkPolicy<G> {
+    async fn play(self: &mut GreedyNetworkPolicy<G>, board: &G) -> Option<G::Move> {
+        let mut scored = vec![];
+        for m in board.possible_moves() {
+            let mut b_after_move = board.clone();
+            b_after_move.play(&m).await;
+            // finished games are valued by their outcome instead of the network.
+            let value = if b_after_move.is_finished() {
+                outcome(&b_after_move, self.color)
+            } else {
+                let turn = b_after_move.turn();
+                let channel = self.prediction_channel.clone();
+                let (_, value) = prediction(
+                    channel,
+                    turn,
+                    &b_after_move,
+                    self.support_size,
+                    self.transform,
+                )
+                .await;
+                if turn == self.color {
+                    value
+                } else {
+                    -value
+                }
+            };
+            scored.push((m, value));
+        }
+        best_scored(&scored)
+    }
+}
+
+/// Greedy network policy builder.
+pub struct GreedyNetwork {
+    /// Value support size, as in the PUCT settings.
+    pub value_support: Option<usize>,
+    /// `ε` of the value transform, as in the PUCT settings.
+    pub value_transform: Option<f32>,
+    /// State evaluation function.
+    pub prediction_channel: mpsc::Sender<PredictionEvaluatorChannel>,
+}
+
+impl fmt::Display for GreedyNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "GreedyNetwork")
+    }
+}
+
+impl<G: Features + SingleWinner + Clone> MultiplayerPolicyBuilder<G> for GreedyNetwork {
+    type P = GreedyNetworkPolicy<G>;
+
+    fn create(&self, color: G::Player) -> Self::P {
+        GreedyNetworkPolicy {
+            color,
+            support_size: self.value_support.unwrap_or(0),
+            transform: self.value_transform,
+            prediction_channel: self.prediction_channel.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+    use crate::game::{Base, GameBuilder, Playable};
+
+    /// Probability of winning of each arm, in tenths.
+    const ARMS: [usize; 8] = [9, 5, 5, 5, 5, 5, 5, 5];
+
+    /// Black pulls an arm, then white's random move decides the winner.
+    #[derive(Clone, Debug)]
+    struct Bandit {
+        arm: Option<usize>,
+        outcome: Option<bool>,
+    }
+
+    impl Base for Bandit {
+        type Move = usize;
+
+        fn possible_moves(&self) -> Vec<usize> {
+            match (self.arm, self.outcome) {
+                (None, _) => (0..ARMS.len()).collect(),
+                (Some(_), None) => (0..10).collect(),
+                _ => vec![],
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Playable for Bandit {
+        async fn play(&mut self, m: &usize) -> f32 {
+            match self.arm {
+                None => self.arm = Some(*m),
+                Some(arm) => self.outcome = Some(*m < ARMS[arm]),
+            }
+            0.
+        }
+    }
+
+    impl Game for Bandit {
+        type Player = Color;
+
+        fn players() -> Vec<Color> {
+            vec![Color::Black, Color::White]
+        }
+
+        fn player_after(player: Color) -> Color {
+            player.adv()
+        }
+
+        fn turn(&self) -> Color {
+            if self.arm.is_none() {
+                Color::Black
+            } else {
+                Color::White
+            }
+        }
+    }
+
+    impl SingleWinner for Bandit {
+        fn winner(&self) -> Option<Color> {
+            self.outcome
+                .map(|win| if win { Color::Black } else { Color::White })
+        }
+    }
+
+    /// Playouts allocated to the best arm over 100 searches of 50 playouts.
+    async fn best_arm_playouts<F: Fn(&ArmStats, f32) -> f32 + Copy>(bound: F) -> f32 {
+        let board = Bandit {
+            arm: None,
+            outcome: None,
+        };
+        let mut total = 0.;
+        for _ in 0..100 {
+            let arms = flat_bandit(&board, Color::Black, 50, bound).await;
+            total += arms.iter().find(|(m, _)| *m == 0).unwrap().1.count;
+        }
+        total
+    }
+
+    #[tokio::test]
+    async fn test_ucb1_tuned_converges_faster() {
+        let tuned = best_arm_playouts(ucb1_tuned).await;
+        let plain = best_arm_playouts(|stats: &ArmStats, total| {
+            ucb1(stats, total, std::f32::consts::SQRT_2)
+        })
+        .await;
+        assert!(tuned > plain);
+    }
+
+    #[tokio::test]
+    async fn test_greedy_captures() {
+        let builder = BreakthroughBuilder { size: 5 };
+        let state = builder
+            .create_from("WWWWW/5/1W3/B4/BBBB1", Color::Black)
+            .await
+            .unwrap();
+        let greedy: Greedy<Breakthrough, _> =
+            Greedy::new(|state: &Breakthrough, pov: Color| state.material_heuristic(pov));
+        let mut policy = greedy.create(Color::Black);
+
+        let mut after_move = state.clone();
+        after_move.play(&policy.play(&state).await.unwrap()).await;
+        let capture = builder
+            .create_from("WWWWW/5/1B3/5/BBBB1", Color::White)
+            .await
+            .unwrap();
+        assert_eq!(after_move, capture);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::game::{Game, ParseMove};
+use crate::policies::{MultiplayerPolicy, MultiplayerPolicyBuilder};
+
+use async_trait::async_trait;
+use std::fmt;
+use std::fmt::Debug;
+use std::io::{self, BufRead, Write};
+use tokio::task;
+
+/// Human policy
+///
+/// Shows the board and reads the move on the standard input, until a legal move is given.
+/// No move is returned once the standard input is closed, which is a resignation.
+pub struct HumanPolicy {}
+
+#[async_trait]
+impl<G: Game + ParseMove + Debug> MultiplayerPolicy<G> for HumanPolicy {
+    async fn play(self: &mut HumanPolicy, board: &G) -> Option<G::Move> {
+        println!("{:?}", board);
+        if board.is_finished() {
+            return None;
+        }
+        loop {
+            print!("Move: ");
+            io::stdout().flush().unwrap();
+
+            // reading the standard input blocks: keep it off the runtime workers.
+            let (read, line) = task::spawn_blocking(|| {
+                let mut line = String::new();
+                let read = io::stdin().lock().read_line(&mut line);
+                (read, line)
+            })
+            .await
+            .unwrap();
+            match read {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => (),
+            }
+            match board.parse_move(line.trim()) {
+                Ok(action) => return Some(action),
+                Err(e) => {
+                    println!("{}", e);
+                    println!("Legal moves: {:?}", board.possible_moves());
+                }
+            }
+        }
+    }
+}
+
+/// Human policy builder.
+#[derive(Default)]
+pub struct Human {}
+
+impl fmt::Display for Human {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Human")
+    }
+}
+
+impl<G: Game + ParseMove + Debug> MultiplayerPolicyBuilder<G> for Human {
+    type P = HumanPolicy;
+
+    fn create(&self, _: G::Player) -> Self::P {
+        HumanPolicy {}
+    }
+}
@@ -1,11 +1,17 @@
 use crate::game::{Base, Game};
-use crate::policies::MultiplayerPolicy;
+use crate::policies::{MultiplayerPolicy, PlayStats};
+use crate::settings::{self, StrError};
 
 use async_trait::async_trait;
+use futures::future::join_all;
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 use std::marker::PhantomData;
+use std::time::{Duration, Instant};
 
 /// MuZero policy.
 pub mod muz;
@@ -19,6 +25,7 @@ pub mod uct;
 pub trait MCTSGame = Game + Clone;
 /* ABSTRACT MCTS */
 
+use std::sync::atomic::{self, AtomicBool};
 use std::sync::RwLock;
 use std::sync::{Arc, Weak};
 
@@ -42,6 +49,8 @@ where
     pub moves: HashMap<G::Move, MCTSNodeChild<G, MCTS>>,
     /// Node information
     pub info: MCTSNode<G, MCTS>,
+    /// Search iteration during which the node was last visited.
+    pub last_access: usize,
 }
 
 impl<G, MCTS> Debug for MCTSTreeNode<G, MCTS>
@@ -77,6 +86,64 @@ where
     }
 }
 
+/// Move statistics, as exposed by any MCTS policy.
+#[derive(Debug, Clone, Copy)]
+pub struct MoveStats {
+    /// Number of times the move has been explored.
+    pub N_a: f32,
+    /// Move value, as stored by the policy.
+    pub Q: f32,
+    /// Move prior probability.
+    pub pi: f32,
+    /// Immediate reward yielded by move.
+    pub reward: f32,
+}
+
+/// Search statistics of the root node after exploration.
+#[derive(Debug, Clone)]
+pub struct SearchInfo<G: Base> {
+    /// Value of the root node: mean discounted move value, weighted by visit counts.
+    pub root_value: f32,
+    /// Root visit count.
+    pub count: f32,
+    /// Root moves statistics.
+    pub moves: HashMap<G::Move, MoveStats>,
+    /// Normalized visit count distribution.
+    pub visit_distribution: HashMap<G::Move, f32>,
+}
+
+impl<G: Base> SearchInfo<G> {
+    /// Shannon entropy of the visit distribution: `ln(n)` when the visits are uniform
+    /// over `n` moves, zero when all of them go to a single move.
+    pub fn entropy(&self) -> f32 {
+        entropy(self.visit_distribution.values())
+    }
+}
+
+/// Probability given to the moves missing from the reference distribution of a KL divergence.
+const KL_EPSILON: f32 = 1e-6;
+
+/// Shannon entropy of a probability distribution, in nats.
+pub fn entropy<'a>(distribution: impl IntoIterator<Item = &'a f32>) -> f32 {
+    -distribution
+        .into_iter()
+        .filter(|p| **p > 0.)
+        .map(|p| p * p.ln())
+        .sum::<f32>()
+}
+
+/// Kullback-Leibler divergence `KL(p || q)` between two distributions over moves, in nats.
+/// Moves missing from `q` are given a tiny probability, so that the divergence stays finite.
+pub fn kl_divergence<M: Hash + Eq>(p: &HashMap<M, f32>, q: &HashMap<M, f32>) -> f32 {
+    p.iter()
+        .filter(|(_, p)| **p > 0.)
+        .map(|(m, p)| {
+            let q = q.get(m).cloned().unwrap_or(0.).max(KL_EPSILON);
+            p * (p / q).ln()
+        })
+        .sum()
+}
+
 /// Interface used to write a policy as an MCTS policy.
 #[async_trait]
 pub trait BaseMCTSPolicy<G: MCTSGame>: Sized {
@@ -122,10 +189,234 @@ pub trait BaseMCTSPolicy<G: MCTSGame>: Sized {
 
     /// Generate playout information starting from board.
     async fn simulate(&self, board: &G) -> Self::PlayoutInfo;
+
+    /// Generic statistics from policy-specific move statistics.
+    fn move_stats(&self, move_info: &Self::MoveInfo) -> MoveStats;
+
+    /// Discount applied to move values when computing the root value.
+    fn discount(&self) -> f32 {
+        1.
+    }
+
+    /// Number of playouts performed concurrently on the same tree.
+    fn parallel_playouts(&self) -> usize {
+        1
+    }
+
+    /// Whether the leaves selected by the parallel playouts are expanded together:
+    /// their transitions are played concurrently instead of one after the other.
+    fn batch_expansions(&self) -> bool {
+        false
+    }
+
+    /// Apply a virtual loss on a move selected by a pending playout.
+    fn add_virtual_loss(&self, _move_info: &mut Self::MoveInfo) {}
+
+    /// Remove a virtual loss applied by `add_virtual_loss`.
+    fn remove_virtual_loss(&self, _move_info: &mut Self::MoveInfo) {}
+
+    /// Progressive widening parameters `(C, alpha)`: a node visited `N` times
+    /// only considers its `ceil(C * N^alpha)` first moves, ordered by `move_priority`.
+    fn progressive_widening(&self) -> Option<(f32, f32)> {
+        None
+    }
+
+    /// Priority of a move for progressive widening, such as its prior. Moves are ordered
+    /// by decreasing priority, so that widening adds moves in a fixed order.
+    fn move_priority(&self, _board: &G, _action: &G::Move, _move_info: &Self::MoveInfo) -> f32 {
+        0.
+    }
+
+    /// Move selected at the root by a tree search, given the playouts budget of the search
+    /// if it has one. `None` selects it with `get_value`, as in the rest of the tree.
+    fn select_root_move(
+        &self,
+        _root: &MCTSNode<G, Self>,
+        _playouts: Option<usize>,
+    ) -> Option<G::Move> {
+        None
+    }
+
+    /// Early stopping settings, when the search can end before using all playouts.
+    fn early_stop(&self) -> Option<settings::EarlyStop> {
+        None
+    }
+
+    /// Called before each search, before the root is evaluated.
+    fn start_search(&mut self) {}
+
+    /// Clears the state kept between searches, before a new game.
+    fn reset(&mut self) {}
 }
 
 use float_ord::FloatOrd;
 
+/// Move of maximal value. Ties are broken on the key of the moves, so that the choice
+/// doesn't depend on the iteration order of the hash maps.
+fn argmax<M: Hash>(values: impl Iterator<Item = (M, f32)>) -> Option<M> {
+    // the key of the best move is only computed once it is tied.
+    let mut best: Option<(M, f32, Option<u64>)> = None;
+    for (action, value) in values {
+        best = match best {
+            None => Some((action, value, None)),
+            Some((best_action, best_value, best_key)) => {
+                match FloatOrd(value).cmp(&FloatOrd(best_value)) {
+                    Ordering::Less => Some((best_action, best_value, best_key)),
+                    Ordering::Greater => Some((action, value, None)),
+                    Ordering::Equal => {
+                        let best_key = best_key.unwrap_or_else(|| move_key(&best_action));
+                        let key = move_key(&action);
+                        if key < best_key {
+                            Some((action, value, Some(key)))
+                        } else {
+                            Some((best_action, best_value, Some(best_key)))
+                        }
+                    }
+                }
+            }
+        };
+    }
+    best.map(|(action, _, _)| action)
+}
+
+/// Key ordering moves deterministically: the hash of the move, which doesn't change
+/// between runs.
+fn move_key<M: Hash>(action: &M) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    action.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Number of nodes in the tree.
+fn count_nodes<G: MCTSGame, MCTS: BaseMCTSPolicy<G>>(root: &MCTSNodeChild<G, MCTS>) -> usize {
+    let mut stack = vec![root.clone()];
+    let mut nodes = 0;
+    while let Some(node) = stack.pop() {
+        nodes += 1;
+        stack.extend(node.read().unwrap().moves.values().cloned());
+    }
+    nodes
+}
+
+/// Simulation of the state, given up if it is still pending at the deadline.
+async fn simulate_until<G: MCTSGame, MCTS: BaseMCTSPolicy<G>>(
+    base_mcts: &MCTS,
+    state: &G,
+    deadline: Option<Instant>,
+) -> Option<MCTS::PlayoutInfo> {
+    match deadline {
+        Some(deadline) => {
+            let deadline = tokio::time::Instant::from_std(deadline);
+            tokio::time::timeout_at(deadline, base_mcts.simulate(state))
+                .await
+                .ok()
+        }
+        None => Some(base_mcts.simulate(state).await),
+    }
+}
+
+/// Copy of a tree, sharing no node with it: later searches on the tree don't alter
+/// the copy.
+pub fn snapshot<G: MCTSGame, MCTS: BaseMCTSPolicy<G>>(
+    root: &MCTSNodeChild<G, MCTS>,
+) -> MCTSNodeChild<G, MCTS> {
+    fn copy<G: MCTSGame, MCTS: BaseMCTSPolicy<G>>(
+        node: &MCTSNodeChild<G, MCTS>,
+        parent: MCTSNodeParent<G, MCTS>,
+    ) -> MCTSNodeChild<G, MCTS> {
+        let node = node.read().unwrap();
+        let copied = Arc::new(RwLock::new(MCTSTreeNode {
+            parent,
+            moves: HashMap::new(),
+            info: MCTSNode {
+                state: node.info.state.clone(),
+                reward: node.info.reward,
+                node: node.info.node,
+                moves: node.info.moves.clone(),
+            },
+            last_access: node.last_access,
+        }));
+        let moves = node
+            .moves
+            .iter()
+            .map(|(m, child)| (*m, copy(child, Some((Arc::downgrade(&copied), *m)))))
+            .collect();
+        copied.write().unwrap().moves = moves;
+        copied
+    }
+    copy(root, None)
+}
+
+/// Search budget of a move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    /// Fixed number of playouts.
+    Playouts(usize),
+    /// Wall-clock time.
+    Time(Duration),
+    /// Whichever of the playouts and the time is exhausted first.
+    Either(usize, Duration),
+}
+
+impl Budget {
+    /// Playouts budget, with an optional time limit.
+    pub fn new(playouts: usize, time: Option<Duration>) -> Self {
+        match time {
+            Some(time) => Budget::Either(playouts, time),
+            None => Budget::Playouts(playouts),
+        }
+    }
+
+    /// Maximum number of playouts, if any.
+    pub fn playouts(&self) -> Option<usize> {
+        match self {
+            Budget::Playouts(n) | Budget::Either(n, _) => Some(*n),
+            Budget::Time(_) => None,
+        }
+    }
+
+    /// Maximum search duration, if any.
+    pub fn time(&self) -> Option<Duration> {
+        match self {
+            Budget::Time(t) | Budget::Either(_, t) => Some(*t),
+            Budget::Playouts(_) => None,
+        }
+    }
+
+    /// Same budget with `playouts` playouts, if it is limited by playouts.
+    pub fn with_playouts(self, playouts: usize) -> Self {
+        match self {
+            Budget::Playouts(_) => Budget::Playouts(playouts),
+            Budget::Either(_, t) => Budget::Either(playouts, t),
+            Budget::Time(t) => Budget::Time(t),
+        }
+    }
+}
+
+/// Playouts of a `(ply threshold, playouts)` schedule at `ply`: the playouts of the
+/// greatest threshold not above `ply`, `default` if there is none.
+pub fn scheduled_playouts(schedule: &[(usize, usize)], default: usize, ply: usize) -> usize {
+    schedule
+        .iter()
+        .filter(|(threshold, _)| *threshold <= ply)
+        .max_by_key(|(threshold, _)| *threshold)
+        .map_or(default, |(_, playouts)| *playouts)
+}
+
+impl From<usize> for Budget {
+    fn from(playouts: usize) -> Self {
+        Budget::Playouts(playouts)
+    }
+}
+
+/// With early stopping, at least `1 / MIN_PLAYOUTS_DIVISOR` of the playouts are performed
+/// before the first check, so that visit counts are meaningful.
+/// Early stopping requires a playouts budget.
+const MIN_PLAYOUTS_DIVISOR: usize = 10;
+
+/// Target of the search diagnostics log messages.
+pub const SEARCH_LOG_TARGET: &str = "mcts::search";
+
 /// Wrapper for MCTS policy.
 pub struct WithMCTSPolicy<G, MCTS>
 where
@@ -134,9 +425,26 @@ where
 {
     /// Structure that handle MCTS business logic.
     pub base_mcts: MCTS,
-    N_PLAYOUTS: usize,
+    budget: Budget,
+    playouts_schedule: Vec<(usize, usize)>,
+    /// Number of moves played since the creation or the last reset.
+    ply: usize,
     /// Root node from the last exploration. Can be taken to gather exploration statistics.
     pub root: Option<MCTSNodeChild<G, MCTS>>,
+    /// Maximal number of nodes in the tree.
+    max_nodes: Option<usize>,
+    /// Number of nodes in the current tree.
+    live_nodes: usize,
+    /// Number of tree searches performed in the current tree.
+    clock: usize,
+    /// Number of tree searches performed by the last search.
+    playouts_done: usize,
+    /// Playouts budget of the current search, if it has one.
+    search_playouts: Option<usize>,
+    /// Whether the search diagnostics are logged after each move.
+    log_search: bool,
+    /// Whether the root has been kept by pondering, to be reused by the next search.
+    reuse_root: bool,
     _g: std::marker::PhantomData<G>,
 }
 
@@ -145,26 +453,50 @@ where
     G: MCTSGame + Clone,
     MCTS: BaseMCTSPolicy<G>,
 {
-    fn select_move(&self, tree_node: &MCTSTreeNode<G, MCTS>, exploration: bool) -> G::Move {
-        *tree_node
-            .info
-            .moves
-            .iter()
-            .map(|(action, move_info)| {
-                (
-                    action,
-                    self.base_mcts.get_value(
-                        &tree_node.info.state,
-                        action,
-                        &tree_node.info.node,
-                        &move_info,
-                        exploration,
-                    ),
-                )
-            })
-            .max_by_key(|x| FloatOrd(x.1))
-            .unwrap()
-            .0
+    /// Moves that can be selected in a node, restricted by progressive widening.
+    fn candidate_moves<'a>(
+        &self,
+        tree_node: &'a MCTSTreeNode<G, MCTS>,
+    ) -> Vec<(&'a G::Move, &'a MCTS::MoveInfo)> {
+        let mut moves: Vec<_> = tree_node.info.moves.iter().collect();
+
+        if let Some((c, alpha)) = self.base_mcts.progressive_widening() {
+            let count: f32 = moves
+                .iter()
+                .map(|(_, move_info)| self.base_mcts.move_stats(move_info).N_a)
+                .sum();
+            let k = ((c * count.powf(alpha)).ceil() as usize).max(1);
+            if k < moves.len() {
+                // ties are broken on the key of the moves rather than on the iteration
+                // order of the hash map, which changes as the tree grows.
+                let state = &tree_node.info.state;
+                moves.sort_by_cached_key(|(action, move_info)| {
+                    let priority = self.base_mcts.move_priority(state, action, move_info);
+                    (Reverse(FloatOrd(priority)), move_key(*action))
+                });
+                moves.truncate(k);
+            }
+        }
+        moves
+    }
+
+    fn select_move(&self, tree_node: &MCTSTreeNode<G, MCTS>, exploration: bool) -> Option<G::Move> {
+        argmax(
+            self.candidate_moves(tree_node)
+                .into_iter()
+                .map(|(action, move_info)| {
+                    (
+                        *action,
+                        self.base_mcts.get_value(
+                            &tree_node.info.state,
+                            action,
+                            &tree_node.info.node,
+                            &move_info,
+                            exploration,
+                        ),
+                    )
+                }),
+        )
     }
 
     fn select(&self, root: MCTSNodeChild<G, MCTS>) -> (Vec<G::Move>, MCTSNodeChild<G, MCTS>) {
@@ -176,12 +508,17 @@ where
         loop {
             let last_node_clone = last_node.clone();
             let last_node_ref = last_node_clone.read().unwrap();
-            if last_node_ref.info.state.is_finished() {
-                /* we're at a leaf node. */
-                return (history, last_node);
+            let action = if last_node_ref.info.state.is_finished() {
+                None
+            } else if history.is_empty() {
+                self.base_mcts
+                    .select_root_move(&last_node_ref.info, self.search_playouts)
+                    .or_else(|| self.select_move(&last_node_ref, true))
             } else {
+                self.select_move(&last_node_ref, true)
+            };
+            if let Some(a) = action {
                 /* play next move */
-                let a = self.select_move(&last_node_ref, true);
                 history.push(a);
 
                 let node_imm = last_node_ref.moves.get(&a);
@@ -195,6 +532,9 @@ where
                 } else {
                     return (history, last_node);
                 }
+            } else {
+                /* we're at a leaf node. */
+                return (history, last_node);
             }
         }
     }
@@ -206,7 +546,64 @@ where
     ) -> MCTSNodeChild<G, MCTS> {
         let mut new_state = tree_node.read().unwrap().info.state.clone();
         let reward = new_state.play(action).await;
+        self.insert_child(tree_node, action, new_state, reward)
+    }
+
+    /// Expand each `(node, action)` of the frontier, playing the transitions
+    /// concurrently with `batch_expansions`. A pair selected several times is expanded once.
+    async fn expand_frontier(
+        &mut self,
+        frontier: &[(MCTSNodeChild<G, MCTS>, G::Move)],
+    ) -> Vec<MCTSNodeChild<G, MCTS>> {
+        let mut unique: Vec<&(MCTSNodeChild<G, MCTS>, G::Move)> = vec![];
+        let mut indices = Vec::with_capacity(frontier.len());
+        for leaf in frontier {
+            let found = unique
+                .iter()
+                .position(|other| Arc::ptr_eq(&leaf.0, &other.0) && leaf.1 == other.1);
+            indices.push(found.unwrap_or_else(|| {
+                unique.push(leaf);
+                unique.len() - 1
+            }));
+        }
+
+        let transitions = if self.base_mcts.batch_expansions() {
+            join_all(unique.iter().map(|(node, action)| {
+                let mut new_state = node.read().unwrap().info.state.clone();
+                async move {
+                    let reward = new_state.play(action).await;
+                    (new_state, reward)
+                }
+            }))
+            .await
+        } else {
+            let mut transitions = Vec::with_capacity(unique.len());
+            for (node, action) in unique.iter() {
+                let mut new_state = node.read().unwrap().info.state.clone();
+                let reward = new_state.play(action).await;
+                transitions.push((new_state, reward));
+            }
+            transitions
+        };
+
+        let children: Vec<_> = unique
+            .iter()
+            .zip(transitions)
+            .map(|((node, action), (new_state, reward))| {
+                self.insert_child(node.clone(), action, new_state, reward)
+            })
+            .collect();
+        indices.into_iter().map(|i| children[i].clone()).collect()
+    }
 
+    /// Add the node reached by playing `action` from `tree_node`.
+    fn insert_child(
+        &mut self,
+        tree_node: MCTSNodeChild<G, MCTS>,
+        action: &G::Move,
+        new_state: G,
+        reward: f32,
+    ) -> MCTSNodeChild<G, MCTS> {
         let new_node = self.base_mcts.default_node(&new_state);
 
         let moves_info = HashMap::from_iter(
@@ -216,7 +613,7 @@ where
                 .map(|m| (*m, self.base_mcts.default_move(&new_state, &m))),
         );
 
-        tree_node.write().unwrap().moves.insert(
+        let replaced = tree_node.write().unwrap().moves.insert(
             *action,
             Arc::new(RwLock::new(MCTSTreeNode {
                 parent: Some((Arc::downgrade(&tree_node), *action)),
@@ -227,44 +624,406 @@ where
                     node: new_node,
                     state: new_state,
                 },
+                last_access: self.clock,
             })),
         );
+        // terminal nodes are never expanded, so they are replaced when selected again.
+        if replaced.is_none() {
+            self.live_nodes += 1;
+        }
         tree_node.read().unwrap().moves.get(action).unwrap().clone()
     }
 
-    async fn tree_search(&mut self, root: MCTSNodeChild<G, MCTS>) {
+    /// Performs one tree search, returning the number of playouts completed before the deadline.
+    async fn tree_search(
+        &mut self,
+        root: MCTSNodeChild<G, MCTS>,
+        deadline: Option<Instant>,
+    ) -> usize {
         /* SELECT */
-        let (history, last_node) = self.select(root);
+        let (history, last_node) = self.select(root.clone());
         /* EXPAND */
-        let created_node = self.expand(last_node, history.last().unwrap()).await;
+        let action = match history.last() {
+            Some(action) => *action,
+            // the root is terminal: there is nothing to explore.
+            None => return 0,
+        };
+        let created_node = self.expand(last_node, &action).await;
+        self.touch(&root, &history);
         /* SIMULATE */
         let state = created_node.read().unwrap().info.state.clone();
-        let playout = self.base_mcts.simulate(&state).await;
+        let playout = match simulate_until(&self.base_mcts, &state, deadline).await {
+            Some(playout) => playout,
+            None => {
+                self.abandon(&created_node);
+                return 0;
+            }
+        };
         /* BACKUP */
         self.base_mcts
             .backpropagate(created_node, &history, playout);
+        1
+    }
+
+    /// Removes a node whose simulation was abandoned at the deadline. As for pruned nodes,
+    /// the move statistics remain in the parent and the node is expanded again when needed.
+    fn abandon(&mut self, node: &MCTSNodeChild<G, MCTS>) {
+        let parent = node.read().unwrap().parent.clone();
+        if let Some((parent, action)) = parent {
+            if let Some(parent) = parent.upgrade() {
+                let mut parent = parent.write().unwrap();
+                if parent
+                    .moves
+                    .get(&action)
+                    .map_or(false, |child| Arc::ptr_eq(child, node))
+                {
+                    parent.moves.remove(&action);
+                    self.live_nodes -= 1;
+                }
+            }
+        }
+    }
+
+    /// Marks the nodes along the path as accessed by the current search.
+    fn touch(&self, root: &MCTSNodeChild<G, MCTS>, history: &[G::Move]) {
+        let mut node = root.clone();
+        root.write().unwrap().last_access = self.clock;
+        for action in history {
+            let next = node.read().unwrap().moves.get(action).cloned();
+            match next {
+                Some(child) => {
+                    child.write().unwrap().last_access = self.clock;
+                    node = child;
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Prunes the least recently used subtrees until the tree holds three quarters of
+    /// `max_nodes`, so that pruning doesn't happen after every search. The root is kept and
+    /// the statistics of pruned moves remain in their parent: they can still be selected and
+    /// are expanded again when needed.
+    fn prune(&mut self, root: &MCTSNodeChild<G, MCTS>, max_nodes: usize) {
+        let target = max_nodes - max_nodes / 4;
+
+        // depth-first listing: parents are listed before their children.
+        let mut nodes = vec![];
+        let mut stack = vec![(root.clone(), None, 0)];
+        while let Some((node, parent, depth)) = stack.pop() {
+            let index = nodes.len();
+            let last_access = {
+                let tree_node = node.read().unwrap();
+                for (action, child) in tree_node.moves.iter() {
+                    stack.push((child.clone(), Some((index, *action)), depth + 1));
+                }
+                tree_node.last_access
+            };
+            nodes.push((node, parent, last_access, depth));
+        }
+
+        let mut sizes = vec![1; nodes.len()];
+        for i in (1..nodes.len()).rev() {
+            if let Some((parent, _)) = nodes[i].1 {
+                sizes[parent] += sizes[i];
+            }
+        }
+        self.live_nodes = nodes.len();
+
+        // the nodes of a path are accessed together: for the same access, the
+        // shallowest node is pruned first, along with its subtree.
+        let mut order: Vec<usize> = (1..nodes.len()).collect();
+        order.sort_by_key(|i| (nodes[*i].2, nodes[*i].3));
+        let mut removed = vec![false; nodes.len()];
+
+        for i in order {
+            if self.live_nodes <= target {
+                break;
+            }
+            let mut ancestors = vec![];
+            let mut parent = nodes[i].1;
+            while let Some((p, _)) = parent {
+                ancestors.push(p);
+                parent = nodes[p].1;
+            }
+            if ancestors.iter().any(|p| removed[*p]) {
+                continue;
+            }
+
+            let (parent, action) = nodes[i].1.unwrap();
+            nodes[parent].0.write().unwrap().moves.remove(&action);
+            removed[i] = true;
+            self.live_nodes -= sizes[i];
+            for p in ancestors {
+                sizes[p] -= sizes[i];
+            }
+        }
+    }
+
+    fn virtual_loss(&self, root: &MCTSNodeChild<G, MCTS>, history: &[G::Move], add: bool) {
+        let mut node = root.clone();
+        for action in history {
+            let next = {
+                let mut tree_node = node.write().unwrap();
+                let move_info = tree_node.info.moves.get_mut(action).unwrap();
+                if add {
+                    self.base_mcts.add_virtual_loss(move_info);
+                } else {
+                    self.base_mcts.remove_virtual_loss(move_info);
+                }
+                tree_node.moves.get(action).cloned()
+            };
+            match next {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+    }
+
+    /// Whether the most visited root move leads the others by more than `lead` visits.
+    fn decided(&self, root: &MCTSNodeChild<G, MCTS>, lead: usize) -> bool {
+        let root = root.read().unwrap();
+        let mut visits: Vec<f32> = root
+            .info
+            .moves
+            .values()
+            .map(|move_info| self.base_mcts.move_stats(move_info).N_a)
+            .collect();
+        visits.sort_by_key(|n| Reverse(FloatOrd(*n)));
+        match visits.as_slice() {
+            [first, second, ..] => first - second > lead as f32,
+            _ => true,
+        }
+    }
+
+    /// Perform `n_workers` tree searches sharing the same tree: selections are
+    /// made one after the other, using virtual loss to spread the workers over
+    /// the tree, then the selected leaves are expanded and all simulations are awaited
+    /// concurrently. All the selections are made before expanding, so that no worker
+    /// descends into a node that another worker created but that is not evaluated yet.
+    /// With `batch_expansions`, the expansions are also awaited concurrently.
+    ///
+    /// Returns the number of playouts completed before the deadline.
+    async fn parallel_tree_search(
+        &mut self,
+        root: MCTSNodeChild<G, MCTS>,
+        n_workers: usize,
+        deadline: Option<Instant>,
+    ) -> usize {
+        /* SELECT */
+        let mut histories = Vec::with_capacity(n_workers);
+        let mut frontier = Vec::with_capacity(n_workers);
+        for _ in 0..n_workers {
+            let (history, last_node) = self.select(root.clone());
+            let action = match history.last() {
+                Some(action) => *action,
+                None => continue,
+            };
+            self.virtual_loss(&root, &history, true);
+            histories.push(history);
+            frontier.push((last_node, action));
+        }
+        /* EXPAND */
+        let created_nodes = self.expand_frontier(&frontier).await;
+        let paths: Vec<_> = histories.into_iter().zip(created_nodes).collect();
+        for (history, _) in paths.iter() {
+            self.touch(&root, history);
+        }
+        /* SIMULATE */
+        let states: Vec<G> = paths
+            .iter()
+            .map(|(_, node)| node.read().unwrap().info.state.clone())
+            .collect();
+        let base_mcts = &self.base_mcts;
+        let playouts = join_all(
+            states
+                .iter()
+                .map(|state| simulate_until(base_mcts, state, deadline)),
+        )
+        .await;
+        /* BACKUP */
+        let mut completed = 0;
+        for ((history, created_node), playout) in paths.into_iter().zip(playouts) {
+            self.virtual_loss(&root, &history, false);
+            match playout {
+                Some(playout) => {
+                    self.base_mcts
+                        .backpropagate(created_node, &history, playout);
+                    completed += 1;
+                }
+                None => self.abandon(&created_node),
+            }
+        }
+        completed
     }
 
     ///
-    /// Instanciate a new MCTS policy, given a BaseMCTS instance.
+    /// Instanciate a new MCTS policy, given a BaseMCTS instance and a search budget.
     ///
-    pub fn new(p: MCTS, N_PLAYOUTS: usize) -> Self {
+    pub fn new(p: MCTS, budget: impl Into<Budget>) -> Self {
         WithMCTSPolicy {
             base_mcts: p,
-            N_PLAYOUTS,
+            budget: budget.into(),
+            playouts_schedule: vec![],
+            ply: 0,
             root: None,
+            max_nodes: None,
+            live_nodes: 0,
+            clock: 0,
+            playouts_done: 0,
+            search_playouts: None,
+            log_search: false,
+            reuse_root: false,
             _g: PhantomData,
         }
     }
-}
 
-#[async_trait]
-impl<G, MCTS> MultiplayerPolicy<G> for WithMCTSPolicy<G, MCTS>
-where
-    G: MCTSGame,
-    MCTS: BaseMCTSPolicy<G> + Sync + Send,
-{
-    async fn play(&mut self, board: &G) -> G::Move {
+    ///
+    /// Varies the playouts with the number of moves played by the policy: from each
+    /// ply threshold of the `(ply threshold, playouts)` schedule, the search performs the
+    /// given playouts. Before the first threshold, the budget playouts are used.
+    ///
+    pub fn with_playouts_schedule(mut self, schedule: Vec<(usize, usize)>) -> Self {
+        self.playouts_schedule = schedule;
+        self
+    }
+
+    ///
+    /// Bounds the number of nodes of the search tree: beyond `max_nodes`, the least
+    /// recently visited subtrees are pruned. `None` lets the tree grow unbounded.
+    ///
+    pub fn with_node_limit(mut self, max_nodes: Option<usize>) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    ///
+    /// Logs, after each move, the chosen move, the root value, the three most visited
+    /// moves with their statistics and the search time. Messages are emitted at debug
+    /// level with the `SEARCH_LOG_TARGET` target.
+    ///
+    pub fn with_search_log(mut self, log_search: bool) -> Self {
+        self.log_search = log_search;
+        self
+    }
+
+    ///
+    /// Number of nodes in the tree of the current or last exploration.
+    ///
+    pub fn live_nodes(&self) -> usize {
+        self.live_nodes
+    }
+
+    ///
+    /// Number of tree searches performed by the last search, which may be less than the
+    /// budget with early stopping or a time limit. The evaluation of a new root isn't counted.
+    ///
+    pub fn playouts_done(&self) -> usize {
+        self.playouts_done
+    }
+
+    ///
+    /// Statistics of the root node from the last exploration.
+    ///
+    /// # Panics
+    /// Panics if no exploration has been performed, or if the root has been taken.
+    ///
+    pub fn search_info(&self) -> SearchInfo<G> {
+        let root = self.root.as_ref().unwrap().read().unwrap();
+        let moves: HashMap<G::Move, MoveStats> = HashMap::from_iter(
+            root.info
+                .moves
+                .iter()
+                .map(|(m, v)| (*m, self.base_mcts.move_stats(v))),
+        );
+
+        let count: f32 = moves.values().map(|v| v.N_a).sum();
+        let discount = self.base_mcts.discount();
+        let (root_value, visit_distribution) = if count > 0. {
+            (
+                moves
+                    .values()
+                    .map(|v| (v.reward + discount * v.Q) * v.N_a / count)
+                    .sum(),
+                HashMap::from_iter(moves.iter().map(|(m, v)| (*m, v.N_a / count))),
+            )
+        } else {
+            (0., HashMap::from_iter(moves.keys().map(|m| (*m, 0.))))
+        };
+
+        SearchInfo {
+            root_value,
+            count,
+            moves,
+            visit_distribution,
+        }
+    }
+
+    ///
+    /// Principal variation from the last exploration: starting from the root, the most
+    /// visited move is followed until reaching a leaf or `max_len` moves.
+    /// Each move is given with its value, as reported by `move_stats`.
+    ///
+    pub fn principal_variation(&self, max_len: usize) -> Vec<(G::Move, f32)> {
+        let mut pv = vec![];
+        let mut node = match self.root.as_ref() {
+            Some(root) => root.clone(),
+            None => return pv,
+        };
+
+        while pv.len() < max_len {
+            let next = {
+                let tree_node = node.read().unwrap();
+                let best = argmax(
+                    tree_node
+                        .info
+                        .moves
+                        .iter()
+                        .map(|(m, v)| (*m, self.base_mcts.move_stats(v).N_a))
+                        .filter(|(_, visits)| *visits > 0.),
+                );
+
+                best.and_then(|action| {
+                    let stats = self.base_mcts.move_stats(&tree_node.info.moves[&action]);
+                    pv.push((action, stats.Q));
+                    tree_node.moves.get(&action).cloned()
+                })
+            };
+            match next {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        pv
+    }
+
+    /// Logs the statistics of the last search, on a single line.
+    fn log_search_info(&self, action: &G::Move, elapsed: Duration) {
+        let search_info = self.search_info();
+        let mut moves: Vec<_> = search_info.moves.iter().collect();
+        moves.sort_by_key(|(_, stats)| Reverse(FloatOrd(stats.N_a)));
+        let top: Vec<String> = moves
+            .iter()
+            .take(3)
+            .map(|(m, stats)| {
+                format!(
+                    "{:?} (N_a: {}, Q: {:.3}, pi: {:.3})",
+                    m, stats.N_a, stats.Q, stats.pi
+                )
+            })
+            .collect();
+        log::debug!(
+            target: SEARCH_LOG_TARGET,
+            "move: {:?}, value: {:.3}, top: [{}], time: {:?}",
+            action,
+            search_info.root_value,
+            top.join(", "),
+            elapsed
+        );
+    }
+
+    /// New tree rooted at the board, evaluated once.
+    async fn new_root(&mut self, board: &G) -> MCTSNodeChild<G, MCTS> {
         let root = Arc::new(RwLock::new(MCTSTreeNode {
             parent: None,
             info: MCTSNode {
@@ -279,19 +1038,692 @@ where
                 ),
             },
             moves: HashMap::new(),
+            last_access: 0,
         }));
+        self.live_nodes = 1;
+        self.clock = 0;
 
         let playout = self.base_mcts.simulate(board).await;
         self.base_mcts.backpropagate(root.clone(), &[], playout);
 
-        for _ in 0..self.N_PLAYOUTS {
-            //println!("####> {} | {:?}", i, root);
-            self.tree_search(root.clone()).await
+        root
+    }
+
+    /// Root kept by pondering, if it holds the given board.
+    ///
+    /// The root is not re-evaluated: root exploration noise isn't sampled again.
+    fn take_reused_root(&mut self, board: &G) -> Option<MCTSNodeChild<G, MCTS>> {
+        if !std::mem::replace(&mut self.reuse_root, false) {
+            return None;
+        }
+        let root = self.root.take()?;
+        let matches = {
+            let root = root.read().unwrap();
+            root.info.state.turn() == board.turn()
+                && root.info.state.possible_moves() == board.possible_moves()
+        };
+        if matches {
+            self.live_nodes = count_nodes(&root);
+            Some(root)
+        } else {
+            None
+        }
+    }
+
+    /// Runs tree searches from the root until the budget is exhausted,
+    /// or until `stop` is set.
+    async fn search(
+        &mut self,
+        root: &MCTSNodeChild<G, MCTS>,
+        budget: Budget,
+        stop: Option<&AtomicBool>,
+    ) {
+        let n_workers = self.base_mcts.parallel_playouts();
+        // the deadline is checked between searches, and the simulations still pending
+        // when it is reached are abandoned: a slow evaluator doesn't delay the move.
+        let deadline = budget.time().map(|time| Instant::now() + time);
+        let mut remaining = budget.playouts();
+        self.playouts_done = 0;
+        self.search_playouts = remaining;
+
+        let early_stop = self.base_mcts.early_stop().filter(|_| remaining.is_some());
+        let mut next_check = remaining.unwrap_or(0);
+        if let Some(early_stop) = early_stop {
+            let first_check = early_stop
+                .check_every
+                .max(next_check / MIN_PLAYOUTS_DIVISOR);
+            next_check = next_check.saturating_sub(first_check);
         }
 
+        while remaining != Some(0) {
+            let n = remaining.map_or(n_workers, |r| n_workers.min(r));
+            self.clock += 1;
+            let completed = if n > 1 {
+                self.parallel_tree_search(root.clone(), n, deadline).await
+            } else {
+                self.tree_search(root.clone(), deadline).await
+            };
+            remaining = remaining.map(|r| r - n);
+            self.playouts_done += completed;
+            debug_assert!(
+                budget.playouts().map_or(true, |p| self.playouts_done <= p),
+                "{} playouts done over a budget of {:?}",
+                self.playouts_done,
+                budget
+            );
+
+            if let Some(max_nodes) = self.max_nodes {
+                if self.live_nodes > max_nodes {
+                    self.prune(root, max_nodes);
+                }
+            }
+
+            if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                break;
+            }
+
+            if stop.map_or(false, |stop| stop.load(atomic::Ordering::Relaxed)) {
+                break;
+            }
+
+            if let (Some(early_stop), Some(remaining)) = (early_stop, remaining) {
+                if remaining <= next_check {
+                    if self.decided(root, remaining + early_stop.margin) {
+                        break;
+                    }
+                    next_check = remaining.saturating_sub(early_stop.check_every);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Searches the best move from the board.
+    ///
+    /// Fails without searching if the game is finished, as there is no move to choose.
+    /// The search diagnostics are logged if enabled.
+    ///
+    pub async fn try_play(&mut self, board: &G) -> Result<G::Move, StrError> {
+        if board.is_finished() || board.possible_moves().is_empty() {
+            return Err(StrError(
+                "The game is finished: no move to play.".to_owned(),
+            ));
+        }
+        let start =
+            if self.log_search && log::log_enabled!(target: SEARCH_LOG_TARGET, log::Level::Debug) {
+                Some(Instant::now())
+            } else {
+                None
+            };
+
+        self.base_mcts.start_search();
+        let root = match self.take_reused_root(board) {
+            Some(root) => root,
+            None => self.new_root(board).await,
+        };
+
+        let budget = match self.budget.playouts() {
+            Some(playouts) => self.budget.with_playouts(scheduled_playouts(
+                &self.playouts_schedule,
+                playouts,
+                self.ply,
+            )),
+            None => self.budget,
+        };
+        self.ply += 1;
+
+        self.search(&root, budget, None).await;
+
         let chosen_move = self.select_move(&root.read().unwrap(), false);
         self.root = Some(root);
 
-        chosen_move
+        if let (Some(start), Some(action)) = (start, chosen_move.as_ref()) {
+            self.log_search_info(action, start.elapsed());
+        }
+        chosen_move.ok_or_else(|| StrError("No move could be selected.".to_owned()))
+    }
+
+    ///
+    /// Searches the board while the opponent is to move, keeping the tree so that
+    /// `advance_root` can reuse it once the opponent has played. Successive calls on the
+    /// same board extend the same tree.
+    ///
+    /// The search stops early once `stop` is set, for instance when the opponent's move
+    /// arrives.
+    ///
+    pub async fn ponder(&mut self, board: &G, stop: &AtomicBool) {
+        if board.is_finished() || board.possible_moves().is_empty() {
+            return;
+        }
+        self.base_mcts.start_search();
+        let root = match self.take_reused_root(board) {
+            Some(root) => root,
+            None => self.new_root(board).await,
+        };
+        self.search(&root, self.budget, Some(stop)).await;
+        self.root = Some(root);
+        self.reuse_root = true;
+    }
+
+    ///
+    /// Moves the root to its child after the given move, so that the next search starts
+    /// from the statistics gathered so far. Returns whether the move had been explored;
+    /// otherwise the tree is dropped and the next search starts from scratch.
+    ///
+    pub fn advance_root(&mut self, action: &G::Move) -> bool {
+        let child = self.root.take().and_then(|root| {
+            let root = root.read().unwrap();
+            root.moves.get(action).cloned()
+        });
+        match child {
+            Some(child) => {
+                child.write().unwrap().parent = None;
+                self.live_nodes = count_nodes(&child);
+                self.root = Some(child);
+                self.reuse_root = true;
+                true
+            }
+            None => {
+                self.reuse_root = false;
+                false
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<G, MCTS> MultiplayerPolicy<G> for WithMCTSPolicy<G, MCTS>
+where
+    G: MCTSGame,
+    MCTS: BaseMCTSPolicy<G> + Sync + Send,
+{
+    async fn play(&mut self, board: &G) -> Option<G::Move> {
+        self.try_play(board).await.ok()
+    }
+
+    async fn play_with_stats(&mut self, board: &G) -> Option<(G::Move, PlayStats<G>)> {
+        let action = self.play(board).await?;
+        let search_info = self.search_info();
+        let stats = PlayStats {
+            value: Some(search_info.root_value),
+            visit_distribution: Some(search_info.visit_distribution),
+        };
+        Some((action, stats))
+    }
+
+    fn reset(&mut self) {
+        self.root = None;
+        self.reuse_root = false;
+        self.ply = 0;
+        self.base_mcts.reset();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::uct::{Heuristic, LeafEval, UCTMoveInfo, UCTNodeInfo, UCTPolicy};
+    use super::{
+        count_nodes, entropy, kl_divergence, snapshot, BaseMCTSPolicy, Budget, SearchInfo,
+        WithMCTSPolicy,
+    };
+    use crate::game::breakthrough::{BTCapture, Breakthrough, BreakthroughBuilder, Color};
+    use crate::game::nim::{Nim, NimBuilder};
+    use crate::game::tictactoe::{TicTacToe, TicTacToeBuilder};
+    use crate::game::{self, Base, GameBuilder, MoveCode, Playable, SingleWinner};
+    use crate::policies::combinator::Verified;
+    use crate::policies::flat::RandomPolicy;
+    use crate::policies::{MultiplayerPolicy, MultiplayerPolicyBuilder};
+    use crate::settings;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    pub(crate) fn uct_config(playouts: usize) -> settings::UCT {
+        settings::UCT {
+            uct_weight: 0.4,
+            playouts,
+            rollout: settings::RolloutKind::Random,
+            progressive_widening: None,
+            early_stop: None,
+            time_budget_ms: None,
+            log_search: false,
+        }
+    }
+
+    #[test]
+    fn test_visit_entropy() {
+        let info = |visits: &[f32]| SearchInfo::<TicTacToe> {
+            root_value: 0.,
+            count: visits.iter().sum(),
+            moves: Default::default(),
+            visit_distribution: visits
+                .iter()
+                .enumerate()
+                .map(|(i, v)| ((i, 0), *v))
+                .collect(),
+        };
+
+        let uniform = info(&[0.25; 4]);
+        assert!((uniform.entropy() - 4f32.ln()).abs() < 1e-5);
+        let skewed = info(&[0.4, 0.3, 0.2, 0.1]);
+        assert!(skewed.entropy() < uniform.entropy());
+        let peaked = info(&[1., 0., 0., 0.]);
+        assert!(peaked.entropy().abs() < 1e-6);
+        assert!(entropy(&[0.999, 0.001]) < 0.01);
+
+        let p = &uniform.visit_distribution;
+        assert!(kl_divergence(p, p).abs() < 1e-6);
+        assert!(kl_divergence(&peaked.visit_distribution, p) > 1.);
+    }
+
+    #[tokio::test]
+    async fn test_search_info_visit_distribution() {
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let uct = uct_config(100);
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        policy.play(&state).await;
+
+        let search_info = policy.search_info();
+        let total: f32 = search_info.visit_distribution.values().sum();
+        assert!((total - 1.).abs() < 1e-4);
+        assert!((search_info.count - 100.).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_playouts_done() {
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let uct = uct_config(100);
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        assert_eq!(policy.playouts_done(), 0);
+        policy.play(&state).await;
+        assert_eq!(policy.playouts_done(), 100);
+
+        // black wins with (2, 0).
+        let mut state = TicTacToeBuilder::default().create(Color::Black).await;
+        for action in &[(0, 0), (1, 1), (1, 0), (2, 2)] {
+            state.play(action).await;
+        }
+        let uct = settings::UCT {
+            playouts: 2000,
+            early_stop: Some(settings::EarlyStop {
+                check_every: 50,
+                margin: 0,
+            }),
+            ..uct
+        };
+        let mut policy: UCTPolicy<TicTacToe> = uct.create(Color::Black);
+        policy.play(&state).await;
+        assert!(policy.playouts_done() < 2000);
+        let count = policy.search_info().count;
+        assert!((count - policy.playouts_done() as f32).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_play_finished_game() {
+        let mut state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        while !state.is_finished() {
+            state.random_move().await;
+        }
+        let uct = uct_config(100);
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        assert!(policy.try_play(&state).await.is_err());
+        assert!(policy.root.is_none());
+        assert!(policy.play(&state).await.is_none());
+        assert!(policy.play_with_stats(&state).await.is_none());
+        assert!(policy.root.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_play_with_stats() {
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let uct = uct_config(100);
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        let (action, stats) = policy.play_with_stats(&state).await.unwrap();
+        assert!(state.possible_moves().contains(&action));
+        assert!(stats.value.is_some());
+        let total: f32 = stats.visit_distribution.unwrap().values().sum();
+        assert!((total - 1.).abs() < 1e-4);
+
+        let mut random = RandomPolicy {};
+        let (action, stats) = random.play_with_stats(&state).await.unwrap();
+        assert!(state.possible_moves().contains(&action));
+        assert!(stats.value.is_none());
+        assert!(stats.visit_distribution.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_principal_variation() {
+        let uct = uct_config(1000);
+        let mut policy: UCTPolicy<TicTacToe> = uct.create(Color::Black);
+        assert!(policy.principal_variation(9).is_empty());
+
+        // black wins with (2, 0), or loses with (2, 1) played by white.
+        let mut state = TicTacToeBuilder::default().create(Color::Black).await;
+        for action in &[(0, 0), (0, 1), (1, 0), (1, 1)] {
+            state.play(action).await;
+        }
+        policy.play(&state).await;
+
+        let pv = policy.principal_variation(9);
+        assert!(!pv.is_empty());
+        for (action, _) in pv.iter() {
+            state.play(action).await;
+        }
+        assert!(state.is_finished());
+        assert_ne!(state.winner(), Some(Color::White));
+    }
+
+    #[tokio::test]
+    async fn test_progressive_widening() {
+        // a single heap of 1000 objects: 1000 possible moves.
+        let state = NimBuilder {
+            initial_heaps: vec![1000],
+        }
+        .create(Color::Black)
+        .await;
+
+        for playouts in &[100, 400, 1600] {
+            let uct = settings::UCT {
+                progressive_widening: Some((1., 0.5)),
+                ..uct_config(*playouts)
+            };
+            let mut policy: UCTPolicy<Nim> = uct.create(Color::Black);
+            policy.play(&state).await;
+
+            let children = policy.root.as_ref().unwrap().read().unwrap().moves.len();
+            assert!(children <= (*playouts as f32).sqrt().ceil() as usize);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_early_stop() {
+        // black wins with (2, 0).
+        let mut state = TicTacToeBuilder::default().create(Color::Black).await;
+        for action in &[(0, 0), (1, 1), (1, 0), (2, 2)] {
+            state.play(action).await;
+        }
+
+        let uct = settings::UCT {
+            early_stop: Some(settings::EarlyStop {
+                check_every: 50,
+                margin: 0,
+            }),
+            ..uct_config(2000)
+        };
+        let mut policy: UCTPolicy<TicTacToe> = uct.create(Color::Black);
+        let action = policy.play(&state).await.unwrap();
+        assert_eq!(action, (2, 0));
+
+        let count = policy.search_info().count;
+        assert!(count >= 200.);
+        assert!(count < 1500.);
+    }
+
+    #[tokio::test]
+    async fn test_time_budget() {
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let uct = uct_config(0);
+        let mut policy: UCTPolicy<Breakthrough> = WithMCTSPolicy::new(
+            MultiplayerPolicyBuilder::<Breakthrough>::create(&uct, Color::Black).base_mcts,
+            Budget::Time(Duration::from_millis(50)),
+        );
+
+        let start = Instant::now();
+        let action = policy.play(&state).await.unwrap();
+        let elapsed = start.elapsed();
+        assert!(state.possible_moves().contains(&action));
+        assert!(elapsed >= Duration::from_millis(50));
+        // generous slack: the overshoot is one tree search, but the test may run on a loaded machine.
+        assert!(elapsed < Duration::from_secs(2));
+        assert!(policy.search_info().count > 0.);
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_tie_break() {
+        // two empty cells left: (0, 2) and (2, 2).
+        let mut state = TicTacToeBuilder::default().create(Color::Black).await;
+        for action in &[(0, 0), (1, 0), (2, 0), (1, 1), (0, 1), (2, 1), (1, 2)] {
+            state.play(action).await;
+        }
+        // without playouts, both root moves are tied.
+        let uct = uct_config(0);
+
+        let mut actions = vec![];
+        for _ in 0..20 {
+            let mut policy: UCTPolicy<TicTacToe> = uct.create(Color::Black);
+            actions.push(policy.play(&state).await.unwrap());
+        }
+        assert!(actions[0] == (0, 2) || actions[0] == (2, 2));
+        assert!(actions.iter().all(|action| *action == actions[0]));
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_beats_rollout() {
+        let uct = uct_config(50);
+        let heuristic: Heuristic<Breakthrough> =
+            Arc::new(|state: &Breakthrough, pov: Color| state.material_heuristic(pov));
+        let heuristic_uct = Verified::new(uct.with_leaf_eval(LeafEval::Heuristic(heuristic)));
+        let uct = Verified::new(uct);
+        let players = [Color::Black, Color::White];
+
+        let n_games = 40;
+        let mut heuristic_wins = 0;
+        for i in 0..n_games {
+            let heuristic_color = players[i % 2];
+            let mut board = BreakthroughBuilder { size: 5 }
+                .create(players[(i / 2) % 2])
+                .await;
+
+            let p_heuristic = Box::new(heuristic_uct.create(heuristic_color));
+            let p_rollout = Box::new(uct.create(heuristic_color.adv()));
+            if heuristic_color == Color::Black {
+                game::simulate(
+                    p_heuristic,
+                    p_rollout,
+                    &mut board,
+                    game::DrawRules::default(),
+                )
+                .await;
+            } else {
+                game::simulate(
+                    p_rollout,
+                    p_heuristic,
+                    &mut board,
+                    game::DrawRules::default(),
+                )
+                .await;
+            }
+
+            if board.winner() == Some(heuristic_color) {
+                heuristic_wins += 1;
+            }
+        }
+        assert!(
+            heuristic_wins > n_games / 2,
+            "Heuristic UCT won {}/{}",
+            heuristic_wins,
+            n_games
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capture_prior() {
+        let uct = uct_config(10);
+        let prior = vec![(1, 0.5)].into_iter().collect();
+        let prior_uct = uct
+            .with_leaf_eval(LeafEval::Rollout)
+            .with_prior::<BTCapture>(prior);
+
+        // black can capture the white pawn with its pawn on b2.
+        let state = BreakthroughBuilder { size: 5 }
+            .create_from("WWWWW/5/2W2/1B3/BBBBB", Color::Black)
+            .await
+            .unwrap();
+        for color in &[Color::Black, Color::White] {
+            let policy: UCTPolicy<Breakthrough> = prior_uct.create(*color);
+            let node = UCTNodeInfo { count: 1. };
+            let initial_value = |action| {
+                let move_info: UCTMoveInfo = policy.base_mcts.default_move(&state, action);
+                policy
+                    .base_mcts
+                    .get_value(&state, action, &node, &move_info, true)
+            };
+
+            let (captures, others): (Vec<_>, Vec<_>) = state
+                .possible_moves()
+                .into_iter()
+                .partition(|action| BTCapture::code(&state, action) == 1);
+            assert_eq!(captures.len(), 1);
+            for other in others.iter() {
+                assert!(initial_value(&captures[0]) > initial_value(other));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_node_limit() {
+        // black wins with (2, 0).
+        let mut state = TicTacToeBuilder::default().create(Color::Black).await;
+        for action in &[(0, 0), (1, 1), (1, 0), (2, 2)] {
+            state.play(action).await;
+        }
+
+        let uct = uct_config(2000);
+        let policy: UCTPolicy<TicTacToe> = uct.create(Color::Black);
+        let mut policy = policy.with_node_limit(Some(40));
+        assert_eq!(policy.play(&state).await, Some((2, 0)));
+
+        let mut stack = vec![policy.root.clone().unwrap()];
+        let mut nodes = 0;
+        while let Some(node) = stack.pop() {
+            nodes += 1;
+            stack.extend(node.read().unwrap().moves.values().cloned());
+        }
+        assert_eq!(nodes, policy.live_nodes());
+        assert!(nodes <= 40);
+        // the statistics of the pruned moves are kept.
+        assert!((policy.search_info().count - 2000.).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_ponder() {
+        let uct = uct_config(300);
+        let mut state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let opening = state.possible_moves()[0];
+        state.play(&opening).await;
+
+        // white is to move: black ponders on the predicted reply.
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        policy.ponder(&state, &AtomicBool::new(false)).await;
+        let (predicted, _) = policy.principal_variation(1)[0];
+        assert!(policy.advance_root(&predicted));
+        state.play(&predicted).await;
+        policy.play(&state).await;
+        let warm_count = policy.search_info().count;
+
+        let mut cold: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        cold.play(&state).await;
+        let cold_count = cold.search_info().count;
+
+        assert!((cold_count - 300.).abs() < 1e-4);
+        assert!(warm_count > cold_count + 1.);
+
+        // an unexplored reply drops the tree.
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        assert!(!policy.advance_root(&predicted));
+        assert!(policy.root.is_none());
+
+        // pondering stops after a single search once stopped.
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        policy.ponder(&state, &AtomicBool::new(true)).await;
+        assert_eq!(policy.playouts_done(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let uct = uct_config(100);
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        policy.ponder(&state, &AtomicBool::new(false)).await;
+        let root = policy.root.clone().unwrap();
+        let copy = snapshot(&root);
+        let nodes = count_nodes(&root);
+        assert_eq!(count_nodes(&copy), nodes);
+
+        // the copy isn't affected by further searches on the tree.
+        policy.ponder(&state, &AtomicBool::new(false)).await;
+        assert!(count_nodes(&root) > nodes);
+        assert_eq!(count_nodes(&copy), nodes);
+    }
+
+    /// Logger capturing the search diagnostics.
+    struct SearchLogger;
+
+    lazy_static::lazy_static! {
+        static ref SEARCH_LOGS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(vec![]);
+    }
+
+    impl log::Log for SearchLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.target() == super::SEARCH_LOG_TARGET
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                SEARCH_LOGS
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}", record.args()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static SEARCH_LOGGER: SearchLogger = SearchLogger;
+
+    #[tokio::test]
+    async fn test_search_log() {
+        log::set_logger(&SEARCH_LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let mut state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let uct = settings::UCT {
+            log_search: true,
+            ..uct_config(50)
+        };
+        let mut policy: UCTPolicy<Breakthrough> = uct.create(Color::Black);
+        let mut opponent: UCTPolicy<Breakthrough> = settings::UCT {
+            log_search: false,
+            ..uct
+        }
+        .create(Color::White);
+
+        let mut actions = vec![];
+        for ply in 0..4 {
+            // both methods log the search.
+            let action = if ply % 2 == 0 {
+                policy.play(&state).await.unwrap()
+            } else {
+                policy.play_with_stats(&state).await.unwrap().0
+            };
+            state.play(&action).await;
+            actions.push(action);
+            if !state.is_finished() {
+                let action = opponent.play(&state).await.unwrap();
+                state.play(&action).await;
+            }
+        }
+
+        let logs = SEARCH_LOGS.lock().unwrap();
+        assert_eq!(logs.len(), actions.len());
+        for (line, action) in logs.iter().zip(actions) {
+            assert!(
+                line.starts_with(&format!("move: {:?},", action)),
+                "{}",
+                line
+            );
+            assert!(line.contains("top: ["));
+        }
     }
 }
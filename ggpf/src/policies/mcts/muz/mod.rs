@@ -1,22 +1,28 @@
 use super::puct::{PUCTPolicy, PUCT};
-use crate::deep::evaluator::{dynamics_task, prediction_task, representation_task};
+use super::scheduled_playouts;
+use crate::deep::evaluator::{
+    check_prediction_shape, dynamics_task, prediction_task, representation_task,
+};
 use crate::deep::evaluator::{
     representation, DynamicsEvaluatorChannel, PredictionEvaluatorChannel,
     RepresentationEvaluatorChannel,
 };
 use crate::deep::file_manager;
-use crate::deep::tf;
+use crate::deep::inference;
+use crate::deep::metrics::MetricsRecorder;
 use crate::game;
 use crate::game::meta::simulated::Simulated;
-use crate::policies::{MultiplayerPolicy, MultiplayerPolicyBuilder};
+use crate::policies::{MultiplayerPolicy, MultiplayerPolicyBuilder, PlayStats};
 use crate::settings;
 
 use async_trait::async_trait;
 use ndarray::Dimension;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::sync::{atomic::AtomicBool, RwLock};
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 /// MuZero policy
 pub struct MuzPolicy<G>
@@ -27,6 +33,8 @@ where
     /// PUCT policy instance. Can be taken to gather statistics.
     pub mcts: Option<PUCTPolicy<Simulated<G>>>,
     config: Muz,
+    /// Number of moves played since the creation or the last reset.
+    ply: usize,
 }
 
 #[async_trait]
@@ -34,7 +42,10 @@ impl<G> MultiplayerPolicy<G> for MuzPolicy<G>
 where
     G: game::Features + 'static,
 {
-    async fn play(&mut self, board: &G) -> G::Move {
+    async fn play(&mut self, board: &G) -> Option<G::Move> {
+        if board.is_finished() {
+            return None;
+        }
         let net_output = representation(
             self.config.channels.representation.clone(),
             self.config.muz.repr_shape,
@@ -49,20 +60,46 @@ where
             board.possible_moves(),
             self.config.channels.dynamics.clone(),
             self.config.muz.reward_support.unwrap_or(0),
+            self.config.muz.transform(),
         );
 
         let mcts_policy_builder = PUCT {
             prediction_channel: self.config.channels.prediction.clone(),
-            config: self.config.muz.puct,
-            n_playouts: self.config.n_playouts,
+            config: settings::PUCT {
+                value_transform: self.config.muz.transform(),
+                ..self.config.muz.puct
+            },
+            n_playouts: scheduled_playouts(
+                &self.config.playouts_schedule,
+                self.config.n_playouts,
+                self.ply,
+            ),
+            playouts_schedule: vec![],
         };
+        self.ply += 1;
 
-        let mut mcts_policy: PUCTPolicy<Simulated<G>> = mcts_policy_builder.create(self.player);
+        let mcts_policy: PUCTPolicy<Simulated<G>> = mcts_policy_builder.create(self.player);
+        let mut mcts_policy = mcts_policy.with_node_limit(self.config.muz.max_nodes);
 
         let action = mcts_policy.play(&simulator).await;
         self.mcts = Some(mcts_policy);
         action
     }
+
+    async fn play_with_stats(&mut self, board: &G) -> Option<(G::Move, PlayStats<G>)> {
+        let action = self.play(board).await?;
+        let search_info = self.mcts.as_ref().unwrap().search_info();
+        let stats = PlayStats {
+            value: Some(search_info.root_value),
+            visit_distribution: Some(search_info.visit_distribution),
+        };
+        Some((action, stats))
+    }
+
+    fn reset(&mut self) {
+        self.mcts = None;
+        self.ply = 0;
+    }
 }
 
 /// Channels that can be used to request inferences from tensorflow.
@@ -81,6 +118,9 @@ pub struct MuzEvaluatorChannels {
 pub struct Muz {
     /// Number of PUCT playouts per move.
     pub n_playouts: usize,
+    /// `(threshold, playouts)` schedule overriding `n_playouts` once the policy has
+    /// played `threshold` moves.
+    pub playouts_schedule: Vec<(usize, usize)>,
     /// Muz settings.
     pub muz: settings::MuZero,
     /// Evaluation channels
@@ -106,6 +146,7 @@ where
             player: color,
             config: self.clone(),
             mcts: None,
+            ply: 0,
         }
     }
 }
@@ -115,6 +156,8 @@ where
 pub struct MuZeroConfig<B, A> {
     /// Number of playouts for search.
     pub n_playouts: usize,
+    /// Playouts schedule of the search, see `Muz::playouts_schedule`.
+    pub playouts_schedule: Vec<(usize, usize)>,
     /// Settings for PUCT search.
     pub muz: settings::MuZero,
     /// Models base directory location.
@@ -127,16 +170,40 @@ pub struct MuZeroConfig<B, A> {
     pub watch_models: bool,
     /// GPU batch size.
     pub batch_size: usize,
+    /// Target prediction latency when the batch size is adaptive, fixed batch size otherwise.
+    pub target_latency: Option<Duration>,
+    /// Limits after which a self-play game is declared a draw.
+    pub draw_rules: game::DrawRules,
+    /// Neural network inference backend.
+    pub backend: settings::Backend,
+    /// Input and output names of the models.
+    pub signature: settings::ModelSignatures,
+}
+
+impl<B, A> MuZeroConfig<B, A> {
+    /// Configuration for evaluation games: the models are loaded once and predictions
+    /// are not batched, the search is deterministic (see `settings::MuZero::evaluation`).
+    pub fn evaluation(self) -> Self {
+        Self {
+            muz: self.muz.evaluation(),
+            watch_models: false,
+            batch_size: 1,
+            ..self
+        }
+    }
 }
 
-/// Structure that manages the tensorflow models and
+/// Structure that manages the models, whatever their backend, and
 /// the batched evaluator tasks.
 pub struct MuzEvaluators<B, A> {
     config: MuZeroConfig<B, A>,
-    prediction_tensorflow: tf::ThreadSafeModel,
-    dynamics_tensorflow: tf::ThreadSafeModel,
-    representation_tensorflow: tf::ThreadSafeModel,
+    prediction_tensorflow: inference::ThreadSafeModel,
+    dynamics_tensorflow: inference::ThreadSafeModel,
+    representation_tensorflow: inference::ThreadSafeModel,
     channels: MuzEvaluatorChannels,
+    shutdown: Arc<AtomicBool>,
+    tasks: Vec<JoinHandle<()>>,
+    metrics: Option<Arc<MetricsRecorder>>,
 }
 
 impl<B, A> Clone for MuzEvaluators<B, A>
@@ -162,6 +229,9 @@ where
                 representation: muz_repr_tx,
                 dynamics: muz_dyn_tx,
             },
+            shutdown: Arc::new(AtomicBool::new(false)),
+            tasks: vec![],
+            metrics: self.metrics.clone(),
         };
         ret.spawn_tensorflow_tasks(muz_repr_rx, muz_pred_rx, muz_dyn_rx);
         ret
@@ -177,7 +247,19 @@ where
     /// the files if necessary.
     /// If `spawn_tensorflow` is set, also spawn evaluators for the current
     /// channels.
+    ///
+    /// # Panics
+    /// Panics if the policy output of the prediction model doesn't match the action shape.
     pub fn new(config: MuZeroConfig<B, A>, spawn_tensorflow: bool) -> MuzEvaluators<B, A> {
+        Self::try_new(config, spawn_tensorflow).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new evaluator manager like `new`, failing if a model can't be loaded
+    /// or if the policy output of the prediction model doesn't match the action shape.
+    pub fn try_new(
+        config: MuZeroConfig<B, A>,
+        spawn_tensorflow: bool,
+    ) -> Result<MuzEvaluators<B, A>, settings::StrError> {
         let (muz_pred_tx, muz_pred_rx) =
             mpsc::channel::<PredictionEvaluatorChannel>(config.batch_size);
         let (muz_repr_tx, muz_repr_rx) =
@@ -188,18 +270,25 @@ where
         let dynamics_path = format!("{}{}", config.networks_path, "dyn");
         let representation_path = format!("{}{}", config.networks_path, "state");
 
-        let prediction_tensorflow = Arc::new((
-            AtomicBool::new(false),
-            RwLock::new(tf::load_model(&prediction_path)),
-        ));
-        let dynamics_tensorflow = Arc::new((
-            AtomicBool::new(false),
-            RwLock::new(tf::load_model(&dynamics_path)),
-        ));
-        let representation_tensorflow = Arc::new((
-            AtomicBool::new(false),
-            RwLock::new(tf::load_model(&representation_path)),
-        ));
+        let (backend, signature) = (config.backend, &config.signature);
+        let prediction_tensorflow = inference::try_load_thread_safe_model(
+            backend,
+            &signature.prediction,
+            &prediction_path,
+        )?;
+        let dynamics_tensorflow =
+            inference::try_load_thread_safe_model(backend, &signature.dynamics, &dynamics_path)?;
+        let representation_tensorflow = inference::try_load_thread_safe_model(
+            backend,
+            &signature.representation,
+            &representation_path,
+        )?;
+        check_prediction_shape(
+            &prediction_tensorflow,
+            config.batch_size,
+            config.muz.repr_shape.size(),
+            config.action_shape.size(),
+        )?;
 
         let watch_models = config.watch_models;
 
@@ -213,6 +302,9 @@ where
                 representation: muz_repr_tx,
                 dynamics: muz_dyn_tx,
             },
+            shutdown: Arc::new(AtomicBool::new(false)),
+            tasks: vec![],
+            metrics: None,
         };
 
         if spawn_tensorflow {
@@ -221,7 +313,7 @@ where
         if watch_models {
             ret.spawn_file_watchers();
         }
-        ret
+        Ok(ret)
     }
 
     /// Get evaluation requests sender channels to give to Muz.
@@ -230,14 +322,71 @@ where
         self.channels.clone()
     }
 
+    /// Record the prediction batches statistics in `metrics`, for the evaluator
+    /// tasks spawned by the next clones.
+    pub fn record_metrics(&mut self, metrics: Arc<MetricsRecorder>) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Load the prediction, dynamics and representation checkpoints found at
+    /// `networks_path`, in place of the current models of every clone.
+    pub fn swap_model(&self, networks_path: &str) {
+        let (backend, signature) = (self.config.backend, &self.config.signature);
+        let prediction_path = format!("{}{}", networks_path, "pv");
+        let dynamics_path = format!("{}{}", networks_path, "dyn");
+        let representation_path = format!("{}{}", networks_path, "state");
+
+        inference::load_checkpoint(
+            &self.prediction_tensorflow,
+            backend,
+            &signature.prediction,
+            &prediction_path,
+        );
+        inference::load_checkpoint(
+            &self.dynamics_tensorflow,
+            backend,
+            &signature.dynamics,
+            &dynamics_path,
+        );
+        inference::load_checkpoint(
+            &self.representation_tensorflow,
+            backend,
+            &signature.representation,
+            &representation_path,
+        );
+    }
+
+    /// Stop the evaluator tasks and wait for their termination.
+    pub async fn shutdown(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        for task in self.tasks.drain(..) {
+            task.await.unwrap();
+        }
+    }
+
     fn spawn_file_watchers(&self) {
         let prediction_path = format!("{}{}", self.config.networks_path, "pv");
         let dynamics_path = format!("{}{}", self.config.networks_path, "dyn");
         let representation_path = format!("{}{}", self.config.networks_path, "state");
 
-        file_manager::watch_model(self.prediction_tensorflow.clone(), &prediction_path);
-        file_manager::watch_model(self.dynamics_tensorflow.clone(), &dynamics_path);
-        file_manager::watch_model(self.representation_tensorflow.clone(), &representation_path);
+        file_manager::watch_model(
+            self.prediction_tensorflow.clone(),
+            self.config.backend,
+            self.config.signature.prediction.clone(),
+            &prediction_path,
+        );
+        file_manager::watch_model(
+            self.dynamics_tensorflow.clone(),
+            self.config.backend,
+            self.config.signature.dynamics.clone(),
+            &dynamics_path,
+        );
+        file_manager::watch_model(
+            self.representation_tensorflow.clone(),
+            self.config.backend,
+            self.config.signature.representation.clone(),
+            &representation_path,
+        );
     }
 
     fn spawn_tensorflow_tasks(
@@ -250,31 +399,141 @@ where
         let action_size = self.config.action_shape.size();
         let repr_size = self.config.muz.repr_shape.size();
 
-        tokio::spawn(prediction_task(
+        self.tasks.push(tokio::spawn(prediction_task(
             self.config.batch_size,
             repr_size,
             action_size,
             2 * self.config.muz.puct.value_support.unwrap_or(0) + 1,
+            self.config.target_latency,
             self.prediction_tensorflow.clone(),
             muz_pred_rx,
+            self.shutdown.clone(),
             None,
-        ));
+            self.metrics.clone(),
+        )));
 
-        tokio::spawn(representation_task(
+        self.tasks.push(tokio::spawn(representation_task(
             self.config.batch_size,
             board_size,
             repr_size,
             self.representation_tensorflow.clone(),
             muz_repr_rx,
-        ));
+            self.shutdown.clone(),
+        )));
 
-        tokio::spawn(dynamics_task(
+        self.tasks.push(tokio::spawn(dynamics_task(
             self.config.batch_size,
             repr_size,
             action_size,
             2 * self.config.muz.reward_support.unwrap_or(0) + 1,
             self.dynamics_tensorflow.clone(),
             muz_dyn_rx,
-        ));
+            self.shutdown.clone(),
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep::mock::mock_alphazero_channel;
+    use crate::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+    use crate::game::{Base, Features, Game, GameBuilder};
+    use crate::policies::mcts::puct::tests::puct_config;
+    use ndarray::Array;
+    use std::sync::Mutex;
+    use tensorflow::Tensor;
+    use tokio::time::timeout;
+
+    /// Deterministic dynamics model, answering the requests by batches of up to
+    /// `batch_size`. The size of each batch is recorded in `batches`.
+    fn batched_dynamics_channel(
+        batch_size: usize,
+        batches: Arc<Mutex<Vec<usize>>>,
+    ) -> mpsc::Sender<DynamicsEvaluatorChannel> {
+        let (tx, mut rx) = mpsc::channel::<DynamicsEvaluatorChannel>(16);
+        tokio::spawn(async move {
+            while let Some(request) = rx.recv().await {
+                let mut pending = vec![request];
+                while pending.len() < batch_size {
+                    match timeout(Duration::from_millis(20), rx.recv()).await {
+                        Ok(Some(request)) => pending.push(request),
+                        _ => break,
+                    }
+                }
+                batches.lock().unwrap().push(pending.len());
+                for ((hidden, action), resp) in pending {
+                    let next: Vec<f32> = hidden
+                        .iter()
+                        .zip(action.iter())
+                        .map(|(h, a)| 0.5 * h + a)
+                        .collect();
+                    let _ = resp.send((Tensor::from(&next[..]), Tensor::from(&[0.][..])));
+                }
+            }
+        });
+        tx
+    }
+
+    /// Statistics of the root moves after a search with the given dynamics batch size.
+    async fn search(
+        state: &Breakthrough,
+        batch_size: usize,
+        batches: Arc<Mutex<Vec<usize>>>,
+    ) -> Vec<(usize, f32, f32)> {
+        let descr = state.get_features();
+        let simulator = Simulated::<Breakthrough>::new(
+            state.turn(),
+            state.state_to_feature(state.turn()),
+            descr,
+            state.possible_moves(),
+            batched_dynamics_channel(batch_size, batches),
+            0,
+            None,
+        );
+
+        let action_shape = Breakthrough::action_dimension(&descr);
+        let prediction_channel = mock_alphazero_channel::<Simulated<Breakthrough>, _>(move |h| {
+            let value = (h.sum() * 0.37).sin();
+            let policy = Array::from_shape_fn(action_shape, |(x, y, z)| {
+                1. + ((x + 2 * y + 3 * z) as f32 + h.sum()).cos()
+            });
+            (policy, value)
+        });
+        let puct = PUCT {
+            config: settings::PUCT {
+                add_exploration_noise: false,
+                parallel_playouts: 4,
+                batch_expansions: true,
+                ..puct_config(0.99)
+            },
+            n_playouts: 40,
+            playouts_schedule: vec![],
+            prediction_channel,
+        };
+        let mut policy: PUCTPolicy<Simulated<Breakthrough>> = puct.create(state.turn());
+        policy.play(&simulator).await;
+
+        let mut stats: Vec<_> = policy
+            .search_info()
+            .moves
+            .iter()
+            .map(|(m, v)| (Breakthrough::action_index(&descr, m), v.N_a, v.Q))
+            .collect();
+        stats.sort_by_key(|(index, _, _)| *index);
+        stats
+    }
+
+    #[tokio::test]
+    async fn test_batched_expansions() {
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+
+        let sequential = Arc::new(Mutex::new(vec![]));
+        let expected = search(&state, 1, sequential.clone()).await;
+        assert!(sequential.lock().unwrap().iter().all(|n| *n == 1));
+
+        let batched = Arc::new(Mutex::new(vec![]));
+        assert_eq!(search(&state, 4, batched.clone()).await, expected);
+        assert!(batched.lock().unwrap().iter().any(|n| *n > 1));
     }
 }
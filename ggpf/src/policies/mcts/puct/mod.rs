@@ -1,22 +1,34 @@
-use crate::deep::evaluator::{prediction, prediction_task, PredictionEvaluatorChannel};
+use crate::deep::evaluator::{
+    check_prediction_shape, prediction_task, try_cached_prediction, try_prediction,
+    PredictionEvaluatorChannel,
+};
+use crate::deep::feature_cache::FeatureCache;
 use crate::deep::file_manager;
-use crate::deep::tf;
+use crate::deep::inference;
+use crate::deep::metrics::MetricsRecorder;
 use crate::game;
-use crate::policies::mcts::{BaseMCTSPolicy, MCTSTreeNode, WithMCTSPolicy};
+use crate::policies::mcts::{
+    argmax, kl_divergence, BaseMCTSPolicy, Budget, MCTSNode, MCTSTreeNode, MoveStats,
+    WithMCTSPolicy,
+};
 use crate::policies::MultiplayerPolicyBuilder;
 use crate::settings;
 
 use async_trait::async_trait;
 use ndarray::Array;
 use ndarray::Dimension;
+use rand::Rng;
 use rand_distr::{Distribution, Gamma};
 use std::collections::HashMap;
 use std::f32;
 use std::fmt;
+use std::hash::Hash;
 use std::iter::*;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 /// PUCT move statistics.
 #[derive(Debug, Clone, Copy)]
@@ -25,10 +37,17 @@ pub struct PUCTMoveInfo {
     pub Q: f32,
     /// Number of times the move has been explored.
     pub N_a: f32,
-    /// Move policy as predicted by the network.
+    /// Move policy used by the search: the network policy, mixed with the exploration
+    /// noise at the root.
     pub pi: f32,
+    /// Move policy as predicted by the network.
+    pub prior: f32,
     /// Immediate reward yielded by move.
     pub reward: f32,
+    /// Gumbel noise sampled at the root (Gumbel mode only).
+    pub gumbel: f32,
+    /// Virtual loss count: number of pending playouts going through this move.
+    pub N_vl: f32,
 }
 
 /// PUCT node statistics.
@@ -36,7 +55,109 @@ pub struct PUCTMoveInfo {
 pub struct PUCTNodeInfo {
     /// Node visit count.
     pub count: f32,
+    /// Highest visit count among the node moves.
+    pub max_N_a: f32,
+    /// Mean of the evaluation of the node and of the values backed up through it,
+    /// relative to the player to move.
+    pub value: f32,
 }
+
+/// Visit count offset of the sigma transformation (`c_visit` in the Gumbel MuZero paper).
+const GUMBEL_C_VISIT: f32 = 50.;
+
+/// Monotonic transformation of a normalized Q value, used in Gumbel mode:
+///
+/// `sigma(q) = (c_visit + max_b N(b)) * c_scale * q`
+fn sigma(q: f32, max_N_a: f32, c_scale: f32) -> f32 {
+    (GUMBEL_C_VISIT + max_N_a) * c_scale * q
+}
+
+///
+/// Visit count of the move selected by sequential halving at the `index`-th root visit
+/// of a search of `playouts` playouts, `considered` moves being considered.
+///
+/// The considered moves are visited in turn, then the best half of them, and so on until
+/// two moves remain: each phase visits its moves `playouts / (ceil(log2(considered)) * moves)`
+/// times, at least once. `None` once the playouts are exhausted.
+///
+fn considered_visit(considered: usize, playouts: usize, index: usize) -> Option<usize> {
+    if index >= playouts {
+        return None;
+    }
+    if considered <= 1 {
+        return Some(index);
+    }
+    let phases = (considered as f32).log2().ceil() as usize;
+    let (mut moves, mut visits, mut index) = (considered, 0, index);
+    loop {
+        let rounds = (playouts / (phases * moves)).max(1);
+        if index < rounds * moves {
+            return Some(visits + index / moves);
+        }
+        index -= rounds * moves;
+        visits += rounds;
+        moves = (moves / 2).max(2);
+    }
+}
+
+/// Completed Q values: `q(a)` for visited moves and the visit-weighted mean of `q`
+/// over visited moves otherwise.
+fn completed_q<M>(
+    moves: &HashMap<M, PUCTMoveInfo>,
+    q: impl Fn(&PUCTMoveInfo) -> f32,
+) -> HashMap<M, f32>
+where
+    M: Copy + Eq + Hash,
+{
+    let count: f32 = moves.values().map(|v| v.N_a).sum();
+    let v_mix = if count > 0. {
+        moves.values().map(|v| v.N_a * q(v)).sum::<f32>() / count
+    } else {
+        0.
+    };
+    HashMap::from_iter(
+        moves
+            .iter()
+            .map(|(m, v)| (*m, if v.N_a > 0. { q(v) } else { v_mix })),
+    )
+}
+
+///
+/// Improved policy of Gumbel MuZero, given root moves statistics.
+///
+/// `pi'(a) = softmax(log(pi(a)) + sigma(completed_q(a)))`
+///
+/// where the completed Q value is `q(a)` for visited moves and the visit-weighted
+/// mean of `q` over visited moves otherwise.
+/// `q` maps move statistics to a normalized value in [0, 1].
+///
+pub fn gumbel_improved_policy<M>(
+    moves: &HashMap<M, PUCTMoveInfo>,
+    q: impl Fn(&PUCTMoveInfo) -> f32,
+    c_scale: f32,
+) -> HashMap<M, f32>
+where
+    M: Copy + Eq + Hash,
+{
+    let max_N_a = moves.values().map(|v| v.N_a).fold(0., f32::max);
+    let completed = completed_q(moves, q);
+
+    let logits: HashMap<M, f32> = HashMap::from_iter(moves.iter().map(|(m, v)| {
+        (
+            *m,
+            v.pi.max(f32::MIN_POSITIVE).ln() + sigma(completed[m], max_N_a, c_scale),
+        )
+    }));
+
+    let max_logit = logits.values().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let z: f32 = logits.values().map(|l| (l - max_logit).exp()).sum();
+    HashMap::from_iter(
+        logits
+            .into_iter()
+            .map(|(m, l)| (m, (l - max_logit).exp() / z)),
+    )
+}
+
 ///
 /// The game state evaluator
 ///
@@ -53,6 +174,9 @@ where
     color: G::Player,
     config: settings::PUCT,
     prediction_channel: mpsc::Sender<PredictionEvaluatorChannel>,
+    feature_cache: Option<Arc<FeatureCache<G>>>,
+    /// Whether the unavailable evaluator was reported during the current search.
+    evaluator_warned: Arc<AtomicBool>,
     /// Minimum Q value encountered in the tree.
     pub min_tree: f32,
     /// Maximum Q value encountered in the tree.
@@ -63,14 +187,33 @@ impl<G> PUCTPolicy_<G>
 where
     G: game::Features + super::MCTSGame,
 {
-    /// Normalize Q value according to minimum and maximum values.
+    /// Normalize Q value in `[0, 1]` according to minimum and maximum values.
+    /// While the tree holds a single value, every Q value is mapped to `0.5`.
     pub fn normalize(&self, x: f32) -> f32 {
         if self.min_tree < self.max_tree {
-            (x - self.min_tree) / (self.max_tree - self.min_tree)
+            ((x - self.min_tree) / (self.max_tree - self.min_tree))
+                .max(0.)
+                .min(1.)
         } else {
-            x
+            0.5
         }
     }
+
+    /// Extend the normalization bounds to include `x`.
+    fn update_bounds(&mut self, x: f32) {
+        self.min_tree = self.min_tree.min(x);
+        self.max_tree = self.max_tree.max(x);
+    }
+
+    /// Improved policy target for the given root moves statistics (Gumbel mode).
+    /// Falls back to a scale of 1 if `gumbel_scale` is not set.
+    pub fn improved_policy(&self, moves: &HashMap<G::Move, PUCTMoveInfo>) -> HashMap<G::Move, f32> {
+        gumbel_improved_policy(
+            moves,
+            |v| self.normalize(v.reward + self.config.discount * v.Q),
+            self.config.gumbel_scale.unwrap_or(1.),
+        )
+    }
 }
 
 type PUCTPlayoutInfo<G> = (
@@ -89,6 +232,10 @@ where
     type MoveInfo = PUCTMoveInfo;
     type PlayoutInfo = PUCTPlayoutInfo<G>;
 
+    /// In Gumbel mode, the final root selection maximizes
+    /// `log(pi(a)) + g(a) + sigma(q(a))` over the most visited moves, which are the moves
+    /// left by the sequential halving, `g` being the Gumbel noise sampled when the root
+    /// was expanded.
     fn get_value(
         &self,
         _board: &G,
@@ -100,11 +247,26 @@ where
         if exploration {
             let N = node_info.count;
             let v = move_info;
+            // pending playouts count as losses.
+            let N_a = v.N_a + v.N_vl;
+            let value = if N_a > 0. {
+                let Q = (v.Q * v.N_a - v.N_vl) / N_a;
+                v.reward + self.config.discount * Q
+            } else {
+                self.config.first_play_urgency(node_info.value)
+            };
             let pb_c =
                 ((N + self.config.c_base + 1.) / self.config.c_base).ln() + self.config.c_init;
-            let prior = pb_c * v.pi * (N.sqrt() / (v.N_a + 1.));
-            let value = self.normalize(move_info.reward + self.config.discount * move_info.Q);
-            prior + value
+            let prior = pb_c * v.pi * (N.sqrt() / (N_a + 1.));
+            prior + self.normalize(value)
+        } else if let Some(c_scale) = self.config.gumbel_scale {
+            let v = move_info;
+            if v.N_a > 0. && v.N_a == node_info.max_N_a {
+                let q = self.normalize(v.reward + self.config.discount * v.Q);
+                v.pi.max(f32::MIN_POSITIVE).ln() + v.gumbel + sigma(q, node_info.max_N_a, c_scale)
+            } else {
+                f32::NEG_INFINITY
+            }
         } else {
             move_info.N_a
         }
@@ -115,12 +277,19 @@ where
             Q: 0.,
             N_a: 0.,
             pi: 1.,
+            prior: 1.,
             reward: 0.,
+            gumbel: 0.,
+            N_vl: 0.,
         }
     }
 
     fn default_node(&self, _board: &G) -> Self::NodeInfo {
-        PUCTNodeInfo { count: 0. }
+        PUCTNodeInfo {
+            count: 0.,
+            max_N_a: 0.,
+            value: 0.,
+        }
     }
 
     fn backpropagate(
@@ -133,13 +302,41 @@ where
         // todo: assert leaf.turn == pov
         // assert_eq!(leaf.borrow().info.state.turn(), *pov);
 
+        if leaf.read().unwrap().parent.is_none() {
+            // root evaluation: a new search starts, bounds of the previous one are stale.
+            self.min_tree = value;
+            self.max_tree = value;
+        }
+
         if let Some(mut policy) = policy {
-            // save probabilities of newly created node.
+            // save probabilities and evaluation of newly created node.
             let mut leaf = leaf.write().unwrap();
-            if leaf.parent.is_none() {
+            leaf.info.node.value = value;
+
+            // the network prior is kept before the exploration noise is added.
+            let z: f32 = leaf
+                .info
+                .moves
+                .keys()
+                .map(|m| policy.get(&m).unwrap())
+                .sum();
+            let z = if z == 0. { 1. } else { z };
+            for (m, info) in leaf.info.moves.iter_mut() {
+                info.prior = policy.get(&m).unwrap() / z;
+            }
+
+            if leaf.parent.is_none() && self.config.gumbel_scale.is_some() {
+                // root node: sample gumbel noise, used for final move selection.
+                let mut rng = rand::thread_rng();
+                for (_, info) in leaf.info.moves.iter_mut() {
+                    let u: f32 = rng.gen_range(f32::MIN_POSITIVE, 1.);
+                    info.gumbel = -(-u.ln()).ln();
+                }
+            } else if leaf.parent.is_none() && self.config.add_exploration_noise {
                 // root node: add dirichlet noise.
                 let frac = self.config.root_exploration_fraction;
-                let gamma = Gamma::new(self.config.root_dirichlet_alpha, 1.0).unwrap();
+                let alpha = self.config.dirichlet_alpha(leaf.info.moves.len());
+                let gamma = Gamma::new(alpha, 1.0).unwrap();
                 for (_, val) in policy.iter_mut() {
                     let noise = gamma.sample(&mut rand::thread_rng());
                     *val = frac * (*val) + (1. - frac) * noise;
@@ -158,7 +355,6 @@ where
             }
         }
 
-        
         // reward when playing action from tree_position.
         let mut position_reward = leaf.read().unwrap().info.reward;
         let mut tree_position = leaf;
@@ -191,6 +387,9 @@ where
             position_reward = tree_node.info.reward;
 
             tree_node.info.node.count += 1.;
+            let count = tree_node.info.node.count;
+            tree_node.info.node.value +=
+                (relative_value - tree_node.info.node.value) / (count + 1.);
 
             let node_reward = tree_node
                 .moves
@@ -205,13 +404,12 @@ where
             (*v).N_a += 1.;
             (*v).Q += (relative_value - (*v).Q) / (*v).N_a;
             (*v).reward = node_reward;
+            let N_a = (*v).N_a;
+            let Q = (*v).Q;
+            self.update_bounds(Q);
 
-            if (*v).Q < self.min_tree {
-                self.min_tree = (*v).Q
-            }
-
-            if (*v).Q > self.max_tree {
-                self.max_tree = (*v).Q
+            if N_a > tree_node.info.node.max_N_a {
+                tree_node.info.node.max_N_a = N_a;
             }
         }
     }
@@ -219,19 +417,120 @@ where
     async fn simulate(&self, board: &G) -> Self::PlayoutInfo {
         if !board.is_finished() {
             // NN predicts a good policy for current player + expectation of winning from this state.
-            let (policy, value) = prediction(
-                self.prediction_channel.clone(),
-                board.turn(),
-                board,
-                self.config.value_support.unwrap_or(0),
-            )
-            .await;
-            let policy = board.feature_to_moves(&policy);
+            let channel = self.prediction_channel.clone();
+            let support_size = self.config.value_support.unwrap_or(0);
+            let transform = self.config.value_transform;
+            let prediction = match &self.feature_cache {
+                Some(cache) => {
+                    try_cached_prediction(
+                        channel,
+                        board.turn(),
+                        board,
+                        support_size,
+                        transform,
+                        cache,
+                    )
+                    .await
+                }
+                None => try_prediction(channel, board.turn(), board, support_size, transform).await,
+            };
+            let (policy, value) = match prediction {
+                Some((policy, value)) => (board.feature_to_moves(&policy), value),
+                None => {
+                    // degrade to a uniform policy rather than stopping the search.
+                    if !self.evaluator_warned.swap(true, Ordering::Relaxed) {
+                        log::warn!("Prediction evaluator unavailable: using a uniform policy.");
+                    }
+                    let moves = board.possible_moves();
+                    let p = 1. / moves.len() as f32;
+                    (moves.into_iter().map(|m| (m, p)).collect(), 0.)
+                }
+            };
             (Some(policy), value, board.turn())
         } else {
             (None, 0., board.turn())
         }
     }
+
+    /// In Gumbel mode, the root moves are searched by sequential halving: the selected
+    /// move maximizes `log(pi(a)) + g(a) + sigma(completed_q(a))` among the moves whose
+    /// visit count, pending playouts included, is the one scheduled by `considered_visit`.
+    /// The first visits thus go to the `gumbel_considered_actions` best moves by prior
+    /// and Gumbel noise. Without a playouts budget, or once the root has more visits
+    /// than the budget, the root moves are selected by the PUCT formula.
+    fn select_root_move(
+        &self,
+        root: &MCTSNode<G, Self>,
+        playouts: Option<usize>,
+    ) -> Option<G::Move> {
+        let c_scale = self.config.gumbel_scale?;
+        let considered = self.config.gumbel_considered_actions.min(root.moves.len());
+        let visits = |v: &PUCTMoveInfo| (v.N_a + v.N_vl) as usize;
+        let index = root.moves.values().map(visits).sum();
+        let target = considered_visit(considered, playouts?, index)?;
+
+        let completed = completed_q(&root.moves, |v| {
+            self.normalize(v.reward + self.config.discount * v.Q)
+        });
+        argmax(
+            root.moves
+                .iter()
+                .filter(|(_, v)| visits(v) == target)
+                .map(|(m, v)| {
+                    let sigma = sigma(completed[m], root.node.max_N_a, c_scale);
+                    (*m, v.pi.max(f32::MIN_POSITIVE).ln() + v.gumbel + sigma)
+                }),
+        )
+    }
+
+    fn move_stats(&self, move_info: &Self::MoveInfo) -> MoveStats {
+        MoveStats {
+            N_a: move_info.N_a,
+            Q: move_info.Q,
+            pi: move_info.pi,
+            reward: move_info.reward,
+        }
+    }
+
+    fn discount(&self) -> f32 {
+        self.config.discount
+    }
+
+    fn parallel_playouts(&self) -> usize {
+        self.config.parallel_playouts
+    }
+
+    fn batch_expansions(&self) -> bool {
+        self.config.batch_expansions
+    }
+
+    fn early_stop(&self) -> Option<settings::EarlyStop> {
+        if self.config.gumbel_scale.is_some() {
+            None
+        } else {
+            self.config.early_stop
+        }
+    }
+
+    fn start_search(&mut self) {
+        self.evaluator_warned.store(false, Ordering::Relaxed);
+    }
+
+    fn reset(&mut self) {
+        self.min_tree = f32::MAX;
+        self.max_tree = -f32::MAX;
+        if let Some(cache) = self.feature_cache.as_ref() {
+            cache.clear();
+        }
+    }
+
+    fn add_virtual_loss(&self, move_info: &mut Self::MoveInfo) {
+        move_info.N_vl += 1.;
+    }
+
+    fn remove_virtual_loss(&self, move_info: &mut Self::MoveInfo) {
+        move_info.N_vl -= 1.;
+    }
 }
 
 ///
@@ -239,6 +538,25 @@ where
 ///
 pub type PUCTPolicy<G> = WithMCTSPolicy<G, PUCTPolicy_<G>>;
 
+impl<G> PUCTPolicy<G>
+where
+    G: game::Features + super::MCTSGame,
+{
+    /// KL divergence between the visit distribution of the last exploration and the
+    /// network prior at the root, without the exploration noise. A high divergence means
+    /// that the search corrects the network.
+    ///
+    /// # Panics
+    /// Panics if no exploration has been performed, or if the root has been taken.
+    pub fn prior_divergence(&self) -> f32 {
+        let visits = self.search_info().visit_distribution;
+        let root = self.root.as_ref().unwrap().read().unwrap();
+        let prior: HashMap<G::Move, f32> =
+            root.info.moves.iter().map(|(m, v)| (*m, v.prior)).collect();
+        kl_divergence(&visits, &prior)
+    }
+}
+
 /// PUCT policy builder
 #[derive(Clone)]
 pub struct PUCT {
@@ -246,10 +564,22 @@ pub struct PUCT {
     pub config: settings::PUCT,
     /// Number of playouts.
     pub n_playouts: usize,
+    /// `(threshold, playouts)` schedule overriding `n_playouts` once the policy has
+    /// played `threshold` moves.
+    pub playouts_schedule: Vec<(usize, usize)>,
     /// State evaluation function.
     pub prediction_channel: mpsc::Sender<PredictionEvaluatorChannel>,
 }
 
+impl settings::Tunable for PUCT {
+    fn with_parameter(&self, name: &str, value: f32) -> Result<Self, settings::StrError> {
+        Ok(PUCT {
+            config: settings::Tunable::with_parameter(&self.config, name, value)?,
+            ..self.clone()
+        })
+    }
+}
+
 impl fmt::Display for PUCT {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "BATCHED PUCT")?;
@@ -269,11 +599,19 @@ where
                 color,
                 config: self.config,
                 prediction_channel: self.prediction_channel.clone(),
+                feature_cache: if self.config.feature_cache > 0 {
+                    Some(Arc::new(FeatureCache::new(self.config.feature_cache)))
+                } else {
+                    None
+                },
+                evaluator_warned: Arc::new(AtomicBool::new(false)),
                 min_tree: f32::MAX,
                 max_tree: -f32::MAX,
             },
-            self.n_playouts,
+            Budget::new(self.n_playouts, self.config.time_budget()),
         )
+        .with_playouts_schedule(self.playouts_schedule.clone())
+        .with_search_log(self.config.log_search)
     }
 }
 
@@ -282,8 +620,12 @@ where
 pub struct AlphaZeroConfig<A, B> {
     /// Number of playouts for search.
     pub n_playouts: usize,
+    /// Playouts schedule of the search, see `PUCT::playouts_schedule`.
+    pub playouts_schedule: Vec<(usize, usize)>,
     /// Settings for PUCT search.
     pub puct: settings::PUCT,
+    /// Value target of the self-play games.
+    pub value_target: settings::ValueTarget,
     /// Model directory location.
     pub network_path: String,
     /// Board space dimensions.
@@ -294,14 +636,38 @@ pub struct AlphaZeroConfig<A, B> {
     pub watch_models: bool,
     /// GPU batch size.
     pub batch_size: usize,
+    /// Target prediction latency when the batch size is adaptive, fixed batch size otherwise.
+    pub target_latency: Option<Duration>,
+    /// Limits after which a self-play game is declared a draw.
+    pub draw_rules: game::DrawRules,
+    /// Neural network inference backend.
+    pub backend: settings::Backend,
+    /// Input and output names of the model.
+    pub signature: settings::ModelSignature,
 }
 
-/// Structure that manages the tensorflow model and
+impl<A, B> AlphaZeroConfig<A, B> {
+    /// Configuration for evaluation games: the model is loaded once and predictions
+    /// are not batched, the search is deterministic (see `settings::PUCT::evaluation`).
+    pub fn evaluation(self) -> Self {
+        Self {
+            puct: self.puct.evaluation(),
+            watch_models: false,
+            batch_size: 1,
+            ..self
+        }
+    }
+}
+
+/// Structure that manages the model, whatever its backend, and
 /// the batched evaluator task.
 pub struct AlphaZeroEvaluators<B, A> {
     config: AlphaZeroConfig<B, A>,
-    prediction_tensorflow: tf::ThreadSafeModel,
+    prediction_tensorflow: inference::ThreadSafeModel,
     channel: mpsc::Sender<PredictionEvaluatorChannel>,
+    shutdown: Arc<AtomicBool>,
+    tasks: Vec<JoinHandle<()>>,
+    metrics: Option<Arc<MetricsRecorder>>,
 }
 
 impl<B, A> Clone for AlphaZeroEvaluators<B, A>
@@ -317,6 +683,9 @@ where
             config: self.config.clone(),
             prediction_tensorflow: self.prediction_tensorflow.clone(),
             channel: alpha_pred_tx,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            tasks: vec![],
+            metrics: self.metrics.clone(),
         };
         ret.spawn_tensorflow_task(alpha_pred_rx);
         ret
@@ -332,20 +701,42 @@ where
     /// the files if necessary.
     /// If `spawn_tensorflow` is set, also spawn evaluators for the current
     /// channels.
+    ///
+    /// # Panics
+    /// Panics if the policy output of the model doesn't match the action shape.
     pub fn new(config: AlphaZeroConfig<B, A>, spawn_tensorflow: bool) -> Self {
+        Self::try_new(config, spawn_tensorflow).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new evaluator manager like `new`, failing if the model can't be loaded
+    /// or if its policy output doesn't match the action shape.
+    pub fn try_new(
+        config: AlphaZeroConfig<B, A>,
+        spawn_tensorflow: bool,
+    ) -> Result<Self, settings::StrError> {
         let (alpha_pred_tx, alpha_pred_rx) =
             mpsc::channel::<PredictionEvaluatorChannel>(2 * config.batch_size);
 
-        let prediction_tensorflow = Arc::new((
-            AtomicBool::new(false),
-            RwLock::new(tf::load_model(&config.network_path)),
-        ));
+        let prediction_tensorflow = inference::try_load_thread_safe_model(
+            config.backend,
+            &config.signature,
+            &config.network_path,
+        )?;
+        check_prediction_shape(
+            &prediction_tensorflow,
+            config.batch_size,
+            config.board_shape.size(),
+            config.action_shape.size(),
+        )?;
         let watch_models = config.watch_models;
 
         let mut ret = Self {
             config,
             prediction_tensorflow,
             channel: alpha_pred_tx,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            tasks: vec![],
+            metrics: None,
         };
 
         if spawn_tensorflow {
@@ -354,7 +745,7 @@ where
         if watch_models {
             ret.spawn_file_watcher();
         }
-        ret
+        Ok(ret)
     }
 
     /// Get evaluation requests sender channel to give to PUCT.
@@ -363,9 +754,40 @@ where
         self.channel.clone()
     }
 
+    /// Record the prediction batches statistics in `metrics`, for the evaluator
+    /// tasks spawned by the next clones.
+    pub fn record_metrics(&mut self, metrics: Arc<MetricsRecorder>) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Load the checkpoint at `path` in place of the current model of every clone.
+    pub fn swap_model(&self, path: &str) {
+        inference::load_checkpoint(
+            &self.prediction_tensorflow,
+            self.config.backend,
+            &self.config.signature,
+            path,
+        );
+    }
+
+    /// Replace the current model of every clone by `network`.
+    pub fn swap_network(&self, network: Box<dyn inference::Inference>) {
+        inference::swap_model(&self.prediction_tensorflow, network);
+    }
+
+    /// Stop the evaluator task and wait for its termination.
+    pub async fn shutdown(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        for task in self.tasks.drain(..) {
+            task.await.unwrap();
+        }
+    }
+
     fn spawn_file_watcher(&self) {
         file_manager::watch_model(
             self.prediction_tensorflow.clone(),
+            self.config.backend,
+            self.config.signature.clone(),
             &self.config.network_path,
         );
     }
@@ -374,14 +796,640 @@ where
         let board_size = self.config.board_shape.size();
         let action_size = self.config.action_shape.size();
 
-        tokio::spawn(prediction_task(
+        self.tasks.push(tokio::spawn(prediction_task(
             self.config.batch_size,
             board_size,
             action_size,
             2 * self.config.puct.value_support.unwrap_or(0) + 1,
+            self.config.target_latency,
             self.prediction_tensorflow.clone(),
             alpha_pred_rx,
+            self.shutdown.clone(),
             None,
-        ));
+            self.metrics.clone(),
+        )));
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::deep::mock::{mock_alphazero_channel, mock_failing_channel, mock_uniform_channel};
+    use crate::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color, Move};
+    use crate::game::tictactoe::{TicTacToe, TicTacToeBuilder};
+    use crate::game::{Base, Features, Game, GameBuilder, Playable};
+    use crate::policies::mcts::MCTSNode;
+    use crate::policies::MultiplayerPolicy;
+    use std::time::Duration;
+
+    pub(crate) fn puct_config(discount: f32) -> settings::PUCT {
+        settings::PUCT {
+            discount,
+            c_base: 19652.,
+            c_init: 1.25,
+            root_dirichlet_alpha: 0.3,
+            root_exploration_fraction: 0.25,
+            add_exploration_noise: true,
+            scale_dirichlet_alpha: false,
+            value_support: None,
+            gumbel_scale: None,
+            gumbel_considered_actions: 16,
+            parallel_playouts: 1,
+            batch_expansions: false,
+            early_stop: None,
+            time_budget_ms: None,
+            feature_cache: 0,
+            value_transform: None,
+            fpu: 0.,
+            fpu_reduction: None,
+            log_search: false,
+        }
+    }
+
+    fn move_info(pi: f32, N_a: f32, Q: f32) -> PUCTMoveInfo {
+        PUCTMoveInfo {
+            Q,
+            N_a,
+            pi,
+            prior: pi,
+            reward: 0.,
+            gumbel: 0.,
+            N_vl: 0.,
+        }
+    }
+
+    #[test]
+    fn test_gumbel_improved_policy() {
+        let moves: HashMap<usize, PUCTMoveInfo> = HashMap::from_iter(vec![
+            (0, move_info(0.5, 2., 0.2)),
+            (1, move_info(0.3, 10., 0.8)),
+            (2, move_info(0.2, 0., 0.)),
+        ]);
+
+        for c_scale in &[0.1, 1., 1000.] {
+            let policy = gumbel_improved_policy(&moves, |v| v.Q, *c_scale);
+            let total: f32 = policy.values().sum();
+            assert!((total - 1.).abs() < 1e-5);
+        }
+
+        let policy = gumbel_improved_policy(&moves, |v| v.Q, 1000.);
+        let best = policy
+            .iter()
+            .max_by_key(|(_, p)| float_ord::FloatOrd(**p))
+            .unwrap()
+            .0;
+        let most_visited = moves
+            .iter()
+            .max_by_key(|(_, v)| float_ord::FloatOrd(v.N_a))
+            .unwrap()
+            .0;
+        assert_eq!(best, most_visited);
+    }
+
+    #[test]
+    fn test_considered_visit() {
+        // 4 moves visited twice, then 2 moves visited 4 times.
+        let schedule: Vec<usize> = (0..16)
+            .map(|i| considered_visit(4, 16, i).unwrap())
+            .collect();
+        assert_eq!(
+            schedule,
+            vec![0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5]
+        );
+        assert_eq!(considered_visit(4, 16, 16), None);
+        assert_eq!(considered_visit(1, 16, 5), Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_sequential_halving() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        for parallel_playouts in &[1, 4] {
+            let config = settings::PUCT {
+                gumbel_scale: Some(1.),
+                gumbel_considered_actions: 4,
+                parallel_playouts: *parallel_playouts,
+                ..puct_config(0.99)
+            };
+            let puct = PUCT {
+                config,
+                n_playouts: 16,
+                playouts_schedule: vec![],
+                prediction_channel: hashed_prediction_channel(5),
+            };
+            let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+            let action = policy.play(&state).await.unwrap();
+
+            let root = policy.root.as_ref().unwrap().read().unwrap();
+            let mut visits: Vec<usize> = root
+                .info
+                .moves
+                .values()
+                .map(|v| v.N_a as usize)
+                .filter(|n| *n > 0)
+                .collect();
+            visits.sort();
+            assert_eq!(visits, vec![2, 2, 6, 6]);
+            // the played move is one of the two moves left.
+            assert_eq!(root.info.moves[&action].N_a as usize, 6);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_root_value_discount() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let (prediction_channel, _) = mpsc::channel(1);
+        let mut policy: PUCTPolicy<Breakthrough> = WithMCTSPolicy::new(
+            PUCTPolicy_ {
+                color: Color::Black,
+                config: puct_config(0.5),
+                prediction_channel,
+                feature_cache: None,
+                evaluator_warned: Arc::new(AtomicBool::new(false)),
+                min_tree: f32::MAX,
+                max_tree: -f32::MAX,
+            },
+            0,
+        );
+
+        let actions = state.possible_moves();
+        let mut moves: HashMap<_, _> = HashMap::from_iter(
+            actions
+                .iter()
+                .map(|m| (*m, policy.base_mcts.default_move(&state, m))),
+        );
+        moves.insert(actions[0], move_info(0.5, 3., 0.4));
+        moves.get_mut(&actions[0]).unwrap().reward = 1.;
+        moves.insert(actions[1], move_info(0.5, 1., -0.2));
+
+        policy.root = Some(Arc::new(RwLock::new(MCTSTreeNode {
+            parent: None,
+            moves: HashMap::new(),
+            info: MCTSNode {
+                node: policy.base_mcts.default_node(&state),
+                state,
+                reward: 0.,
+                moves,
+            },
+            last_access: 0,
+        })));
+
+        // ((1 + 0.5 * 0.4) * 3 + (0 + 0.5 * -0.2) * 1) / 4
+        let search_info = policy.search_info();
+        assert!((search_info.root_value - 0.875).abs() < 1e-5);
+        assert!((search_info.count - 4.).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_first_play_urgency() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let actions = state.possible_moves();
+
+        // the first move has been visited and found bad, while the position looks good.
+        let select = |config: settings::PUCT| {
+            let (prediction_channel, _) = mpsc::channel(1);
+            let policy: PUCTPolicy<Breakthrough> = WithMCTSPolicy::new(
+                PUCTPolicy_ {
+                    color: Color::Black,
+                    config,
+                    prediction_channel,
+                    feature_cache: None,
+                    evaluator_warned: Arc::new(AtomicBool::new(false)),
+                    min_tree: -1.,
+                    max_tree: 1.,
+                },
+                0,
+            );
+            let mut moves: HashMap<_, _> =
+                HashMap::from_iter(actions.iter().map(|m| (*m, move_info(0.01, 0., 0.))));
+            moves.insert(actions[0], move_info(0.9, 10., -0.5));
+            let root = MCTSTreeNode {
+                parent: None,
+                moves: HashMap::new(),
+                info: MCTSNode {
+                    node: PUCTNodeInfo {
+                        count: 10.,
+                        max_N_a: 10.,
+                        value: 0.8,
+                    },
+                    state: state.clone(),
+                    reward: 0.,
+                    moves,
+                },
+                last_access: 0,
+            };
+            policy.select_move(&root, true).unwrap()
+        };
+
+        let pessimistic = settings::PUCT {
+            fpu: -1.,
+            ..puct_config(1.)
+        };
+        assert_eq!(select(pessimistic), actions[0]);
+
+        let reduced = settings::PUCT {
+            fpu: -1.,
+            fpu_reduction: Some(0.2),
+            ..puct_config(1.)
+        };
+        assert_ne!(select(reduced), actions[0]);
+    }
+
+    /// Evaluator answering a policy and a value that depend on the state, so that
+    /// searches don't depend on the order in which ties are broken.
+    fn hashed_prediction_channel(size: usize) -> mpsc::Sender<PredictionEvaluatorChannel> {
+        let action_dimension = Breakthrough::action_dimension(&size);
+        let action_size = Breakthrough::num_actions(&size);
+        mock_alphazero_channel::<Breakthrough, _>(move |features| {
+            let seed = features.iter().enumerate().fold(0u64, |h, (i, x)| {
+                h.wrapping_mul(31) ^ (u64::from(x.to_bits()) + i as u64)
+            });
+            // pseudo-random number in [0, 1).
+            let noise = |k: u64| {
+                let x = (seed ^ k).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                (x >> 40) as f32 / (1u64 << 24) as f32
+            };
+            let policy: Vec<f32> = (0..action_size as u64)
+                .map(|a| (4. * noise(a)).exp())
+                .collect();
+            let value = 2. * noise(action_size as u64) - 1.;
+            (
+                Array::from_shape_vec(action_dimension, policy).unwrap(),
+                value,
+            )
+        })
+    }
+
+    fn check_consistency(
+        node: &Arc<RwLock<MCTSTreeNode<Breakthrough, PUCTPolicy_<Breakthrough>>>>,
+    ) {
+        let node = node.read().unwrap();
+        let total: f32 = node.info.moves.values().map(|v| v.N_a).sum();
+        if node.parent.is_some() && !node.moves.is_empty() {
+            assert!((node.info.node.count - total).abs() < 1e-4);
+        }
+        for v in node.info.moves.values() {
+            assert!(v.N_vl.abs() < 1e-4);
+        }
+        for child in node.moves.values() {
+            check_consistency(child);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_playouts_consistency() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let config = settings::PUCT {
+            parallel_playouts: 8,
+            ..puct_config(0.99)
+        };
+        let puct = PUCT {
+            config,
+            n_playouts: 203,
+            playouts_schedule: vec![],
+            prediction_channel: mock_uniform_channel::<Breakthrough>(&5),
+        };
+        let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+        policy.play(&state).await;
+
+        let search_info = policy.search_info();
+        assert!((search_info.count - 203.).abs() < 1e-4);
+        let root = policy.root.as_ref().unwrap();
+        assert!((root.read().unwrap().info.node.count - 203.).abs() < 1e-4);
+        check_consistency(root);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_playouts_stay_on_evaluated_nodes() {
+        // X O X
+        // O O X
+        // . . .   three moves left, X to play.
+        let mut state = TicTacToeBuilder {}.create(Color::Black).await;
+        for m in &[(0, 0), (0, 1), (0, 2), (1, 0), (1, 2), (1, 1)] {
+            state.play(m).await;
+        }
+        let config = settings::PUCT {
+            parallel_playouts: 8,
+            ..puct_config(0.99)
+        };
+        let puct = PUCT {
+            config,
+            n_playouts: 8,
+            playouts_schedule: vec![],
+            prediction_channel: mock_uniform_channel::<TicTacToe>(&()),
+        };
+        let mut policy: PUCTPolicy<TicTacToe> = puct.create(Color::Black);
+        policy.play(&state).await;
+
+        // the workers of a single batch only expand moves of the evaluated root.
+        let root = policy.root.as_ref().unwrap().read().unwrap();
+        assert!(!root.moves.is_empty());
+        for child in root.moves.values() {
+            assert!(child.read().unwrap().moves.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_time_budget_slow_evaluator() {
+        // the root is evaluated at once, then every evaluation takes 10 seconds.
+        let (tx, mut rx) = mpsc::channel::<PredictionEvaluatorChannel>(16);
+        tokio::spawn(async move {
+            let mut first = true;
+            while let Some((_, resp)) = rx.recv().await {
+                if !first {
+                    tokio::time::delay_for(Duration::from_secs(10)).await;
+                }
+                first = false;
+                let policy = vec![1. / 75.; 75];
+                let _ = resp.send((
+                    tensorflow::Tensor::from(&policy[..]),
+                    tensorflow::Tensor::from(&[0.][..]),
+                ));
+            }
+        });
+
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let config = settings::PUCT {
+            time_budget_ms: Some(50),
+            ..puct_config(0.99)
+        };
+        let puct = PUCT {
+            config,
+            n_playouts: 1000,
+            playouts_schedule: vec![],
+            prediction_channel: tx,
+        };
+        let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+
+        let start = std::time::Instant::now();
+        let action = policy.play(&state).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(state.possible_moves().contains(&action));
+        assert_eq!(policy.playouts_done(), 0);
+        // the abandoned node is removed from the tree.
+        let root = policy.root.as_ref().unwrap().read().unwrap();
+        assert!(root.moves.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_evaluator_failure() {
+        let mut state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let puct = PUCT {
+            config: puct_config(0.99),
+            n_playouts: 50,
+            playouts_schedule: vec![],
+            prediction_channel: mock_failing_channel::<Breakthrough>(&5, 20),
+        };
+        let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+
+        // the evaluator stops during the first search.
+        for _ in 0..2 {
+            let action = policy.play(&state).await.unwrap();
+            assert!(state.possible_moves().contains(&action));
+            state.play(&action).await;
+            state.random_move().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_playouts_schedule() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let puct = PUCT {
+            config: puct_config(0.99),
+            n_playouts: 100,
+            playouts_schedule: vec![(0, 50), (10, 200)],
+            prediction_channel: mock_uniform_channel::<Breakthrough>(&5),
+        };
+        let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+
+        for ply in 0..12 {
+            policy.play(&state).await;
+            let expected = if ply < 10 { 50. } else { 200. };
+            assert!(
+                (policy.search_info().count - expected).abs() < 1e-4,
+                "ply {}: {} playouts",
+                ply,
+                policy.search_info().count
+            );
+        }
+
+        policy.reset();
+        policy.play(&state).await;
+        assert!((policy.search_info().count - 50.).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_reset_searches_like_new() {
+        // no dirichlet noise: searches are deterministic.
+        let config = settings::PUCT {
+            root_exploration_fraction: 1.,
+            ..puct_config(0.99)
+        };
+        let puct = PUCT {
+            config,
+            n_playouts: 50,
+            playouts_schedule: vec![],
+            prediction_channel: hashed_prediction_channel(5),
+        };
+        let game_builder = BreakthroughBuilder { size: 5 };
+
+        // the first game leaves the Q value bounds set.
+        let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+        let mut state = game_builder.create(Color::Black).await;
+        while !state.is_finished() {
+            if state.turn() == Color::Black {
+                let action = policy.play(&state).await.unwrap();
+                state.play(&action).await;
+            } else {
+                state.random_move().await;
+            }
+        }
+        assert!(policy.base_mcts.max_tree > policy.base_mcts.min_tree);
+
+        policy.reset();
+        assert!(policy.root.is_none());
+
+        let mut fresh: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+        let mut state = game_builder.create(Color::Black).await;
+        while !state.is_finished() {
+            if state.turn() == Color::Black {
+                let action = policy.play(&state).await.unwrap();
+                fresh.play(&state).await;
+                assert_eq!(
+                    policy.search_info().visit_distribution,
+                    fresh.search_info().visit_distribution
+                );
+                state.play(&action).await;
+            } else {
+                state.random_move().await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exploration_noise_toggle() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let channel = hashed_prediction_channel(5);
+
+        async fn root_priors(
+            config: settings::PUCT,
+            channel: mpsc::Sender<PredictionEvaluatorChannel>,
+            state: &Breakthrough,
+        ) -> HashMap<Move, f32> {
+            let puct = PUCT {
+                config,
+                n_playouts: 1,
+                playouts_schedule: vec![],
+                prediction_channel: channel,
+            };
+            let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+            policy.play(state).await;
+            let root = policy.root.as_ref().unwrap().read().unwrap();
+            let mut priors = HashMap::new();
+            for (m, v) in root.info.moves.iter() {
+                priors.insert(*m, v.pi);
+            }
+            priors
+        }
+
+        let quiet = puct_config(0.99).without_exploration_noise();
+        let first = root_priors(quiet, channel.clone(), &state).await;
+        let second = root_priors(quiet, channel.clone(), &state).await;
+        assert_eq!(first, second);
+
+        for scale_dirichlet_alpha in &[false, true] {
+            let noisy = settings::PUCT {
+                scale_dirichlet_alpha: *scale_dirichlet_alpha,
+                ..puct_config(0.99)
+            };
+            let perturbed = root_priors(noisy, channel.clone(), &state).await;
+            assert!(first.iter().any(|(m, pi)| (perturbed[m] - pi).abs() > 1e-6));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prior_divergence_without_noise() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let channel = hashed_prediction_channel(5);
+        let search = |config| {
+            let puct = PUCT {
+                config,
+                n_playouts: 20,
+                playouts_schedule: vec![],
+                prediction_channel: channel.clone(),
+            };
+            let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+            let state = state.clone();
+            async move {
+                policy.play(&state).await;
+                policy
+            }
+        };
+
+        let quiet = search(puct_config(0.99).without_exploration_noise()).await;
+        let noisy = search(puct_config(0.99)).await;
+        let prior: HashMap<Move, f32> = {
+            let quiet_root = quiet.root.as_ref().unwrap().read().unwrap();
+            let noisy_root = noisy.root.as_ref().unwrap().read().unwrap();
+            for (m, v) in noisy_root.info.moves.iter() {
+                assert!((v.prior - quiet_root.info.moves[m].pi).abs() < 1e-6);
+            }
+            assert!(noisy_root
+                .info
+                .moves
+                .values()
+                .any(|v| (v.pi - v.prior).abs() > 1e-6));
+            noisy_root
+                .info
+                .moves
+                .iter()
+                .map(|(m, v)| (*m, v.prior))
+                .collect()
+        };
+
+        let visits = noisy.search_info().visit_distribution;
+        assert!((noisy.prior_divergence() - kl_divergence(&visits, &prior)).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_evaluation_settings() {
+        let self_play = settings::PUCT {
+            gumbel_scale: Some(1.),
+            ..puct_config(0.99)
+        };
+        let config = self_play.evaluation();
+        assert!(!config.add_exploration_noise);
+        assert!(config.gumbel_scale.is_none());
+
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let channel = hashed_prediction_channel(5);
+        let search = || {
+            let puct = PUCT {
+                config,
+                n_playouts: 50,
+                playouts_schedule: vec![],
+                prediction_channel: channel.clone(),
+            };
+            let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+            let state = state.clone();
+            async move {
+                let action = policy.play(&state).await.unwrap();
+                (action, policy.search_info().visit_distribution)
+            }
+        };
+
+        let (first_move, first_visits) = search().await;
+        let (second_move, second_visits) = search().await;
+        assert_eq!(first_move, second_move);
+        assert_eq!(first_visits, second_visits);
+    }
+
+    fn check_normalized(
+        policy: &PUCTPolicy_<Breakthrough>,
+        node: &Arc<RwLock<MCTSTreeNode<Breakthrough, PUCTPolicy_<Breakthrough>>>>,
+    ) {
+        let node = node.read().unwrap();
+        for v in node.info.moves.values().filter(|v| v.N_a > 0.) {
+            assert!(policy.min_tree <= v.Q && v.Q <= policy.max_tree);
+            let q = policy.normalize(v.Q);
+            assert!((0. ..=1.).contains(&q));
+        }
+        for child in node.moves.values() {
+            check_normalized(policy, child);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_normalized_values() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let puct = PUCT {
+            config: puct_config(0.99),
+            n_playouts: 100,
+            playouts_schedule: vec![],
+            prediction_channel: hashed_prediction_channel(5),
+        };
+        let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+        policy.play(&state).await;
+
+        assert!(policy.base_mcts.min_tree < policy.base_mcts.max_tree);
+        check_normalized(&policy.base_mcts, policy.root.as_ref().unwrap());
+    }
+
+    #[allow(clippy::float_cmp)]
+    #[tokio::test]
+    async fn test_normalize_single_value() {
+        let state: Breakthrough = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+        let puct = PUCT {
+            config: puct_config(1.),
+            n_playouts: 20,
+            playouts_schedule: vec![],
+            prediction_channel: mock_uniform_channel::<Breakthrough>(&5),
+        };
+        let mut policy: PUCTPolicy<Breakthrough> = puct.create(Color::Black);
+        policy.play(&state).await;
+
+        // every evaluation is 0: the tree holds a single value.
+        assert_eq!(policy.base_mcts.min_tree, policy.base_mcts.max_tree);
+        assert_eq!(policy.base_mcts.normalize(0.), 0.5);
+        check_normalized(&policy.base_mcts, policy.root.as_ref().unwrap());
     }
 }
@@ -1,6 +1,6 @@
 use crate::game::{Game, Playout, SingleWinner};
 use crate::policies::{
-    mcts::{BaseMCTSPolicy, MCTSTreeNode, WithMCTSPolicy},
+    mcts::{BaseMCTSPolicy, Budget, MCTSTreeNode, MoveStats, WithMCTSPolicy},
     MultiplayerPolicyBuilder,
 };
 use crate::settings;
@@ -29,6 +29,7 @@ pub struct RAVENodeInfo {
 pub struct RAVEPolicy_<G: Game> {
     color: G::Player,
     uct_weight: f32,
+    rave_bias: f32,
 }
 
 #[async_trait]
@@ -43,11 +44,14 @@ impl<G: super::MCTSGame + SingleWinner> BaseMCTSPolicy<G> for RAVEPolicy_<G> {
         _action: &G::Move,
         node_info: &Self::NodeInfo,
         move_info: &Self::MoveInfo,
-        _exploration: bool,
+        exploration: bool,
     ) -> f32 {
-        let optimistic = board.turn() == self.color;
-        let value = self.eval(*node_info, move_info, optimistic);
         let multiplier = if board.turn() == self.color { 1. } else { -1. };
+        let value = if exploration {
+            self.eval(*node_info, move_info, multiplier)
+        } else {
+            move_info.count
+        };
         multiplier * value
     }
 
@@ -96,10 +100,10 @@ impl<G: super::MCTSGame + SingleWinner> BaseMCTSPolicy<G> for RAVEPolicy_<G> {
             move_info.count += 1.;
             move_info.wins += (z - move_info.wins) / move_info.count;
 
-            // RAVE is weaker than UCT: TODO investigate
             /* Store AMAF statistics */
+            // every first move of the player in the rest of the game, including `action`.
             index -= 1;
-            for u in (index + 2..whole_history.len()).step_by(2) {
+            for u in (index..whole_history.len()).step_by(2) {
                 let action_u = whole_history[u];
                 if (index..u).step_by(2).all(|i| action_u != whole_history[i]) {
                     if let Some(mut v_amaf) = node.info.moves.get_mut(&action_u) {
@@ -145,11 +149,22 @@ impl<G: super::MCTSGame + SingleWinner> BaseMCTSPolicy<G> for RAVEPolicy_<G> {
         let default: Vec<G::Move> = default.iter().map(|(_, m)| *m).collect();
         (s.winner() == Some(self.color), default)
     }
+
+    fn move_stats(&self, move_info: &Self::MoveInfo) -> MoveStats {
+        MoveStats {
+            N_a: move_info.count,
+            Q: move_info.wins,
+            pi: 0.,
+            reward: 0.,
+        }
+    }
 }
 
 impl<G: super::MCTSGame> RAVEPolicy_<G> {
-    fn beta(v: &RAVEMoveInfo) -> f32 {
-        let b = 0.0001;
+    /// Weight of the AMAF value (Silver's schedule): close to 1 when the move
+    /// has few visits compared to its AMAF count, and decreasing to 0 as visits grow.
+    fn beta(&self, v: &RAVEMoveInfo) -> f32 {
+        let b = self.rave_bias;
         let mut div = v.count_AMAF + v.count + 4. * v.count_AMAF * v.count * b * b;
         if div == 0. {
             div = 1.
@@ -161,15 +176,11 @@ impl<G: super::MCTSGame> RAVEPolicy_<G> {
         self: &RAVEPolicy_<G>,
         node_info: RAVENodeInfo,
         v: &RAVEMoveInfo,
-        optimistic: bool,
+        multiplier: f32,
     ) -> f32 {
-        let multiplier = if optimistic { 1. } else { -1. };
-        let v_mean =
-            v.wins + multiplier * self.uct_weight * (node_info.count.ln() / (1. + v.count)).sqrt();
-        let v_AMAF = v.wins_AMAF;
-
-        let beta = Self::beta(v);
-        (1. - beta) * v_mean + beta * v_AMAF
+        let beta = self.beta(v);
+        let value = (1. - beta) * v.wins + beta * v.wins_AMAF;
+        value + multiplier * self.uct_weight * (node_info.count.ln() / (1. + v.count)).sqrt()
     }
 }
 
@@ -183,6 +194,10 @@ impl fmt::Display for RAVE {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "RAVE")?;
         writeln!(f, "|| uct_weight: {}", self.uct_weight)?;
+        writeln!(f, "|| rave_bias: {}", self.rave_bias)?;
+        if let Some(time_budget) = self.time_budget() {
+            writeln!(f, "|| time_budget: {:?}", time_budget)?;
+        }
         writeln!(f, "|| N_PLAYOUT: {}", self.playouts)
     }
 }
@@ -200,8 +215,88 @@ where
             RAVEPolicy_ {
                 color,
                 uct_weight: self.uct_weight,
+                rave_bias: self.rave_bias,
             },
-            self.playouts,
+            Budget::new(self.playouts, self.time_budget()),
         )
+        .with_search_log(self.log_search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+    use crate::game::{self, GameBuilder};
+    use crate::policies::combinator::Verified;
+    use crate::policies::mcts::tests::uct_config;
+
+    fn move_info(count: f32, count_AMAF: f32) -> RAVEMoveInfo {
+        RAVEMoveInfo {
+            wins: 0.,
+            count,
+            wins_AMAF: 0.,
+            count_AMAF,
+        }
+    }
+
+    #[test]
+    fn test_beta_schedule() {
+        let policy: RAVEPolicy_<Breakthrough> = RAVEPolicy_ {
+            color: Color::Black,
+            uct_weight: 0.4,
+            rave_bias: 0.1,
+        };
+
+        assert!((policy.beta(&move_info(0., 10.)) - 1.).abs() < 1e-6);
+        assert!(policy.beta(&move_info(2., 10.)) > 0.5);
+        assert!(policy.beta(&move_info(1000., 1000.)) < 0.1);
+
+        let mut previous = 1.;
+        for &count in &[1., 10., 100., 1000.] {
+            let beta = policy.beta(&move_info(count, 2. * count));
+            assert!(beta < previous);
+            previous = beta;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rave_beats_uct() {
+        let rave = settings::RAVE {
+            uct_weight: 0.4,
+            playouts: 100,
+            rave_bias: 0.1,
+            time_budget_ms: None,
+            log_search: false,
+        };
+        let (rave, uct) = (Verified::new(rave), Verified::new(uct_config(100)));
+        let players = [Color::Black, Color::White];
+
+        let n_games = 200;
+        let mut rave_wins = 0;
+        for i in 0..n_games {
+            let rave_color = players[i % 2];
+            let mut board = BreakthroughBuilder { size: 5 }
+                .create(players[(i / 2) % 2])
+                .await;
+
+            let p_rave = Box::new(rave.create(rave_color));
+            let p_uct = Box::new(uct.create(rave_color.adv()));
+            if rave_color == Color::Black {
+                game::simulate(p_rave, p_uct, &mut board, game::DrawRules::default()).await;
+            } else {
+                game::simulate(p_uct, p_rave, &mut board, game::DrawRules::default()).await;
+            }
+
+            if board.winner() == Some(rave_color) {
+                rave_wins += 1;
+            }
+        }
+        assert!(
+            rave_wins > n_games / 2,
+            "RAVE won {}/{}",
+            rave_wins,
+            n_games
+        );
     }
 }
@@ -1,17 +1,55 @@
-use crate::game::{Game, Playout, SingleWinner};
+use crate::game::{self, Base, Game, MoveCode, Playable, SingleWinner};
 use crate::policies::{
-    mcts::{BaseMCTSPolicy, MCTSTreeNode, WithMCTSPolicy},
-    MultiplayerPolicyBuilder,
+    mcts::{BaseMCTSPolicy, Budget, MCTSTreeNode, MoveStats, WithMCTSPolicy},
+    rollout, MultiplayerPolicyBuilder,
 };
 use crate::settings;
 
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::f32;
 use std::fmt;
 use std::sync::{Arc, RwLock};
 
 /* UCT */
 
+/// Static evaluation of a position, in [-1, 1] from the point of view of the given player.
+pub type Heuristic<G> = Arc<dyn Fn(&G, <G as Game>::Player) -> f32 + Send + Sync>;
+
+/// Initial value of a move, in [-1, 1] from the point of view of the player to move.
+pub type MovePrior<G> = Arc<dyn Fn(&G, &<G as Base>::Move) -> f32 + Send + Sync>;
+
+/// Evaluation of the leaves of a UCT search.
+pub enum LeafEval<G: Game> {
+    /// Outcome of a playout until the end of the game.
+    Rollout,
+    /// Heuristic value of the leaf.
+    Heuristic(Heuristic<G>),
+    /// Playout of at most the given number of moves,
+    /// followed by the heuristic value of the reached position if the game is not finished.
+    RolloutHeuristic(usize, Heuristic<G>),
+}
+
+impl<G: Game> Clone for LeafEval<G> {
+    fn clone(&self) -> Self {
+        match self {
+            LeafEval::Rollout => LeafEval::Rollout,
+            LeafEval::Heuristic(h) => LeafEval::Heuristic(h.clone()),
+            LeafEval::RolloutHeuristic(depth, h) => LeafEval::RolloutHeuristic(*depth, h.clone()),
+        }
+    }
+}
+
+impl<G: Game> fmt::Debug for LeafEval<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeafEval::Rollout => write!(f, "Rollout"),
+            LeafEval::Heuristic(_) => write!(f, "Heuristic"),
+            LeafEval::RolloutHeuristic(depth, _) => write!(f, "RolloutHeuristic({})", depth),
+        }
+    }
+}
+
 /// UCT move information.
 #[derive(Debug, Clone, Copy)]
 pub struct UCTMoveInfo {
@@ -32,6 +70,11 @@ pub struct UCTNodeInfo {
 pub struct UCTPolicy_<G: Game> {
     color: G::Player,
     uct_weight: f32,
+    rollout: settings::RolloutKind,
+    leaf_eval: LeafEval<G>,
+    progressive_widening: Option<(f32, f32)>,
+    early_stop: Option<settings::EarlyStop>,
+    prior: Option<MovePrior<G>>,
 }
 
 #[async_trait]
@@ -42,7 +85,8 @@ where
 {
     type NodeInfo = UCTNodeInfo;
     type MoveInfo = UCTMoveInfo;
-    type PlayoutInfo = bool;
+    /// Value of the leaf for the policy's player, in [0, 1].
+    type PlayoutInfo = f32;
 
     fn get_value(
         &self,
@@ -69,8 +113,14 @@ where
         UCTNodeInfo { count: 0. }
     }
 
-    fn default_move(&self, _board: &G, _action: &G::Move) -> Self::MoveInfo {
-        UCTMoveInfo { Q: 0., N_a: 0. }
+    fn default_move(&self, board: &G, action: &G::Move) -> Self::MoveInfo {
+        // the prior is only used until the first visit, which overwrites the value.
+        let Q = match &self.prior {
+            Some(prior) if board.turn() == self.color => prior(board, action),
+            Some(prior) => -prior(board, action),
+            None => 0.,
+        };
+        UCTMoveInfo { Q, N_a: 0. }
     }
 
     fn backpropagate(
@@ -79,7 +129,7 @@ where
         _history: &[G::Move],
         playout: Self::PlayoutInfo,
     ) {
-        let z = if playout { 1. } else { 0. };
+        let z = playout;
 
         let mut current_node = leaf;
         while current_node.read().unwrap().parent.is_some() {
@@ -128,7 +178,58 @@ where
     }*/
 
     async fn simulate(&self, board: &G) -> <Self as BaseMCTSPolicy<G>>::PlayoutInfo {
-        board.playout_board(self.color).await.0.winner() == Some(self.color)
+        match &self.leaf_eval {
+            LeafEval::Rollout => {
+                let winner = rollout(board, self.color, self.rollout).await.winner();
+                if winner == Some(self.color) {
+                    1.
+                } else {
+                    0.
+                }
+            }
+            LeafEval::Heuristic(heuristic) => (1. + heuristic(board, self.color)) / 2.,
+            LeafEval::RolloutHeuristic(depth, heuristic) => {
+                let mut state = board.clone();
+                for _ in 0..*depth {
+                    if state.is_finished() {
+                        break;
+                    }
+                    match self.rollout {
+                        settings::RolloutKind::Random => {
+                            state.random_move().await;
+                        }
+                        settings::RolloutKind::Decisive => {
+                            let action = game::decisive_move(&state).await;
+                            state.play(&action).await;
+                        }
+                    }
+                }
+
+                let value = if state.is_finished() {
+                    state.outcome().value(&self.color)
+                } else {
+                    heuristic(&state, self.color)
+                };
+                (1. + value) / 2.
+            }
+        }
+    }
+
+    fn move_stats(&self, move_info: &Self::MoveInfo) -> MoveStats {
+        MoveStats {
+            N_a: move_info.N_a,
+            Q: move_info.Q,
+            pi: 0.,
+            reward: 0.,
+        }
+    }
+
+    fn progressive_widening(&self) -> Option<(f32, f32)> {
+        self.progressive_widening
+    }
+
+    fn early_stop(&self) -> Option<settings::EarlyStop> {
+        self.early_stop
     }
 }
 
@@ -138,10 +239,65 @@ pub type UCTPolicy<G> = WithMCTSPolicy<G, UCTPolicy_<G>>;
 /// UCT policy builder.
 type UCT = settings::UCT;
 
+impl UCT {
+    /// UCT policy builder evaluating the leaves with `leaf_eval` instead of rollouts.
+    pub fn with_leaf_eval<G: Game>(self, leaf_eval: LeafEval<G>) -> UCTWithLeafEval<G> {
+        UCTWithLeafEval {
+            config: self,
+            leaf_eval,
+            prior: None,
+        }
+    }
+}
+
+/// UCT policy builder with a custom leaf evaluation.
+pub struct UCTWithLeafEval<G: Game> {
+    /// UCT settings.
+    pub config: UCT,
+    /// Leaf evaluation.
+    pub leaf_eval: LeafEval<G>,
+    /// Initial value of the moves before their first visit, zero if not given.
+    pub prior: Option<MovePrior<G>>,
+}
+
+impl<G: Game + 'static> UCTWithLeafEval<G> {
+    /// Seeds the initial value of the moves with the weights of their `M` code.
+    /// Moves whose code has no weight start at zero.
+    pub fn with_prior<M: MoveCode<G> + 'static>(self, prior: HashMap<usize, f32>) -> Self {
+        let prior: MovePrior<G> = Arc::new(move |board: &G, action: &G::Move| {
+            *prior.get(&M::code(board, action)).unwrap_or(&0.)
+        });
+        UCTWithLeafEval {
+            prior: Some(prior),
+            ..self
+        }
+    }
+}
+
+impl<G: Game> fmt::Display for UCTWithLeafEval<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.config)?;
+        if self.prior.is_some() {
+            writeln!(f, "|| prior: MoveCode weights")?;
+        }
+        writeln!(f, "|| leaf_eval: {:?}", self.leaf_eval)
+    }
+}
+
 impl fmt::Display for UCT {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "UCT")?;
         writeln!(f, "|| uct_weight: {}", self.uct_weight)?;
+        writeln!(f, "|| rollout: {:?}", self.rollout)?;
+        if let Some((c, alpha)) = self.progressive_widening {
+            writeln!(f, "|| progressive_widening: C = {}, alpha = {}", c, alpha)?;
+        }
+        if let Some(early_stop) = self.early_stop {
+            writeln!(f, "|| early_stop: {:?}", early_stop)?;
+        }
+        if let Some(time_budget) = self.time_budget() {
+            writeln!(f, "|| time_budget: {:?}", time_budget)?;
+        }
         writeln!(f, "|| N_PLAYOUT: {}", self.playouts)
     }
 }
@@ -155,12 +311,32 @@ where
     type P = UCTPolicy<G>;
 
     fn create(&self, color: G::Player) -> Self::P {
+        self.with_leaf_eval(LeafEval::Rollout).create(color)
+    }
+}
+
+impl<G> MultiplayerPolicyBuilder<G> for UCTWithLeafEval<G>
+where
+    G::Move: Send,
+    G::Player: Send,
+    G: super::MCTSGame + SingleWinner,
+{
+    type P = UCTPolicy<G>;
+
+    fn create(&self, color: G::Player) -> Self::P {
+        let config = &self.config;
         WithMCTSPolicy::new(
             UCTPolicy_ {
                 color,
-                uct_weight: self.uct_weight,
+                uct_weight: config.uct_weight,
+                rollout: config.rollout,
+                leaf_eval: self.leaf_eval.clone(),
+                progressive_widening: config.progressive_widening,
+                early_stop: config.early_stop,
+                prior: self.prior.clone(),
             },
-            self.playouts,
+            Budget::new(config.playouts, config.time_budget()),
         )
+        .with_search_log(config.log_search)
     }
 }
@@ -1,14 +1,23 @@
-use crate::game::{Game, NoFeatures};
+use crate::game::{Game, NoFeatures, Playout};
 use crate::settings;
 
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::fmt::Display;
 
+///
+/// Policies combining other policies.
+///
+pub mod combinator;
 ///
 /// Policies that doesn't perform any tree search.
 ///
 pub mod flat;
 ///
+/// Policy reading the moves of a human player from the terminal.
+///
+pub mod human;
+///
 /// Monte-Carlo Tree Search (MCTS) based policies.
 ///
 pub mod mcts;
@@ -25,8 +34,38 @@ pub mod nrpa;
 ///
 pub mod ppa;
 
+/// Simulate a game from the board until reaching a final state,
+/// selecting moves according to the rollout kind.
+pub async fn rollout<G>(board: &G, pov: G::Player, kind: settings::RolloutKind) -> G
+where
+    G: game::SingleWinner + Clone,
+{
+    match kind {
+        settings::RolloutKind::Random => board.playout_board(pov).await.0,
+        settings::RolloutKind::Decisive => game::decisive_playout(board, pov).await.0,
+    }
+}
+
 /* MULTIPLAYER POLICY TRAITS */
 
+/// Evaluation of the position that led a policy to its move.
+#[derive(Debug, Clone)]
+pub struct PlayStats<G: game::Base> {
+    /// Value estimate of the position, from the point of view of the player.
+    pub value: Option<f32>,
+    /// Normalized visit count distribution over the moves.
+    pub visit_distribution: Option<HashMap<G::Move, f32>>,
+}
+
+impl<G: game::Base> Default for PlayStats<G> {
+    fn default() -> Self {
+        PlayStats {
+            value: None,
+            visit_distribution: None,
+        }
+    }
+}
+
 ///
 ///A static policy.
 ///
@@ -34,8 +73,24 @@ pub mod ppa;
 pub trait MultiplayerPolicy<T: Game> {
     ///
     /// Chooses the next action given the current game state.
+    /// Returns `None` if the game is finished, as there is no move to choose.
+    ///
+    async fn play(&mut self, board: &T) -> Option<T::Move>;
+
     ///
-    async fn play(&mut self, board: &T) -> T::Move;
+    /// Chooses the next action, along with the statistics gathered to choose it.
+    /// Policies that don't search report no statistics.
+    ///
+    async fn play_with_stats(&mut self, board: &T) -> Option<(T::Move, PlayStats<T>)> {
+        let action = self.play(board).await?;
+        Some((action, PlayStats::default()))
+    }
+
+    ///
+    /// Clears the state kept from the previous moves, so that the policy can be reused
+    /// for a new game.
+    ///
+    fn reset(&mut self) {}
 }
 ///
 ///A static policy builder.
@@ -105,6 +160,8 @@ where
         "rand" => Box::new(flat::Random {}),
         "flat" => Box::new(config.policies.flat),
         "flat_ucb" => Box::new(config.policies.flat_ucb),
+        "flat_ucb_tuned" => Box::new(config.policies.flat_ucb_tuned),
+        "greedy" => Box::new(flat::Greedy::new(flat::outcome::<G>)),
         "uct" => Box::new(config.policies.uct),
         "rave" => Box::new(config.policies.rave),
         "ppa" => Box::new(ppa::PPA::<_, NoFeatures>::new(config.policies.ppa)),
@@ -112,3 +169,48 @@ where
         _ => panic!("Policy '{}' not found.", name),
     }
 }
+
+/// Creates the policies of given names for player `color`, as a list of
+/// interchangeable policies.
+pub fn build_all<'a, G>(
+    config: &settings::Config,
+    names: &[&str],
+    color: G::Player,
+) -> Vec<game::MatchPolicy<'a, G>>
+where
+    G: mcts::MCTSGame + game::SingleWinner + 'a + std::hash::Hash + Eq,
+    G::Move: Send,
+{
+    names
+        .iter()
+        .map(|name| get_multi(config.clone(), name).create(color))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::breakthrough::{Breakthrough, BreakthroughBuilder, Color};
+    use crate::game::{Base, GameBuilder};
+
+    #[tokio::test]
+    async fn test_build_all() {
+        let config: settings::Config = serde_json::from_str(
+            r#"{
+                "game": { "kind": "Breakthrough", "size": 5 },
+                "self_play": { "batch_size": 1, "evaluators": 1, "generators": 1 },
+                "mcts": { "playouts": 50 }
+            }"#,
+        )
+        .unwrap();
+        let state = BreakthroughBuilder { size: 5 }.create(Color::Black).await;
+
+        let mut policies =
+            build_all::<Breakthrough>(&config, &["rand", "uct", "flat"], Color::Black);
+        assert_eq!(policies.len(), 3);
+        for policy in policies.iter_mut() {
+            let action = policy.play(&state).await.unwrap();
+            assert!(state.possible_moves().contains(&action));
+        }
+    }
+}
@@ -68,6 +68,13 @@ pub struct NMCS {
     level: usize,
 }
 
+impl NMCS {
+    /// Instanciate a new policy builder for NMCS, given the nesting level.
+    pub fn new(level: usize) -> Self {
+        NMCS { level }
+    }
+}
+
 impl Default for NMCS {
     fn default() -> NMCS {
         NMCS { level: 2 }
@@ -137,13 +144,7 @@ impl<G: Game + SingleWinner + Clone> MultiNMCSPolicy<G> {
                 d += 1.;
             }
 
-            let score = if board.winner() == Some(self.color) {
-                1.
-            } else if !board.is_finished() {
-                0.0
-            } else {
-                -1.0
-            };
+            let score = board.outcome().value(&self.color);
 
             if self.s.discounting {
                 score / d
@@ -157,7 +158,7 @@ impl<G: Game + SingleWinner + Clone> MultiNMCSPolicy<G> {
 
 #[async_trait]
 impl<G: Game + SingleWinner + Clone> MultiplayerPolicy<G> for MultiNMCSPolicy<G> {
-    async fn play(self: &mut MultiNMCSPolicy<G>, board: &G) -> G::Move {
+    async fn play(self: &mut MultiNMCSPolicy<G>, board: &G) -> Option<G::Move> {
         let mut best_move = None;
         let mut max_visited = 0.;
 
@@ -173,7 +174,7 @@ impl<G: Game + SingleWinner + Clone> MultiplayerPolicy<G> for MultiNMCSPolicy<G>
                 best_move = Some(m);
             }
         }
-        best_move.unwrap()
+        best_move
     }
 }
 
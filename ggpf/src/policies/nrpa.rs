@@ -1,5 +1,6 @@
 use super::super::game::{MoveCode, Singleplayer};
 use super::{SingleplayerPolicy, SingleplayerPolicyBuilder};
+use crate::settings;
 
 use futures::future::{BoxFuture, FutureExt};
 use rand::seq::SliceRandom;
@@ -68,10 +69,10 @@ impl<G: Singleplayer + Clone, M: MoveCode<G>> NRPAPolicy<G, M> {
                 //println!("{:?}", history);
                 (score, history)
             } else {
-                let mut best_score = 0.;
+                let mut best_score = f32::NEG_INFINITY;
                 let mut best_hist = vec![];
 
-                for _ in 0..self.s.N {
+                for _ in 0..self.s.config.iterations {
                     let (result, history) =
                         self.nested(board, level - 1, playout_policy.clone()).await;
                     if result >= best_score {
@@ -102,26 +103,23 @@ impl<G: Singleplayer + Clone, M: MoveCode<G>> NRPAPolicy<G, M> {
         history: &[G::Move],
         playout_policy: &mut HashMap<usize, f32>,
     ) {
+        let alpha = self.s.config.alpha;
+        // probabilities are computed with the policy before adaptation.
+        let policy = playout_policy.clone();
+        let weight = |code: usize| policy.get(&code).unwrap_or(&0.).exp();
+
         let mut board = board.clone();
         for action in history {
             let move_node = playout_policy.entry(M::code(&board, action)).or_insert(0.);
-            *move_node += self.s.alpha;
-
-            let z: f32 = board
-                .possible_moves()
-                .iter()
-                .map(|m| {
-                    playout_policy
-                        .get(&M::code(&board, &m))
-                        .unwrap_or(&0.)
-                        .exp()
-                })
-                .sum();
-
-            for m in board.possible_moves() {
-                let move_node = playout_policy.entry(M::code(&board, &m)).or_insert(0.);
-                let v = move_node.exp();
-                *move_node -= self.s.alpha * v / z;
+            *move_node += alpha;
+
+            let moves = board.possible_moves();
+            let z: f32 = moves.iter().map(|m| weight(M::code(&board, m))).sum();
+
+            for m in moves.iter() {
+                let code = M::code(&board, m);
+                let move_node = playout_policy.entry(code).or_insert(0.);
+                *move_node -= alpha * weight(code) / z;
             }
 
             board.play(action).await;
@@ -133,33 +131,26 @@ use async_trait::async_trait;
 #[async_trait]
 impl<G: Singleplayer + Clone, M: MoveCode<G> + Send> SingleplayerPolicy<G> for NRPAPolicy<G, M> {
     async fn solve(self: &mut NRPAPolicy<G, M>, board: &G) -> Vec<G::Move> {
-        let (_, policy) = self.nested(board, self.s.level, HashMap::new()).await;
+        let (_, policy) = self
+            .nested(board, self.s.config.level, HashMap::new())
+            .await;
         policy
     }
 }
 
 /// Nested Rollout Policy Adaptation policy builder.
 pub struct NRPA<G: Singleplayer, M: MoveCode<G>> {
-    N: usize,
-    level: usize,
-    alpha: f32,
+    /// NRPA settings.
+    pub config: settings::NRPA,
     _m: PhantomData<M>,
     _g: PhantomData<G>,
 }
 
 impl<G: Singleplayer, M: MoveCode<G>> NRPA<G, M> {
-    /// Instanciate a new policy builder for NRPA
-    ///
-    /// # Params
-    ///
-    /// - `N`: number of calls per level.
-    /// - `level`: nesting level.
-    /// - `alpha`: policy gradient multiplier.
-    pub fn new(N: usize, level: usize, alpha: f32) -> Self {
+    /// Instanciate a new policy builder for NRPA.
+    pub fn new(config: settings::NRPA) -> Self {
         Self {
-            N,
-            level,
-            alpha,
+            config,
             _m: PhantomData,
             _g: PhantomData,
         }
@@ -176,13 +167,7 @@ impl<G: Singleplayer, M: MoveCode<G>> Clone for NRPA<G, M> {
 
 impl<G: Singleplayer, M: MoveCode<G>> Default for NRPA<G, M> {
     fn default() -> NRPA<G, M> {
-        NRPA::<G, M> {
-            N: 1,
-            alpha: 1.0,
-            level: 3,
-            _m: PhantomData,
-            _g: PhantomData,
-        }
+        NRPA::new(settings::NRPA::default())
     }
 }
 
@@ -196,3 +181,41 @@ impl<G: Singleplayer + Clone, M: MoveCode<G> + Send> SingleplayerPolicyBuilder<G
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::weak_schur::{WeakSchurCode, WeakSchurNumber, WeakSchurNumberBuilder};
+    use crate::game::{Playable, SingleplayerGameBuilder};
+
+    /// Mean number of integers placed in 4 partitions, over 20 runs.
+    async fn mean_score(level: usize, iterations: usize) -> f32 {
+        let game_builder = WeakSchurNumberBuilder {
+            partitions: 4,
+            bound: None,
+            consecutive_rule: false,
+        };
+        let builder = NRPA::<WeakSchurNumber, WeakSchurCode>::new(settings::NRPA {
+            level,
+            iterations,
+            alpha: 1.,
+        });
+
+        let mut total = 0.;
+        for _ in 0..20 {
+            let mut state = SingleplayerGameBuilder::create(&game_builder).await;
+            for action in builder.create().solve(&state).await {
+                total += state.play(&action).await;
+            }
+        }
+        total / 20.
+    }
+
+    #[tokio::test]
+    async fn test_level_improves_score() {
+        // same budget of 400 rollouts.
+        let level_1 = mean_score(1, 400).await;
+        let level_2 = mean_score(2, 20).await;
+        assert!(level_2 >= level_1);
+    }
+}
@@ -218,7 +218,10 @@ where
     G: Game + SingleWinner + Clone + Eq + Hash,
     M: MoveCode<G>,
 {
-    async fn play(self: &mut PPAPolicy<G, M>, board: &G) -> G::Move {
+    async fn play(self: &mut PPAPolicy<G, M>, board: &G) -> Option<G::Move> {
+        if board.is_finished() {
+            return None;
+        }
         for _ in 0..self.s.config.playouts {
             self.simulate(board).await
         }
@@ -234,7 +237,12 @@ where
                 best_move = Some(m);
             }
         }
-        best_move.unwrap()
+        best_move
+    }
+
+    fn reset(&mut self) {
+        self.tree.clear();
+        self.playout_policy.clear();
     }
 }
 
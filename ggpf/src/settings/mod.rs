@@ -1,6 +1,7 @@
 //! Settings: configuration file definitions and utilities.
 
 use serde_derive::Deserialize;
+use std::time::Duration;
 
 #[derive(Deserialize, Clone, Debug)]
 #[serde(tag = "kind")]
@@ -22,6 +23,22 @@ pub enum Game {
         /// Gym executor remote address.
         #[serde(default = "default_remote")]
         remote: String,
+        /// Run the environment in-process instead of using the Gym executor.
+        /// Python calls are serialized, this is meant for single-threaded generation.
+        #[serde(default)]
+        local: bool,
+        /// Number of values of each dimension of a continuous action space.
+        #[serde(default = "default_action_bins")]
+        action_bins: usize,
+        /// Number of environment steps each action is repeated for.
+        #[serde(default = "default_frame_skip")]
+        frame_skip: usize,
+        /// Deadline of each request to the Gym executor, in milliseconds.
+        #[serde(default = "default_timeout_ms")]
+        timeout_ms: u64,
+        /// Number of reconnections to the Gym executor after a failed request.
+        #[serde(default = "default_max_retries")]
+        max_retries: usize,
     },
 }
 
@@ -29,6 +46,22 @@ fn default_remote() -> String {
     "localhost:1337".into()
 }
 
+fn default_action_bins() -> usize {
+    3
+}
+
+fn default_frame_skip() -> usize {
+    1
+}
+
+fn default_timeout_ms() -> u64 {
+    10000
+}
+
+fn default_max_retries() -> usize {
+    3
+}
+
 impl Game {
     /// Game display name.
     pub fn name(&self) -> String {
@@ -56,22 +89,83 @@ pub struct SelfPlay {
     pub evaluators: usize,
     /// Number of generators: tasks that generate games.
     pub generators: usize,
+    /// Resign when the root value of the player to move stays below this threshold.
+    pub resign_threshold: Option<f32>,
+    #[serde(default = "default_resign_plies")]
+    /// Number of consecutive moves below the threshold before resigning.
+    pub resign_plies: usize,
+    #[serde(default = "default_resign_disabled_fraction")]
+    /// Fraction of games where resignation is not applied, to measure false positives.
+    pub resign_disabled_fraction: f32,
+    #[serde(default)]
+    /// Adapt the prediction batch size, up to `batch_size`, to reach the target latency.
+    pub adaptive: bool,
+    #[serde(default = "default_target_latency_ms")]
+    /// Target latency of a prediction request in adaptive mode, in milliseconds.
+    pub target_latency_ms: f32,
+    #[serde(default)]
+    /// Check that the policies only play legal moves, panicking otherwise.
+    pub verify_moves: bool,
+}
+
+impl SelfPlay {
+    /// Target latency of a prediction request, if the batch size is adaptive.
+    pub fn target_latency(&self) -> Option<Duration> {
+        if self.adaptive {
+            Some(Duration::from_secs_f32(self.target_latency_ms / 1000.))
+        } else {
+            None
+        }
+    }
+}
+
+fn default_resign_plies() -> usize {
+    3
+}
+
+fn default_target_latency_ms() -> f32 {
+    5.
+}
+
+fn default_resign_disabled_fraction() -> f32 {
+    0.1
 }
 
 const DEFAULT_PLAYOUTS: usize = 200;
 
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+/// Move selection in playouts.
+pub enum RolloutKind {
+    /// Uniformly random moves.
+    Random,
+    /// Immediately winning moves first, then moves that don't let the opponent win immediately.
+    Decisive,
+}
+
+impl Default for RolloutKind {
+    fn default() -> Self {
+        RolloutKind::Random
+    }
+}
+
 /* Standard policies */
-#[derive(Deserialize, Copy, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 /// MCTS-based policies settings.
 pub struct MCTS {
     /// Number of playouts per turn.
     pub playouts: usize,
+    #[serde(default)]
+    /// `(threshold, playouts)` schedule of the PUCT and MuZero searches, overriding
+    /// `playouts` once the policy has played `threshold` moves. The opponent's moves
+    /// aren't counted.
+    pub playouts_schedule: Vec<(usize, usize)>,
 }
 
 impl Default for MCTS {
     fn default() -> Self {
         Self {
             playouts: DEFAULT_PLAYOUTS,
+            playouts_schedule: vec![],
         }
     }
 }
@@ -84,6 +178,22 @@ pub struct RAVE {
     pub uct_weight: f32,
     /// Number of playouts per turn.
     pub playouts: usize,
+    #[serde(default = "default_rave_bias")]
+    /// RAVE bias: the lower it is, the longer AMAF statistics are trusted.
+    pub rave_bias: f32,
+    #[serde(default)]
+    /// Time limit per move (in milliseconds), in addition to the playouts.
+    pub time_budget_ms: Option<u64>,
+    #[serde(default)]
+    /// Log the search diagnostics of each move, at debug level.
+    pub log_search: bool,
+}
+
+impl RAVE {
+    /// Time limit per move.
+    pub fn time_budget(&self) -> Option<Duration> {
+        self.time_budget_ms.map(Duration::from_millis)
+    }
 }
 
 impl Default for RAVE {
@@ -91,10 +201,17 @@ impl Default for RAVE {
         Self {
             uct_weight: default_uct(),
             playouts: DEFAULT_PLAYOUTS,
+            rave_bias: default_rave_bias(),
+            time_budget_ms: None,
+            log_search: false,
         }
     }
 }
 
+fn default_rave_bias() -> f32 {
+    0.1
+}
+
 #[derive(Deserialize, Copy, Clone, Debug)]
 /// UCT settings.
 pub struct UCT {
@@ -103,6 +220,29 @@ pub struct UCT {
     pub uct_weight: f32,
     /// Number of playouts per turn.
     pub playouts: usize,
+    #[serde(default)]
+    /// Playout move selection.
+    pub rollout: RolloutKind,
+    #[serde(default)]
+    /// Progressive widening `(C, alpha)`: a node visited `N` times only considers
+    /// its `ceil(C * N^alpha)` first moves.
+    pub progressive_widening: Option<(f32, f32)>,
+    #[serde(default)]
+    /// Stop the search once the best move is decided.
+    pub early_stop: Option<EarlyStop>,
+    #[serde(default)]
+    /// Time limit per move (in milliseconds), in addition to the playouts.
+    pub time_budget_ms: Option<u64>,
+    #[serde(default)]
+    /// Log the search diagnostics of each move, at debug level.
+    pub log_search: bool,
+}
+
+impl UCT {
+    /// Time limit per move.
+    pub fn time_budget(&self) -> Option<Duration> {
+        self.time_budget_ms.map(Duration::from_millis)
+    }
 }
 
 impl Default for UCT {
@@ -110,10 +250,25 @@ impl Default for UCT {
         Self {
             uct_weight: default_uct(),
             playouts: DEFAULT_PLAYOUTS,
+            rollout: RolloutKind::default(),
+            progressive_widening: None,
+            early_stop: None,
+            time_budget_ms: None,
+            log_search: false,
         }
     }
 }
 
+#[derive(Deserialize, Copy, Clone, Debug)]
+/// Early stopping of tree searches: the search ends when the most visited root move
+/// can't be overtaken with the remaining playouts.
+pub struct EarlyStop {
+    /// Number of playouts between two checks.
+    pub check_every: usize,
+    /// Visits lead required on top of the remaining playouts.
+    pub margin: usize,
+}
+
 #[derive(Deserialize, Copy, Clone, Debug)]
 /// Flat UCB Monte Carlo settings.
 pub struct FlatUCBMonteCarlo {
@@ -133,17 +288,36 @@ impl Default for FlatUCBMonteCarlo {
     }
 }
 
+#[derive(Deserialize, Copy, Clone, Debug)]
+/// Flat UCB1-Tuned Monte Carlo settings.
+pub struct FlatUCB1Tuned {
+    /// Number of playouts per turn.
+    pub playouts: usize,
+}
+
+impl Default for FlatUCB1Tuned {
+    fn default() -> Self {
+        Self {
+            playouts: DEFAULT_PLAYOUTS,
+        }
+    }
+}
+
 #[derive(Deserialize, Copy, Clone, Debug)]
 /// Flat Monte Carlo settings.
 pub struct FlatMonteCarlo {
     /// Number of playouts per turn.
     pub playouts: usize,
+    #[serde(default)]
+    /// Playout move selection.
+    pub rollout: RolloutKind,
 }
 
 impl Default for FlatMonteCarlo {
     fn default() -> Self {
         Self {
             playouts: DEFAULT_PLAYOUTS,
+            rollout: RolloutKind::default(),
         }
     }
 }
@@ -174,6 +348,27 @@ fn default_uct() -> f32 {
     0.4
 }
 
+#[derive(Deserialize, Copy, Clone, Debug)]
+/// NRPA settings.
+pub struct NRPA {
+    /// Nesting level.
+    pub level: usize,
+    /// Number of iterations at each level.
+    pub iterations: usize,
+    /// α value used in policy gradient.
+    pub alpha: f32,
+}
+
+impl Default for NRPA {
+    fn default() -> Self {
+        Self {
+            level: 3,
+            iterations: 20,
+            alpha: 1.,
+        }
+    }
+}
+
 #[derive(Deserialize, Copy, Clone, Debug, Default)]
 /// Policies settings node.
 pub struct Policies {
@@ -190,8 +385,14 @@ pub struct Policies {
     /// Flat UCB Monte Carlo settings
     pub flat_ucb: FlatUCBMonteCarlo,
     #[serde(default)]
+    /// Flat UCB1-Tuned Monte Carlo settings
+    pub flat_ucb_tuned: FlatUCB1Tuned,
+    #[serde(default)]
     /// UCT settings
     pub uct: UCT,
+    #[serde(default)]
+    /// NRPA settings
+    pub nrpa: NRPA,
 }
 /* DL-based policies */
 #[derive(Deserialize, Copy, Clone, Debug)]
@@ -207,8 +408,249 @@ pub struct PUCT {
     pub root_dirichlet_alpha: f32,
     /// Root exploration fraction.
     pub root_exploration_fraction: f32,
+    #[serde(default = "default_add_exploration_noise")]
+    /// Add Dirichlet noise to the root priors. Enabled by default for self-play,
+    /// evaluation disables it.
+    pub add_exploration_noise: bool,
+    #[serde(default)]
+    /// Use `root_dirichlet_alpha / number of legal moves` as Dirichlet alpha.
+    pub scale_dirichlet_alpha: bool,
     /// Value support encoding.
     pub value_support: Option<usize>,
+    /// Gumbel scale (`c_scale`). When set, the root moves are searched by sequential
+    /// halving and the played move is selected by Gumbel sampling instead of visit counts,
+    /// and the policy target is the improved policy.
+    pub gumbel_scale: Option<f32>,
+    #[serde(default = "default_gumbel_considered_actions")]
+    /// Number of root moves considered by the sequential halving in Gumbel mode
+    /// (`m` in the Gumbel MuZero paper).
+    pub gumbel_considered_actions: usize,
+    #[serde(default = "default_parallel_playouts")]
+    /// Number of playouts performed concurrently on the same tree, using virtual loss.
+    pub parallel_playouts: usize,
+    #[serde(default)]
+    /// Expand the leaves selected by the parallel playouts together, so that the
+    /// MuZero dynamics inferences of one search iteration are requested as one batch.
+    pub batch_expansions: bool,
+    #[serde(default)]
+    /// Stop the search once the best move is decided. Ignored with Gumbel sampling,
+    /// as the selected move doesn't follow visit counts.
+    pub early_stop: Option<EarlyStop>,
+    #[serde(default)]
+    /// Time limit per move (in milliseconds), in addition to the playouts.
+    pub time_budget_ms: Option<u64>,
+    #[serde(default)]
+    /// Number of state features kept in memory by each policy, so that identical
+    /// states are featurized once. Zero disables the cache.
+    pub feature_cache: usize,
+    #[serde(skip, default = "default_value_transform")]
+    /// `ε` of the value transform used by the value support, if any. Not read from the
+    /// configuration: MuZero sets it from its own settings.
+    pub value_transform: Option<f32>,
+    #[serde(default)]
+    /// First-play urgency: value of the moves that haven't been visited yet.
+    pub fpu: f32,
+    #[serde(default)]
+    /// When set, unvisited moves take the value of their parent node minus this
+    /// reduction instead of `fpu`.
+    pub fpu_reduction: Option<f32>,
+    #[serde(default)]
+    /// Log the search diagnostics of each move, at debug level.
+    pub log_search: bool,
+}
+
+impl PUCT {
+    /// Time limit per move.
+    pub fn time_budget(&self) -> Option<Duration> {
+        self.time_budget_ms.map(Duration::from_millis)
+    }
+
+    /// Settings for evaluation games, without root exploration noise.
+    pub fn without_exploration_noise(self) -> Self {
+        Self {
+            add_exploration_noise: false,
+            ..self
+        }
+    }
+
+    /// Deterministic settings for evaluation games: given the same model, two searches
+    /// of the same position visit the same moves and select the same move.
+    ///
+    /// Compared to self-play:
+    /// - `add_exploration_noise` is disabled: no Dirichlet noise on the root priors,
+    /// - `gumbel_scale` is unset: the move with the most visits is selected instead
+    ///   of sampling it with Gumbel noise.
+    ///
+    /// A time budget still makes the search depend on the speed of the machine.
+    pub fn evaluation(self) -> Self {
+        Self {
+            gumbel_scale: None,
+            ..self.without_exploration_noise()
+        }
+    }
+
+    /// Value of an unvisited move, given the value of its parent node.
+    pub fn first_play_urgency(&self, parent_value: f32) -> f32 {
+        match self.fpu_reduction {
+            Some(reduction) => parent_value - reduction,
+            None => self.fpu,
+        }
+    }
+
+    /// Dirichlet alpha of the root noise, given the number of legal moves.
+    pub fn dirichlet_alpha(&self, n_moves: usize) -> f32 {
+        if self.scale_dirichlet_alpha && n_moves > 0 {
+            self.root_dirichlet_alpha / n_moves as f32
+        } else {
+            self.root_dirichlet_alpha
+        }
+    }
+}
+
+fn default_add_exploration_noise() -> bool {
+    true
+}
+
+/// Settings whose numerical parameters can be set by name, for parameter sweeps.
+pub trait Tunable: Sized {
+    /// Copy of the settings with the parameter `name` set to `value`.
+    fn with_parameter(&self, name: &str, value: f32) -> Result<Self, StrError>;
+}
+
+fn unknown_parameter<T>(settings: &str, name: &str) -> Result<T, StrError> {
+    Err(StrError(format!(
+        "Unknown {} parameter `{}`.",
+        settings, name
+    )))
+}
+
+impl Tunable for UCT {
+    fn with_parameter(&self, name: &str, value: f32) -> Result<Self, StrError> {
+        let mut settings = *self;
+        match name {
+            "uct_weight" => settings.uct_weight = value,
+            "playouts" => settings.playouts = value as usize,
+            _ => return unknown_parameter("UCT", name),
+        }
+        Ok(settings)
+    }
+}
+
+impl Tunable for RAVE {
+    fn with_parameter(&self, name: &str, value: f32) -> Result<Self, StrError> {
+        let mut settings = *self;
+        match name {
+            "uct_weight" => settings.uct_weight = value,
+            "playouts" => settings.playouts = value as usize,
+            "rave_bias" => settings.rave_bias = value,
+            _ => return unknown_parameter("RAVE", name),
+        }
+        Ok(settings)
+    }
+}
+
+impl Tunable for PUCT {
+    fn with_parameter(&self, name: &str, value: f32) -> Result<Self, StrError> {
+        let mut settings = *self;
+        match name {
+            "c_base" => settings.c_base = value,
+            "c_init" => settings.c_init = value,
+            "discount" => settings.discount = value,
+            _ => return unknown_parameter("PUCT", name),
+        }
+        Ok(settings)
+    }
+}
+
+fn default_parallel_playouts() -> usize {
+    1
+}
+
+fn default_gumbel_considered_actions() -> usize {
+    16
+}
+
+fn default_value_transform() -> Option<f32> {
+    Some(default_epsilon())
+}
+
+fn default_epsilon() -> f32 {
+    crate::deep::tf::VALUE_TRANSFORM_EPSILON
+}
+
+fn default_mu_value_transform() -> bool {
+    true
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(tag = "kind")]
+/// Value target recorded for each move of a self-play game,
+/// relative to the player to move.
+pub enum ValueTarget {
+    /// Final game outcome: 1 for the winner, -1 for the loser and 0 for a draw.
+    FinalOutcome,
+    /// Root value of the search.
+    RootValue,
+    /// λ-return, mixing the root values of the following moves with the final outcome.
+    TdLambda {
+        /// Weight of the following targets: 0 bootstraps on the next root value,
+        /// 1 is the final outcome.
+        lambda: f32,
+    },
+}
+
+impl Default for ValueTarget {
+    fn default() -> Self {
+        ValueTarget::RootValue
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+/// Neural network inference backend.
+pub enum Backend {
+    /// TensorFlow saved models.
+    TensorFlow,
+    /// ONNX models, loaded from `model.onnx` in the model directory.
+    Onnx,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::TensorFlow
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+/// Names of the inputs and outputs of the models, for models exported with other
+/// signature names than the default ones.
+///
+/// TensorFlow names are operation names, followed by `:index` to select an output
+/// of the operation other than the first one. ONNX names are the graph input and output names.
+pub struct ModelSignature {
+    /// Board input, the hidden state for the dynamics network.
+    /// Defaults to `serving_default_board`, or the first ONNX input.
+    pub board: Option<String>,
+    /// Action input of the dynamics network.
+    /// Defaults to `serving_default_action`, or the second ONNX input.
+    pub action: Option<String>,
+    /// Outputs: policy and value for the prediction network, next hidden state and reward
+    /// for the dynamics network, hidden state for the representation network.
+    /// Defaults to the outputs of `StatefulPartitionedCall`, or the ONNX outputs in graph order.
+    pub outputs: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+/// Input and output names of each network, as their inputs and outputs differ.
+pub struct ModelSignatures {
+    #[serde(default)]
+    /// Prediction network, the only network of AlphaZero.
+    pub prediction: ModelSignature,
+    #[serde(default)]
+    /// MuZero dynamics network.
+    pub dynamics: ModelSignature,
+    #[serde(default)]
+    /// MuZero representation network.
+    pub representation: ModelSignature,
 }
 
 #[derive(Deserialize, Copy, Clone, Debug)]
@@ -216,6 +658,9 @@ pub struct PUCT {
 pub struct AlphaZero {
     /// Underlying PUCT policy.
     pub puct: PUCT,
+    #[serde(default)]
+    /// Value target of the self-play games.
+    pub value_target: ValueTarget,
 }
 
 #[derive(Deserialize, Copy, Clone, Debug)]
@@ -231,6 +676,36 @@ pub struct MuZero {
     pub unroll_steps: usize,
     /// Temporal-difference steps when training.
     pub td_steps: usize,
+    #[serde(default = "default_mu_value_transform")]
+    /// Apply the value transform `h(x) = sign(x) (sqrt(|x| + 1) - 1) + εx` to the values
+    /// and rewards encoded by a support.
+    pub value_transform: bool,
+    #[serde(default = "default_epsilon")]
+    /// `ε` of the value transform.
+    pub epsilon: f32,
+    #[serde(default)]
+    /// Maximal number of nodes of the search tree, the least recently visited subtrees
+    /// being pruned beyond it. Unbounded by default.
+    pub max_nodes: Option<usize>,
+}
+
+impl MuZero {
+    /// Settings for evaluation games, see `PUCT::evaluation`.
+    pub fn evaluation(self) -> Self {
+        Self {
+            puct: self.puct.evaluation(),
+            ..self
+        }
+    }
+
+    /// `ε` of the value transform, if enabled.
+    pub fn transform(&self) -> Option<f32> {
+        if self.value_transform {
+            Some(self.epsilon)
+        } else {
+            None
+        }
+    }
 }
 
 /// Global configuration.
@@ -249,10 +724,29 @@ pub struct Config {
     #[serde(default)]
     /// Policies settings.
     pub policies: Policies,
+    /// Maximum number of plies before a game is declared a draw.
+    pub max_plies: Option<usize>,
+    /// Number of occurrences of a position before a game is declared a draw.
+    pub max_repetitions: Option<usize>,
+    #[serde(default)]
+    /// Neural network inference backend.
+    pub backend: Backend,
+    #[serde(default)]
+    /// Input and output names of the models.
+    pub signature: ModelSignatures,
 }
 
+use crate::game::DrawRules;
 use crate::policies::mcts::{muz::MuZeroConfig, puct::AlphaZeroConfig};
 impl Config {
+    /// Draw rules from the global configuration.
+    pub fn draw_rules(&self) -> DrawRules {
+        DrawRules {
+            max_plies: self.max_plies,
+            max_repetitions: self.max_repetitions,
+        }
+    }
+
     /// Build an AlphaZeroConfig from the global configuration if possible.
     pub fn get_alphazero<A, B>(
         &self,
@@ -266,10 +760,16 @@ impl Config {
                 action_shape,
                 board_shape,
                 puct: alpha_config.puct,
+                value_target: alpha_config.value_target,
                 network_path: model_path,
                 watch_models: true,
                 batch_size: self.self_play.batch_size,
+                target_latency: self.self_play.target_latency(),
                 n_playouts: self.mcts.playouts,
+                playouts_schedule: self.mcts.playouts_schedule.clone(),
+                draw_rules: self.draw_rules(),
+                backend: self.backend,
+                signature: self.signature.prediction.clone(),
             };
             Some(alpha_config)
         } else {
@@ -288,7 +788,12 @@ impl Config {
                 networks_path: models_path,
                 watch_models: true,
                 batch_size: self.self_play.batch_size,
+                target_latency: self.self_play.target_latency(),
                 n_playouts: self.mcts.playouts,
+                playouts_schedule: self.mcts.playouts_schedule.clone(),
+                draw_rules: self.draw_rules(),
+                backend: self.backend,
+                signature: self.signature.clone(),
             };
             Some(mu_config)
         } else {
@@ -332,3 +837,73 @@ impl error::Error for StrError {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{Ix1, Ix3};
+
+    #[test]
+    fn test_model_signatures() {
+        let puct = r#"{
+            "discount": 1.0,
+            "c_base": 19652.0,
+            "c_init": 1.25,
+            "root_dirichlet_alpha": 0.3,
+            "root_exploration_fraction": 0.25
+        }"#;
+        let config: Config = serde_json::from_str(&format!(
+            r#"{{
+                "game": {{ "kind": "Breakthrough", "size": 5 }},
+                "self_play": {{ "batch_size": 1, "evaluators": 1, "generators": 1 }},
+                "mcts": {{ "playouts": 50 }},
+                "alpha": {{ "puct": {} }},
+                "mu": {{ "puct": {}, "repr_shape": [5, 5, 16], "unroll_steps": 5, "td_steps": 10 }},
+                "signature": {{
+                    "prediction": {{ "outputs": ["policy", "value"] }},
+                    "dynamics": {{ "board": "hidden", "action": "move" }}
+                }}
+            }}"#,
+            puct, puct
+        ))
+        .unwrap();
+
+        let alpha = config.get_alphazero(Ix1(75), Ix3(5, 5, 3)).unwrap();
+        assert_eq!(alpha.signature, config.signature.prediction);
+        assert_eq!(
+            alpha.signature.outputs,
+            Some(vec!["policy".to_owned(), "value".to_owned()])
+        );
+
+        let mu = config.get_muzero(Ix1(75), Ix3(5, 5, 3)).unwrap();
+        assert_eq!(mu.signature.prediction, alpha.signature);
+        assert_eq!(mu.signature.dynamics.board, Some("hidden".to_owned()));
+        assert_eq!(mu.signature.dynamics.action, Some("move".to_owned()));
+        assert_eq!(mu.signature.dynamics.outputs, None);
+        assert_eq!(mu.signature.representation, ModelSignature::default());
+    }
+
+    #[test]
+    fn test_playouts_schedule() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "game": { "kind": "Breakthrough", "size": 5 },
+                "self_play": { "batch_size": 1, "evaluators": 1, "generators": 1 },
+                "mcts": { "playouts": 50, "playouts_schedule": [[10, 200]] },
+                "alpha": {
+                    "puct": {
+                        "discount": 1.0,
+                        "c_base": 19652.0,
+                        "c_init": 1.25,
+                        "root_dirichlet_alpha": 0.3,
+                        "root_exploration_fraction": 0.25
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let alpha = config.get_alphazero(Ix1(75), Ix3(5, 5, 3)).unwrap();
+        assert_eq!(alpha.n_playouts, 50);
+        assert_eq!(alpha.playouts_schedule, vec![(10, 200)]);
+    }
+}
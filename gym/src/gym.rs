@@ -96,9 +96,9 @@ impl SpaceData {
                 let vpyo = spaces
                     .to_vec()
                     .into_iter()
-                    .map(|s| s.into_pyo().expect("Unable to parse tuple"))
-                    .collect::<Vec<_>>();
-                vpyo.into_py(py)
+                    .map(|s| s.into_pyo())
+                    .collect::<Result<Vec<_>, _>>()?;
+                pyo3::types::PyTuple::new(py, &vpyo).to_object(py)
             }
         })
     }
@@ -124,19 +124,19 @@ impl SpaceTemplate {
                     .map_err(|_| GymError::InvalidConversion)?;
                 Ok(SpaceData::BOX(v.into()))
             }
-            SpaceTemplate::TUPLE { .. } => {
-                unimplemented!("Never used for now.")
-                /*
-                let mut tuple = vec![];
-                let mut i = 0;
-                let mut item = pyo.cast_as::<pyo3::types::PyTuple>(py).unwrap().get_item(i);
-                while item.is_ok() {
-                    let pyo_item = self.extract_data(item.unwrap())?;
-                    tuple.push(pyo_item);
-                    i += 1;
-                    item = pyo.get_item(py, i);
+            SpaceTemplate::TUPLE { spaces } => {
+                let items = pyo
+                    .cast_as::<pyo3::types::PyTuple>(py)
+                    .map_err(|_| GymError::InvalidConversion)?;
+                if items.len() != spaces.len() {
+                    return Err(GymError::InvalidConversion);
                 }
-                Ok(SpaceData::TUPLE(tuple.into()))*/
+                let tuple = spaces
+                    .iter()
+                    .zip(items.iter())
+                    .map(|(space, item)| space.extract_data(item.to_object(py)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(SpaceData::TUPLE(tuple.into()))
             }
         }
     }
@@ -196,21 +196,17 @@ impl SpaceTemplate {
                 SpaceTemplate::BOX { high, low, shape }
             }
             "Tuple" => {
-                unimplemented!("Tuple is not supported.");
-                /*
-                let mut i = 0;
-                let mut tuple = vec![];
-                let mut item = pyo.get_item(py, i);
-
-                while item.is_ok() {
-                    let pyo_item = item.unwrap();
-                    let space = SpaceTemplate::extract_template(pyo_item);
-                    tuple.push(space);
-                    i += 1;
-                    item = pyo.get_item(py, i);
-                }
+                let spaces = pyo
+                    .getattr(py, "spaces")
+                    .expect("Unable to get attribute 'spaces'");
+                let spaces = spaces
+                    .cast_as::<pyo3::types::PyTuple>(py)
+                    .expect("Unable to convert 'spaces' to a tuple")
+                    .iter()
+                    .map(|space| SpaceTemplate::extract_template(space.to_object(py)))
+                    .collect();
 
-                SpaceTemplate::TUPLE { spaces: tuple }*/
+                SpaceTemplate::TUPLE { spaces }
             }
             _ => unreachable!(),
         }
@@ -284,17 +280,10 @@ impl Environment {
                     .map_err(|_| GymError::InvalidAction)?
             }
             Action::TUPLE(_) => {
-                unimplemented!("Tuple is not supported.");
-                /*
-                let vpyo = spaces
-                    .to_vec()
-                    .into_iter()
-                    .map(|s| s.into_pyo().unwrap())
-                    .collect::<Vec<_>>();
-                let tpyo = PyTuple::new(py, &vpyo);
+                let tpyo = action.clone().into_pyo()?;
                 self.env
                     .call_method(py, "step", (tpyo,), None)
-                    .map_err(|_| GymError::InvalidAction)?*/
+                    .map_err(|_| GymError::InvalidAction)?
             }
         };
 
@@ -494,16 +483,30 @@ mod tests {
     #[test]
     fn test_tuple_template() {
         let client = GymClient::default();
-        let _ = client.make("Blackjack-v0");
+        let env = client.make("Blackjack-v0");
+        match env.observation_space() {
+            SpaceTemplate::TUPLE { spaces } => {
+                assert_eq!(spaces.len(), 3);
+                assert!(spaces
+                    .iter()
+                    .all(|s| matches!(s, SpaceTemplate::DISCRETE { .. })));
+            }
+            x => panic!("Unexpected observation space {:?}", x),
+        }
     }
 
     #[test]
     fn test_tuple_obs() {
         let client = GymClient::default();
         let env = client.make("Blackjack-v0");
-        env.reset().unwrap();
+        let obs = env.reset().unwrap().get_tuple().unwrap();
+        assert_eq!(obs.len(), 3);
+        assert!(obs.iter().all(|x| matches!(x, SpaceData::DISCRETE(_))));
         let action = env.action_space().sample();
-        env.step(&action).unwrap();
+        let state = env.step(&action).unwrap();
+        let obs = state.observation.get_tuple().unwrap();
+        assert_eq!(obs.len(), 3);
+        assert!(obs.iter().all(|x| matches!(x, SpaceData::DISCRETE(_))));
     }
 
     #[test]
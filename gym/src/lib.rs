@@ -53,6 +53,7 @@ pub trait GymRunner {
     async fn init(name: String, render: bool);
     async fn reset() -> SpaceData;
     async fn play(action: usize) -> State;
+    async fn play_continuous(action: Vec<f64>) -> State;
     async fn action_space() -> SpaceTemplate;
     async fn observation_space() -> SpaceTemplate;
 }
@@ -50,7 +50,7 @@ def policy_value_network_alpha(config):
 
     value   = residual_block(x, "pv_e")
     value   = layers.Flatten()(value)
-    value   = layers.Dense((1), activation='sigmoid', name='value', kernel_regularizer=l2(config.training.weight_decay), bias_regularizer=l2(config.training.weight_decay))(value)
+    value   = layers.Dense((1), activation='tanh', name='value', kernel_regularizer=l2(config.training.weight_decay), bias_regularizer=l2(config.training.weight_decay))(value)
 
     return keras.Model(inputs=input, outputs={"policy": policy, "value": value})
 
@@ -17,7 +17,7 @@ from tensorflow.keras.utils import Sequence
 # |__/  |__/|________/|__/      |________/|__/  |__/    |__/          |_______/  \______/ |__/      |__/      |________/|__/  |__/
 
 class GameEntry:
-    def __init__(self, state, policy, value, action, reward, turn):
+    def __init__(self, state, policy, value, action, reward, turn, target_value=None):
         super().__init__()
         self.state = state
         self.policy = policy
@@ -25,6 +25,7 @@ class GameEntry:
         self.action = action
         self.reward = reward
         self.turn = turn
+        self.target_value = target_value
 
 class ReplayBuffer:
     def __init__(self, states_count, max_index, index, games):
@@ -80,8 +81,9 @@ class BufferThread(Thread):
             new_value  = np.array(game["value"], dtype=float).reshape((-1))
             new_action = np.array(game["action"], dtype=float).reshape((-1,)+action_shape)
             new_reward = np.array(game["reward"], dtype=float).reshape((-1,))
+            new_target_value = np.array(game["target_value"], dtype=float).reshape((-1)) if "target_value" in game else None
             
-            self.replay_buffer.games[self.replay_buffer.index] = GameEntry(new_state, new_policy, new_value, new_action, new_reward, game["turn"])
+            self.replay_buffer.games[self.replay_buffer.index] = GameEntry(new_state, new_policy, new_value, new_action, new_reward, game["turn"], new_target_value)
             self.replay_buffer.states_count += 1
             self.replay_buffer.max_index = min(self.replay_buffer.max_index + 1, self.config.training.replay_buffer)
             
@@ -135,6 +137,7 @@ class MuGenerator(Sequence):
         move_id = np.random.randint(game_length)
 
         action_shape = get_action_shape(self.config)
+        epsilon = value_transform_epsilon(self.config)
 
 
         target_policy = np.zeros((self.config.mu.unroll_steps,)+action_shape)
@@ -149,26 +152,31 @@ class MuGenerator(Sequence):
 
             # compute target value
             value = 0
-            if i+self.config.mu.td_steps < game_length:
-                value += game.value[i + self.config.mu.td_steps] * self.config.mu.puct.discount ** self.config.mu.td_steps
+            if getattr(game, "target_value", None) is not None:
+                # n-step value computed by the generator.
+                if i < game_length:
+                    value = game.target_value[i]
+            else:
+                if i+self.config.mu.td_steps < game_length:
+                    value += game.value[i + self.config.mu.td_steps] * self.config.mu.puct.discount ** self.config.mu.td_steps
 
-            for j, reward in enumerate(game.reward[i:i+self.config.mu.td_steps]):
-                discounted_reward = reward * self.config.mu.puct.discount ** j
-                if game.turn[i+j] == game.turn[i]:
-                    value += discounted_reward
-                else:
-                    value -= discounted_reward
+                for j, reward in enumerate(game.reward[i:i+self.config.mu.td_steps]):
+                    discounted_reward = reward * self.config.mu.puct.discount ** j
+                    if game.turn[i+j] == game.turn[i]:
+                        value += discounted_reward
+                    else:
+                        value -= discounted_reward
 
             # still in game
             if i < game_length:
-                target_reward[t_idx] = value_to_support(game.reward[i], self.config.mu.reward_support)
-                target_value[t_idx] = value_to_support(value, self.config.mu.puct.value_support)
+                target_reward[t_idx] = value_to_support(game.reward[i], self.config.mu.reward_support, epsilon)
+                target_value[t_idx] = value_to_support(value, self.config.mu.puct.value_support, epsilon)
                 target_actions[t_idx] = game.action[i]
                 target_policy[t_idx] = game.policy[i]
             # game has finished
             else:
-                target_reward[t_idx] = value_to_support(0, self.config.mu.reward_support)
-                target_value[t_idx] = value_to_support(0, self.config.mu.puct.value_support)
+                target_reward[t_idx] = value_to_support(0, self.config.mu.reward_support, epsilon)
+                target_value[t_idx] = value_to_support(0, self.config.mu.puct.value_support, epsilon)
                 random_action = (np.random.random(
                     size=len(action_shape)) * action_shape).astype(int)
                 target_actions[t_idx][random_action] = 1
@@ -232,6 +240,19 @@ class MuGenerator(Sequence):
 #                                                                                                                                        
 # 
 
+# value target of a recorded state, relative to the player to move in that state:
+# 1 for a win, -1 for a loss and 0 for a draw, as written by the generator.
+def alpha_value_target(game, move_id):
+    if getattr(game, "target_value", None) is not None:
+        # value target computed by the generator, according to its value_target setting.
+        return game.target_value[move_id]
+    elif game.reward[-1] == 0:
+        # drawn game: no winning move.
+        return 0
+    else:
+        # the last mover won the game.
+        return 1 if game.turn[move_id] == game.turn[-1] else -1
+
 class AlphaZeroGenerator(Sequence):
     def __init__(self, replay_buffer, config):
         self.replay_buffer = replay_buffer
@@ -250,9 +271,7 @@ class AlphaZeroGenerator(Sequence):
         game_length = len(game.state)
         move_id = np.random.randint(game_length)
 
-        value = 1 if game.turn[move_id] == game.turn[-1] else 0
-
-        return game.state[move_id], game.policy[move_id], value
+        return game.state[move_id], game.policy[move_id], alpha_value_target(game, move_id)
 
     def __getitem__(self, index):
         state  = np.zeros((self.config.training.batch,)+get_board_shape(self.config))
@@ -37,9 +37,18 @@ def get_action_shape(config):
     
 import numpy as np
 # scalar to categorical transformation.
-def value_to_support(v, support_size):
+# epsilon of the value transform, None if disabled.
+def value_transform_epsilon(config):
+    if getattr(config.mu, "value_transform", True):
+        return getattr(config.mu, "epsilon", 0.001)
+    return None
+
+def value_to_support(v, support_size, epsilon=0.001):
     # invertible transformation
-    scaled = np.sign(v) * ((np.sqrt(np.abs(v)+1)-1)) + 0.001*v
+    if epsilon is None:
+        scaled = v
+    else:
+        scaled = np.sign(v) * ((np.sqrt(np.abs(v)+1)-1)) + epsilon*v
     # clamp to support
     clamped = np.clip(scaled, -support_size, support_size)
 
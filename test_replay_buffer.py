@@ -0,0 +1,30 @@
+import unittest
+import numpy as np
+from replay_buffer import GameEntry, alpha_value_target
+
+def game_entry(turn, reward, target_value=None):
+    n = len(turn)
+    return GameEntry(np.zeros((n, 1)), np.zeros((n, 1)), np.zeros(n), np.zeros((n, 1)), np.array(reward, dtype=float), turn, target_value)
+
+class TestAlphaValueTarget(unittest.TestCase):
+    def test_won_game(self):
+        # the player 0 plays the last move and wins.
+        game = game_entry([0, 1, 0], [0, 0, 1])
+        self.assertEqual([alpha_value_target(game, i) for i in range(3)], [1, -1, 1])
+
+    def test_lost_game(self):
+        # the player 1 plays the last move and wins.
+        game = game_entry([0, 1, 0, 1], [0, 0, 0, 1])
+        self.assertEqual([alpha_value_target(game, i) for i in range(4)], [-1, 1, -1, 1])
+
+    def test_drawn_game(self):
+        game = game_entry([0, 1, 0], [0, 0, 0])
+        self.assertEqual([alpha_value_target(game, i) for i in range(3)], [0, 0, 0])
+
+    def test_generator_target_value(self):
+        # the value written by the generator takes precedence.
+        game = game_entry([0, 1, 0], [0, 0, 1], np.array([0.5, -0.25, 1.]))
+        self.assertEqual([alpha_value_target(game, i) for i in range(3)], [0.5, -0.25, 1.])
+
+if __name__ == "__main__":
+    unittest.main()
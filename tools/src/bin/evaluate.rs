@@ -5,33 +5,54 @@
 //! Usage: `cargo run --release --bin evaluate -- -c breakthrough -p ppa -a puct -n 100`
 //!
 //! Launches `-n` games with a random starting player and count victories for the first policy.
+//! With `--seed`, the starting player of game `i` is drawn from a generator seeded with `seed ^ i`,
+//! so that the schedule of starting players is reproducible.
 
 use ggpf::game;
 use ggpf::game::breakthrough::*;
 use ggpf::game::meta::with_history::*;
 use ggpf::game::*;
+use ggpf::policies::flat::GreedyNetwork;
 use ggpf::policies::{get_multi, mcts::muz::*, mcts::puct::*, DynMultiplayerPolicyBuilder};
 use ggpf::settings::{self, Config, StrError};
 
 use atomic_counter::{AtomicCounter, RelaxedCounter};
 use clap::{value_t, App, Arg};
 use indicatif::{ProgressBar, ProgressStyle};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use sloth::Lazy;
 use std::error;
+use std::fmt;
 use std::fs;
 use std::sync::Arc;
 use tokio::runtime;
 
+/// Starting player of game `i`, drawn from a generator seeded with `seed ^ i` if a seed
+/// is given.
+fn starting_player<G: Game>(i: usize, seed: Option<u64>) -> G::Player {
+    let players = G::players();
+    *match seed {
+        Some(seed) => players.choose(&mut StdRng::seed_from_u64(seed ^ i as u64)),
+        None => players.choose(&mut rand::thread_rng()),
+    }
+    .unwrap()
+}
+
 pub async fn game_match<'a, 'c, 'b, 'd, GB>(
     n: usize,
     pb1: Box<dyn DynMultiplayerPolicyBuilder<'static, GB::G> + Sync + 'c>,
     pb2: Box<dyn DynMultiplayerPolicyBuilder<'static, GB::G> + Sync + 'd>,
     game_factory: GB,
     silent: bool,
+    log: bool,
+    rules: game::DrawRules,
+    seed: Option<u64>,
 ) -> usize
 where
-    GB::G: game::Game + game::SingleWinner + 'static,
+    GB::G: game::Game + game::SingleWinner + Hash + 'static,
+    <GB::G as Game>::Move: fmt::Display,
     GB: game::GameBuilder + Clone + Sync + Send + 'static,
 {
     let pb = if silent {
@@ -50,12 +71,10 @@ where
     let pb = Arc::new(pb);
 
     let count_victory_thr: Vec<_> = (0..n)
-        .map(|_| {
+        .map(|i| {
             let p1 = pb1.create(<GB::G as Game>::players()[0]);
             let p2 = pb2.create(<GB::G as Game>::players()[1]);
-            let starting_player = *<GB::G as Game>::players()
-                .choose(&mut rand::thread_rng())
-                .unwrap();
+            let starting_player = starting_player::<GB::G>(i, seed);
 
             let c1 = c1.clone();
             let c2 = c2.clone();
@@ -65,14 +84,24 @@ where
             tokio::spawn(async move {
                 let mut game = game_factory.create(starting_player).await;
 
-                game::simulate(p1, p2, &mut game).await;
-
-                let result = if game.winner() == Some(<GB::G as Game>::players()[0]) {
-                    c1.inc();
-                    1
+                if log && i == 0 {
+                    let transcript = game::simulate_with_log(p1, p2, &mut game, rules).await;
+                    println!("{}", transcript.transcript());
                 } else {
-                    c2.inc();
-                    0
+                    game::simulate(p1, p2, &mut game, rules).await;
+                }
+
+                // drawn games are not counted.
+                let result = match game.winner() {
+                    Some(winner) if winner == <GB::G as Game>::players()[0] => {
+                        c1.inc();
+                        1
+                    }
+                    Some(_) => {
+                        c2.inc();
+                        0
+                    }
+                    None => 0,
                 };
 
                 if let Some(pb) = pb.as_ref() {
@@ -125,7 +154,18 @@ async fn run() -> Result<()> {
                 .long("policy")
                 .takes_value(true)
                 .possible_values(&[
-                    "rand", "flat", "flat_ucb", "uct", "rave", "ppa", "nmcs", "alpha", "mu",
+                    "rand",
+                    "flat",
+                    "flat_ucb",
+                    "flat_ucb_tuned",
+                    "greedy",
+                    "uct",
+                    "rave",
+                    "ppa",
+                    "nmcs",
+                    "alpha",
+                    "greedy_network",
+                    "mu",
                 ]),
         )
         .arg(
@@ -134,7 +174,18 @@ async fn run() -> Result<()> {
                 .long("against")
                 .takes_value(true)
                 .possible_values(&[
-                    "rand", "flat", "flat_ucb", "uct", "rave", "ppa", "nmcs", "alpha", "mu",
+                    "rand",
+                    "flat",
+                    "flat_ucb",
+                    "flat_ucb_tuned",
+                    "greedy",
+                    "uct",
+                    "rave",
+                    "ppa",
+                    "nmcs",
+                    "alpha",
+                    "greedy_network",
+                    "mu",
                 ]),
         )
         .arg(
@@ -146,6 +197,17 @@ async fn run() -> Result<()> {
         )
         .arg(Arg::with_name("n").short("n").takes_value(true))
         .arg(Arg::with_name("only-result").long("only-result"))
+        .arg(
+            Arg::with_name("log")
+                .long("log")
+                .help("Print the move list of the first game."),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .takes_value(true)
+                .help("Seed the choice of the starting player of each game."),
+        )
         .get_matches();
 
     let config_file = format!("config/{}.toml", args.value_of("config").unwrap());
@@ -178,6 +240,7 @@ async fn next<GB>(config: Config, args: clap::ArgMatches<'_>, game_builder: GB)
 where
     GB: GameBuilder + 'static,
     GB::G: Features + SingleWinner + Clone + Hash + Eq + 'static,
+    <GB::G as Game>::Move: fmt::Display,
 {
     /* Build game to gathe settings*/
     let g: GB::G = game_builder.create(<GB::G as Game>::players()[0]).await;
@@ -188,19 +251,17 @@ where
 
     let muz_config = config
         .get_muzero(action_shape.clone(), board_shape.clone())
-        .map(|mut x| {
-            x.watch_models = false;
-            x.batch_size = 50;
-            x
+        .map(|x| MuZeroConfig {
+            batch_size: 50,
+            ..x.evaluation()
         });
     let muz_evals = Lazy::new(|| MuzEvaluators::new(muz_config.unwrap(), true));
 
     let alpha_config = config
         .get_alphazero(action_shape, board_shape)
-        .map(|mut x| {
-            x.watch_models = false;
-            x.batch_size = 50;
-            x
+        .map(|x| AlphaZeroConfig {
+            batch_size: 50,
+            ..x.evaluation()
         });
 
     let alpha_evals = Lazy::new(|| AlphaZeroEvaluators::new(alpha_config.unwrap(), true));
@@ -209,39 +270,59 @@ where
     let p1 = if choice_1 == "alpha" {
         let alpha_conf = config.alpha.expect("Alpha not configured.");
         Box::new(PUCT {
-            config: alpha_conf.puct,
+            config: alpha_conf.puct.evaluation(),
             n_playouts: config.mcts.playouts,
+            playouts_schedule: config.mcts.playouts_schedule.clone(),
             prediction_channel: alpha_evals.get_channel(),
         })
     } else if choice_1 == "mu" {
-        let mu_conf = config.mu.expect("Mu not configured.");
+        let mu_conf = config.mu.expect("Mu not configured.").evaluation();
         Box::new(Muz {
             muz: mu_conf,
             n_playouts: config.mcts.playouts,
+            playouts_schedule: config.mcts.playouts_schedule.clone(),
             channels: muz_evals.get_channels(),
         })
+    } else if choice_1 == "greedy_network" {
+        let alpha_conf = config.alpha.expect("Alpha not configured.");
+        Box::new(GreedyNetwork {
+            value_support: alpha_conf.puct.value_support,
+            value_transform: alpha_conf.puct.value_transform,
+            prediction_channel: alpha_evals.get_channel(),
+        })
     } else {
         get_multi(config.clone(), choice_1)
     };
 
     //let gb = BreakthroughBuilder {};
 
+    let rules = config.draw_rules();
+
     /* Build contender. */
     let choice_2 = args.value_of("against").unwrap_or("rand");
     let p2 = if choice_2 == "alpha" {
         let alpha_conf = config.alpha.expect("Alpha not configured.");
         Box::new(PUCT {
-            config: alpha_conf.puct,
+            config: alpha_conf.puct.evaluation(),
             n_playouts: config.mcts.playouts,
+            playouts_schedule: config.mcts.playouts_schedule.clone(),
             prediction_channel: alpha_evals.get_channel(),
         })
     } else if choice_2 == "mu" {
-        let mu_conf = config.mu.expect("Mu not configured.");
+        let mu_conf = config.mu.expect("Mu not configured.").evaluation();
         Box::new(Muz {
             muz: mu_conf,
             n_playouts: config.mcts.playouts,
+            playouts_schedule: config.mcts.playouts_schedule.clone(),
             channels: muz_evals.get_channels(),
         })
+    } else if choice_2 == "greedy_network" {
+        let alpha_conf = config.alpha.expect("Alpha not configured.");
+        Box::new(GreedyNetwork {
+            value_support: alpha_conf.puct.value_support,
+            value_transform: alpha_conf.puct.value_transform,
+            prediction_channel: alpha_evals.get_channel(),
+        })
     } else {
         get_multi(config, choice_2)
     };
@@ -254,10 +335,43 @@ where
     }
 
     let n_games = value_t!(args.value_of("n"), usize).unwrap_or(100);
+    let seed = if args.is_present("seed") {
+        Some(value_t!(args.value_of("seed"), u64)?)
+    } else {
+        None
+    };
 
     println!(
         "{}",
-        game_match(n_games, p1, p2, game_builder, silent).await
+        game_match(
+            n_games,
+            p1,
+            p2,
+            game_builder,
+            silent,
+            args.is_present("log"),
+            rules,
+            seed
+        )
+        .await
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_starting_players() {
+        let schedule = |seed| -> Vec<Color> {
+            (0..20)
+                .map(|i| starting_player::<Breakthrough>(i, seed))
+                .collect()
+        };
+        let seeded = schedule(Some(42));
+        assert_eq!(seeded, schedule(Some(42)));
+        // both players start some games.
+        assert!(seeded.contains(&Color::Black) && seeded.contains(&Color::White));
+    }
+}
@@ -10,6 +10,7 @@
 //! To launch the trainer, use `python training.py --config breakthrough -m alpha|mu`.
 
 use ggpf::deep::file_manager;
+use ggpf::deep::metrics::{self, Metrics};
 use ggpf::deep::self_play::GameHistoryEntry;
 use ggpf::game::breakthrough::BreakthroughBuilder;
 use ggpf::game::meta::with_history::*;
@@ -85,12 +86,21 @@ async fn run() -> Result<()> {
         settings::Game::Gym {
             name,
             remote,
+            local,
             history,
+            action_bins,
+            frame_skip,
+            timeout_ms,
+            max_retries,
         } => {
             let gb = GymBuilder {
-                address: remote,
+                address: if local { None } else { Some(remote) },
                 game_name: name,
                 render: false,
+                action_bins,
+                frame_skip,
+                timeout_ms,
+                max_retries,
             };
 
             if let Some(history) = history {
@@ -121,6 +131,9 @@ where
 
     // Game channel.
     let (tx_games, mut rx_games) = mpsc::channel::<GameHistoryEntry<GB::G>>(1024);
+    // Metrics channel.
+    let (tx_metrics, rx_metrics) = mpsc::channel::<Metrics>(16);
+    tokio::spawn(metrics::stdout_reporter(rx_metrics));
 
     match method {
         Method::AlphaZero => {
@@ -130,6 +143,8 @@ where
                     config.self_play,
                     gb,
                     tx_games,
+                    Some(tx_metrics),
+                    None,
                 ));
             } else {
                 return Err(Box::new(StrError(
@@ -144,6 +159,8 @@ where
                     config.self_play,
                     gb,
                     tx_games,
+                    Some(tx_metrics),
+                    None,
                 ));
             } else {
                 return Err(Box::new(StrError(
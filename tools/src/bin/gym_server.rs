@@ -67,6 +67,24 @@ impl GymRunner for GymServer {
         }
     }
 
+    type PlayContinuousFut = impl Future<Output = gym::State>;
+
+    fn play_continuous(self, _: context::Context, action: Vec<f64>) -> Self::PlayContinuousFut {
+        log::info!("Play {:?}.", action);
+
+        async move {
+            if let Some((ref game, render)) = *self.game.lock().unwrap() {
+                let res = game.step(&gym::SpaceData::BOX(action.into())).unwrap();
+                if render {
+                    game.render();
+                }
+                res
+            } else {
+                panic!("The game hasn't been initialized.");
+            }
+        }
+    }
+
     type ActionSpaceFut = impl Future<Output = gym::SpaceTemplate>;
 
     fn action_space(self, _: context::Context) -> Self::ActionSpaceFut {
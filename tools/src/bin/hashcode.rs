@@ -0,0 +1,109 @@
+#![allow(non_snake_case)]
+
+//! # HASHCODE - single-player policies on the Hashcode 2020 book scanning problem.
+//!
+//! Usage: `cargo run --release --bin hashcode -- -f a_example.txt -p nrpa -l 3 -n 10`
+//!
+//! Reads the problem file `-f`, schedules the library signups and book scans
+//! and reports the best score found over `-n` runs.
+
+use ggpf::game::hashcode_20::{Hashcode20, HashcodeBuilder, HashcodeCode};
+use ggpf::game::*;
+use ggpf::policies::{flat, nmcs, nrpa, SingleplayerPolicy, SingleplayerPolicyBuilder};
+use ggpf::settings;
+
+use clap::{value_t, App, Arg};
+use std::time::Instant;
+use tokio::runtime;
+
+fn main() {
+    let mut threaded_rt = runtime::Builder::new()
+        .threaded_scheduler()
+        .enable_all()
+        .core_threads(8)
+        .build()
+        .unwrap();
+
+    threaded_rt.block_on(run());
+}
+
+/// Plays the sequence of moves given by the policy, returning the final state.
+async fn solve<P: SingleplayerPolicy<Hashcode20>>(
+    policy: &mut P,
+    game_builder: &HashcodeBuilder,
+) -> Hashcode20 {
+    let mut state = SingleplayerGameBuilder::create(game_builder).await;
+    for action in policy.solve(&state).await {
+        state.play(&action).await;
+    }
+    state
+}
+
+async fn run() {
+    let args = App::new("ggpf-hashcode")
+        .arg(
+            Arg::with_name("file")
+                .short("f")
+                .long("file")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("policy")
+                .short("p")
+                .long("policy")
+                .takes_value(true)
+                .possible_values(&["rand", "nmcs", "nrpa"]),
+        )
+        .arg(
+            Arg::with_name("level")
+                .short("l")
+                .long("level")
+                .takes_value(true),
+        )
+        .arg(Arg::with_name("n").short("n").takes_value(true))
+        .get_matches();
+
+    let game_builder = HashcodeBuilder::new(args.value_of("file").unwrap());
+    let level = value_t!(args.value_of("level"), usize).unwrap_or(2);
+    let n_runs = value_t!(args.value_of("n"), usize).unwrap_or(1);
+
+    let mut best: Option<Hashcode20> = None;
+    for i in 0..n_runs {
+        let start = Instant::now();
+        let state = match args.value_of("policy").unwrap_or("nrpa") {
+            "rand" => {
+                let builder = flat::Random {};
+                let mut policy = SingleplayerPolicyBuilder::<Hashcode20>::create(&builder);
+                solve(&mut policy, &game_builder).await
+            }
+            "nmcs" => {
+                let builder = nmcs::NMCS::new(level);
+                let mut policy = SingleplayerPolicyBuilder::<Hashcode20>::create(&builder);
+                solve(&mut policy, &game_builder).await
+            }
+            _ => {
+                let builder = nrpa::NRPA::<Hashcode20, HashcodeCode>::new(settings::NRPA {
+                    level,
+                    ..settings::NRPA::default()
+                });
+                solve(&mut builder.create(), &game_builder).await
+            }
+        };
+        println!(
+            "Run {}: scored {} in {:.2}s",
+            i,
+            state.score(),
+            start.elapsed().as_secs_f32()
+        );
+
+        if best.as_ref().map_or(true, |b| b.score() < state.score()) {
+            best = Some(state);
+        }
+    }
+
+    if let Some(best) = best {
+        println!("Best: {}", best.score());
+        println!("{:?}", best);
+    }
+}
@@ -3,20 +3,32 @@
 //! Usage: `cargo run --release --bin perf`
 //!
 //! Performance is tested on 5x5 breakthrough/PUCT.
+//!
+//! Usage: `cargo run --release --bin perf -- games -g tictactoe -p uct -c breakthrough -n 100`
+//!
+//! Plays `-n` self-play games of a policy, configured by `-c`, and reports the number
+//! of games and moves per second.
 
 #![allow(non_snake_case)]
 
+use ggpf::arena;
 use ggpf::deep::evaluator::PredictionEvaluatorChannel;
-use ggpf::deep::tf;
+use ggpf::deep::inference;
 use ggpf::game::breakthrough::{Breakthrough, BreakthroughBuilder};
 use ggpf::game::meta::with_history::*;
+use ggpf::game::tictactoe::TicTacToeBuilder;
 use ggpf::game::*;
+use ggpf::policies::get_multi;
+use ggpf::settings::{Backend, Config, ModelSignature};
 
+use clap::{value_t, App, Arg, ArgMatches, SubCommand};
 use ndarray::Dimension;
+use std::error;
+use std::fs;
+use std::hash::Hash;
 use std::path::Path;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
-use std::sync::RwLock;
 use tokio::runtime;
 use tokio::sync::mpsc;
 
@@ -26,6 +38,52 @@ const MODEL_PATH: &str = "data/alpha-breakthrough-5/model/";
 type G = WithHistory<Breakthrough>;
 /// Entry point.
 fn main() {
+    let args = App::new("ggpf-perf")
+        .subcommand(
+            SubCommand::with_name("games")
+                .about("Benchmark self-play games of a policy.")
+                .arg(
+                    Arg::with_name("game")
+                        .short("g")
+                        .long("game")
+                        .takes_value(true)
+                        .possible_values(&["breakthrough", "tictactoe"]),
+                )
+                .arg(
+                    Arg::with_name("policy")
+                        .short("p")
+                        .long("policy")
+                        .takes_value(true)
+                        .possible_values(&[
+                            "rand",
+                            "flat",
+                            "flat_ucb",
+                            "flat_ucb_tuned",
+                            "greedy",
+                            "uct",
+                            "rave",
+                            "ppa",
+                            "nmcs",
+                        ]),
+                )
+                .arg(
+                    Arg::with_name("config")
+                        .short("c")
+                        .long("config")
+                        .takes_value(true)
+                        .possible_values(&["breakthrough", "atari"]),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .short("s")
+                        .long("size")
+                        .takes_value(true)
+                        .help("Board size for breakthrough."),
+                )
+                .arg(Arg::with_name("n").short("n").takes_value(true)),
+        )
+        .get_matches();
+
     let mut threaded_rt = runtime::Builder::new()
         .threaded_scheduler()
         .enable_all()
@@ -33,7 +91,49 @@ fn main() {
         .build()
         .unwrap();
 
-    threaded_rt.block_on(run());
+    match args.subcommand() {
+        ("games", Some(args)) => {
+            if let Err(e) = threaded_rt.block_on(run_games(args)) {
+                println!("Error: {}", e)
+            }
+        }
+        _ => threaded_rt.block_on(run()),
+    }
+}
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Run the self-play games benchmark.
+async fn run_games(args: &ArgMatches<'_>) -> Result<()> {
+    let config_file = format!(
+        "config/{}.toml",
+        args.value_of("config").unwrap_or("breakthrough")
+    );
+    let config: Config = toml::from_str(&fs::read_to_string(config_file)?)?;
+    let policy = args.value_of("policy").unwrap_or("rand");
+    let n_games = value_t!(args.value_of("n"), usize).unwrap_or(100);
+
+    match args.value_of("game").unwrap_or("breakthrough") {
+        "tictactoe" => benchmark(config, policy, n_games, TicTacToeBuilder::default()).await,
+        _ => {
+            let size = value_t!(args.value_of("size"), usize).unwrap_or(5);
+            benchmark(config, policy, n_games, BreakthroughBuilder { size }).await
+        }
+    }
+    Ok(())
+}
+
+async fn benchmark<GB>(config: Config, policy: &str, n_games: usize, game_builder: GB)
+where
+    GB: GameBuilder + 'static,
+    GB::G: SingleWinner + Clone + Hash + Eq + 'static,
+{
+    let rules = config.draw_rules();
+    let builder = get_multi(config, policy);
+    println!("{}", builder);
+
+    let throughput = arena::benchmark(game_builder, builder.as_ref(), n_games, rules).await;
+    println!("{}", throughput);
 }
 
 use indicatif::{ProgressBar, ProgressStyle};
@@ -57,10 +157,11 @@ async fn run() {
     };
 
     // Load neural network
-    let prediction_tensorflow = Arc::new((
-        AtomicBool::new(false),
-        RwLock::new(tf::load_model(&MODEL_PATH)),
-    ));
+    let prediction_tensorflow = inference::load_thread_safe_model(
+        Backend::TensorFlow,
+        &ModelSignature::default(),
+        MODEL_PATH,
+    );
 
     // Game builder.
     let game_builder = WithHistoryGB::new(BreakthroughBuilder { size: 5 }, 2);
@@ -69,7 +170,17 @@ async fn run() {
 
     let ft = breakthrough.get_features();
     let board_size = G::state_dimension(&ft).size();
-    let action_size = G::action_dimension(&ft).size();
+    let action_size = G::num_actions(&ft);
+
+    if let Err(e) = ggpf::deep::evaluator::check_prediction_shape(
+        &prediction_tensorflow,
+        GPU_BATCH_SIZE,
+        board_size,
+        action_size,
+    ) {
+        println!("{}", e);
+        return;
+    }
 
     let indicator_bar = ProgressBar::new_spinner();
     indicator_bar.set_style(
@@ -95,6 +206,7 @@ async fn run() {
                         Breakthrough::players()[0],
                         &bt,
                         1,
+                        Some(ggpf::deep::tf::VALUE_TRANSFORM_EPSILON),
                     )
                     .await;
                 }
@@ -110,9 +222,12 @@ async fn run() {
             board_size,
             action_size,
             1,
+            None,
             prediction_tensorflow,
             pred_rx,
+            Arc::new(AtomicBool::new(false)),
             Some(bb),
+            None,
         )));
     }
 
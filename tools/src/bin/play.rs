@@ -0,0 +1,150 @@
+#![allow(non_snake_case)]
+
+//! # PLAY - play Breakthrough against a policy from the terminal.
+//!
+//! Usage: `cargo run --release --bin play -- -c breakthrough -p uct --color black`
+//!
+//! Moves are written as `a2->b3`: the pawn on `a2` moves to `b3`.
+
+use ggpf::game::breakthrough::*;
+use ggpf::game::*;
+use ggpf::policies::human::Human;
+use ggpf::policies::mcts::puct::*;
+use ggpf::policies::{get_multi, DynMultiplayerPolicyBuilder};
+use ggpf::settings::{self, Config, StrError};
+
+use clap::{App, Arg};
+use std::error;
+use std::fs;
+use tokio::runtime;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+fn main() {
+    let mut threaded_rt = runtime::Builder::new()
+        .threaded_scheduler()
+        .enable_all()
+        .core_threads(2)
+        .build()
+        .unwrap();
+
+    if let Err(e) = threaded_rt.block_on(run()) {
+        println!("Error: {}", e)
+    }
+}
+
+async fn run() -> Result<()> {
+    let args = App::new("ggpf-play")
+        .arg(
+            Arg::with_name("policy")
+                .short("p")
+                .long("policy")
+                .takes_value(true)
+                .possible_values(&[
+                    "rand",
+                    "flat",
+                    "flat_ucb",
+                    "flat_ucb_tuned",
+                    "greedy",
+                    "uct",
+                    "rave",
+                    "ppa",
+                    "nmcs",
+                    "alpha",
+                ]),
+        )
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .takes_value(true)
+                .possible_values(&["black", "white"])
+                .help("Color of the human player, black plays first."),
+        )
+        .get_matches();
+
+    let config_file = format!("config/{}.toml", args.value_of("config").unwrap());
+    let config = fs::read_to_string(config_file)?;
+    let config: Config = toml::from_str(&config)?;
+
+    let size = match config.game {
+        settings::Game::Breakthrough { size, .. } => size,
+        _ => {
+            return Err(Box::new(StrError(
+                "Only Breakthrough can be played from the terminal.".to_owned(),
+            )))
+        }
+    };
+    let human_color = match args.value_of("color").unwrap_or("black") {
+        "black" => Color::Black,
+        _ => Color::White,
+    };
+    let policy_color = human_color.adv();
+
+    let game_builder = BreakthroughBuilder { size };
+    let mut state = game_builder.create(Color::Black).await;
+
+    /* Evaluators have to outlive the game. */
+    let mut alpha_evals = None;
+    let choice = args.value_of("policy").unwrap_or("uct");
+    let policy: Box<dyn DynMultiplayerPolicyBuilder<'static, Breakthrough> + Sync + Send> =
+        if choice == "alpha" {
+            let ft = state.get_features();
+            let board_shape = Breakthrough::state_dimension(&ft);
+            let action_shape = Breakthrough::action_dimension(&ft);
+            let alpha_config = config
+                .get_alphazero(action_shape, board_shape)
+                .ok_or_else(|| StrError("Alpha not configured.".to_owned()))?
+                .evaluation();
+            let puct = alpha_config.puct;
+            let evaluators =
+                alpha_evals.get_or_insert(AlphaZeroEvaluators::new(alpha_config, true));
+
+            Box::new(PUCT {
+                config: puct,
+                n_playouts: config.mcts.playouts,
+                playouts_schedule: config.mcts.playouts_schedule.clone(),
+                prediction_channel: evaluators.get_channel(),
+            })
+        } else {
+            get_multi(config.clone(), choice)
+        };
+    let mut policy = policy.create(policy_color);
+    let mut human =
+        DynMultiplayerPolicyBuilder::<'static, Breakthrough>::create(&Human {}, human_color);
+
+    let mut draw = DrawDetector::new(config.draw_rules());
+    while !state.is_finished() {
+        if draw.record(position_hash(&state)) {
+            break;
+        }
+        let action = if state.turn() == human_color {
+            match human.play(&state).await {
+                Some(action) => action,
+                None => {
+                    println!("You resign.");
+                    return Ok(());
+                }
+            }
+        } else {
+            policy
+                .play(&state)
+                .await
+                .ok_or_else(|| StrError("The policy didn't play a move.".to_owned()))?
+        };
+        state.play(&action).await;
+    }
+
+    println!("{:?}", state);
+    match state.winner() {
+        Some(winner) if winner == human_color => println!("You win!"),
+        Some(_) => println!("You lose."),
+        None => println!("Draw."),
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+#![allow(non_snake_case)]
+
+//! # SOLO - single-player policies on the weak schur number problem.
+//!
+//! Usage: `cargo run --release --bin solo -- -p nrpa -k 3 -b 23 -n 10`
+//!
+//! Partitions the integers `1, 2, 3, ...` in `-k` weakly sum-free partitions, up to the bound `-b`,
+//! and reports the best partition found over `-n` runs.
+
+use ggpf::game::weak_schur::{WeakSchurCode, WeakSchurNumber, WeakSchurNumberBuilder};
+use ggpf::game::*;
+use ggpf::policies::{flat, nmcs, nrpa, SingleplayerPolicy, SingleplayerPolicyBuilder};
+use ggpf::settings;
+
+use clap::{value_t, App, Arg};
+use std::time::Instant;
+use tokio::runtime;
+
+fn main() {
+    let mut threaded_rt = runtime::Builder::new()
+        .threaded_scheduler()
+        .enable_all()
+        .core_threads(8)
+        .build()
+        .unwrap();
+
+    threaded_rt.block_on(run());
+}
+
+/// Plays the sequence of moves given by the policy, returning the final state.
+async fn solve<P: SingleplayerPolicy<WeakSchurNumber>>(
+    policy: &mut P,
+    game_builder: &WeakSchurNumberBuilder,
+) -> WeakSchurNumber {
+    let mut state = SingleplayerGameBuilder::create(game_builder).await;
+    for action in policy.solve(&state).await {
+        state.play(&action).await;
+    }
+    state
+}
+
+async fn run() {
+    let args = App::new("ggpf-solo")
+        .arg(
+            Arg::with_name("policy")
+                .short("p")
+                .long("policy")
+                .takes_value(true)
+                .possible_values(&["rand", "nmcs", "nrpa"]),
+        )
+        .arg(
+            Arg::with_name("partitions")
+                .short("k")
+                .long("partitions")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("bound")
+                .short("b")
+                .long("bound")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("level")
+                .short("l")
+                .long("level")
+                .takes_value(true),
+        )
+        .arg(Arg::with_name("n").short("n").takes_value(true))
+        .arg(
+            Arg::with_name("consecutive")
+                .long("consecutive")
+                .help("Place integers with their predecessor whenever possible."),
+        )
+        .get_matches();
+
+    let game_builder = WeakSchurNumberBuilder {
+        partitions: value_t!(args.value_of("partitions"), usize).unwrap_or(3),
+        bound: value_t!(args.value_of("bound"), usize).ok(),
+        consecutive_rule: args.is_present("consecutive"),
+    };
+    let level = value_t!(args.value_of("level"), usize).unwrap_or(3);
+    let n_runs = value_t!(args.value_of("n"), usize).unwrap_or(1);
+
+    let mut best: Option<WeakSchurNumber> = None;
+    for i in 0..n_runs {
+        let start = Instant::now();
+        let state = match args.value_of("policy").unwrap_or("nrpa") {
+            "rand" => {
+                let builder = flat::Random {};
+                let mut policy = SingleplayerPolicyBuilder::<WeakSchurNumber>::create(&builder);
+                solve(&mut policy, &game_builder).await
+            }
+            "nmcs" => {
+                let builder = nmcs::NMCS::new(level);
+                let mut policy = SingleplayerPolicyBuilder::<WeakSchurNumber>::create(&builder);
+                solve(&mut policy, &game_builder).await
+            }
+            _ => {
+                let builder = nrpa::NRPA::<WeakSchurNumber, WeakSchurCode>::new(settings::NRPA {
+                    level,
+                    ..settings::NRPA::default()
+                });
+                solve(&mut builder.create(), &game_builder).await
+            }
+        };
+        println!(
+            "Run {}: reached {} in {:.2}s",
+            i,
+            state.last_value(),
+            start.elapsed().as_secs_f32()
+        );
+
+        if best
+            .as_ref()
+            .map_or(true, |b| b.last_value() < state.last_value())
+        {
+            best = Some(state);
+        }
+    }
+
+    if let Some(best) = best {
+        println!("Best: {}", best.last_value());
+        println!("{:?}", best);
+    }
+}
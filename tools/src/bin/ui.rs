@@ -12,9 +12,9 @@ use ggpf::game::meta::{
     simulated::Simulated,
     with_history::{IWithHistory, WithHistoryGB},
 };
-use ggpf::game::openai::{Gym, GymBuilder};
+use ggpf::game::openai::GymBuilder;
 use ggpf::game::*;
-use ggpf::policies::mcts::MCTSTreeNode;
+use ggpf::policies::mcts::{snapshot, MCTSTreeNode, SearchInfo};
 use ggpf::policies::{
     mcts::muz::{Muz, MuzEvaluators, MuzPolicy},
     mcts::puct::*,
@@ -35,10 +35,13 @@ use flexi_logger::{LogTarget, Logger};
 use ggpf::settings::{Config, Method};
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::sync::RwLock;
 use std::{error, fmt, fs};
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::task::{self, JoinHandle};
 
 #[derive(Clone)]
 /// Entry for the tree view.
@@ -240,6 +243,17 @@ where
     }
 }
 
+/// Number of moves of the principal variation shown after each search.
+const PV_LENGTH: usize = 8;
+
+/// Principal variation as a line of moves with their values.
+fn format_pv<M: fmt::Debug>(pv: &[(M, f32)]) -> String {
+    pv.iter()
+        .map(|(action, q)| format!("{:?} ({:.2})", action, q))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// AlphaZero event loop, managing the game instance.
 async fn event_loop_alpha<GV, PB2>(
     initial_state: GV::G,
@@ -250,40 +264,55 @@ async fn event_loop_alpha<GV, PB2>(
 ) where
     PB2: MultiplayerPolicyBuilder<GV::G>,
     GV: GameView,
-    GV::G: Game + SingleWinner + Features + Clone,
+    GV::G: Game + SingleWinner + Features + Clone + 'static,
 {
     let mut state = initial_state;
 
-    let mut p1 = pb1.create(<GV::G as Game>::players()[0]);
+    let p1 = Arc::new(AsyncMutex::new(pb1.create(<GV::G as Game>::players()[0])));
     let mut p2 = pb2.create(<GV::G as Game>::players()[1]);
+    // stop flag and handle of the search running during the opponent's turn.
+    let mut pondering: Option<(Arc<AtomicBool>, JoinHandle<()>)> = None;
 
-    while rx.recv().ok().is_some() {
+    // the runtime keeps running the pondering task while waiting for the UI.
+    while task::block_in_place(|| rx.recv()).is_ok() {
         // at each step a Next is received
         if !state.is_finished() {
             let p1_to_play = state.turn() == <GV::G as Game>::players()[0];
 
             let action = if p1_to_play {
-                let action = p1.play(&state).await;
+                let mut p1 = p1.lock().await;
+                let action = p1
+                    .play(&state)
+                    .await
+                    .expect("The policy should play a move in an unfinished game.");
                 /* UPDATE TREE VIEW*/
-                let root_node = p1.root.take().unwrap();
-                let count = root_node.read().unwrap().info.node.count;
-                let root_value: f32 = root_node
-                    .read()
-                    .unwrap()
-                    .info
-                    .moves
-                    .iter()
-                    .map(|(_, v)| (v.reward + 0.997 * v.Q * v.N_a / count)) // TODO: not hardcode discount.
-                    .sum();
+                let SearchInfo {
+                    root_value, count, ..
+                } = p1.search_info();
+                log::info!("PV: {}", format_pv(&p1.principal_variation(PV_LENGTH)));
+                // the tree keeps growing while pondering: the view gets a copy.
+                let root_node = snapshot(p1.root.as_ref().unwrap());
 
                 tx.send(move |ui: &mut GameDuelUI<GV, GV::G>| {
                     ui.new_policy_tree(root_node, root_value, count)
                 });
 
                 /* UPDATE STATE*/
+                p1.advance_root(&action);
                 action
             } else {
-                p2.play(&state).await
+                let action = p2
+                    .play(&state)
+                    .await
+                    .expect("The policy should play a move in an unfinished game.");
+                if let Some((stop, handle)) = pondering.take() {
+                    stop.store(true, Ordering::Relaxed);
+                    handle.await.unwrap();
+                }
+                if p1.lock().await.advance_root(&action) {
+                    log::info!("Reusing pondered tree.");
+                }
+                action
             };
             log::info!("{:?}", action);
             state.play(&action).await;
@@ -297,6 +326,18 @@ async fn event_loop_alpha<GV, PB2>(
         } else {
             log::info!("Turn to {:?}.", state.turn());
         }
+
+        if !state.is_finished() && state.turn() != <GV::G as Game>::players()[0] {
+            // search during the opponent's turn, reused if it plays an explored move.
+            let stop = Arc::new(AtomicBool::new(false));
+            let handle = tokio::spawn({
+                let p1 = p1.clone();
+                let stop = stop.clone();
+                let state = state.clone();
+                async move { p1.lock().await.ponder(&state, &stop).await }
+            });
+            pondering = Some((stop, handle));
+        }
     }
 }
 
@@ -323,11 +364,20 @@ async fn event_loop_muz<GV, PB2>(
             let p1_to_play = state.turn() == <GV::G as Game>::players()[0];
 
             let action = if p1_to_play {
-                let action = p1.play(&state).await;
+                let action = p1
+                    .play(&state)
+                    .await
+                    .expect("The policy should play a move in an unfinished game.");
                 /* UPDATE TREE VIEW*/
                 let mut muz_puct = p1.mcts.take().unwrap();
+                let SearchInfo {
+                    root_value, count, ..
+                } = muz_puct.search_info();
+                log::info!(
+                    "PV: {}",
+                    format_pv(&muz_puct.principal_variation(PV_LENGTH))
+                );
                 let root_node = muz_puct.root.take().unwrap();
-                let visit_count = root_node.read().unwrap().info.node.count;
 
                 log::info!(
                     "Min/max: {}/{}",
@@ -335,23 +385,16 @@ async fn event_loop_muz<GV, PB2>(
                     muz_puct.base_mcts.max_tree
                 );
 
-                let root_value: f32 = root_node
-                    .read()
-                    .unwrap()
-                    .info
-                    .moves
-                    .iter()
-                    .map(|(_, v)| (v.reward + 0.997 * v.Q * v.N_a / visit_count)) // TODO: not hardcode discount.
-                    .sum();
-
                 tx.send(move |ui: &mut GameDuelUI<GV, Simulated<GV::G>>| {
-                    ui.new_policy_tree(root_node, root_value, visit_count)
+                    ui.new_policy_tree(root_node, root_value, count)
                 });
 
                 /* UPDATE STATE*/
                 action
             } else {
-                p2.play(&state).await
+                p2.play(&state)
+                    .await
+                    .expect("The policy should play a move in an unfinished game.")
             };
             log::info!("{:?}", action);
             state.play(&action).await;
@@ -405,18 +448,18 @@ where
         Method::AlphaZero => {
             let gui_events = GameDuelUI::<GV, GV::G>::new(&mut siv).render(view, tx);
 
-            if let Some(mut alpha_config) = config.get_alphazero(action_shape, board_shape) {
+            if let Some(alpha_config) = config.get_alphazero(action_shape, board_shape) {
+                let alpha_config = alpha_config.evaluation();
                 std::thread::spawn(move || {
                     threaded_rt
                         .block_on(async {
-                            alpha_config.watch_models = false;
-                            alpha_config.batch_size = 1;
-
-                            let alpha_evals = AlphaZeroEvaluators::new(alpha_config.clone(), true);
+                            let mut alpha_evals =
+                                AlphaZeroEvaluators::new(alpha_config.clone(), true);
 
                             let puct = PUCT {
                                 config: alpha_config.puct,
                                 n_playouts: config.mcts.playouts,
+                                playouts_schedule: config.mcts.playouts_schedule.clone(),
                                 prediction_channel: alpha_evals.get_channel(),
                             };
 
@@ -429,7 +472,9 @@ where
                                 rx,
                                 gui_events,
                             ));
-                            b.await
+                            let res = b.await;
+                            alpha_evals.shutdown().await;
+                            res
                         })
                         .unwrap();
                 });
@@ -440,18 +485,17 @@ where
         Method::MuZero => {
             let gui_events = GameDuelUI::<GV, Simulated<GV::G>>::new(&mut siv).render(view, tx);
 
-            if let Some(mut mu_config) = config.get_muzero(action_shape, board_shape) {
+            if let Some(mu_config) = config.get_muzero(action_shape, board_shape) {
+                let mu_config = mu_config.evaluation();
                 std::thread::spawn(move || {
                     threaded_rt
                         .block_on(async {
-                            mu_config.watch_models = false;
-                            mu_config.batch_size = 1;
-
-                            let mu_evals = MuzEvaluators::new(mu_config.clone(), true);
+                            let mut mu_evals = MuzEvaluators::new(mu_config.clone(), true);
 
                             let muz = Muz {
                                 muz: mu_config.muz,
                                 n_playouts: config.mcts.playouts,
+                                playouts_schedule: config.mcts.playouts_schedule.clone(),
                                 channels: mu_evals.get_channels(),
                             };
 
@@ -464,7 +508,9 @@ where
                                 rx,
                                 gui_events,
                             ));
-                            b.await
+                            let res = b.await;
+                            mu_evals.shutdown().await;
+                            res
                         })
                         .unwrap();
                 });
@@ -477,38 +523,63 @@ where
     siv.run();
 }
 
-/// Use MuZero with remote Gym.
-async fn run_gym(config: Config, mut game_builder: GymBuilder, method: Method) {
+/// Use MuZero with remote Gym, stacking the last `history` frames if given.
+async fn run_gym(config: Config, game_builder: GymBuilder, history: Option<usize>, method: Method) {
+    let mut silent_builder = game_builder.clone();
+    silent_builder.render = false;
+
+    if let Some(history) = history {
+        run_remote(
+            config,
+            WithHistoryGB::new(silent_builder, history),
+            WithHistoryGB::new(game_builder, history),
+            method,
+        )
+        .await
+    } else {
+        run_remote(config, silent_builder, game_builder, method).await
+    }
+}
+
+/// Use MuZero on a remotely rendered game. `silent_builder` is only used to
+/// retrieve the game features.
+async fn run_remote<GB>(config: Config, silent_builder: GB, game_builder: GB, method: Method)
+where
+    GB: GameBuilder + Send + Sync,
+    GB::G: Features + Clone + 'static,
+{
     if let Method::MuZero = method {
-        game_builder.render = false;
-        let state = SingleplayerGameBuilder::create(&game_builder).await;
-        game_builder.render = true;
+        let player = <GB::G as Game>::players()[0];
+        let state = silent_builder.create(player).await;
 
         let ft = state.get_features();
-        let action_shape = Gym::action_dimension(&ft);
-        let board_shape = Gym::state_dimension(&ft);
+        let action_shape = GB::G::action_dimension(&ft);
+        let board_shape = GB::G::state_dimension(&ft);
 
         drop(state);
 
-        if let Some(mut mu_config) = config.get_muzero(action_shape, board_shape) {
-            mu_config.watch_models = false;
-            mu_config.batch_size = 1;
+        if let Some(mu_config) = config.get_muzero(action_shape, board_shape) {
+            let mu_config = mu_config.evaluation();
 
             let mu_evals = MuzEvaluators::new(mu_config.clone(), true);
 
             let muz = Muz {
                 muz: mu_config.muz,
                 n_playouts: config.mcts.playouts,
+                playouts_schedule: config.mcts.playouts_schedule.clone(),
                 channels: mu_evals.get_channels(),
             };
 
-            let mut muz_p: MuzPolicy<Gym> = muz.create(0);
+            let mut muz_p: MuzPolicy<GB::G> = muz.create(player);
 
             loop {
-                let mut state = SingleplayerGameBuilder::create(&game_builder).await;
+                let mut state = game_builder.create(player).await;
 
                 while !state.is_finished() {
-                    let action = muz_p.play(&state).await;
+                    let action = muz_p
+                        .play(&state)
+                        .await
+                        .expect("The policy should play a move in an unfinished game.");
                     state.play(&action).await;
                     tokio::time::delay_for(std::time::Duration::from_millis(1000)).await;
                 }
@@ -577,12 +648,21 @@ fn main() -> Result<()> {
         settings::Game::Gym {
             name,
             remote,
+            local,
             history,
+            action_bins,
+            frame_skip,
+            timeout_ms,
+            max_retries,
         } => {
             let gb = GymBuilder {
-                address: remote,
+                address: if local { None } else { Some(remote) },
                 game_name: name,
                 render: true,
+                action_bins,
+                frame_skip,
+                timeout_ms,
+                max_retries,
             };
 
             let mut threaded_rt = tokio::runtime::Builder::new()
@@ -592,12 +672,7 @@ fn main() -> Result<()> {
                 .build()
                 .unwrap();
 
-            if let Some(_history) = history {
-                panic!("History not supported yet.");
-            } else {
-                let game_builder = gb;
-                threaded_rt.block_on(run_gym(config, game_builder, method))
-            }
+            threaded_rt.block_on(run_gym(config, gb, history, method))
         }
     };
     Ok(())
@@ -99,7 +99,7 @@ if method == "alpha":
 
         start_epoch = 0
         network.compile(optimizer="adam", loss={
-                        "policy": "categorical_crossentropy", "value": "binary_crossentropy"})
+                        "policy": "categorical_crossentropy", "value": "mean_squared_error"})
         models.save_model(network, model_path, save_format="tf")
 
     trainGenerator = AlphaZeroGenerator(replay_buffer, config)